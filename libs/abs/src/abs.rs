@@ -155,8 +155,10 @@ impl Pos {
         Self::from_bits(self.0.saturating_add(1))
     }
 
+    /// Along with `to_bits`, this allows storing a `Pos` exactly, which is not always
+    /// possible using `f32`s.
     #[must_use]
-    fn from_bits(bits: i64) -> Self {
+    pub fn from_bits(bits: i64) -> Self {
         Self(
             std::cmp::max(
                 bits,
@@ -164,6 +166,11 @@ impl Pos {
             )
         )
     }
+
+    #[must_use]
+    pub const fn to_bits(self) -> i64 {
+        self.0
+    }
 }
 
 #[macro_export]
@@ -34,6 +34,13 @@ impl Cursors {
         Self { cursors }
     }
 
+    /// Like `new`, but without the sorting and bounds checking. This is meant for
+    /// cursors that were checked when they were first made, and then stored somewhere,
+    /// like the ones in an edit history, where the rope they were for is not around.
+    pub fn new_unchecked(cursors: Vec1<Cursor>) -> Self {
+        Self { cursors }
+    }
+
     pub fn mapped_ref<F, Out>(&self, mapper: F) -> Vec1<Out>
     where
         F: FnMut(&Cursor) -> Out,
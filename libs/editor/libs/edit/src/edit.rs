@@ -321,12 +321,60 @@ pub fn get_delete_lines_edit(original_rope: &Rope, original_cursors: &Cursors) -
     }
 
     let mut edit = get_delete_edit(original_rope, &Cursors::new(original_rope, extended_cursors));
-
-    edit.cursors.old = original_cursors.clone();
-
+
+    edit.cursors.old = original_cursors.clone();
+
     edit
 }
 
+/// Returns an edit that, if applied, replaces the text in each of the given ranges with the
+/// string `get_string` returns for the index of that range, leaving a cursor after each
+/// replacement. The indexes go from the range nearest the start of the rope, to the one
+/// nearest the end. As with `get_range_replacements_edit`, ranges that overlap an earlier
+/// one are skipped. If any of the ranges are not inside the rope, then the edit does
+/// nothing.
+pub fn get_replace_ranges_edit<F>(
+    original_rope: &Rope,
+    original_cursors: &Cursors,
+    ranges: Vec1<(Position, Position)>,
+    get_string: F,
+) -> Edit
+where
+    F: Fn(usize) -> String,
+{
+    // We don't turn the ranges into `Cursors` and use `get_insert_edit`, since `Cursors`
+    // merges touching ranges, and each range needs its own replacement.
+    let offset_ranges: Option<Vec<AbsoluteCharOffsetRange>> = ranges
+        .iter()
+        .map(|(p1, p2)| {
+            let o1 = pos_to_char_offset(original_rope, p1)?;
+            let o2 = pos_to_char_offset(original_rope, p2)?;
+            Some(AbsoluteCharOffsetRange::new(o1, o2))
+        })
+        .collect();
+    // Skipping the invalid ranges would change which index the later ones get.
+    let mut offset_ranges = some_or!(
+        offset_ranges,
+        return Change {
+            old: original_cursors.clone(),
+            new: original_cursors.clone(),
+        }.into()
+    );
+    offset_ranges.sort_by_key(|r| r.min());
+
+    let replacements = offset_ranges
+        .into_iter()
+        .enumerate()
+        .map(|(index, range)| RangeReplacement {
+            range,
+            chars: get_string(index),
+            selections: Vec::new(),
+        })
+        .collect();
+
+    get_range_replacements_edit(original_rope, original_cursors, replacements)
+}
+
 /// returns an edit that if applied will delete the highlighted region at each cursor if there is
 /// one, and which does nothing otherwise.
 pub fn get_cut_edit(original_rope: &Rope, original_cursors: &Cursors) -> Edit {
@@ -591,6 +639,802 @@ pub fn get_tab_out_edit(original_rope: &Rope, original_cursors: &Cursors) -> Edi
     )
 }
 
+/// Returns the first and last lines the cursor touches. A multi-line selection that ends
+/// at the start of a line does not count as touching that line.
+fn selected_line_range(c: &Cursor) -> (usize, usize) {
+    let position = c.get_position();
+    let highlight_position = c.get_highlight_position_or_position();
+    let first = min(position, highlight_position);
+    let last = max(position, highlight_position);
+
+    if first.line < last.line && last.offset == 0 {
+        (first.line, last.line - 1)
+    } else {
+        (first.line, last.line)
+    }
+}
+
+/// Returns the line ranges the cursors touch, sorted, with overlapping or adjacent ranges
+/// merged together, since the lines in those need to be handled together.
+fn merged_line_ranges(cursors: &Vec1<Cursor>) -> Vec<(usize, usize)> {
+    let mut ranges: Vec<(usize, usize)> = cursors.iter().map(selected_line_range).collect();
+    ranges.sort();
+
+    let mut merged: Vec<(usize, usize)> = Vec::with_capacity(ranges.len());
+    for (first, last) in ranges {
+        match merged.last_mut() {
+            Some((_, previous_last)) if first <= *previous_last + 1 => {
+                *previous_last = max(*previous_last, last);
+            }
+            _ => merged.push((first, last)),
+        }
+    }
+
+    merged
+}
+
+/// Returns the range of chars covering the given lines, including their line endings.
+fn line_range_chars(rope: &Rope, first: usize, last: usize) -> Option<AbsoluteCharOffsetRange> {
+    Some(AbsoluteCharOffsetRange::new(
+        rope.line_to_char(LineIndex(first))?,
+        rope.line_to_char(LineIndex(last + 1))?,
+    ))
+}
+
+/// Returns the text of the line without its line ending, and then the line ending, which
+/// may be empty if this is the last line.
+fn line_content_and_ending(rope: &Rope, index: usize) -> Option<(String, String)> {
+    let mut line = copy_string(rope, line_range_chars(rope, index, index)?);
+    let content_len = line.trim_end_matches(|c| c == '\r' || c == '\n').len();
+    let ending = line.split_off(content_len);
+    Some((line, ending))
+}
+
+/// Replaces the text in each of the given ranges of `rope` with the given string, and
+/// returns range edits describing that, last range first. The ranges must be sorted and
+/// must not overlap.
+fn apply_replacements_backwards(
+    rope: &mut Rope,
+    replacements: &[(AbsoluteCharOffsetRange, String)],
+) -> Vec<RangeEdits> {
+    let mut range_edits = Vec::with_capacity(replacements.len());
+    // We go backwards so our own inserts and deletes don't mess up the earlier indexes.
+    for (range, chars) in replacements.iter().rev() {
+        let char_count = chars.chars().count();
+
+        let (delete_range, _, _) = delete_within_range(rope, *range);
+        rope.insert(range.min(), chars);
+
+        range_edits.push(RangeEdits {
+            insert_range: Some(RangeEdit {
+                chars: chars.clone(),
+                range: AbsoluteCharOffsetRange::new(range.min(), range.min() + char_count),
+            }),
+            delete_range: Some(delete_range),
+        });
+    }
+
+    range_edits
+}
+
+/// Builds an edit out of range edits that were not produced by `get_edit`, adding empty
+/// range edits if needed so there are at least as many of them as there are cursors.
+fn edit_from_range_edits(mut range_edits: Vec<RangeEdits>, cursors: Change<Cursors>) -> Edit {
+    if range_edits.is_empty() {
+        return Change {
+            new: cursors.old.clone(),
+            old: cursors.old,
+        }.into();
+    }
+
+    let cursor_count = max(cursors.old.len(), cursors.new.len());
+    if range_edits.len() < cursor_count {
+        range_edits.resize_with(cursor_count, RangeEdits::default);
+    }
+
+    Edit {
+        // This unwrap is fine because we checked `range_edits` was not empty above.
+        range_edits: Vec1::try_from_vec(range_edits).unwrap(),
+        cursors,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SwapDirection {
+    /// Towards the start of the rope.
+    Up,
+    /// Towards the end of the rope.
+    Down,
+}
+
+/// Returns an edit that, if applied, swaps the line(s) each cursor intersects with and the
+/// line above or below them, depending on `direction`. The cursors move along with their
+/// lines. A multi-line selection that ends at the start of a line does not count as
+/// touching that line. Line endings stay where they are, so a final line without a line
+/// ending can be moved.
+pub fn get_swap_lines_edit(
+    original_rope: &Rope,
+    original_cursors: &Cursors,
+    direction: SwapDirection,
+) -> Edit {
+    let cursors = original_cursors.get_cloned_cursors();
+    let line_count = original_rope.len_lines().0;
+
+    let moving: Vec<(usize, usize)> = merged_line_ranges(&cursors)
+        .into_iter()
+        .filter(|&(first, last)| match direction {
+            SwapDirection::Up => first > 0,
+            SwapDirection::Down => last + 1 < line_count,
+        })
+        .collect();
+
+    let mut replacements = Vec::with_capacity(moving.len());
+    for &(first, last) in moving.iter() {
+        let (region_first, region_last) = match direction {
+            SwapDirection::Up => (first - 1, last),
+            SwapDirection::Down => (first, last + 1),
+        };
+
+        let mut contents = Vec::with_capacity(region_last - region_first + 1);
+        let mut endings = Vec::with_capacity(region_last - region_first + 1);
+        for index in region_first..=region_last {
+            let (content, ending) = some_or!(line_content_and_ending(original_rope, index), continue);
+            contents.push(content);
+            endings.push(ending);
+        }
+
+        match direction {
+            SwapDirection::Up => contents.rotate_left(1),
+            SwapDirection::Down => contents.rotate_right(1),
+        }
+
+        let chars: String = contents
+            .into_iter()
+            .zip(endings)
+            .map(|(content, ending)| content + &ending)
+            .collect();
+
+        let region = some_or!(line_range_chars(original_rope, region_first, region_last), continue);
+        replacements.push((region, chars));
+    }
+
+    let mut rope = original_rope.clone();
+    let range_edits = apply_replacements_backwards(&mut rope, &replacements);
+
+    let new_cursors = cursors.mapped_ref(|c| {
+        let (first, _) = selected_line_range(c);
+        let is_moving = moving.iter().any(|&(f, l)| f <= first && first <= l);
+        if !is_moving {
+            return c.clone();
+        }
+
+        let shift = |p: Position| Position {
+            line: match direction {
+                SwapDirection::Up => p.line - 1,
+                SwapDirection::Down => p.line + 1,
+            },
+            ..p
+        };
+
+        let mut new = Cursor::new_with_highlight(
+            shift(c.get_position()),
+            shift(c.get_highlight_position_or_position()),
+        );
+        new.sticky_offset = c.sticky_offset;
+        new
+    });
+
+    edit_from_range_edits(
+        range_edits,
+        Change {
+            old: original_cursors.clone(),
+            new: Cursors::new(&rope, new_cursors),
+        },
+    )
+}
+
+/// A pair of non-overlapping ranges of text to be swapped. Cursors in `moved` move along
+/// with it, as do cursors in `other` and in the text between the two.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct RangeSwap {
+    pub moved: AbsoluteCharOffsetRange,
+    pub other: AbsoluteCharOffsetRange,
+}
+
+impl RangeSwap {
+    fn ordered(&self) -> (AbsoluteCharOffsetRange, AbsoluteCharOffsetRange) {
+        if self.moved.min() <= self.other.min() {
+            (self.moved, self.other)
+        } else {
+            (self.other, self.moved)
+        }
+    }
+
+    fn region(&self) -> AbsoluteCharOffsetRange {
+        let (first, second) = self.ordered();
+        AbsoluteCharOffsetRange::new(first.min(), second.max())
+    }
+
+    /// Where the text at `offset` ends up after the swap.
+    fn map_offset(&self, offset: AbsoluteCharOffset) -> AbsoluteCharOffset {
+        let (first, second) = self.ordered();
+        let len = |r: AbsoluteCharOffsetRange| r.max().0 - r.min().0;
+        // After the swap the region holds the second range, then the text between,
+        // then the first range.
+        let new_second_min = first.min().0;
+        let new_between_min = new_second_min + len(second);
+        let new_first_min = new_between_min + (second.min().0 - first.max().0);
+
+        let in_range = |r: AbsoluteCharOffsetRange| r.min() <= offset && offset <= r.max();
+        let (moved_min, other_min) = if self.moved.min() <= self.other.min() {
+            (new_first_min, new_second_min)
+        } else {
+            (new_second_min, new_first_min)
+        };
+
+        AbsoluteCharOffset(if in_range(self.moved) {
+            offset.0 - self.moved.min().0 + moved_min
+        } else if in_range(self.other) {
+            offset.0 - self.other.min().0 + other_min
+        } else if first.max() < offset && offset < second.min() {
+            offset.0 - first.max().0 + new_between_min
+        } else {
+            offset.0
+        })
+    }
+}
+
+/// Returns an edit that, if applied, performs each of the given swaps. Swaps whose ranges
+/// overlap each other, or that overlap an earlier swap, are skipped.
+pub fn get_swap_ranges_edit(
+    original_rope: &Rope,
+    original_cursors: &Cursors,
+    mut swaps: Vec<RangeSwap>,
+) -> Edit {
+    swaps.retain(|s| {
+        let (first, second) = s.ordered();
+        first.max() <= second.min()
+    });
+    swaps.sort_by_key(|s| s.region().min());
+    swaps.dedup();
+
+    let mut kept: Vec<RangeSwap> = Vec::with_capacity(swaps.len());
+    for swap in swaps {
+        match kept.last() {
+            Some(last) if swap.region().min() < last.region().max() => {}
+            _ => kept.push(swap),
+        }
+    }
+
+    let cursors = original_cursors.get_cloned_cursors();
+
+    let replacements: Vec<_> = kept
+        .iter()
+        .map(|swap| {
+            let (first, second) = swap.ordered();
+
+            let mut chars = copy_string(original_rope, second);
+            chars.push_str(&copy_string(
+                original_rope,
+                AbsoluteCharOffsetRange::new(first.max(), second.min()),
+            ));
+            chars.push_str(&copy_string(original_rope, first));
+
+            (swap.region(), chars)
+        })
+        .collect();
+
+    let mut rope = original_rope.clone();
+    let range_edits = apply_replacements_backwards(&mut rope, &replacements);
+
+    let map_position = |p: Position| -> Position {
+        let offset = some_or!(pos_to_char_offset(original_rope, &p), return p);
+        let mapped = kept
+            .iter()
+            .find(|s| {
+                let region = s.region();
+                region.min() <= offset && offset <= region.max()
+            })
+            .map(|s| s.map_offset(offset))
+            .unwrap_or(offset);
+
+        char_offset_to_pos(&rope, mapped).unwrap_or(p)
+    };
+
+    let new_cursors = cursors.mapped_ref(|c| {
+        cur!{
+            map_position(c.get_position()),
+            map_position(c.get_highlight_position_or_position())
+        }
+    });
+
+    edit_from_range_edits(
+        range_edits,
+        Change {
+            old: original_cursors.clone(),
+            new: Cursors::new(&rope, new_cursors),
+        },
+    )
+}
+
+/// Text to put in place of a range of text, along with the parts of that text that the
+/// cursors should select afterwards.
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct RangeReplacement {
+    pub range: AbsoluteCharOffsetRange,
+    pub chars: String,
+    /// Pairs of char offsets into `chars`, each from the start of a selection to its end.
+    pub selections: Vec<(usize, usize)>,
+}
+
+/// Returns an edit that, if applied, performs each of the given replacements, leaving a
+/// cursor selecting each of their `selections`, or if there are none of those, a cursor
+/// after each replacement. Replacements that overlap an earlier one are skipped.
+pub fn get_range_replacements_edit(
+    original_rope: &Rope,
+    original_cursors: &Cursors,
+    mut replacements: Vec<RangeReplacement>,
+) -> Edit {
+    replacements.sort_by_key(|r| (r.range.min(), r.range.max()));
+
+    let mut kept: Vec<RangeReplacement> = Vec::with_capacity(replacements.len());
+    for replacement in replacements {
+        match kept.last() {
+            Some(last) if replacement.range.min() < last.range.max() => {}
+            _ => kept.push(replacement),
+        }
+    }
+
+    let pairs: Vec<_> = kept
+        .iter()
+        .map(|RangeReplacement { range, chars, .. }| (*range, chars.clone()))
+        .collect();
+
+    let mut rope = original_rope.clone();
+    let range_edits = apply_replacements_backwards(&mut rope, &pairs);
+
+    let has_selections = kept.iter().any(|r| !r.selections.is_empty());
+
+    let mut total_delta: isize = 0;
+    let mut new_cursors = Vec::with_capacity(kept.len());
+    for RangeReplacement { range, chars, selections } in kept.iter() {
+        let new_min = (range.min().0 as isize + total_delta) as usize;
+        let char_count = chars.chars().count();
+        total_delta += char_count as isize - (range.max().0 - range.min().0) as isize;
+
+        let to_pos = |offset: usize| char_offset_to_pos(&rope, AbsoluteCharOffset(new_min + offset));
+
+        if has_selections {
+            for &(start, end) in selections.iter() {
+                if let (Some(start), Some(end)) = (to_pos(start), to_pos(end)) {
+                    new_cursors.push(cur!{end, start});
+                }
+            }
+        } else if let Some(p) = to_pos(char_count) {
+            new_cursors.push(Cursor::new(p));
+        }
+    }
+
+    match Vec1::try_from_vec(new_cursors) {
+        Ok(new_cursors) => edit_from_range_edits(
+            range_edits,
+            Change {
+                old: original_cursors.clone(),
+                new: Cursors::new(&rope, new_cursors),
+            },
+        ),
+        Err(_) => Change {
+            old: original_cursors.clone(),
+            new: original_cursors.clone(),
+        }.into(),
+    }
+}
+
+/// Returns an edit that, if applied, toggles line comments starting with `comment_start`
+/// on the line(s) each cursor intersects with. If every non-blank line in a group of
+/// touching lines is already commented, then those lines are uncommented, along with a
+/// single space after the `comment_start` if there is one. Otherwise, the lines are
+/// commented at the smallest indentation among them, so the comments line up.
+pub fn get_toggle_comment_edit(
+    original_rope: &Rope,
+    original_cursors: &Cursors,
+    comment_start: &str,
+) -> Edit {
+    let cursors = original_cursors.get_cloned_cursors();
+    let comment_start_char_count = comment_start.chars().count();
+    let comment_with_space = format!("{} ", comment_start);
+
+    let mut changes = Vec::new();
+    let mut blocks = Vec::new();
+    for (first, last) in merged_line_ranges(&cursors) {
+        let mut lines = Vec::with_capacity(last - first + 1);
+        for index in first..=last {
+            let (content, ending) = some_or!(line_content_and_ending(original_rope, index), continue);
+            let indent = indent_char_count(&content);
+            let is_blank = indent == content.chars().count();
+            lines.push((index, content, ending, indent, is_blank));
+        }
+
+        let non_blank = || lines.iter().filter(|(_, _, _, _, is_blank)| !is_blank);
+        let min_indent = some_or!(non_blank().map(|(_, _, _, indent, _)| *indent).min(), continue);
+        let should_uncomment = non_blank().all(|(_, content, _, indent, _)|
+            content.chars().skip(*indent).collect::<String>().starts_with(comment_start)
+        );
+
+        let mut chars = String::new();
+        for (index, content, ending, indent, is_blank) in lines.iter() {
+            if *is_blank {
+                chars.push_str(content);
+                chars.push_str(ending);
+                continue
+            }
+
+            if should_uncomment {
+                let (before, comment) = split_at_char(content, *indent);
+                let after = &comment[comment_start.len()..];
+                let (after, removed) = match after.strip_prefix(' ') {
+                    Some(after) => (after, comment_start_char_count + 1),
+                    None => (after, comment_start_char_count),
+                };
+                chars.push_str(before);
+                chars.push_str(after);
+                changes.push(LineChange { line: *index, column: *indent, removed, inserted: 0 });
+            } else {
+                let (before, after) = split_at_char(content, min_indent);
+                chars.push_str(before);
+                chars.push_str(&comment_with_space);
+                chars.push_str(after);
+                changes.push(LineChange {
+                    line: *index,
+                    column: min_indent,
+                    removed: 0,
+                    inserted: comment_start_char_count + 1,
+                });
+            }
+            chars.push_str(ending);
+        }
+
+        blocks.push((first, last, chars));
+    }
+
+    get_line_rewrite_edit(original_rope, original_cursors, blocks, &changes)
+}
+
+/// A change within a single line, where `removed` chars were removed starting at char
+/// `column`, and then `inserted` chars were inserted there.
+#[derive(Clone, Copy, Debug)]
+struct LineChange {
+    line: usize,
+    column: usize,
+    removed: usize,
+    inserted: usize,
+}
+
+/// Returns an edit that replaces the lines in each of the blocks, which are given as the
+/// first line, the last line, and the new text of those lines, including line endings.
+/// The blocks must be sorted and must not overlap, and the new text must not add or
+/// remove any line endings. The cursors are moved along with the text, as described by
+/// `changes`.
+fn get_line_rewrite_edit(
+    original_rope: &Rope,
+    original_cursors: &Cursors,
+    blocks: Vec<(usize, usize, String)>,
+    changes: &[LineChange],
+) -> Edit {
+    let replacements: Vec<_> = blocks
+        .into_iter()
+        .filter_map(|(first, last, chars)| {
+            line_range_chars(original_rope, first, last).map(|region| (region, chars))
+        })
+        .collect();
+
+    let mut rope = original_rope.clone();
+    let range_edits = apply_replacements_backwards(&mut rope, &replacements);
+
+    let map_position = |p: Position| -> Position {
+        let change = changes.iter().find(|change| change.line == p.line);
+        let LineChange { column, removed, inserted, .. } = *some_or!(change, return p);
+        let offset = p.offset.0;
+
+        let new_offset = if offset >= column + removed {
+            offset - removed + inserted
+        } else if offset > column {
+            column + inserted
+        } else {
+            offset
+        };
+
+        Position { offset: CharOffset(new_offset), ..p }
+    };
+
+    let new_cursors = original_cursors.get_cloned_cursors().mapped_ref(|c| {
+        cur!{
+            map_position(c.get_position()),
+            map_position(c.get_highlight_position_or_position())
+        }
+    });
+
+    edit_from_range_edits(
+        range_edits,
+        Change {
+            old: original_cursors.clone(),
+            new: Cursors::new(&rope, new_cursors),
+        },
+    )
+}
+
+/// Splits `s` before the char with the given index, or at the end if there is no such char.
+fn split_at_char(s: &str, char_index: usize) -> (&str, &str) {
+    let byte_index = s
+        .char_indices()
+        .nth(char_index)
+        .map(|(i, _)| i)
+        .unwrap_or_else(|| s.len());
+    s.split_at(byte_index)
+}
+
+fn indent_char_count(content: &str) -> usize {
+    content
+        .chars()
+        .take_while(|c| c.is_whitespace())
+        .count()
+}
+
+fn indent_of(content: &str) -> &str {
+    split_at_char(content, indent_char_count(content)).0
+}
+
+/// Returns `indent` with `levels` levels of indentation added, or removed if `levels` is
+/// negative. A removed level is either a tab, or up to `TAB_STR_CHAR_COUNT` spaces.
+fn adjust_indent(indent: &str, levels: isize) -> String {
+    let mut output = indent.to_owned();
+    if levels >= 0 {
+        for _ in 0..levels {
+            output.push_str(TAB_STR);
+        }
+    } else {
+        for _ in 0..levels.abs() {
+            if output.ends_with('\t') {
+                output.pop();
+            } else {
+                for _ in 0..TAB_STR_CHAR_COUNT {
+                    if !output.ends_with(TAB_STR_CHAR) {
+                        break
+                    }
+                    output.pop();
+                }
+            }
+        }
+    }
+    output
+}
+
+fn is_open_bracket(c: char) -> bool {
+    c == '(' || c == '[' || c == '{'
+}
+
+fn is_close_bracket(c: char) -> bool {
+    c == ')' || c == ']' || c == '}'
+}
+
+/// Returns how many brackets in `s` are opened and not closed again later in `s`.
+fn unclosed_open_bracket_count(s: &str) -> usize {
+    s.chars().fold(0, |count, c| if is_open_bracket(c) {
+        count + 1
+    } else if is_close_bracket(c) {
+        count.saturating_sub(1)
+    } else {
+        count
+    })
+}
+
+/// Returns how many levels deeper than the previous line the next line should be indented.
+/// `previous_start` and `next_start` are the offsets of the first non-whitespace chars of
+/// the lines, or where those would be if the lines are blank. `previous_text` is the part
+/// of the previous line from `previous_start` on that ends up staying on that line, and
+/// `next_text` is the next line from `next_start` on. We ask `nesting_depth` first, and
+/// fall back to looking at the brackets in the text if it returns `None`.
+fn indent_level_change<D>(
+    nesting_depth: &mut D,
+    (previous_start, previous_text): (AbsoluteCharOffset, &str),
+    (next_start, next_text): (AbsoluteCharOffset, &str),
+) -> isize
+where
+    D: FnMut(AbsoluteCharOffset) -> Option<usize>,
+{
+    match (nesting_depth(previous_start), nesting_depth(next_start)) {
+        (Some(previous), Some(next)) => next as isize - previous as isize,
+        _ => {
+            let opens = unclosed_open_bracket_count(previous_text) > 0;
+            let closes = next_text.starts_with(is_close_bracket);
+            opens as isize - closes as isize
+        }
+    }
+}
+
+/// Returns the indentation the given line should have, judging by the nearest non-blank
+/// line above it. See `get_new_line_edit` for what `nesting_depth` should return.
+fn expected_indent<D>(rope: &Rope, line_index: usize, nesting_depth: &mut D) -> Option<String>
+where
+    D: FnMut(AbsoluteCharOffset) -> Option<usize>,
+{
+    let (content, _) = line_content_and_ending(rope, line_index)?;
+    let line_start = rope.line_to_char(LineIndex(line_index))?;
+    let indent = indent_char_count(&content);
+    let next = (line_start + indent, &content[indent_of(&content).len()..]);
+
+    for previous_index in (0..line_index).rev() {
+        let (previous_content, _) = line_content_and_ending(rope, previous_index)?;
+        let previous_indent = indent_of(&previous_content);
+        if previous_indent.len() == previous_content.len() {
+            continue
+        }
+
+        let previous_start = rope.line_to_char(LineIndex(previous_index))?
+            + previous_indent.chars().count();
+        let levels = indent_level_change(
+            nesting_depth,
+            (previous_start, &previous_content[previous_indent.len()..]),
+            next,
+        );
+
+        return Some(adjust_indent(previous_indent, levels));
+    }
+
+    // There is no line above to go by.
+    Some(TAB_STR.repeat(nesting_depth(next.0).unwrap_or(0)))
+}
+
+/// Returns an edit that, if applied, after deleting the highlighted region at each cursor
+/// if there is one, inserts a line break at each cursor, followed by the indentation that
+/// the new line is expected to have. That is, the indentation of the line the cursor was
+/// on, adjusted for any brackets that are opened before the cursor, or closed right after
+/// it. A cursor within the indentation of its line just pushes the line down.
+///
+/// `nesting_depth` should return how many pairs of brackets enclose the given offset,
+/// counting pairs opened on the same line as one, or `None` if that is not known. In the
+/// `None` case the brackets on the lines themselves are looked at instead.
+pub fn get_new_line_edit<D>(
+    original_rope: &Rope,
+    original_cursors: &Cursors,
+    mut nesting_depth: D,
+) -> Edit
+where
+    D: FnMut(AbsoluteCharOffset) -> Option<usize>,
+{
+    let mut new_line_for = |cursor: &Cursor| -> Option<String> {
+        let (o1, o2) = offset_pair(original_rope, cursor);
+        let o1 = o1?;
+        let range = AbsoluteCharOffsetRange::new(o1, o2.unwrap_or(o1));
+
+        let start_line = original_rope.char_to_line(range.min())?;
+        let start_line_start = original_rope.line_to_char(start_line)?;
+        let (content, _) = line_content_and_ending(original_rope, start_line.0)?;
+        let previous_indent = indent_of(&content);
+        let previous_indent_count = previous_indent.chars().count();
+        let column = range.min() - start_line_start;
+        if column.0 <= previous_indent_count {
+            return None;
+        }
+        let previous_text = split_at_char(&content, column.0).0;
+        let previous_text = &previous_text[previous_indent.len()..];
+
+        let end_line = original_rope.char_to_line(range.max())?;
+        let end_line_start = original_rope.line_to_char(end_line)?;
+        let (end_content, _) = line_content_and_ending(original_rope, end_line.0)?;
+        let rest = split_at_char(&end_content, (range.max() - end_line_start).0).1;
+        let next_text = rest.trim_start();
+        let next_start = range.max() + (rest.chars().count() - next_text.chars().count());
+
+        let levels = indent_level_change(
+            &mut nesting_depth,
+            (start_line_start + previous_indent_count, previous_text),
+            (next_start, next_text),
+        );
+
+        Some(adjust_indent(previous_indent, levels))
+    };
+
+    // `get_insert_edit` numbers the cursors starting from the one closest to the start.
+    let new_lines: Vec<String> = original_cursors
+        .get_cloned_cursors()
+        .iter()
+        .rev()
+        .map(|c| format!("\n{}", new_line_for(c).unwrap_or_default()))
+        .collect();
+
+    get_insert_edit(original_rope, original_cursors, |i| new_lines[i].clone())
+}
+
+/// Returns an edit that, if applied, indents the lines each cursor intersects with, if
+/// every cursor either has a selection, or is within the indentation of its line.
+/// Otherwise, this is the same as `get_tab_in_edit`. In each group of touching lines,
+/// if the first non-blank line is indented less than the lines above suggest it should
+/// be, then the lines are indented so that it ends up where expected, keeping the lines'
+/// indentation relative to each other. Otherwise, a level of indentation is added, so
+/// pressing tab repeatedly keeps indenting the lines further.
+///
+/// See `get_new_line_edit` for what `nesting_depth` should return.
+pub fn get_auto_indent_tab_in_edit<D>(
+    original_rope: &Rope,
+    original_cursors: &Cursors,
+    mut nesting_depth: D,
+) -> Edit
+where
+    D: FnMut(AbsoluteCharOffset) -> Option<usize>,
+{
+    let cursors = original_cursors.get_cloned_cursors();
+
+    let all_at_line_starts = cursors.iter().all(|c| {
+        let position = c.get_position();
+        if c.get_highlight_position().is_some() && c.get_highlight_position() != Some(position) {
+            return true;
+        }
+        line_content_and_ending(original_rope, position.line)
+            .map(|(content, _)| position.offset.0 <= indent_char_count(&content))
+            .unwrap_or(false)
+    });
+    if !all_at_line_starts {
+        return get_tab_in_edit(original_rope, original_cursors);
+    }
+
+    let mut changes = Vec::new();
+    let mut blocks = Vec::new();
+    for (first, last) in merged_line_ranges(&cursors) {
+        let mut lines = Vec::with_capacity(last - first + 1);
+        for index in first..=last {
+            let (content, ending) = some_or!(line_content_and_ending(original_rope, index), continue);
+            let indent = indent_char_count(&content);
+            let is_blank = indent == content.chars().count();
+            lines.push((index, content, ending, indent, is_blank));
+        }
+
+        // If every line is blank, we indent them all, so a blank line can be tabbed into.
+        let all_blank = lines.iter().all(|(_, _, _, _, is_blank)| *is_blank);
+        let (first_index, first_content, _, first_indent, _) = some_or!(
+            lines.iter().find(|(_, _, _, _, is_blank)| all_blank || !is_blank),
+            continue
+        );
+
+        let current = indent_of(first_content);
+        let expected = some_or!(
+            expected_indent(original_rope, *first_index, &mut nesting_depth),
+            continue
+        );
+        let expected_count = expected.chars().count();
+
+        let extra = if expected_count > *first_indent {
+            match expected.strip_prefix(current) {
+                Some(extra) => extra.to_owned(),
+                None => TAB_STR_CHAR.to_string().repeat(expected_count - first_indent),
+            }
+        } else {
+            TAB_STR.to_owned()
+        };
+        let extra_count = extra.chars().count();
+
+        let mut chars = String::new();
+        for (index, content, ending, indent, is_blank) in lines.iter() {
+            if *is_blank && !all_blank {
+                chars.push_str(content);
+                chars.push_str(ending);
+                continue
+            }
+
+            let column = min(*indent, *first_indent);
+            let (before, after) = split_at_char(content, column);
+            chars.push_str(before);
+            chars.push_str(&extra);
+            chars.push_str(after);
+            chars.push_str(ending);
+            changes.push(LineChange { line: *index, column, removed: 0, inserted: extra_count });
+        }
+
+        blocks.push((first, last, chars));
+    }
+
+    get_line_rewrite_edit(original_rope, original_cursors, blocks, &changes)
+}
 
 /// The length of `range_edits` must be greater than or equal to the length of the
 /// two `Vec1`s in `cursors`. This is because we assume this is the case in `read_at`
@@ -601,6 +1445,15 @@ pub struct Edit {
 }
 
 impl Edit {
+    /// This is meant for rebuilding an edit from the parts returned by the other methods,
+    /// like after it has been saved to disk.
+    pub fn from_parts(range_edits: Vec1<RangeEdits>, cursors: Change<Cursors>) -> Edit {
+        Edit {
+            range_edits,
+            cursors,
+        }
+    }
+
     pub fn range_edits(&self) -> &Vec1<RangeEdits> {
         &self.range_edits
     }
@@ -687,7 +1540,7 @@ pub struct RangeEdits {
 }
 
 impl RangeEdits {
-    fn apply(&self, rope: &mut Rope) {
+    pub fn apply(&self, rope: &mut Rope) {
         if let Some(RangeEdit { range, .. }) = self.delete_range {
             rope.remove(range.range());
         }
@@ -3,7 +3,7 @@ use rope_pos::{
     nearest_valid_position_on_same_line,
 };
 use editor_types::{Cursor, SetPositionAction};
-use macros::d;
+use macros::{d, some_or};
 use panic_safe_rope::{LineIndex, Rope, RopeLine, RopeSliceTrait};
 use platform_types::*;
 use std::borrow::{Borrow, Cow};
@@ -191,6 +191,54 @@ fn move_to_rope_end(rope: &Rope, cursor: &mut Cursor, action: SetPositionAction)
     move_to(rope, cursor, last_position(rope), action)
 }
 
+/// If the cursor is on a line hidden by one of the `shown` folds, as returned by
+/// `folds::shown`, this moves it to the nearest visible line in the direction of the
+/// move, or the other direction if there are no visible lines that way.
+pub fn out_of_folds(
+    rope: &Rope,
+    cursor: &mut Cursor,
+    r#move: Move,
+    shown: &[Fold],
+    action: SetPositionAction,
+) {
+    let fold = some_or!(folds::hiding(shown, cursor.get_position().line), return);
+
+    use Move::*;
+    let is_forward = match r#move {
+        Down | Right | ToLineEnd | ToBufferEnd | ToNextLikelyEditLocation => true,
+        Up | Left | ToLineStart | ToBufferStart | ToPreviousLikelyEditLocation => false,
+    };
+
+    let line_after = fold.last_line.checked_add(1)
+        .filter(|&line| line < rope.len_lines().0);
+    let line_before = fold.first_line.checked_sub(1);
+
+    let (line, is_after) = match (line_after, line_before) {
+        (Some(after), _) if is_forward => (after, true),
+        (_, Some(before)) => (before, false),
+        (Some(after), None) => (after, true),
+        (None, None) => return,
+    };
+
+    let offset = match r#move {
+        Up | Down => cursor.sticky_offset,
+        _ if is_after => d!(),
+        _ => some_or!(nth_line_count(rope, line), return),
+    };
+
+    let position = some_or!(
+        nearest_valid_position_on_same_line(rope, Position { line, offset }),
+        return
+    );
+
+    cursor.set_position_custom(position, action);
+    match r#move {
+        // We want to keep going to the same offset on the next line we move to.
+        Up | Down => {}
+        _ => cursor.sticky_offset = position.offset,
+    }
+}
+
 pub fn get_previous_selection_point(rope: &Rope, position: Position) -> Option<Position> {
     get_previous_position(rope, position, OffsetKind::SelectionPoint)
 }
@@ -2,13 +2,42 @@ extern crate cc;
 
 use std::path::PathBuf;
 
+/// The vendored grammars, by directory, along with the scanner file, if the grammar
+/// has one. Each of these should have an entry in `LANGUAGES` in `parsers.rs`.
+const GRAMMARS: [(&str, Option<&str>); 5] = [
+    ("tree-sitter-rust", Some("scanner.c")),
+    ("tree-sitter-c", None),
+    ("tree-sitter-json", None),
+    ("tree-sitter-markdown", Some("scanner.cc")),
+    ("tree-sitter-toml", Some("scanner.c")),
+];
+
 fn main() {
-    let dir: PathBuf = ["tree-sitter-rust", "src"].iter().collect();
-
-    cc::Build::new()
-        .include(&dir)
-        .file(dir.join("parser.c"))
-        .file(dir.join("scanner.c"))
-        .warnings(false)
-        .compile("tree-sitter-rust");
-}
\ No newline at end of file
+    for &(name, scanner) in GRAMMARS.iter() {
+        let dir: PathBuf = [name, "src"].iter().collect();
+
+        let mut build = cc::Build::new();
+        build
+            .include(&dir)
+            .file(dir.join("parser.c"))
+            .warnings(false);
+
+        match scanner {
+            // C++ scanners need to be compiled separately, since `parser.c` is C.
+            Some(scanner) if scanner.ends_with(".cc") => {
+                cc::Build::new()
+                    .cpp(true)
+                    .include(&dir)
+                    .file(dir.join(scanner))
+                    .warnings(false)
+                    .compile(&format!("{}-scanner", name));
+            }
+            Some(scanner) => {
+                build.file(dir.join(scanner));
+            }
+            None => {}
+        }
+
+        build.compile(name);
+    }
+}
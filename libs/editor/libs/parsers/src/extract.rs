@@ -0,0 +1,1028 @@
+//! Refactorings that move the selected code somewhere else, and refer to it from where it
+//! was. These work on Rust parse trees.
+use edit::TAB_STR;
+use macros::some_or;
+use tree_sitter::{Node, Tree};
+
+use std::collections::HashMap;
+
+/// A pair of byte indexes, from the start of a range to the end of it.
+type Range = (usize, usize);
+
+/// The name given to extracted functions. The user is expected to rename them.
+pub const FUNCTION_NAME: &str = "funcName";
+
+/// The name given to extracted variables. The user is expected to rename them.
+pub const VARIABLE_NAME: &str = "varName";
+
+/// Written in place of the types of an extracted function that we cannot tell. This does
+/// not name a type, so the code does not compile until the user writes the real one.
+pub const TYPE_PLACEHOLDER: &str = "TypeName";
+
+/// A variable that the selected code uses from, or provides to, the code around it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Variable {
+    pub name: String,
+    /// The type the variable was declared with, if it was declared with one.
+    pub type_text: Option<String>,
+    pub is_mut: bool,
+}
+
+/// What the extracted function gives back to where it is called.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Returns {
+    /// The value of the selected expression, which has the given type, if we can tell.
+    Value(Option<String>),
+    /// The variables that the selected statements declare, that the code after them uses.
+    Variables(Vec<Variable>),
+}
+
+/// How to replace the selected code with a call to a new function that contains it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FunctionExtraction {
+    /// The bytes that the call goes in place of.
+    pub range: Range,
+    pub parameters: Vec<Variable>,
+    /// The names of the parameters that the selected code might change. These are
+    /// passed by mutable reference, so the changes are seen after the call.
+    pub mutable_references: Vec<String>,
+    pub returns: Returns,
+    /// The byte that the definition goes at. This is right after the item in the
+    /// outermost scope of the innermost module that the selected code is in.
+    pub definition_at: usize,
+    /// The indentation of that item.
+    pub indent: String,
+    /// The lines of the selected code, with the indentation they share removed, and the
+    /// `mutable_references` dereferenced.
+    body_lines: Vec<String>,
+}
+
+impl FunctionExtraction {
+    fn is_mutable_reference(&self, v: &Variable) -> bool {
+        self.mutable_references.contains(&v.name)
+    }
+
+    /// The code that goes in place of the selected code.
+    pub fn call(&self) -> String {
+        let call = format!(
+            "{}({})",
+            FUNCTION_NAME,
+            self.parameters.iter()
+                .map(|v| if self.is_mutable_reference(v) {
+                    format!("&mut {}", v.name)
+                } else {
+                    v.name.clone()
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        let binding = |v: &Variable| if v.is_mut {
+            format!("mut {}", v.name)
+        } else {
+            v.name.clone()
+        };
+
+        match &self.returns {
+            Returns::Value(_) => call,
+            Returns::Variables(variables) => match variables.as_slice() {
+                [] => format!("{};", call),
+                [variable] => format!("let {} = {};", binding(variable), call),
+                _ => format!(
+                    "let ({}) = {};",
+                    variables.iter().map(binding).collect::<Vec<_>>().join(", "),
+                    call
+                ),
+            },
+        }
+    }
+
+    /// The code that goes at `definition_at`, including the blank line separating it
+    /// from the item before it.
+    pub fn definition(&self) -> String {
+        fn type_of(v: &Variable) -> &str {
+            v.type_text.as_deref().unwrap_or(TYPE_PLACEHOLDER)
+        }
+
+        let parameters = self.parameters.iter()
+            .map(|v| if self.is_mutable_reference(v) {
+                format!("{}: &mut {}", v.name, type_of(v))
+            } else {
+                format!("{}: {}", v.name, type_of(v))
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let (return_type, tail) = match &self.returns {
+            Returns::Value(type_text) => (
+                Some(type_text.clone().unwrap_or_else(|| TYPE_PLACEHOLDER.to_owned())),
+                None,
+            ),
+            Returns::Variables(variables) => match variables.as_slice() {
+                [] => (None, None),
+                [variable] => (Some(type_of(variable).to_owned()), Some(variable.name.clone())),
+                _ => (
+                    Some(format!(
+                        "({})",
+                        variables.iter().map(type_of).collect::<Vec<_>>().join(", ")
+                    )),
+                    Some(format!(
+                        "({})",
+                        variables.iter()
+                            .map(|v| v.name.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )),
+                ),
+            },
+        };
+
+        let mut definition = format!("\n\n{}fn {}({})", self.indent, FUNCTION_NAME, parameters);
+        if let Some(return_type) = return_type {
+            definition.push_str(" -> ");
+            definition.push_str(&return_type);
+        }
+        definition.push_str(" {\n");
+
+        let body_indent = format!("{}{}", self.indent, TAB_STR);
+        for line in self.body_lines.iter().chain(tail.iter()) {
+            if !line.is_empty() {
+                definition.push_str(&body_indent);
+                definition.push_str(line);
+            }
+            definition.push('\n');
+        }
+
+        definition.push_str(&self.indent);
+        definition.push('}');
+
+        definition
+    }
+}
+
+/// Returns how to extract the code in `selection` into a new function. If the selection
+/// is not exactly some statements, or an expression, then the smallest expression that
+/// contains it is extracted. Returns `None` if there is no such expression, or if the
+/// code has a `return`, `?`, `break` or `continue` that leaves it, since those would
+/// leave the new function instead.
+pub fn function_extraction(
+    tree: &Tree,
+    text: &str,
+    selection: Range,
+) -> Option<FunctionExtraction> {
+    let selection = trimmed(text, selection)?;
+    let target = extraction_target(tree, selection)?;
+
+    let (first_node, range) = match target {
+        Target::Expression(node) => (node, (node.start_byte(), node.end_byte())),
+        Target::Statements(block, range) => (
+            statements(block).into_iter().find(|(_, r)| r.0 == range.0)?.0,
+            range,
+        ),
+    };
+
+    let nodes = nodes_within(&target, range);
+    if nodes.iter().any(|&node| jumps_out_of(node, range, text)) {
+        return None
+    }
+
+    let visible = visible_variables(first_node, range.0, text);
+
+    let mut parameters: Vec<Variable> = Vec::new();
+    // The places the selected code uses the parameters.
+    let mut parameter_uses: Vec<Node> = Vec::new();
+    // Variables declared inside the selected code, along with where they start to be
+    // visible from.
+    let mut declared: Vec<(&str, usize)> = Vec::new();
+    for node in nodes {
+        let name = some_or!(text.get(node.start_byte()..node.end_byte()), continue);
+        if is_binding(node, text) {
+            let let_end = ancestors(node)
+                .take_while(|a| a.start_byte() >= range.0)
+                .find(|a| a.kind() == "let_declaration")
+                .map(|a| a.end_byte());
+            declared.push((name, let_end.unwrap_or_else(|| node.end_byte())));
+        } else if is_use(node) {
+            let is_declared_inside = declared.iter()
+                .any(|&(n, from)| n == name && from <= node.start_byte());
+            if is_declared_inside {
+                continue
+            }
+
+            if !parameters.iter().any(|v| v.name == name) {
+                parameters.push(some_or!(visible.get(name), continue).clone());
+            }
+            parameter_uses.push(node);
+        }
+    }
+
+    // Variables that are not declared `mut` cannot be changed, except through interior
+    // mutability, or if they are mutable references already, and both of those work the
+    // same when the variable is passed by value.
+    let mutable_references: Vec<String> = parameters.iter()
+        .filter(|v| v.is_mut && parameter_uses.iter().any(|&u|
+            node_text(u, text) == v.name && might_mutate(u)
+        ))
+        .map(|v| v.name.clone())
+        .collect();
+
+    let mut body = text[range.0..range.1].to_owned();
+    // We go backwards, to keep the earlier indexes valid.
+    for &u in parameter_uses.iter().rev() {
+        if mutable_references.iter().any(|n| n == node_text(u, text))
+        && !is_auto_dereferenced(u) {
+            body.insert(u.start_byte() - range.0, '*');
+        }
+    }
+
+    let returns = match target {
+        Target::Expression(node) => Returns::Value(guess_type(node, text, &visible)),
+        Target::Statements(block, range) => {
+            let mut used_after = Vec::new();
+            for (statement, _) in statements(block).into_iter().filter(|(_, r)| r.0 >= range.1) {
+                for_each_descendant(statement, &mut |node| {
+                    if is_use(node) {
+                        used_after.push(&text[node.start_byte()..node.end_byte()]);
+                    }
+                });
+            }
+
+            let mut returned: Vec<Variable> = Vec::new();
+            for (statement, _) in statements(block)
+                .into_iter()
+                .filter(|(_, r)| range.0 <= r.0 && r.1 <= range.1)
+                .filter(|(s, _)| s.kind() == "let_declaration")
+            {
+                for variable in let_variables(statement, text) {
+                    if !used_after.contains(&variable.name.as_str()) {
+                        continue
+                    }
+                    match returned.iter_mut().find(|v| v.name == variable.name) {
+                        Some(v) => *v = variable,
+                        None => returned.push(variable),
+                    }
+                }
+            }
+
+            Returns::Variables(returned)
+        },
+    };
+
+    let (definition_at, indent) = definition_spot(first_node, text);
+
+    Some(FunctionExtraction {
+        range,
+        parameters,
+        mutable_references,
+        returns,
+        definition_at,
+        indent,
+        body_lines: unindented(&body, line_indent(text, range.0)),
+    })
+}
+
+/// Whether `node` is a `return`, `?`, `break` or `continue` that goes somewhere outside
+/// of `range`.
+fn jumps_out_of(node: Node, range: Range, text: &str) -> bool {
+    let is_function = |n: &Node| matches!(
+        n.kind(),
+        "function_item" | "closure_expression" | "async_block"
+    );
+    let is_loop = |n: &Node| matches!(
+        n.kind(),
+        "loop_expression" | "while_expression" | "while_let_expression" | "for_expression"
+    );
+    let label = |n: Node| {
+        let mut cursor = n.walk();
+        let label = n.children(&mut cursor).find(|c| c.kind() == "loop_label");
+        label.map(|l| node_text(l, text))
+    };
+
+    let destination = match node.kind() {
+        "return_expression" | "try_expression" => ancestors(node).find(is_function),
+        "break_expression" | "continue_expression" => {
+            let node_label = label(node);
+            ancestors(node)
+                .take_while(|a| !is_function(a))
+                .find(|a| is_loop(a) && (node_label.is_none() || label(*a) == node_label))
+        },
+        _ => return false,
+    };
+
+    destination
+        .map(|d| d.start_byte() < range.0 || range.1 < d.end_byte())
+        .unwrap_or(true)
+}
+
+/// How to replace the selected expression, and maybe other copies of it, with a variable
+/// that is declared before the statement they are in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VariableExtraction {
+    /// The bytes that the variable goes in place of, in order. This includes the selected
+    /// expression.
+    pub occurrences: Vec<Range>,
+    /// The byte that the declaration goes at. This is the start of the statement that
+    /// the first occurrence is in.
+    pub declaration_at: usize,
+    /// The indentation of that statement.
+    pub indent: String,
+    /// The lines of the selected expression, with the indentation they share removed.
+    value_lines: Vec<String>,
+}
+
+impl VariableExtraction {
+    /// The code that goes at `declaration_at`, including the indentation for the statement
+    /// that then follows it.
+    pub fn declaration(&self) -> String {
+        format!(
+            "let {} = {};\n{}",
+            VARIABLE_NAME,
+            self.value_lines.join(&format!("\n{}", self.indent)),
+            self.indent,
+        )
+    }
+}
+
+/// Returns how to extract the expression in `selection` into a new variable. If the
+/// selection is not exactly an expression, then the smallest expression that contains it
+/// is extracted. If `every_occurrence` is true, then the other expressions in the same
+/// block that have the same text, and use the same variables, without any of those
+/// possibly changing in between, are replaced too. Returns `None` if there is no such
+/// expression, if it uses variables that are not visible outside the statement it is
+/// in, like closure parameters, or if it is not always evaluated exactly once when that
+/// statement is, like on the right of a `&&`, in a `match` arm, or in a closure.
+pub fn variable_extraction(
+    tree: &Tree,
+    text: &str,
+    selection: Range,
+    every_occurrence: bool,
+) -> Option<VariableExtraction> {
+    let (start, end) = trimmed(text, selection)?;
+
+    let mut expression = tree.root_node().descendant_for_byte_range(start, end)?;
+    while !is_expression(expression) {
+        expression = expression.parent()?;
+    }
+
+    let statement = enclosing_statement(expression)?;
+    if is_evaluated_conditionally(expression, statement) {
+        return None
+    }
+    let block = statement.parent()?;
+
+    let mut occurrences = vec![expression];
+    if every_occurrence {
+        let value = node_text(expression, text);
+        let mut candidates = Vec::new();
+        for_each_descendant(block, &mut |node| {
+            if node != expression
+            && node_text(node, text) == value
+            && is_expression(node)
+            && !is_assigned_to(node) {
+                candidates.push(node);
+            }
+        });
+
+        // The value could be different on the other side of a change to a variable it
+        // uses, including a change in the selected expression itself.
+        let changes = changes_to_variables_of(expression, block, text);
+        candidates.retain(|candidate| {
+            let start = std::cmp::min(candidate.start_byte(), expression.start_byte());
+            let end = std::cmp::max(candidate.end_byte(), expression.end_byte());
+            !changes.iter().any(|&change| start <= change && change < end)
+        });
+
+        for candidate in candidates {
+            let overlaps = occurrences.iter().any(|o|
+                candidate.start_byte() < o.end_byte() && o.start_byte() < candidate.end_byte()
+            );
+            if !overlaps {
+                occurrences.push(candidate);
+            }
+        }
+        occurrences.sort_by_key(|o| o.start_byte());
+    }
+
+    // The declaration goes before the first occurrence, but if the selected expression
+    // would not mean the same thing there, or the first occurrence might not have been
+    // evaluated there, then we leave out the occurrences before it.
+    let (statement, at_statement) = loop {
+        let statement = std::iter::once(occurrences[0])
+            .chain(ancestors(occurrences[0]))
+            .find(|n| n.parent() == Some(block))?;
+        let at_statement = visible_variables(statement, statement.start_byte(), text);
+        if uses_the_same_variables(expression, text, &at_statement)
+        && !is_evaluated_conditionally(occurrences[0], statement) {
+            break (statement, at_statement)
+        }
+        if occurrences[0] == expression {
+            return None
+        }
+        occurrences.remove(0);
+    };
+
+    occurrences.retain(|&o| uses_the_same_variables(o, text, &at_statement));
+
+    Some(VariableExtraction {
+        occurrences: occurrences.iter().map(|o| (o.start_byte(), o.end_byte())).collect(),
+        declaration_at: statement.start_byte(),
+        indent: line_indent(text, statement.start_byte()).to_owned(),
+        value_lines: unindented_lines(text, (expression.start_byte(), expression.end_byte())),
+    })
+}
+
+/// Whether each variable that `node` uses is the same as the one with that name in
+/// `variables`. If it is, then `node` means the same thing where those are visible.
+fn uses_the_same_variables(
+    node: Node,
+    text: &str,
+    variables: &HashMap<String, Variable>,
+) -> bool {
+    let visible = visible_variables(node, node.start_byte(), text);
+
+    let mut uses_the_same = true;
+    for_each_descendant(node, &mut |n| {
+        if is_use(n) {
+            let name = node_text(n, text);
+            if visible.get(name) != variables.get(name) {
+                uses_the_same = false;
+            }
+        }
+    });
+
+    uses_the_same
+}
+
+/// Whether `node` might be evaluated a different number of times than `statement`,
+/// which it is inside of, or is. This is the case on the right of a `&&` or a `||`, in
+/// the branches of an `if` or a `match`, in loops and in closures.
+fn is_evaluated_conditionally(node: Node, statement: Node) -> bool {
+    let mut child = node;
+    for ancestor in ancestors(node) {
+        if child == statement {
+            break
+        }
+        let field = |name| ancestor.child_by_field_name(name);
+
+        let is_conditional = match ancestor.kind() {
+            "binary_expression" => {
+                field("right") == Some(child)
+                && field("operator").map(|o| matches!(o.kind(), "&&" | "||")).unwrap_or(false)
+            },
+            "if_expression" => field("condition") != Some(child),
+            "if_let_expression" | "for_expression" => field("value") != Some(child),
+            "while_expression"
+            | "while_let_expression"
+            | "loop_expression"
+            | "match_arm"
+            | "last_match_arm"
+            | "closure_expression"
+            | "async_block" => true,
+            _ => false,
+        };
+        if is_conditional {
+            return true
+        }
+
+        child = ancestor;
+    }
+
+    false
+}
+
+/// The bytes in `block` at which the variables that `expression` uses might be changed.
+fn changes_to_variables_of(expression: Node, block: Node, text: &str) -> Vec<usize> {
+    let mut names = Vec::new();
+    for_each_descendant(expression, &mut |n| {
+        if is_use(n) {
+            names.push(node_text(n, text));
+        }
+    });
+
+    let mut changes = Vec::new();
+    for_each_descendant(block, &mut |n| {
+        let name = node_text(n, text);
+        if !(is_use(n) && names.contains(&name) && might_mutate(n)) {
+            return
+        }
+
+        // A method call only changes variables that are declared `mut`, or are mutable
+        // references. If we cannot tell which variable it is, we assume it might change.
+        let can_change = visible_variables(n, n.start_byte(), text)
+            .get(name)
+            .map(|v| v.is_mut || v.type_text.as_deref().unwrap_or("").starts_with("&mut"))
+            .unwrap_or(true);
+        if can_change {
+            changes.push(n.start_byte());
+        }
+    });
+
+    changes
+}
+
+/// The innermost statement in a block that `node` is inside of, or is.
+fn enclosing_statement(node: Node) -> Option<Node> {
+    std::iter::once(node)
+        .chain(ancestors(node))
+        .find(|n| n.parent().map(|p| p.kind() == "block").unwrap_or(false))
+}
+
+/// Whether the variable used at `node` might be changed there, by assigning to it or to a
+/// part of it, by borrowing it mutably, or by calling a method on it, since the method
+/// might take `&mut self`.
+fn might_mutate(node: Node) -> bool {
+    let mut place = node;
+    loop {
+        let parent = some_or!(place.parent(), return false);
+        match parent.kind() {
+            "field_expression" if parent.child_by_field_name("value") == Some(place) => {
+                let is_method_call = parent.parent()
+                    .filter(|p| p.kind() == "call_expression")
+                    .and_then(|p| p.child_by_field_name("function"))
+                    == Some(parent);
+                if is_method_call {
+                    return true
+                }
+            },
+            "index_expression" if parent.named_child(0) == Some(place) => {},
+            "unary_expression" if parent.child(0).map(|c| c.kind() == "*").unwrap_or(false) => {},
+            "parenthesized_expression" => {},
+            "assignment_expression" | "compound_assignment_expr" => {
+                return parent.child_by_field_name("left") == Some(place)
+            },
+            "reference_expression" => return has_mutable_specifier(parent),
+            _ => return false,
+        }
+        place = parent;
+    }
+}
+
+/// Whether the variable used at `node` would be dereferenced automatically if it was a
+/// reference, so that it does not need a `*` in front of it.
+fn is_auto_dereferenced(node: Node) -> bool {
+    let parent = some_or!(node.parent(), return false);
+
+    match parent.kind() {
+        "field_expression" => parent.child_by_field_name("value") == Some(node),
+        "index_expression" => parent.named_child(0) == Some(node),
+        // Macro arguments are not parsed as expressions, so we go by the next token.
+        "token_tree" => node.next_sibling()
+            .map(|s| matches!(s.kind(), "." | "["))
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+/// Whether `node` is the place an assignment stores its value into.
+fn is_assigned_to(node: Node) -> bool {
+    node.parent()
+        .filter(|p| matches!(p.kind(), "assignment_expression" | "compound_assignment_expr"))
+        .and_then(|p| p.child_by_field_name("left"))
+        == Some(node)
+}
+
+/// Returns `selection` without any whitespace at either end.
+fn trimmed(text: &str, (start, end): Range) -> Option<Range> {
+    let selected = text.get(start..end)?;
+    let start = start + (selected.len() - selected.trim_start().len());
+    let end = std::cmp::max(start, end - (selected.len() - selected.trim_end().len()));
+
+    Some((start, end))
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Target<'tree> {
+    /// An expression whose value is used.
+    Expression(Node<'tree>),
+    /// Some statements in a block, and the range they cover.
+    Statements(Node<'tree>, Range),
+}
+
+fn extraction_target(tree: &Tree, (start, end): Range) -> Option<Target> {
+    let mut node = tree.root_node().descendant_for_byte_range(start, end)?;
+
+    if start < end {
+        let block = match node.kind() {
+            "block" => Some(node),
+            "let_declaration" => node.parent().filter(|p| p.kind() == "block"),
+            _ => None,
+        };
+
+        if let Some(block) = block {
+            let selected: Vec<_> = statements(block)
+                .into_iter()
+                .filter(|(_, r)| start <= r.0 && r.1 <= end)
+                .collect();
+
+            match (selected.first(), selected.last()) {
+                (Some((_, first)), Some((last_node, last)))
+                if first.0 == start && last.1 == end => {
+                    if !is_tail(*last_node) {
+                        return Some(Target::Statements(block, (start, end)));
+                    } else if selected.len() == 1 {
+                        return Some(Target::Expression(*last_node));
+                    }
+                },
+                _ => {}
+            }
+        }
+    }
+
+    loop {
+        if node.kind() == "let_declaration" {
+            let block = node.parent().filter(|p| p.kind() == "block")?;
+            let range = statements(block)
+                .into_iter()
+                .find(|(statement, _)| *statement == node)?
+                .1;
+            return Some(Target::Statements(block, range));
+        }
+
+        if is_expression(node) {
+            let parent = node.parent();
+            return Some(match parent {
+                Some(block) if block.kind() == "block" && !is_tail(node) => {
+                    let range = statements(block)
+                        .into_iter()
+                        .find(|(statement, _)| *statement == node)?
+                        .1;
+                    Target::Statements(block, range)
+                },
+                _ => Target::Expression(node),
+            });
+        }
+
+        node = node.parent()?;
+    }
+}
+
+/// The statements in `block`, along with the range each covers, including any
+/// semicolon after them.
+fn statements(block: Node) -> Vec<(Node, Range)> {
+    let mut statements: Vec<(Node, Range)> = Vec::new();
+
+    let mut cursor = block.walk();
+    for child in block.children(&mut cursor) {
+        if child.kind() == ";" {
+            if let Some((_, range)) = statements.last_mut() {
+                range.1 = child.end_byte();
+            }
+        } else if child.is_named() {
+            statements.push((child, (child.start_byte(), child.end_byte())));
+        }
+    }
+
+    statements
+}
+
+/// Whether `node` is the expression at the end of a block, that gives the block its value.
+/// Expressions that always have the value `()`, like loops, do not count, since they are
+/// written as statements.
+fn is_tail(node: Node) -> bool {
+    let is_always_unit = match node.kind() {
+        "for_expression" | "while_expression" | "while_let_expression" => true,
+        "if_expression" | "if_let_expression" =>
+            node.child_by_field_name("alternative").is_none(),
+        _ => false,
+    };
+
+    node.parent().map(|p| p.kind() == "block").unwrap_or(false)
+    && is_expression(node)
+    && !is_always_unit
+    && node.next_sibling().map(|s| s.kind() == "}").unwrap_or(true)
+}
+
+fn is_expression(node: Node) -> bool {
+    let kind = node.kind();
+    let is_expression_kind = kind.ends_with("_expression")
+        || kind.ends_with("_literal")
+        || matches!(
+            kind,
+            "compound_assignment_expr"
+            | "macro_invocation"
+            | "block"
+            | "async_block"
+            | "const_block"
+            | "unsafe_block"
+            | "identifier"
+            | "scoped_identifier"
+            | "generic_function"
+            | "self"
+        );
+
+    if !is_expression_kind || is_named_by_parent(node) {
+        return false
+    }
+
+    match node.parent() {
+        Some(parent) => {
+            let parent_kind = parent.kind();
+            !(parent_kind == "token_tree" || parent_kind.ends_with("pattern"))
+            // These blocks are part of the syntax of their parent, so a call cannot
+            // replace them.
+            && !(kind == "block" && matches!(
+                parent_kind,
+                "function_item"
+                | "if_expression"
+                | "if_let_expression"
+                | "else_clause"
+                | "while_expression"
+                | "while_let_expression"
+                | "loop_expression"
+                | "for_expression"
+                | "async_block"
+                | "const_block"
+                | "unsafe_block"
+            ))
+        }
+        None => true,
+    }
+}
+
+/// Whether `node` is a part of its parent that is not an expression, even though it
+/// might look like one, like the name of a function, or a variable being declared.
+fn is_named_by_parent(node: Node) -> bool {
+    let parent = some_or!(node.parent(), return false);
+
+    is_in_pattern(node)
+    || ["name", "type", "label", "path", "macro", "field"].iter()
+        .any(|&field| parent.child_by_field_name(field) == Some(node))
+}
+
+/// Whether `node` is a name in a pattern.
+fn is_in_pattern(node: Node) -> bool {
+    if !matches!(node.kind(), "identifier" | "shorthand_field_identifier") {
+        return false
+    }
+    let parent = some_or!(node.parent(), return false);
+
+    (
+        parent.kind().ends_with("pattern")
+        || parent.kind() == "closure_parameters"
+        || parent.child_by_field_name("pattern") == Some(node)
+    )
+    && parent.child_by_field_name("type") != Some(node)
+}
+
+/// Whether `node` declares a variable.
+fn is_binding(node: Node, text: &str) -> bool {
+    // Capitalized names in patterns are almost always enum variants or constants.
+    is_in_pattern(node)
+    && !node_text(node, text).starts_with(|c: char| c.is_uppercase())
+}
+
+/// Whether `node` refers to a variable.
+fn is_use(node: Node) -> bool {
+    node.kind() == "identifier" && !is_named_by_parent(node)
+}
+
+fn ancestors(node: Node) -> impl Iterator<Item = Node> {
+    std::iter::successors(node.parent(), |n| n.parent())
+}
+
+fn for_each_descendant<'tree>(node: Node<'tree>, f: &mut impl FnMut(Node<'tree>)) {
+    f(node);
+
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        for_each_descendant(child, f);
+    }
+}
+
+/// The nodes of the target that are within `range`, in order.
+fn nodes_within<'tree>(target: &Target<'tree>, range: Range) -> Vec<Node<'tree>> {
+    let mut nodes = Vec::new();
+
+    match *target {
+        Target::Expression(node) => {
+            for_each_descendant(node, &mut |n| nodes.push(n));
+        },
+        Target::Statements(block, _) => {
+            for (statement, r) in statements(block) {
+                if range.0 <= r.0 && r.1 <= range.1 {
+                    for_each_descendant(statement, &mut |n| nodes.push(n));
+                }
+            }
+        },
+    }
+
+    nodes
+}
+
+/// The variables that are visible from `start`, which is the start of `node`, by name.
+fn visible_variables(node: Node, start: usize, text: &str) -> HashMap<String, Variable> {
+    let mut variables: HashMap<String, Variable> = HashMap::new();
+    // We go from the innermost scope outwards, so the first variable with a given name
+    // that we see shadows the rest.
+    let mut add = |vs: Vec<Variable>| {
+        for v in vs {
+            variables.entry(v.name.clone()).or_insert(v);
+        }
+    };
+
+    let mut child = node;
+    for ancestor in ancestors(node) {
+        let field = |name| ancestor.child_by_field_name(name);
+
+        match ancestor.kind() {
+            "block" => {
+                let mut cursor = ancestor.walk();
+                let lets: Vec<_> = ancestor.named_children(&mut cursor)
+                    .filter(|c| c.kind() == "let_declaration" && c.end_byte() <= start)
+                    .collect();
+                for l in lets.into_iter().rev() {
+                    add(let_variables(l, text));
+                }
+            },
+            "function_item" | "closure_expression" => {
+                if field("parameters") != Some(child) {
+                    if let Some(parameters) = field("parameters") {
+                        let mut cursor = parameters.walk();
+                        for p in parameters.named_children(&mut cursor) {
+                            add(parameter_variables(p, text));
+                        }
+                    }
+                }
+
+                // The variables of the functions around a function are not visible in it.
+                if ancestor.kind() == "function_item" {
+                    break
+                }
+            },
+            "for_expression" | "if_let_expression" | "while_let_expression" => {
+                if field("value") != Some(child) && field("pattern") != Some(child) {
+                    if let Some(pattern) = field("pattern") {
+                        add(pattern_variables(pattern, None, false, text));
+                    }
+                }
+            },
+            "match_arm" => {
+                if field("pattern") != Some(child) {
+                    if let Some(pattern) = field("pattern") {
+                        add(pattern_variables(pattern, None, false, text));
+                    }
+                }
+            },
+            _ => {}
+        }
+
+        child = ancestor;
+    }
+
+    variables
+}
+
+fn let_variables(let_declaration: Node, text: &str) -> Vec<Variable> {
+    let pattern = some_or!(let_declaration.child_by_field_name("pattern"), return Vec::new());
+
+    pattern_variables(
+        pattern,
+        let_declaration.child_by_field_name("type").map(|t| node_text(t, text).to_owned()),
+        has_mutable_specifier(let_declaration),
+        text,
+    )
+}
+
+fn parameter_variables(parameter: Node, text: &str) -> Vec<Variable> {
+    match parameter.kind() {
+        "parameter" => {
+            let pattern = some_or!(parameter.child_by_field_name("pattern"), return Vec::new());
+
+            pattern_variables(
+                pattern,
+                parameter.child_by_field_name("type").map(|t| node_text(t, text).to_owned()),
+                has_mutable_specifier(parameter),
+                text,
+            )
+        },
+        _ => pattern_variables(parameter, None, false, text),
+    }
+}
+
+fn has_mutable_specifier(node: Node) -> bool {
+    let mut cursor = node.walk();
+    let has = node.children(&mut cursor).any(|c| c.kind() == "mutable_specifier");
+    has
+}
+
+/// The variables that `pattern` declares. If the pattern is just a name, then that
+/// variable has the passed type and mutability.
+fn pattern_variables(
+    pattern: Node,
+    type_text: Option<String>,
+    is_mut: bool,
+    text: &str,
+) -> Vec<Variable> {
+    if pattern.kind() == "identifier" {
+        return vec![Variable {
+            name: node_text(pattern, text).to_owned(),
+            type_text,
+            is_mut,
+        }];
+    }
+
+    let mut variables = Vec::new();
+    for_each_descendant(pattern, &mut |node| {
+        if is_binding(node, text) {
+            variables.push(Variable {
+                name: node_text(node, text).to_owned(),
+                type_text: None,
+                is_mut: node.parent().map(|p| p.kind() == "mut_pattern").unwrap_or(false),
+            });
+        }
+    });
+
+    variables
+}
+
+fn node_text<'text>(node: Node, text: &'text str) -> &'text str {
+    text.get(node.start_byte()..node.end_byte()).unwrap_or_default()
+}
+
+/// A guess at the type of the expression, for the cases where that is easy.
+fn guess_type(node: Node, text: &str, visible: &HashMap<String, Variable>) -> Option<String> {
+    let node_text = node_text(node, text);
+
+    let suffix = |suffixes: &[&str]| suffixes.iter()
+        .find(|s| node_text.ends_with(*s))
+        .map(|s| (*s).to_owned());
+
+    match node.kind() {
+        "integer_literal" => suffix(&[
+            "u8", "u16", "u32", "u64", "u128", "usize",
+            "i8", "i16", "i32", "i64", "i128", "isize",
+        ]).or_else(|| Some("i32".to_owned())),
+        "float_literal" => suffix(&["f32", "f64"]).or_else(|| Some("f64".to_owned())),
+        "string_literal" | "raw_string_literal" => Some("&'static str".to_owned()),
+        "char_literal" => Some("char".to_owned()),
+        "boolean_literal" => Some("bool".to_owned()),
+        "binary_expression" => {
+            let operator = node.child_by_field_name("operator")?;
+            if matches!(
+                operator.kind(),
+                "==" | "!=" | "<" | "<=" | ">" | ">=" | "&&" | "||"
+            ) {
+                Some("bool".to_owned())
+            } else {
+                None
+            }
+        },
+        "parenthesized_expression" => guess_type(node.named_child(0)?, text, visible),
+        "identifier" => visible.get(node_text)?.type_text.clone(),
+        _ => None,
+    }
+}
+
+/// Where the definition of a function extracted from the code at `node` should go, and
+/// the indentation it should have.
+fn definition_spot(node: Node, text: &str) -> (usize, String) {
+    let is_module = |n: Node| n.kind() == "source_file"
+        || (
+            n.kind() == "declaration_list"
+            && n.parent().map(|p| p.kind() == "mod_item").unwrap_or(false)
+        );
+
+    let mut item = node;
+    while let Some(parent) = item.parent() {
+        if is_module(parent) {
+            break
+        }
+        item = parent;
+    }
+
+    (item.end_byte(), line_indent(text, item.start_byte()).to_owned())
+}
+
+/// The whitespace at the start of the line that `byte` is on, up to `byte`.
+fn line_indent(text: &str, byte: usize) -> &str {
+    let before = &text[..byte];
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let line = &before[line_start..];
+
+    &line[..line.len() - line.trim_start().len()]
+}
+
+/// The lines of the text in `range`, with the indentation of the line it starts on
+/// removed from each line after the first.
+fn unindented_lines(text: &str, range: Range) -> Vec<String> {
+    unindented(&text[range.0..range.1], line_indent(text, range.0))
+}
+
+/// The lines of `code`, with `indent` removed from each line after the first.
+fn unindented(code: &str, indent: &str) -> Vec<String> {
+    code.lines()
+        .enumerate()
+        .map(|(i, line)| {
+            if i == 0 {
+                line.to_owned()
+            } else if line.trim().is_empty() {
+                String::new()
+            } else {
+                line.strip_prefix(indent).unwrap_or_else(|| line.trim_start()).to_owned()
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests;
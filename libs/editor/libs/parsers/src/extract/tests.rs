@@ -0,0 +1,501 @@
+use super::*;
+use tree_sitter::Parser;
+
+fn rust_tree(code: &str) -> Tree {
+    let mut parser = Parser::new();
+    parser.set_language(unsafe { crate::tree_sitter_rust() }).unwrap();
+
+    parser.parse(code, None).expect("parse failed")
+}
+
+/// Extracts the first occurrence of `selected` in `code`.
+fn extraction(code: &str, selected: &str) -> FunctionExtraction {
+    let start = code.find(selected).expect("selected text not found");
+
+    function_extraction(&rust_tree(code), code, (start, start + selected.len()))
+        .expect("no extraction")
+}
+
+/// Returns `code` with the call and the definition from `extraction` in it.
+fn extracted(code: &str, selected: &str) -> String {
+    let extraction = extraction(code, selected);
+
+    let mut output = code.to_owned();
+    // The definition goes after the call, so we insert it first, to keep the
+    // call's range valid.
+    output.insert_str(extraction.definition_at, &extraction.definition());
+    output.replace_range(extraction.range.0..extraction.range.1, &extraction.call());
+
+    output
+}
+
+fn var(name: &str, type_text: Option<&str>) -> Variable {
+    Variable {
+        name: name.to_owned(),
+        type_text: type_text.map(str::to_owned),
+        is_mut: false,
+    }
+}
+
+#[test]
+fn extracting_statements_that_take_0_params_and_return_nothing_produces_the_expected_call() {
+    let extraction = extraction("fn f() {\n    println!(\"hi\");\n    g();\n}", "println!(\"hi\");");
+
+    assert_eq!(extraction.parameters, vec![]);
+    assert_eq!(extraction.returns, Returns::Variables(vec![]));
+    assert_eq!(extraction.call(), "funcName();");
+}
+
+#[test]
+fn extracting_statements_that_take_n_params_and_return_nothing_produces_the_expected_call() {
+    let code = "fn f(a: u8, b: &str) {\n    let c = 1;\n    println!(\"{} {} {}\", a, b, c);\n}";
+    let extraction = extraction(code, "println!(\"{} {} {}\", a, b, c);");
+
+    assert_eq!(
+        extraction.parameters,
+        vec![var("a", Some("u8")), var("b", Some("&str")), var("c", None)]
+    );
+    assert_eq!(extraction.returns, Returns::Variables(vec![]));
+    assert_eq!(extraction.call(), "funcName(a, b, c);");
+}
+
+#[test]
+fn extracting_statements_that_take_0_params_and_return_m_things_produces_the_expected_call() {
+    let one = extraction(
+        "fn f() {\n    let x: u32 = 1;\n    g(x);\n}",
+        "let x: u32 = 1;"
+    );
+
+    assert_eq!(one.parameters, vec![]);
+    assert_eq!(one.returns, Returns::Variables(vec![var("x", Some("u32"))]));
+    assert_eq!(one.call(), "let x = funcName();");
+
+    let two = extraction(
+        "fn f() {\n    let x = 1;\n    let mut y = 2;\n    y += x;\n}",
+        "let x = 1;\n    let mut y = 2;"
+    );
+
+    assert_eq!(two.parameters, vec![]);
+    assert_eq!(
+        two.returns,
+        Returns::Variables(vec![var("x", None), Variable { is_mut: true, ..var("y", None) }])
+    );
+    assert_eq!(two.call(), "let (x, mut y) = funcName();");
+}
+
+#[test]
+fn extracting_an_expression_that_takes_0_params_produces_the_expected_call() {
+    let extraction = extraction("fn f() -> u8 {\n    g(1 + 2)\n}", "1 + 2");
+
+    assert_eq!(extraction.parameters, vec![]);
+    assert_eq!(extraction.returns, Returns::Value(None));
+    assert_eq!(extraction.call(), "funcName()");
+}
+
+#[test]
+fn extracting_statements_that_take_n_params_and_return_m_things_produces_the_expected_call() {
+    let code = "fn f(a: i64, b: i64) {\n    let sum = a + b;\n    let product = a * b;\n    g(sum, product);\n}";
+    let extraction = extraction(code, "let sum = a + b;\n    let product = a * b;");
+
+    assert_eq!(extraction.parameters, vec![var("a", Some("i64")), var("b", Some("i64"))]);
+    assert_eq!(
+        extraction.returns,
+        Returns::Variables(vec![var("sum", None), var("product", None)])
+    );
+    assert_eq!(extraction.call(), "let (sum, product) = funcName(a, b);");
+}
+
+#[test]
+fn extracting_an_expression_that_takes_n_params_produces_the_expected_call() {
+    let extraction = extraction("fn f(a: u8, b: u8) -> bool {\n    a < b\n}", "a < b");
+
+    assert_eq!(extraction.parameters, vec![var("a", Some("u8")), var("b", Some("u8"))]);
+    assert_eq!(extraction.returns, Returns::Value(Some("bool".to_owned())));
+    assert_eq!(extraction.call(), "funcName(a, b)");
+}
+
+#[test]
+fn extracting_statements_that_take_0_params_and_return_nothing_produces_the_expected_definition() {
+    assert_eq!(
+        extracted("fn f() {\n    println!(\"hi\");\n    g();\n}", "println!(\"hi\");"),
+        "fn f() {\n    funcName();\n    g();\n}\n\nfn funcName() {\n    println!(\"hi\");\n}"
+    );
+}
+
+#[test]
+fn extracting_statements_that_take_n_params_and_return_nothing_produces_the_expected_definition() {
+    assert_eq!(
+        extracted(
+            "fn f(a: u8, b: &str) {\n    if a > 0 {\n        println!(\"{}\", b);\n    }\n}",
+            "if a > 0 {\n        println!(\"{}\", b);\n    }"
+        ),
+        "fn f(a: u8, b: &str) {\n    funcName(a, b);\n}\n\nfn funcName(a: u8, b: &str) {\n    if a > 0 {\n        println!(\"{}\", b);\n    }\n}"
+    );
+}
+
+#[test]
+fn extracting_statements_that_take_0_params_and_return_m_things_produces_the_expected_definition() {
+    assert_eq!(
+        extracted(
+            "fn f() {\n    let x: u32 = 1;\n    let y: u32 = 2;\n    g(x, y);\n}",
+            "let x: u32 = 1;\n    let y: u32 = 2;"
+        ),
+        "fn f() {\n    let (x, y) = funcName();\n    g(x, y);\n}\n\nfn funcName() -> (u32, u32) {\n    let x: u32 = 1;\n    let y: u32 = 2;\n    (x, y)\n}"
+    );
+}
+
+#[test]
+fn extracting_statements_that_take_n_params_and_return_m_things_produces_the_expected_definition() {
+    assert_eq!(
+        extracted(
+            "fn f(a: i64) {\n    let b = 2;\n    let sum: i64 = a + b;\n    g(sum);\n}",
+            "let sum: i64 = a + b;"
+        ),
+        "fn f(a: i64) {\n    let b = 2;\n    let sum = funcName(a, b);\n    g(sum);\n}\n\nfn funcName(a: i64, b: TypeName) -> i64 {\n    let sum: i64 = a + b;\n    sum\n}"
+    );
+}
+
+#[test]
+fn extracting_an_expression_produces_the_expected_definition() {
+    assert_eq!(
+        extracted("fn f(a: u8, b: u8) -> bool {\n    a < b\n}", "a < b"),
+        "fn f(a: u8, b: u8) -> bool {\n    funcName(a, b)\n}\n\nfn funcName(a: u8, b: u8) -> bool {\n    a < b\n}"
+    );
+}
+
+#[test]
+fn extracting_a_partial_selection_extracts_the_smallest_expression_containing_it() {
+    let code = "fn f(a: u8) -> u8 {\n    g(a * 3) + 1\n}";
+    // Just part of the `3` and the `)` after it.
+    let start = code.find("3)").unwrap();
+
+    let extraction = function_extraction(&rust_tree(code), code, (start, start + 2))
+        .unwrap();
+
+    assert_eq!(&code[extraction.range.0..extraction.range.1], "g(a * 3)");
+    assert_eq!(extraction.call(), "funcName(a)");
+}
+
+#[test]
+fn extracting_the_variable_being_declared_extracts_the_whole_declaration() {
+    let code = "fn f() {\n    let x = 1;\n    g(x);\n}";
+    let start = code.find("x =").unwrap();
+
+    let extraction = function_extraction(&rust_tree(code), code, (start, start + 1))
+        .unwrap();
+
+    assert_eq!(&code[extraction.range.0..extraction.range.1], "let x = 1;");
+    assert_eq!(extraction.call(), "let x = funcName();");
+}
+
+#[test]
+fn extracting_variables_declared_and_used_only_inside_does_not_return_them() {
+    let extraction = extraction(
+        "fn f() {\n    let x = 1;\n    g(x);\n    h();\n}",
+        "let x = 1;\n    g(x);"
+    );
+
+    assert_eq!(extraction.parameters, vec![]);
+    assert_eq!(extraction.returns, Returns::Variables(vec![]));
+}
+
+#[test]
+fn extracting_code_that_uses_closure_and_pattern_variables_passes_them_as_params() {
+    let extraction = extraction(
+        "fn f(v: Vec<(u8, u8)>) {\n    for (a, b) in v {\n        let c = |d| a + b + d;\n        c(1);\n    }\n}",
+        "a + b + d"
+    );
+
+    // `d` is declared by the closure, around the selected expression.
+    assert_eq!(extraction.parameters, vec![var("a", None), var("b", None), var("d", None)]);
+}
+
+#[test]
+fn extracting_code_does_not_pass_the_variables_of_an_outer_function_as_params() {
+    let extraction = extraction(
+        "fn f(a: u8) {\n    fn g() {\n        h(a);\n    }\n}",
+        "h(a);"
+    );
+
+    assert_eq!(extraction.parameters, vec![]);
+}
+
+#[test]
+fn extracting_code_does_not_pass_enum_variants_or_functions_as_params() {
+    let extraction = extraction(
+        "fn f(o: Option<u8>) -> u8 {\n    match o {\n        Some(x) => g(x),\n        None => 0,\n    }\n}",
+        "match o {\n        Some(x) => g(x),\n        None => 0,\n    }"
+    );
+
+    assert_eq!(extraction.parameters, vec![var("o", Some("Option<u8>"))]);
+}
+
+#[test]
+fn extracting_code_inside_an_impl_puts_the_definition_after_the_impl() {
+    assert_eq!(
+        extracted(
+            "struct S;\n\nimpl S {\n    fn f(&self) -> u8 {\n        1 + 2\n    }\n}\n",
+            "1 + 2"
+        ),
+        "struct S;\n\nimpl S {\n    fn f(&self) -> u8 {\n        funcName()\n    }\n}\n\nfn funcName() -> TypeName {\n    1 + 2\n}\n"
+    );
+}
+
+#[test]
+fn extracting_code_puts_the_definition_in_the_outermost_scope_of_the_innermost_module() {
+    assert_eq!(
+        extracted(
+            "mod m {\n    mod n {\n        fn f() {\n            let x = 'a';\n            g(x);\n        }\n    }\n}",
+            "'a'"
+        ),
+        "mod m {\n    mod n {\n        fn f() {\n            let x = funcName();\n            g(x);\n        }\n\n        fn funcName() -> char {\n            'a'\n        }\n    }\n}"
+    );
+}
+
+#[test]
+fn extracting_code_that_changes_a_mut_variable_passes_it_by_mutable_reference() {
+    assert_eq!(
+        extracted(
+            "fn f() {\n    let mut x: u8 = 1;\n    x += 1;\n    g(x);\n}",
+            "x += 1;"
+        ),
+        "fn f() {\n    let mut x: u8 = 1;\n    funcName(&mut x);\n    g(x);\n}\n\nfn funcName(x: &mut u8) {\n    *x += 1;\n}"
+    );
+}
+
+#[test]
+fn extracting_code_that_calls_a_method_on_a_mut_variable_passes_it_by_mutable_reference() {
+    assert_eq!(
+        extracted(
+            "fn f(mut v: Vec<u8>) {\n    v.push(1);\n    g(v.len(), v[0]);\n}",
+            "v.push(1);\n    g(v.len(), v[0]);"
+        ),
+        "fn f(mut v: Vec<u8>) {\n    funcName(&mut v);\n}\n\nfn funcName(v: &mut Vec<u8>) {\n    v.push(1);\n    g(v.len(), v[0]);\n}"
+    );
+}
+
+#[test]
+fn extracting_code_that_only_reads_a_mut_variable_passes_it_by_value() {
+    let extraction = extraction(
+        "fn f() {\n    let mut x: u8 = 1;\n    g(x + 1);\n    x = 2;\n}",
+        "g(x + 1);"
+    );
+
+    assert_eq!(extraction.mutable_references, Vec::<String>::new());
+    assert_eq!(extraction.call(), "funcName(x);");
+}
+
+#[test]
+fn extracting_code_that_returns_or_breaks_out_of_the_selection_returns_none() {
+    let none_for = |code: &str, selected: &str| {
+        let start = code.find(selected).expect("selected text not found");
+
+        assert_eq!(
+            function_extraction(&rust_tree(code), code, (start, start + selected.len())),
+            None,
+            "for {:?}",
+            selected
+        );
+    };
+
+    none_for("fn f(a: u8) -> u8 {\n    if a > 1 {\n        return 1;\n    }\n    a\n}", "if a > 1 {\n        return 1;\n    }");
+    none_for("fn f(s: &str) -> Result<u8, E> {\n    let a = s.parse()?;\n    Ok(a)\n}", "let a = s.parse()?;");
+    none_for("fn f() {\n    loop {\n        g();\n        break;\n    }\n}", "g();\n        break;");
+    none_for("fn f() {\n    for i in 0..3 {\n        continue;\n    }\n}", "continue;");
+    none_for(
+        "fn f() {\n    'outer: loop {\n        loop {\n            break 'outer;\n        }\n    }\n}",
+        "loop {\n            break 'outer;\n        }"
+    );
+}
+
+#[test]
+fn extracting_code_that_returns_or_breaks_only_inside_of_the_selection_works() {
+    let extraction = extraction(
+        "fn f() {\n    loop {\n        let c = || { return 1; };\n        if g(c) {\n            break;\n        }\n    }\n    h();\n}",
+        "loop {\n        let c = || { return 1; };\n        if g(c) {\n            break;\n        }\n    }"
+    );
+
+    assert_eq!(extraction.call(), "funcName();");
+}
+
+#[test]
+fn extracting_an_empty_selection_outside_of_any_expression_returns_none() {
+    let code = "struct S;\n";
+
+    assert_eq!(function_extraction(&rust_tree(code), code, (0, 0)), None);
+}
+
+/// Returns `code` with the declaration and the uses from the extraction of the first
+/// occurrence of `selected` in it.
+fn variable_extracted(code: &str, selected: &str, every_occurrence: bool) -> String {
+    let start = code.find(selected).expect("selected text not found");
+    let extraction = variable_extraction(
+        &rust_tree(code),
+        code,
+        (start, start + selected.len()),
+        every_occurrence,
+    ).expect("no extraction");
+
+    let mut output = code.to_owned();
+    // We go backwards, to keep the earlier ranges valid.
+    for &(start, end) in extraction.occurrences.iter().rev() {
+        output.replace_range(start..end, VARIABLE_NAME);
+    }
+    output.insert_str(extraction.declaration_at, &extraction.declaration());
+
+    output
+}
+
+#[test]
+fn extracting_a_variable_declares_it_on_the_line_before_the_statement() {
+    assert_eq!(
+        variable_extracted("fn f(a: u8) {\n    if true {\n        g(a + 1);\n    }\n}", "a + 1", false),
+        "fn f(a: u8) {\n    if true {\n        let varName = a + 1;\n        g(varName);\n    }\n}"
+    );
+}
+
+#[test]
+fn extracting_a_variable_from_a_partial_selection_extracts_the_smallest_expression_containing_it() {
+    assert_eq!(
+        variable_extracted("fn f() {\n    let x = g(1, 2) * 3;\n}", "1, 2", false),
+        "fn f() {\n    let varName = g(1, 2);\n    let x = varName * 3;\n}"
+    );
+}
+
+#[test]
+fn extracting_a_multi_line_variable_reindents_it() {
+    assert_eq!(
+        variable_extracted(
+            "fn f() {\n    if c {\n        g(h(\n            1,\n        ));\n    }\n}",
+            "h(\n            1,\n        )",
+            false
+        ),
+        "fn f() {\n    if c {\n        let varName = h(\n            1,\n        );\n        g(varName);\n    }\n}"
+    );
+}
+
+#[test]
+fn extracting_a_variable_only_replaces_the_selected_occurrence_by_default() {
+    assert_eq!(
+        variable_extracted("fn f(a: u8) {\n    g(a * 2);\n    h(a * 2);\n}", "a * 2", false),
+        "fn f(a: u8) {\n    let varName = a * 2;\n    g(varName);\n    h(a * 2);\n}"
+    );
+}
+
+#[test]
+fn extracting_a_variable_everywhere_replaces_the_identical_occurrences_in_the_block() {
+    assert_eq!(
+        variable_extracted(
+            "fn f(a: u8) {\n    g(a * 2);\n    if true {\n        h(a * 2);\n    }\n}",
+            "a * 2",
+            true
+        ),
+        "fn f(a: u8) {\n    let varName = a * 2;\n    g(varName);\n    if true {\n        h(varName);\n    }\n}"
+    );
+}
+
+#[test]
+fn extracting_a_variable_everywhere_declares_it_before_the_first_occurrence() {
+    // The second occurrence is the selected one, but the first one is in the block too.
+    let code = "fn f(a: u8) {\n    g(a * 2);\n    h(a * 2);\n}";
+    let start = code.rfind("a * 2").unwrap();
+
+    let extraction = variable_extraction(&rust_tree(code), code, (start, start + 5), true)
+        .unwrap();
+
+    assert_eq!(extraction.declaration_at, code.find("g(").unwrap());
+    assert_eq!(extraction.occurrences.len(), 2);
+}
+
+#[test]
+fn extracting_a_variable_everywhere_skips_occurrences_that_use_other_variables() {
+    assert_eq!(
+        variable_extracted(
+            "fn f(a: u8) {\n    g(a * 2);\n    {\n        let a = 3;\n        h(a * 2);\n    }\n}",
+            "a * 2",
+            true
+        ),
+        "fn f(a: u8) {\n    let varName = a * 2;\n    g(varName);\n    {\n        let a = 3;\n        h(a * 2);\n    }\n}"
+    );
+}
+
+#[test]
+fn extracting_a_variable_everywhere_skips_places_that_are_assigned_to() {
+    assert_eq!(
+        variable_extracted("fn f(mut s: S) {\n    g(s.a);\n    s.a = 1;\n}", "s.a", true),
+        "fn f(mut s: S) {\n    let varName = s.a;\n    g(varName);\n    s.a = 1;\n}"
+    );
+}
+
+#[test]
+fn extracting_a_variable_that_uses_a_closure_parameter_returns_none() {
+    let code = "fn f(v: Vec<u8>) {\n    g(v.iter().map(|x| x + 1));\n}";
+    let start = code.find("x + 1").unwrap();
+
+    assert_eq!(variable_extraction(&rust_tree(code), code, (start, start + 5), false), None);
+}
+
+#[test]
+fn extracting_a_variable_that_is_not_always_evaluated_returns_none() {
+    let none_for = |code: &str, selected: &str| {
+        let start = code.find(selected).expect("selected text not found");
+
+        assert_eq!(
+            variable_extraction(&rust_tree(code), code, (start, start + selected.len()), false),
+            None,
+            "for {:?}",
+            selected
+        );
+    };
+
+    none_for("fn f(a: Option<u8>) {\n    let b = a.is_some() && a.unwrap() > 0;\n}", "a.unwrap()");
+    none_for("fn f(a: bool) {\n    let b = a || g();\n}", "g()");
+    none_for("fn f(o: Option<u8>) {\n    let b = match o {\n        Some(x) => x,\n        None => g(),\n    };\n}", "g()");
+    none_for("fn f(v: Vec<u8>) {\n    h(v.iter().map(|x| g(1)));\n}", "g(1)");
+    none_for("fn f(n: Vec<u8>, mut i: usize) {\n    while i < n.len() {\n        i += 1;\n    }\n}", "n.len()");
+}
+
+#[test]
+fn extracting_a_variable_on_the_left_of_a_short_circuiting_operator_works() {
+    assert_eq!(
+        variable_extracted("fn f(a: Option<u8>, c: bool) {\n    let b = a.is_some() && c;\n}", "a.is_some()", false),
+        "fn f(a: Option<u8>, c: bool) {\n    let varName = a.is_some();\n    let b = varName && c;\n}"
+    );
+}
+
+#[test]
+fn extracting_a_variable_everywhere_skips_occurrences_on_the_other_side_of_a_change() {
+    let code = "fn f(mut a: u8) {\n    g(a * 2);\n    a += 1;\n    h(a * 2);\n}";
+
+    assert_eq!(
+        variable_extracted(code, "a * 2", true),
+        "fn f(mut a: u8) {\n    let varName = a * 2;\n    g(varName);\n    a += 1;\n    h(a * 2);\n}"
+    );
+
+    let start = code.rfind("a * 2").unwrap();
+    let extraction = variable_extraction(&rust_tree(code), code, (start, start + 5), true)
+        .unwrap();
+
+    assert_eq!(extraction.declaration_at, code.find("h(").unwrap());
+    assert_eq!(extraction.occurrences, vec![(start, start + 5)]);
+}
+
+#[test]
+fn extracting_a_variable_everywhere_does_not_merge_expressions_that_change_things() {
+    assert_eq!(
+        variable_extracted("fn f(mut v: Vec<u8>) {\n    g(v.pop());\n    h(v.pop());\n}", "v.pop()", true),
+        "fn f(mut v: Vec<u8>) {\n    let varName = v.pop();\n    g(varName);\n    h(v.pop());\n}"
+    );
+}
+
+#[test]
+fn extracting_a_variable_everywhere_declares_it_before_the_first_occurrence_that_is_always_evaluated() {
+    let code = "fn f(a: Option<u8>, c: bool) {\n    let b = c && a.unwrap() > 0;\n    g(a.unwrap());\n}";
+    let start = code.rfind("a.unwrap()").unwrap();
+
+    let extraction = variable_extraction(&rust_tree(code), code, (start, start + 10), true)
+        .unwrap();
+
+    assert_eq!(extraction.declaration_at, code.find("g(").unwrap());
+    assert_eq!(extraction.occurrences, vec![(start, start + 10)]);
+}
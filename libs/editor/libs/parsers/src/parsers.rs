@@ -1,7 +1,8 @@
 #![deny(unused)]
 use macros::{d, dbg, fmt_debug, fmt_display, some_or, u};
-use platform_types::{BufferName, Rope, Spans};
-use edit::{Change, Edit, RangeEdits};
+use platform_types::{AbsoluteCharOffset, BufferName, ByteIndex, Fold, Rope, SpanKind, Spans};
+use edit::{Edit, RangeEdits, RangeReplacement, RangeSwap, SwapDirection};
+use rope_pos::AbsoluteCharOffsetRange;
 
 use tree_sitter::{
     InputEdit,
@@ -14,10 +15,7 @@ use tree_sitter::{
     Tree,
 };
 
-use std::{
-    borrow::Cow,
-    cmp::{max, min}
-};
+use std::borrow::Cow;
 
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
 pub enum Style {
@@ -60,29 +58,38 @@ impl Iterator for Style {
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
 pub enum ParserKind {
     Plaintext,
-    Rust(Style)
+    /// Parsed into a tree with the grammar of the given language.
+    Tree(LanguageId, Style)
 }
 d!(for ParserKind: ParserKind::Plaintext);
 
 fmt_display!(
     for ParserKind: match p {
         Plaintext => "txt".to_string(),
-        Rust(s) => format!("rs({})", s),
+        Tree(id, s) => format!("{}({})", id.spec().name, s),
     }
 );
 
 impl Iterator for ParserKind {
     type Item = ParserKind;
 
+    /// Goes through each style of each registered language in turn.
     fn next(&mut self) -> Option<Self::Item> {
         use ParserKind::*;
         match self {
             Plaintext => {
-                Some(Rust(d!()))
+                LanguageId::all().next()
+                    .map(|id| Tree(id, d!()))
             },
-            Rust(style) => {
+            Tree(id, style) => {
+                let id = *id;
                 style.next()
-                    .map(Rust)
+                    .map(|s| Tree(id, s))
+                    .or_else(||
+                        LanguageId::all()
+                            .find(|other| other.0 > id.0)
+                            .map(|other| Tree(other, d!()))
+                    )
             }
         }.map(|p| {
             *self = p;
@@ -94,12 +101,122 @@ impl Iterator for ParserKind {
 impl ParserKind {
     pub fn default_from_name(name: &BufferName) -> Self {
         u!{ParserKind}
-        match name.get_extension_or_empty() {
-            "rs" => Rust(d!()),
-            _ => Plaintext,
+        let extension = name.get_extension_or_empty();
+        LanguageId::all()
+            .find(|id| id.spec().extensions.contains(&extension))
+            .map(|id| Tree(id, d!()))
+            .unwrap_or(Plaintext)
+    }
+
+    /// The chars that start a comment that runs until the end of the line, in the
+    /// language this kind of parser is for, if that language has such comments.
+    pub fn line_comment_start(&self) -> Option<&'static str> {
+        u!{ParserKind}
+        match self {
+            Tree(id, _) => id.spec().line_comment_start,
+            Plaintext => None,
         }
     }
 }
+
+/// Everything we need to know about a language in order to parse and highlight it.
+/// To add a language, vendor its grammar, compile it in `build.rs`, and add a spec
+/// for it to `LANGUAGES`.
+pub struct LanguageSpec {
+    /// A short name for the language, shown to the user.
+    pub name: &'static str,
+    /// Files with these extensions are parsed as this language by default.
+    pub extensions: &'static [&'static str],
+    /// The chars that start a comment that runs until the end of the line, if the
+    /// language has such comments.
+    pub line_comment_start: Option<&'static str>,
+    grammar: unsafe extern "C" fn() -> Language,
+    /// The query used for `Style::Basic`. Captures named `comment` are highlighted as
+    /// comments, and ones named `string` as strings.
+    highlight_query: &'static str,
+}
+
+extern "C" {
+    fn tree_sitter_rust() -> Language;
+    fn tree_sitter_c() -> Language;
+    fn tree_sitter_json() -> Language;
+    fn tree_sitter_markdown() -> Language;
+    fn tree_sitter_toml() -> Language;
+}
+
+pub static LANGUAGES: [LanguageSpec; 5] = [
+    LanguageSpec {
+        name: "rs",
+        extensions: &["rs"],
+        line_comment_start: Some("//"),
+        grammar: tree_sitter_rust,
+        highlight_query: "
+(line_comment) @comment
+(block_comment) @comment
+(string_literal) @string
+(char_literal) @string
+",
+    },
+    LanguageSpec {
+        name: "c",
+        extensions: &["c", "h"],
+        line_comment_start: Some("//"),
+        grammar: tree_sitter_c,
+        highlight_query: "
+(comment) @comment
+(string_literal) @string
+(system_lib_string) @string
+(char_literal) @string
+",
+    },
+    LanguageSpec {
+        name: "json",
+        extensions: &["json"],
+        line_comment_start: None,
+        grammar: tree_sitter_json,
+        highlight_query: "
+(string) @string
+",
+    },
+    LanguageSpec {
+        name: "md",
+        extensions: &["md", "markdown"],
+        line_comment_start: None,
+        grammar: tree_sitter_markdown,
+        highlight_query: "
+(html_block) @comment
+(code_span) @string
+(fenced_code_block) @string
+(indented_code_block) @string
+",
+    },
+    LanguageSpec {
+        name: "toml",
+        extensions: &["toml"],
+        line_comment_start: Some("#"),
+        grammar: tree_sitter_toml,
+        highlight_query: "
+(comment) @comment
+(string) @string
+",
+    },
+];
+
+/// Which of the `LANGUAGES` a buffer is in.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct LanguageId(usize);
+
+pub const RUST: LanguageId = LanguageId(0);
+
+impl LanguageId {
+    pub fn all() -> impl Iterator<Item = LanguageId> {
+        (0..LANGUAGES.len()).map(LanguageId)
+    }
+
+    pub fn spec(self) -> &'static LanguageSpec {
+        &LANGUAGES[self.0]
+    }
+}
 
 #[derive(Debug)]
 pub enum Parsers {
@@ -140,17 +257,36 @@ struct BufferState {
     parser: Parser,
     tree: Option<Tree>,
     spans: Option<CachedSpans>,
+    /// What `tree` and `spans` were made with, if anything.
+    kind: Option<ParserKind>,
+    /// The edits that have been applied to `tree` since `spans` were made, in the
+    /// order they were applied.
+    edits: Vec<InputEdit>,
+    bracket_matches: Option<CachedBracketMatches>,
 }
 
 d!{for BufferState: BufferState{
     parser: Parser::new(),
     tree: None,
     spans: None,
+    kind: None,
+    edits: Vec::new(),
+    bracket_matches: None,
 }}
 
 struct CachedSpans {
     spans: Spans,
-    hash: u64,
+    /// The version of the text the spans were made from. See `Parsers::get_spans`.
+    version: u64,
+    /// The length in bytes of the text the spans were made from.
+    len: usize,
+}
+
+struct CachedBracketMatches {
+    /// The version of the text the matches were found in. See `Parsers::get_spans`.
+    version: u64,
+    offsets: Vec<AbsoluteCharOffset>,
+    matches: Vec<Option<BracketMatch>>,
 }
 
 #[cfg(not(feature = "fast_hash"))]
@@ -161,18 +297,24 @@ use fast_hash::Map as HashMap;
 
 type ParserMap = HashMap<BufferName, BufferState>;
 
+/// A language from `LANGUAGES`, ready to be used.
+#[derive(Debug)]
+struct LoadedLanguage {
+    language: Language,
+    highlight_query: Query,
+}
+
 pub struct InitializedParsers {
     parser_map: ParserMap,
-    rust_basic_query: Query,
-    rust_lang: Language,
+    /// Indexed by `LanguageId`.
+    languages: Vec<LoadedLanguage>,
 }
 
 impl std::fmt::Debug for InitializedParsers {
     fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
         fmt.debug_struct("InitializedParsers")
            .field("parser_map", &("TODO parser_map: Debug".to_string()))
-           .field("rust_basic_query", &self.rust_basic_query)
-           .field("rust_lang", &self.rust_lang)
+           .field("languages", &self.languages)
            .finish()
     }
 }
@@ -198,52 +340,52 @@ fmt_display!{
 
 impl std::error::Error for SpanError {}
 
-type SpansResult<'to_parse> = Result<Spans, (ToParse<'to_parse>, SpanError)>;
+type SpansResult = Result<Spans, SpanError>;
 
 impl Parsers {
+    /// Returns the spans of `rope`, the text of the buffer with the given name.
+    /// `version` must be different whenever the text is, so that if it is the same as
+    /// last time, we can return the same spans without looking at the text at all.
     #[perf_viz::record]
     pub fn get_spans(
         &mut self,
-        to_parse: ToParse<'_>,
+        rope: &Rope,
+        version: u64,
         buffer_name: &BufferName,
         kind: ParserKind
     ) -> Spans {
         match self.get_spans_result(
-            to_parse,
+            rope,
+            version,
             buffer_name,
             kind,
         ) {
             Ok(spans) => spans,
-            Err((to_parse, err)) => {
+            Err(err) => {
                 // TODO: Propagate error to the editor view so it can be 
                 // displayed to the user.
                 eprintln!("{}", err);
-                query::plaintext_spans_for(to_parse)
+                query::plaintext_spans_for(rope.len_bytes().0)
             }
         }
     }
 
-    pub fn get_spans_result<'to_parse>(
+    pub fn get_spans_result(
         &mut self,
-        to_parse: ToParse<'to_parse>,
+        rope: &Rope,
+        version: u64,
         buffer_name: &BufferName,
         kind: ParserKind
-    ) -> SpansResult<'to_parse> {
+    ) -> SpansResult {
         u!{Parsers}
         self.attempt_init();
 
         match self {
             Initialized(p) => {
-                p.get_spans(to_parse, buffer_name, kind)
+                p.get_spans(rope, version, buffer_name, kind)
             },
-            NotInitializedYet => Err((
-                to_parse,
-                SpanError::NotInitializedYet
-            )),
-            FailedToInitialize(_) => Err((
-                to_parse,
-                SpanError::FailedToInitialize
-            )),
+            NotInitializedYet => Err(SpanError::NotInitializedYet),
+            FailedToInitialize(_) => Err(SpanError::FailedToInitialize),
         }
     }
 
@@ -268,7 +410,7 @@ impl Parsers {
                     &mut initialized.parser_map,
                     buffer_name,
                     kind,
-                    initialized.rust_lang,
+                    &initialized.languages,
                 );
                 dbg!(buffer_state.tree.is_some());
                 macro_rules! cont {
@@ -279,128 +421,28 @@ impl Parsers {
                     }}
                 }
                 if let Some(tree) = buffer_state.tree.as_mut() {
-                    for i in 0..edit.range_edits().len() {
+                    // Each range edit is relative to the rope after the previous ones,
+                    // so we apply them to a copy as we go.
+                    let mut rope = rope.clone();
+
+                    for range_edits in edit.range_edits().iter() {
+                        if range_edits.delete_range.is_none()
+                        && range_edits.insert_range.is_none() {
+                            // The edit apparently changed no characters, so it
+                            // seems there is nothing to tell the parser about.
+                            continue
+                        }
+
                         let input_edit = {
                             perf_viz::record_guard!("convert edit");
-                            let (
-                                Change{ old, new },
-                                RangeEdits{ delete_range, insert_range }
-                            ) = some_or!(edit.read_at(i), cont!());
-                        
-                            let (start_byte, old_end_byte, new_end_byte) = 
-                            match dbg!(delete_range, insert_range) {
-                                (Some(del_range), Some(ins_range)) => {
-                                    let del_start_byte = rope.char_to_byte(
-                                        del_range.range.min()
-                                    );
-                                    let del_end_byte = rope.char_to_byte(
-                                        del_range.range.max()
-                                    );
-                                    let ins_start_byte = rope.char_to_byte(
-                                        ins_range.range.min()
-                                    );
-                                    let ins_end_byte = ins_start_byte
-                                        .map(|b| b + ins_range.chars.len());
-    
-                                    debug_assert_eq!(
-                                        del_start_byte,
-                                        ins_start_byte
-                                    );
-                                    (del_start_byte, del_end_byte, ins_end_byte)
-                                }
-                                (Some(del_range), None) => {
-                                    let start_byte = rope.char_to_byte(
-                                        del_range.range.min()
-                                    );
-                                    let end_byte = rope.char_to_byte(
-                                        del_range.range.max()
-                                    );
-                                    (start_byte, end_byte, start_byte)
-                                },
-                                (None, Some(ins_range)) => {
-                                    let start_byte = rope.char_to_byte(
-                                        ins_range.range.min()
-                                    );
-    
-                                    let end_byte = start_byte
-                                        .map(|b| b + ins_range.chars.len());
-    
-                                    dbg!(rope, ins_range.range.max(), end_byte);
-                                    (start_byte, start_byte, end_byte)
-                                }
-                                (None, None) => {
-                                    // The edit apparently changed no characters,
-                                    // so it seems there is nothing to tell the parser
-                                    // about.
-                                    continue
-                                },
-                            };
-    
-                            let start_byte = some_or!(start_byte, cont!()).0;
-                            let old_end_byte = some_or!(old_end_byte, cont!()).0;
-                            let new_end_byte = some_or!(new_end_byte, cont!()).0;
-    
-                            let (start_pos, old_end_pos, new_end_pos) = match (old, new) {
-                                (Some(old), Some(new)) => {
-                                    let old_pos = old.get_position();
-                                    let old_h_pos = old.get_highlight_position_or_position();
-                                    let old_min = min(old_pos, old_h_pos);
-                                    let old_end_pos = max(old_pos, old_h_pos);
-            
-                                    let new_pos = new.get_position();
-                                    let new_h_pos = new.get_highlight_position_or_position();
-                                    let new_min = min(new_pos, new_h_pos);
-                                    let new_end_pos = max(new_pos, new_h_pos);
-            
-                                    let start_pos = min(old_min, new_min);
-    
-                                    (start_pos, old_end_pos, new_end_pos)
-                                },
-                                (Some(old), None) => {
-                                    let old_pos = old.get_position();
-                                    let old_h_pos = old.get_highlight_position_or_position();
-                                    let old_min = min(old_pos, old_h_pos);
-                                    let old_end_pos = max(old_pos, old_h_pos);
-    
-                                    let start_pos = old_min;
-    
-                                    (start_pos, old_end_pos, start_pos)
-                                },
-                                (None, Some(new)) => {
-                                    let new_pos = new.get_position();
-                                    let new_h_pos = new.get_highlight_position_or_position();
-                                    let new_min = min(new_pos, new_h_pos);
-                                    let new_end_pos = max(new_pos, new_h_pos);
-            
-                                    let start_pos = new_min;
-    
-                                    (start_pos, start_pos, new_end_pos)
-                                },
-                                (None, None) => cont!(),
-                            };
-
-                            dbg!(InputEdit{
-                                start_byte,
-                                old_end_byte,
-                                new_end_byte,
-                                start_position: Point{ 
-                                    row: start_pos.line,
-                                    column: start_pos.offset.0
-                                },
-                                old_end_position: Point{ 
-                                    row: old_end_pos.line,
-                                    column: old_end_pos.offset.0
-                                },
-                                new_end_position: Point{ 
-                                    row: new_end_pos.line,
-                                    column: new_end_pos.offset.0
-                                },
-                            })
+                            some_or!(apply_for_input_edit(range_edits, &mut rope), cont!())
                         };
 
                         perf_viz::start_record!("tree.edit");
                         tree.edit(&input_edit);
                         perf_viz::end_record!("tree.edit");
+
+                        buffer_state.edits.push(input_edit);
                     }
                 }
             },
@@ -410,6 +452,221 @@ impl Parsers {
         }
     }
 
+    /// Returns the folds that the syntax of the buffer suggests, as of the last time spans
+    /// were requested for it, sorted and without duplicates. If the buffer has not been
+    /// parsed, then there are no such folds.
+    pub fn get_fold_candidates(
+        &self,
+        buffer_name: &BufferName,
+        kind: ParserKind,
+    ) -> Vec<Fold> {
+        self.get_tree(buffer_name, kind)
+            .map(query::fold_candidates)
+            .unwrap_or_default()
+    }
+
+    /// For each of the `selections`, returns a swap that moves the smallest named node
+    /// containing the selection past its previous or next named sibling, depending on
+    /// `direction`. Nodes without a sibling in that direction are skipped over in favour
+    /// of their ancestors.
+    pub fn get_sibling_swaps(
+        &self,
+        buffer_name: &BufferName,
+        kind: ParserKind,
+        rope: &Rope,
+        selections: &[(AbsoluteCharOffset, AbsoluteCharOffset)],
+        direction: SwapDirection,
+    ) -> Vec<RangeSwap> {
+        let tree = some_or!(self.get_tree(buffer_name, kind), return Vec::new());
+
+        selections.iter()
+            .filter_map(|&(o1, o2)| {
+                let range = AbsoluteCharOffsetRange::new(o1, o2);
+                let (start, end) = query::sibling_swap(
+                    tree,
+                    (rope.char_to_byte(range.min())?, rope.char_to_byte(range.max())?),
+                    direction,
+                )?;
+
+                let to_chars = |(start, end): (ByteIndex, ByteIndex)| Some(
+                    AbsoluteCharOffsetRange::new(
+                        rope.byte_to_char(start)?,
+                        rope.byte_to_char(end)?,
+                    )
+                );
+
+                Some(RangeSwap {
+                    moved: to_chars(start)?,
+                    other: to_chars(end)?,
+                })
+            })
+            .collect()
+    }
+
+    /// For each of the `offsets`, returns the bracket right after the offset, or failing
+    /// that, the one right before it, along with the bracket that matches it if there is
+    /// one. The parse tree is used to find the match where possible, and otherwise we
+    /// count brackets in the text, skipping any that are in strings or comments.
+    pub fn get_bracket_matches(
+        &mut self,
+        buffer_name: &BufferName,
+        kind: ParserKind,
+        rope: &Rope,
+        version: u64,
+        offsets: &[AbsoluteCharOffset],
+    ) -> Vec<Option<BracketMatch>> {
+        // This is called on every render, and counting brackets can mean scanning the
+        // whole text, so we keep the matches until the text or the offsets change.
+        if let Parsers::Initialized(p) = self {
+            let cached = p.parser_map
+                .get(buffer_name)
+                .filter(|state| state.kind == Some(kind))
+                .and_then(|state| state.bracket_matches.as_ref())
+                .filter(|cached| cached.version == version && cached.offsets == offsets);
+            if let Some(cached) = cached {
+                return cached.matches.clone();
+            }
+        }
+
+        // Getting the spans also brings the tree up to date with `rope`.
+        let skipped = skipped_ranges(rope, &self.get_spans(rope, version, buffer_name, kind));
+        let tree = self.get_tree(buffer_name, kind);
+
+        let matches: Vec<_> = offsets.iter()
+            .map(|&offset| bracket_match(rope, tree, &skipped, offset))
+            .collect();
+
+        if let Parsers::Initialized(p) = self {
+            let state = get_or_init_buffer_state(
+                &mut p.parser_map,
+                buffer_name,
+                kind,
+                &p.languages,
+            );
+            state.bracket_matches = Some(CachedBracketMatches {
+                version,
+                offsets: offsets.to_vec(),
+                matches: matches.clone(),
+            });
+        }
+
+        matches
+    }
+
+    /// For each of the `selections`, returns the offsets of the closest pair of matching
+    /// brackets that encloses the selection, not counting a pair whose contents are
+    /// exactly the selection, so that asking again with the contents selected gives the
+    /// next pair out.
+    pub fn get_enclosing_brackets(
+        &mut self,
+        buffer_name: &BufferName,
+        kind: ParserKind,
+        rope: &Rope,
+        version: u64,
+        selections: &[(AbsoluteCharOffset, AbsoluteCharOffset)],
+    ) -> Vec<Option<(AbsoluteCharOffset, AbsoluteCharOffset)>> {
+        let skipped = skipped_ranges(rope, &self.get_spans(rope, version, buffer_name, kind));
+        let tree = self.get_tree(buffer_name, kind);
+
+        selections.iter()
+            .map(|&(o1, o2)| {
+                let selection = AbsoluteCharOffsetRange::new(o1, o2);
+                let mut start = selection.min();
+                loop {
+                    let open = unmatched_open_bracket_before(rope, &skipped, start)?;
+                    let close = bracket_match(rope, tree, &skipped, open)
+                        .and_then(|m| m.matching);
+
+                    match close {
+                        Some(close) if close >= selection.max()
+                            && !(open + 1 == selection.min() && close == selection.max()) => {
+                            return Some((open, close));
+                        }
+                        _ => {
+                            start = open;
+                        }
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Returns a function that, given an offset, returns how many pairs of brackets,
+    /// opened on distinct lines, enclose that offset according to the parse tree. It
+    /// returns `None` where the tree cannot tell, including when there is no tree.
+    pub fn get_nesting_depth_fn<'parsers>(
+        &'parsers mut self,
+        buffer_name: &BufferName,
+        kind: ParserKind,
+        rope: &'parsers Rope,
+        version: u64,
+    ) -> impl Fn(AbsoluteCharOffset) -> Option<usize> + 'parsers {
+        // Getting the spans also brings the tree up to date with `rope`.
+        self.get_spans(rope, version, buffer_name, kind);
+        let tree = self.get_tree(buffer_name, kind);
+
+        move |offset| query::nesting_depth(tree?, rope.char_to_byte(offset)?)
+    }
+
+    /// Returns the replacements that do `extraction` to the code in `selection`, with the
+    /// new name selected in every place it appears afterwards. See `Extraction` for what
+    /// each kind does. This only knows how to do this for Rust.
+    pub fn get_extraction(
+        &mut self,
+        buffer_name: &BufferName,
+        kind: ParserKind,
+        rope: &Rope,
+        version: u64,
+        selection: (AbsoluteCharOffset, AbsoluteCharOffset),
+        extraction: Extraction,
+    ) -> Vec<RangeReplacement> {
+        u!{ParserKind}
+        match kind {
+            Tree(id, _) if id == RUST => {},
+            Tree(_, _) | Plaintext => return Vec::new(),
+        }
+
+        // Getting the spans also brings the tree up to date with `rope`.
+        self.get_spans(rope, version, buffer_name, kind);
+        let tree = some_or!(self.get_tree(buffer_name, kind), return Vec::new());
+
+        let text: ToParse = rope.into();
+        let selection = AbsoluteCharOffsetRange::new(selection.0, selection.1);
+        let selection = some_or!(
+            byte_range(rope, selection.min(), selection.max()),
+            return Vec::new()
+        );
+
+        match extraction {
+            Extraction::Function => {
+                function_extraction_replacements(tree, rope, &text, selection)
+            },
+            Extraction::Variable { every_occurrence } => variable_extraction_replacements(
+                tree,
+                rope,
+                &text,
+                selection,
+                every_occurrence,
+            ),
+        }.unwrap_or_default()
+    }
+
+    fn get_tree(&self, buffer_name: &BufferName, kind: ParserKind) -> Option<&Tree> {
+        use Parsers::*;
+        u!{ParserKind}
+
+        match (self, kind) {
+            (Initialized(p), Tree(_, _)) => {
+                p.parser_map
+                    .get(buffer_name)
+                    .and_then(|state| state.tree.as_ref())
+            },
+            (Initialized(_), Plaintext)
+            | (NotInitializedYet, _)
+            | (FailedToInitialize(_), _) => None,
+        }
+    }
+
     /// This method should be called when a buffer is closed, so we don't waste memory
     /// on storing the parse state for it.
     pub fn remove_buffer_state(&mut self, buffer_name: &BufferName) {
@@ -424,23 +681,358 @@ impl Parsers {
     }
 }
 
+/// A refactoring that moves the selected code somewhere else, and refers to it from
+/// where it was, as done by `Parsers::get_extraction`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Extraction {
+    /// Moves the selected code into a new function, and calls that function in its
+    /// place. See `extract::function_extraction` for which code gets moved.
+    Function,
+    /// Moves the selected expression into a new variable declared before the statement
+    /// it is in, and uses that variable in its place, and in place of every identical
+    /// expression in the same block, if `every_occurrence` is true. See
+    /// `extract::variable_extraction` for which code gets moved.
+    Variable { every_occurrence: bool },
+}
+
+fn byte_range(
+    rope: &Rope,
+    start: AbsoluteCharOffset,
+    end: AbsoluteCharOffset,
+) -> Option<(usize, usize)> {
+    Some((rope.char_to_byte(start)?.0, rope.char_to_byte(end)?.0))
+}
+
+fn function_extraction_replacements(
+    tree: &Tree,
+    rope: &Rope,
+    text: &str,
+    selection: (usize, usize),
+) -> Option<Vec<RangeReplacement>> {
+    use extract::FUNCTION_NAME;
+
+    let extraction = extract::function_extraction(tree, text, selection)?;
+
+    let to_char = |byte| rope.byte_to_char(ByteIndex(byte));
+    let name_selection = |code: &str, name_byte: usize| {
+        let start = code[..name_byte].chars().count();
+        (start, start + FUNCTION_NAME.chars().count())
+    };
+
+    let call = extraction.call();
+    let call_name = name_selection(&call, call.find(FUNCTION_NAME)?);
+
+    let definition = extraction.definition();
+    let definition_name = name_selection(
+        &definition,
+        definition.find(&format!("fn {}", FUNCTION_NAME))? + "fn ".len()
+    );
+    let definition_at = to_char(extraction.definition_at)?;
+
+    Some(vec![
+        RangeReplacement {
+            range: AbsoluteCharOffsetRange::new(
+                to_char(extraction.range.0)?,
+                to_char(extraction.range.1)?,
+            ),
+            chars: call,
+            selections: vec![call_name],
+        },
+        RangeReplacement {
+            range: AbsoluteCharOffsetRange::new(definition_at, definition_at),
+            chars: definition,
+            selections: vec![definition_name],
+        },
+    ])
+}
+
+fn variable_extraction_replacements(
+    tree: &Tree,
+    rope: &Rope,
+    text: &str,
+    selection: (usize, usize),
+    every_occurrence: bool,
+) -> Option<Vec<RangeReplacement>> {
+    use extract::VARIABLE_NAME;
+
+    let extraction = extract::variable_extraction(tree, text, selection, every_occurrence)?;
+
+    let to_char = |byte| rope.byte_to_char(ByteIndex(byte));
+    let name_len = VARIABLE_NAME.chars().count();
+
+    let declaration_at = to_char(extraction.declaration_at)?;
+    let mut replacements = vec![RangeReplacement {
+        range: AbsoluteCharOffsetRange::new(declaration_at, declaration_at),
+        chars: extraction.declaration(),
+        selections: vec![("let ".len(), "let ".len() + name_len)],
+    }];
+
+    for &(start, end) in extraction.occurrences.iter() {
+        replacements.push(RangeReplacement {
+            range: AbsoluteCharOffsetRange::new(to_char(start)?, to_char(end)?),
+            chars: VARIABLE_NAME.to_owned(),
+            selections: vec![(0, name_len)],
+        });
+    }
+
+    Some(replacements)
+}
+
+/// A bracket next to a cursor, along with the bracket that matches it, if there is one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BracketMatch {
+    pub bracket: AbsoluteCharOffset,
+    pub matching: Option<AbsoluteCharOffset>,
+}
+
+const BRACKET_PAIRS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+
+/// Returns the pair of brackets `c` is a part of, and whether `c` is the opening one.
+fn bracket_pair(c: char) -> Option<((char, char), bool)> {
+    BRACKET_PAIRS.iter().find_map(|&(open, close)| {
+        if c == open {
+            Some(((open, close), true))
+        } else if c == close {
+            Some(((open, close), false))
+        } else {
+            None
+        }
+    })
+}
+
+/// Returns the sorted ranges of chars that are in strings or comments, according to
+/// `spans`. Brackets in these ranges don't count.
+fn skipped_ranges(rope: &Rope, spans: &Spans) -> Vec<AbsoluteCharOffsetRange> {
+    let mut output = Vec::new();
+
+    let mut start = ByteIndex::default();
+    for span in spans.clone() {
+        if span.kind == SpanKind::COMMENT || span.kind == SpanKind::STRING {
+            if let (Some(min), Some(max)) = (
+                rope.byte_to_char(start),
+                rope.byte_to_char(span.one_past_end),
+            ) {
+                output.push(AbsoluteCharOffsetRange::new(min, max));
+            }
+        }
+        start = span.one_past_end;
+    }
+
+    output
+}
+
+fn is_skipped(skipped: &[AbsoluteCharOffsetRange], offset: AbsoluteCharOffset) -> bool {
+    use std::cmp::Ordering::*;
+    skipped.binary_search_by(|range| {
+        if range.max() <= offset {
+            Less
+        } else if range.min() > offset {
+            Greater
+        } else {
+            Equal
+        }
+    }).is_ok()
+}
+
+fn bracket_match(
+    rope: &Rope,
+    tree: Option<&Tree>,
+    skipped: &[AbsoluteCharOffsetRange],
+    offset: AbsoluteCharOffset,
+) -> Option<BracketMatch> {
+    let (bracket, pair, is_open) = std::iter::once(Some(offset))
+        .chain(std::iter::once(offset.checked_sub_one()))
+        .filter_map(|o| {
+            let o = o?;
+            let (pair, is_open) = bracket_pair(rope.char(o)?)?;
+            Some((o, pair, is_open))
+        })
+        .find(|&(o, _, _)| !is_skipped(skipped, o))?;
+
+    if let Some(tree) = tree {
+        let byte = rope.char_to_byte(bracket)?;
+        match query::matching_bracket(tree, byte, pair, is_open) {
+            query::TreeBracket::NotABracket => return None,
+            query::TreeBracket::Matched(matching) => return Some(BracketMatch {
+                bracket,
+                matching: rope.byte_to_char(matching),
+            }),
+            query::TreeBracket::Unknown => {}
+        }
+    }
+
+    Some(BracketMatch {
+        bracket,
+        matching: scan_for_matching_bracket(rope, skipped, bracket, pair, is_open),
+    })
+}
+
+/// Finds the bracket that matches the one at `bracket` by counting the brackets of the
+/// same kind, skipping over any that are in strings or comments.
+fn scan_for_matching_bracket(
+    rope: &Rope,
+    skipped: &[AbsoluteCharOffsetRange],
+    bracket: AbsoluteCharOffset,
+    (open, close): (char, char),
+    is_open: bool,
+) -> Option<AbsoluteCharOffset> {
+    let mut depth: usize = 0;
+    let mut count = |c: char, offset: AbsoluteCharOffset, (same, other): (char, char)| {
+        if is_skipped(skipped, offset) {
+            return false;
+        }
+
+        if c == same {
+            depth += 1;
+        } else if c == other {
+            if depth == 0 {
+                return true;
+            }
+            depth -= 1;
+        }
+        false
+    };
+
+    if is_open {
+        let mut offset = bracket.checked_add_one()?;
+        for c in rope.chars_at(offset)? {
+            if count(c, offset, (open, close)) {
+                return Some(offset);
+            }
+            offset = offset.checked_add_one()?;
+        }
+    } else {
+        let mut chars = rope.chars_at(bracket)?;
+        let mut offset = bracket;
+        while let Some(c) = chars.prev() {
+            offset = offset.checked_sub_one()?;
+            if count(c, offset, (close, open)) {
+                return Some(offset);
+            }
+        }
+    }
+
+    None
+}
+
+/// Returns the offset of the closest opening bracket before `offset` that is not closed
+/// before `offset` by a closing bracket of the same kind.
+fn unmatched_open_bracket_before(
+    rope: &Rope,
+    skipped: &[AbsoluteCharOffsetRange],
+    offset: AbsoluteCharOffset,
+) -> Option<AbsoluteCharOffset> {
+    // Indexed like `BRACKET_PAIRS`.
+    let mut close_counts = [0usize; BRACKET_PAIRS.len()];
+
+    let mut chars = rope.chars_at(offset)?;
+    let mut offset = offset;
+    while let Some(c) = chars.prev() {
+        offset = offset.checked_sub_one()?;
+        if is_skipped(skipped, offset) {
+            continue
+        }
+
+        // Each kind of bracket is counted separately, like `scan_for_matching_bracket`
+        // does, so a `(` is never treated as closed by a `]`.
+        let index = some_or!(
+            BRACKET_PAIRS.iter().position(|&(open, close)| c == open || c == close),
+            continue
+        );
+        let close_count = &mut close_counts[index];
+
+        if c == BRACKET_PAIRS[index].1 {
+            *close_count += 1;
+        } else if *close_count == 0 {
+            return Some(offset);
+        } else {
+            *close_count -= 1;
+        }
+    }
+
+    None
+}
+
+/// Applies the range edits to the rope, returning how the tree-sitter tree of the rope's
+/// text should be edited to match.
+fn apply_for_input_edit(range_edits: &RangeEdits, rope: &mut Rope) -> Option<InputEdit> {
+    let start = match (&range_edits.delete_range, &range_edits.insert_range) {
+        (Some(del), _) => del.range.min(),
+        (None, Some(ins)) => ins.range.min(),
+        (None, None) => return None,
+    };
+    let old_end = range_edits.delete_range
+        .as_ref()
+        .map(|del| del.range.max())
+        .unwrap_or(start);
+    let inserted_len = range_edits.insert_range
+        .as_ref()
+        .map(|ins| ins.chars.len())
+        .unwrap_or(0);
+
+    let start_byte = rope.char_to_byte(start);
+    let old_end_byte = rope.char_to_byte(old_end);
+    let start_position = start_byte.and_then(|b| point_at(rope, b));
+    let old_end_position = old_end_byte.and_then(|b| point_at(rope, b));
+
+    range_edits.apply(rope);
+
+    let start_byte = start_byte?;
+    let new_end_byte = ByteIndex(start_byte.0 + inserted_len);
+
+    Some(InputEdit{
+        start_byte: start_byte.0,
+        old_end_byte: old_end_byte?.0,
+        new_end_byte: new_end_byte.0,
+        start_position: start_position?,
+        old_end_position: old_end_position?,
+        new_end_position: point_at(rope, new_end_byte)?,
+    })
+}
+
+fn point_at(rope: &Rope, byte: ByteIndex) -> Option<Point> {
+    let line = rope.byte_to_line(byte)?;
+    let line_start = rope.line_to_byte(line)?;
+
+    Some(Point{
+        row: line.0,
+        column: byte.0 - line_start.0,
+    })
+}
+
 fn get_or_init_buffer_state<'map>(
     parser_map: &'map mut ParserMap,
     buffer_name: &BufferName,
     kind: ParserKind,
-    // TODO pass a reference to all the langs, if/when we support more than one.
-    rust_lang: Language, 
+    languages: &[LoadedLanguage],
 ) -> &'map mut BufferState {
     u!{ParserKind}
     let buffer_state = parser_map
         .entry(buffer_name.clone())
         .or_insert_with(BufferState::default);
 
+    if buffer_state.kind == Some(kind) {
+        return buffer_state;
+    }
+
+    // The cached spans were made with another language or style. A tree from another
+    // language cannot be reused either, but one from another style can be.
+    buffer_state.spans = None;
+    buffer_state.bracket_matches = None;
+    match (buffer_state.kind, kind) {
+        (Some(Tree(old_id, _)), Tree(id, _)) if old_id == id => {},
+        _ => {
+            buffer_state.tree = None;
+            buffer_state.edits.clear();
+        }
+    }
+    buffer_state.kind = Some(kind);
+
     // We can assume that `set_language` will return `Ok` because we should
     // have already tried it once in `InitializedParsers::new`
     match kind {
-        Rust(_) => {
-            let _res = buffer_state.parser.set_language(rust_lang);
+        Tree(id, _) => {
+            let _res = buffer_state.parser.set_language(languages[id.0].language);
             debug_assert!(
                 _res.is_ok(),
                 "Failed to set language for {}",
@@ -455,30 +1047,27 @@ fn get_or_init_buffer_state<'map>(
 
 impl InitializedParsers {
     #[perf_viz::record]
-    fn get_spans<'to_parse>(
+    fn get_spans(
         &mut self,
-        to_parse: ToParse<'to_parse>,
+        rope: &Rope,
+        version: u64,
         buffer_name: &BufferName,
         kind: ParserKind,
-    ) -> SpansResult<'to_parse> {
+    ) -> SpansResult {
         dbg!("get_spans");
         use ParserKind::*;
         use Style::*;
         match kind {
             Plaintext => {
-                Ok(query::plaintext_spans_for(to_parse))
+                Ok(query::plaintext_spans_for(rope.len_bytes().0))
             }
-            Rust(style) => {
+            Tree(id, style) => {
                 let state = get_or_init_buffer_state(
                     &mut self.parser_map,
                     buffer_name,
                     kind,
-                    self.rust_lang,
+                    &self.languages,
                 );
-                
-                perf_viz::start_record!("hash for caching");
-                let fresh_hash = hash_to_parse(&to_parse);
-                perf_viz::end_record!("hash for caching");
 
                 // This was wriiten right after this caching was introduced.
                 //
@@ -495,31 +1084,36 @@ impl InitializedParsers {
                 // we'd also have to figure out how to signal to the client that
                 // `update_and_render` should be called again.
                 //
-                if let Some(CachedSpans{ spans, hash }) = state.spans.as_ref() {
-                    if *hash == fresh_hash {
-                        return Ok(spans.clone());
+                if let Some(cached) = state.spans.as_ref() {
+                    if cached.version == version {
+                        state.edits.clear();
+                        return Ok(cached.spans.clone());
                     }
                 }
 
-                perf_viz::start_record!("state.parser.parse");
+                perf_viz::start_record!("rope to string");
+                let to_parse: ToParse = rope.into();
+                perf_viz::end_record!("rope to string");
 
-                // This edit call that should do nothing, is here as a workaround
-                // for the `asking_to_parse_the_empty_string_twice_does_not_panic`
-                // test failing.
-                if let Some(t) = state.tree.as_mut() {
-                    t.edit(&InputEdit{
-                        start_byte: d!(),
-                        old_end_byte: d!(),
-                        new_end_byte: d!(),
-                        start_position: d!(),
-                        old_end_position: d!(),
-                        new_end_position: d!(),
-                    });
-                }
+                let edits = std::mem::take(&mut state.edits);
+                let cached_spans = state.spans.take();
+
+                // The old tree is only useful if it was edited to match the text.
+                // If the text changed without us being told how, then reusing it
+                // would give us a tree that does not match the text.
+                let old_tree = match &cached_spans {
+                    _ if edits.is_empty() => None,
+                    Some(cached) if edited_len(cached.len, &edits) != to_parse.len() => {
+                        None
+                    },
+                    _ => state.tree.take(),
+                };
+
+                perf_viz::start_record!("state.parser.parse");
 
                 state.tree = state.parser.parse(
                     to_parse.as_ref(),
-                    state.tree.as_ref()
+                    old_tree.as_ref()
                 );
 
                 // Quoting the `parse` method docs:
@@ -537,21 +1131,53 @@ impl InitializedParsers {
                 perf_viz::end_record!("state.parser.parse");
 
                 if let Some(tree) = state.tree.as_ref() {
-                    let spans = match style {
-                        Basic => {
+                    let highlight_query = &self.languages[id.0].highlight_query;
+                    let spans = match (style, old_tree, cached_spans) {
+                        (Basic, Some(old_tree), Some(cached)) => {
+                            query::reused_spans_for(
+                                tree,
+                                &old_tree,
+                                &edits,
+                                &Vec::from(cached.spans),
+                                &to_parse,
+                                |range| query::spans_within(
+                                    tree,
+                                    highlight_query,
+                                    &to_parse,
+                                    range
+                                ),
+                            )
+                        },
+                        (Extra, Some(old_tree), Some(cached)) => {
+                            query::reused_spans_for(
+                                tree,
+                                &old_tree,
+                                &edits,
+                                &Vec::from(cached.spans),
+                                &to_parse,
+                                |range| query::totally_classified_spans_within(
+                                    tree,
+                                    &to_parse,
+                                    range
+                                ),
+                            )
+                        },
+                        (Basic, _, _) => {
                             query::spans_for(
                                 tree,
-                                &self.rust_basic_query,
+                                highlight_query,
                                 &to_parse
                             )
                         },
-                        Extra => {
+                        (Extra, _, _) => {
                             query::totally_classified_spans_for(
                                 tree,
                                 &to_parse,
                             )
                         },
-                        TreeDepth => {
+                        // The depth of a node depends on every node above it, so
+                        // there is not much to reuse here.
+                        (TreeDepth, _, _) => {
                             query::tree_depth_spans_for(
                                 tree,
                                 &to_parse
@@ -561,32 +1187,27 @@ impl InitializedParsers {
 
                     state.spans = Some(CachedSpans{
                         spans: spans.clone(),
-                        hash: fresh_hash,
+                        version,
+                        len: to_parse.len(),
                     });
 
                     Ok(spans)
                 } else {
                     state.spans = None;
-                    Err((
-                        to_parse,
-                        SpanError::ParseReturnedNone(buffer_name.clone())
-                    ))
+                    Err(SpanError::ParseReturnedNone(buffer_name.clone()))
                 }
             }
         }
     }
 }
 
-fn hash_to_parse<'to_parse>(to_parse: &ToParse<'to_parse>) -> u64 {
-    use core::hash::{Hash, Hasher};
-    
-    let mut hasher: fast_hash::Hasher = d!();
-    to_parse.hash(&mut hasher);
-    hasher.finish()
+/// The length that text of length `len` has after `edits` are applied to it.
+fn edited_len(len: usize, edits: &[InputEdit]) -> usize {
+    edits.iter().fold(len, |len, edit| {
+        (len + edit.new_end_byte).saturating_sub(edit.old_end_byte)
+    })
 }
 
-extern "C" { fn tree_sitter_rust() -> Language; }
-
 impl Parsers {
     fn attempt_init(&mut self) {
         use Parsers::*;
@@ -604,44 +1225,44 @@ impl Parsers {
 
 impl InitializedParsers {
     fn new() -> Result<Self, InitializationError> {
-        let rust_lang = unsafe { tree_sitter_rust() };
+        let mut first: BufferState = d!();
+        let mut languages = Vec::with_capacity(LANGUAGES.len());
+        for spec in LANGUAGES.iter() {
+            let language = unsafe { (spec.grammar)() };
 
-        let mut first: BufferState = d!();
-        // We make sure that each supported language works at the start
-        // so we can assume `set_language` always returns `Ok` afterwards.
+            // We make sure that each supported language works at the start
+            // so we can assume `set_language` always returns `Ok` afterwards.
+            first.parser.set_language(
+                language
+            )?;
 
-        first.parser.set_language(
-            rust_lang
-        )?;
+            let highlight_query = Query::new(
+                language,
+                spec.highlight_query
+            )?;
+
+            languages.push(LoadedLanguage {
+                language,
+                highlight_query,
+            });
+        }
 
         let mut parser_map: ParserMap = d!();
         parser_map.insert(d!(), first);
 
-        let rust_basic_query = Query::new(
-            rust_lang,
-            RUST_BASIC_QUERY_SOURCE
-        )?;
-
         Ok(InitializedParsers {
             parser_map,
-            rust_lang,
-            rust_basic_query,
+            languages,
         })
     }
 }
-
-const RUST_BASIC_QUERY_SOURCE: &str = "
-(line_comment) @comment
-(block_comment) @comment
-(string_literal) @string
-(char_literal) @string
-";
+
+mod extract;
 
 mod query {
-    use crate::{
-        ToParse
-    };
+    use crate::BRACKET_PAIRS;
     use tree_sitter::{
+        InputEdit,
         Node,
         Query,
         QueryCapture,
@@ -649,7 +1270,14 @@ mod query {
         Tree,
         TreeCursor
     };
-    pub use platform_types::{ByteIndex, Spans, SpanView, SpanKind, sk, sv};
+    pub use platform_types::{ByteIndex, Fold, Spans, SpanView, SpanKind, sk, sv};
+    use edit::SwapDirection;
+    use macros::some_or;
+
+    use std::cmp::min;
+
+    /// A range of bytes, including both ends.
+    pub type ByteRange = (usize, usize);
 
     enum SpanKindSpec {
         DropNode,
@@ -679,17 +1307,17 @@ mod query {
     }
 
     struct Match<'capture> {
-        pattern_index: usize,
+        capture_name: &'capture str,
         capture: QueryCapture<'capture>
     }
 
-    fn rust_basic_span_kind_from_match(Match {    
-        pattern_index,
+    fn basic_span_kind_from_match(Match {
+        capture_name,
         capture: _
     } : Match) -> SpanKind {
-        match pattern_index {
-            0 | 1 => SpanKind::COMMENT,
-            2 => SpanKind::STRING,
+        match capture_name {
+            "comment" => SpanKind::COMMENT,
+            "string" => SpanKind::STRING,
             _ => SpanKind::PLAIN,
         }
     }
@@ -700,12 +1328,12 @@ mod query {
     }
 
     #[perf_viz::record]
-    pub fn plaintext_spans_for(s: ToParse<'_>) -> Spans {
-        Spans::from(vec![plaintext_end_span_for(s)])
+    pub fn plaintext_spans_for(len: usize) -> Spans {
+        Spans::from(vec![plaintext_end_span_for(len)])
     }
     
-    fn plaintext_end_span_for(s: ToParse<'_>) -> SpanView {
-        sv!(i s.len(), k PLAIN)
+    fn plaintext_end_span_for(len: usize) -> SpanView {
+        sv!(i len, k PLAIN)
     }
 
     #[perf_viz::record]
@@ -718,8 +1346,25 @@ mod query {
             tree,
             query,
             to_parse,
-            // TODO take a parameter that determines what to pass here
-            rust_basic_span_kind_from_match,
+            None,
+            basic_span_kind_from_match,
+        )
+    }
+
+    /// Like `spans_for`, except that the spans are only correct within `range`.
+    #[perf_viz::record]
+    pub fn spans_within<'to_parse>(
+        tree: &Tree,
+        query: &Query,
+        to_parse: &'to_parse str,
+        range: ByteRange,
+    ) -> Spans {
+        spans_for_inner(
+            tree,
+            query,
+            to_parse,
+            Some(range),
+            basic_span_kind_from_match,
         )
     }
     
@@ -727,6 +1372,7 @@ mod query {
         tree: &Tree,
         query: &Query,
         to_parse: &'to_parse str,
+        within: Option<ByteRange>,
         span_kind_from_match: fn(Match) -> SpanKind,
     ) -> Spans {
         let mut spans = Vec::with_capacity(get_spans_capacity(
@@ -757,7 +1403,7 @@ mod query {
                 }
             }
         
-            if get_prev!().one_past_end <= node.start_byte() {
+            while get_prev!().one_past_end <= node.start_byte() {
                 if let Some(s) = span_stack.pop() {
                     spans.push(s);
                 }
@@ -775,22 +1421,26 @@ mod query {
         };
     
         let mut query_cursor = QueryCursor::new();
+        if let Some((start, end)) = within {
+            // Widened so that nodes that only touch the ends are included.
+            query_cursor.set_byte_range(start.saturating_sub(1), end + 1);
+        }
 
         let text_callback = |n: Node| {
             let r = n.range();
             &to_parse[r.start_byte..r.end_byte]
         };
 
+        let capture_names = query.capture_names();
+
         for q_match in query_cursor.matches(
             query,
             tree.root_node(),
             text_callback,
         ) {
-            let pattern_index: usize = q_match.pattern_index;
-
             for q_capture in q_match.captures.iter() {
                 receive_match(Match {
-                    pattern_index,
+                    capture_name: &capture_names[q_capture.index as usize],
                     capture: *q_capture
                 });
             }
@@ -834,14 +1484,29 @@ mod query {
         to_parse: &'to_parse str,
     ) -> Spans {
         totally_classified_spans_for_inner(
-            tree,
+            nodes_from_tree(tree),
             to_parse,
             rust_extra_span_kind_from_node,
         )
     }
 
-    fn totally_classified_spans_for_inner<'to_parse>(
+    /// Like `totally_classified_spans_for`, except that the spans are only correct
+    /// within `range`.
+    #[perf_viz::record]
+    pub fn totally_classified_spans_within<'to_parse>(
         tree: &Tree,
+        to_parse: &'to_parse str,
+        range: ByteRange,
+    ) -> Spans {
+        totally_classified_spans_for_inner(
+            nodes_touching(tree, range),
+            to_parse,
+            rust_extra_span_kind_from_node,
+        )
+    }
+
+    fn totally_classified_spans_for_inner<'tree, 'to_parse>(
+        nodes: impl IntoIterator<Item = Node<'tree>>,
         to_parse: &'to_parse str,
         span_kind_from_node: fn(Node) -> SpanKindSpec,
     ) -> Spans {
@@ -852,7 +1517,7 @@ mod query {
         let mut drop_until_end_byte = None;
     
         perf_viz::start_record!("DepthFirst::new(tree)");
-        for node in nodes_from_tree(tree) {
+        for node in nodes {
             perf_viz::record_guard!("for (_, node) in DepthFirst::new(tree) body");
             use SpanKindSpec::*;
     
@@ -922,6 +1587,127 @@ mod query {
     fn nodes_from_tree(tree: &Tree) -> Vec<Node> {
         DepthFirst::new(tree).map(|(_, node)| node).collect::<Vec<_>>()
     }
+
+    /// The root node, followed by the nodes under each of the root's children that
+    /// touch `range`, in the same order that `nodes_from_tree` would produce them.
+    fn nodes_touching(tree: &Tree, (start, end): ByteRange) -> Vec<Node> {
+        let root = tree.root_node();
+        let mut nodes = vec![root];
+
+        let mut cursor = root.walk();
+        for child in root.children(&mut cursor) {
+            if child.start_byte() <= end && start <= child.end_byte() {
+                nodes.extend(DepthFirst::from_node(child).map(|(_, node)| node));
+            }
+        }
+
+        nodes
+    }
+
+    /// Returns the spans for `tree`, which was parsed from `to_parse` using `old_tree`,
+    /// after `edits` were applied to `old_tree`. The spans of the parts of the text
+    /// that the edits did not change are taken from `old_spans`, which should be the
+    /// spans of the text that `old_tree` was parsed from. The rest are taken from
+    /// `spans_within`, which should return spans that are correct within the passed
+    /// range.
+    #[perf_viz::record]
+    pub fn reused_spans_for(
+        tree: &Tree,
+        old_tree: &Tree,
+        edits: &[InputEdit],
+        old_spans: &[SpanView],
+        to_parse: &str,
+        mut spans_within: impl FnMut(ByteRange) -> Spans,
+    ) -> Spans {
+        let changed_ranges: Vec<_> = old_tree.changed_ranges(tree).collect();
+
+        // Each of the root's children, along with the text between it and the
+        // previous one, gets the same spans no matter what is outside of it. So we
+        // can reuse the old spans of each of those chunks that was not changed.
+        let root = tree.root_node();
+        let mut cursor = root.walk();
+        let mut chunk_ends: Vec<usize> = root.children(&mut cursor)
+            .map(|child| child.end_byte())
+            .collect();
+        chunk_ends.push(to_parse.len());
+
+        let mut spans = Vec::with_capacity(old_spans.len() + chunk_ends.len());
+
+        let mut start = 0;
+        for end in chunk_ends {
+            if end <= start {
+                continue;
+            }
+            let range = (start, end);
+
+            let is_changed = changed_ranges.iter()
+                .any(|r| r.start_byte <= end && start <= r.end_byte);
+
+            match old_range_for(edits, range).filter(|_| !is_changed) {
+                Some(old_range) => {
+                    push_clipped(
+                        &mut spans,
+                        old_spans,
+                        old_range,
+                        start as isize - old_range.0 as isize
+                    );
+                },
+                None => {
+                    let fresh_spans: Vec<SpanView> = spans_within(range).into();
+                    push_clipped(&mut spans, &fresh_spans, range, 0);
+                },
+            }
+
+            start = end;
+        }
+
+        if !spans.is_empty() {
+            dedup_by_kind_keeping_last(&mut spans);
+        }
+
+        spans.into()
+    }
+
+    /// Maps `range` in the edited text back to where it was before `edits` were
+    /// applied, if none of the edits touched it.
+    fn old_range_for(edits: &[InputEdit], (mut start, mut end): ByteRange) -> Option<ByteRange> {
+        for edit in edits.iter().rev() {
+            if end < edit.start_byte {
+                // The edit was entirely after the range, so it did not move it.
+            } else if start > edit.new_end_byte {
+                start = start - edit.new_end_byte + edit.old_end_byte;
+                end = end - edit.new_end_byte + edit.old_end_byte;
+            } else {
+                return None;
+            }
+        }
+
+        Some((start, end))
+    }
+
+    /// Pushes the parts of `spans` that are in the range from `start` up to, but not
+    /// including `end`, moved over by `shift`.
+    fn push_clipped(
+        output: &mut Vec<SpanView>,
+        spans: &[SpanView],
+        (start, end): ByteRange,
+        shift: isize,
+    ) {
+        let first_index = spans.partition_point(|s| s.one_past_end.0 <= start);
+
+        for s in spans[first_index..].iter() {
+            let one_past_end = min(s.one_past_end.0, end);
+
+            output.push(SpanView {
+                one_past_end: ByteIndex((one_past_end as isize + shift) as usize),
+                kind: s.kind,
+            });
+
+            if one_past_end == end {
+                break;
+            }
+        }
+    }
     
     type Depth = u8;
     
@@ -1013,6 +1799,15 @@ mod query {
                 done: false,
             }
         }
+
+        /// Goes through `node` and the nodes under it, with `node` at depth 1.
+        fn from_node(node: Node<'tree>) -> Self {
+            DepthFirst {
+                depth: 1,
+                cursor: node.walk(),
+                done: false,
+            }
+        }
     }
     
     impl <'tree> Iterator for DepthFirst<'tree> {
@@ -1101,6 +1896,194 @@ mod query {
         spans.truncate(write + 1);
     }
 
+    /// Each named node that spans enough lines suggests a fold of the lines strictly
+    /// between its first and last lines, so that, for example, the signature and the
+    /// closing brace of a function stay visible when its body is folded.
+    #[perf_viz::record]
+    pub fn fold_candidates(tree: &Tree) -> Vec<Fold> {
+        let mut folds: Vec<Fold> = DepthFirst::new(tree)
+            .filter(|(depth, node)| *depth > 0 && node.is_named())
+            .filter_map(|(_, node)| {
+                let start = node.start_position();
+                let end = node.end_position();
+                // A node that ends at the start of a line does not really include that line.
+                let last_row = if end.column == 0 {
+                    end.row.checked_sub(1)?
+                } else {
+                    end.row
+                };
+
+                let first_line = start.row + 1;
+                let last_line = last_row.checked_sub(1)?;
+
+                if first_line <= last_line {
+                    Some(Fold { first_line, last_line })
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        folds.sort();
+        folds.dedup();
+
+        folds
+    }
+
+    /// What the tree says about a bracket.
+    #[derive(Debug, PartialEq, Eq)]
+    pub enum TreeBracket {
+        /// The char is part of some other token, like a string or a comment.
+        NotABracket,
+        /// The matching bracket starts at this byte.
+        Matched(ByteIndex),
+        /// The tree does not know where the matching bracket is, if there is one.
+        /// This happens when the brackets are not balanced, for example.
+        Unknown,
+    }
+
+    /// Looks for the bracket matching the one at `byte` among the siblings of the
+    /// bracket's node, since tree-sitter puts a pair of brackets in the same node.
+    pub fn matching_bracket(
+        tree: &Tree,
+        byte: ByteIndex,
+        (open, close): (char, char),
+        is_open: bool,
+    ) -> TreeBracket {
+        let node = some_or!(
+            tree.root_node().descendant_for_byte_range(byte.0, byte.0 + 1),
+            return TreeBracket::Unknown
+        );
+
+        let (own, other) = if is_open { (open, close) } else { (close, open) };
+        let is_bracket = |node: Node, c: char| {
+            let mut buffer = [0; 4];
+            node.kind() == c.encode_utf8(&mut buffer)
+        };
+
+        if !is_bracket(node, own) || node.start_byte() != byte.0 {
+            return if node.is_error() {
+                TreeBracket::Unknown
+            } else {
+                TreeBracket::NotABracket
+            };
+        }
+
+        let mut sibling = if is_open { node.next_sibling() } else { node.prev_sibling() };
+        while let Some(s) = sibling {
+            if is_bracket(s, other) {
+                return if s.is_missing() {
+                    TreeBracket::Unknown
+                } else {
+                    TreeBracket::Matched(ByteIndex(s.start_byte()))
+                };
+            }
+
+            sibling = if is_open { s.next_sibling() } else { s.prev_sibling() };
+        }
+
+        TreeBracket::Unknown
+    }
+
+    /// Returns how many pairs of brackets enclose `byte`, counting pairs that were
+    /// opened on the same line as one, since that is how they are usually indented.
+    /// A pair encloses `byte` if its opening bracket ends at or before it, and its
+    /// closing bracket starts after it, or is missing because the code is unfinished.
+    /// If a closing bracket starts at `byte`, then the pairs opened on the same line as
+    /// its opening bracket do not count either, so `})` is as deep as the line with
+    /// `({` on it. Returns `None` if `byte` is inside an error node, since then the tree cannot
+    /// be relied on.
+    pub fn nesting_depth(tree: &Tree, byte: ByteIndex) -> Option<usize> {
+        let mut node = tree.root_node().descendant_for_byte_range(byte.0, byte.0)?;
+
+        // Ancestors never start on a later row, so equal rows are always next to each other.
+        let mut previous_row = None;
+        let mut closed_row = None;
+        let mut depth = 0;
+        loop {
+            if node.is_error() {
+                return None;
+            }
+
+            let count = node.child_count();
+            if let (true, Some(first), Some(last)) = (
+                count >= 2,
+                node.child(0),
+                node.child(count.saturating_sub(1)),
+            ) {
+                let is_pair = BRACKET_PAIRS.iter().any(|&(open, close)| {
+                    let mut buffer = [0; 4];
+                    first.kind() == open.encode_utf8(&mut buffer)
+                    && last.kind() == close.encode_utf8(&mut buffer)
+                });
+
+                let row = first.start_position().row;
+                if is_pair && !last.is_missing() && last.start_byte() == byte.0 {
+                    closed_row = Some(row);
+                }
+
+                if is_pair
+                && first.end_byte() <= byte.0
+                && (last.is_missing() || byte.0 < last.start_byte()) {
+                    if previous_row != Some(row) && closed_row != Some(row) {
+                        depth += 1;
+                        previous_row = Some(row);
+                    }
+                }
+            }
+
+            node = some_or!(node.parent(), break);
+        }
+
+        Some(depth)
+    }
+
+    /// Returns whether `node` is in a field of its parent, like the name of a function,
+    /// as opposed to being, say, one of the statements in a block.
+    fn is_field(node: Node) -> bool {
+        let parent = some_or!(node.parent(), return false);
+        let mut cursor = parent.walk();
+        if cursor.goto_first_child() {
+            loop {
+                if cursor.node() == node {
+                    return cursor.field_name().is_some();
+                }
+                if !cursor.goto_next_sibling() {
+                    break
+                }
+            }
+        }
+        false
+    }
+
+    /// Returns the byte range of the smallest named node containing `selection` that has
+    /// a named sibling in the given direction, along with the byte range of that sibling.
+    /// Nodes in fields of their parent are skipped, so we don't, for example, swap the
+    /// name and the type of a parameter.
+    pub fn sibling_swap(
+        tree: &Tree,
+        (start, end): (ByteIndex, ByteIndex),
+        direction: SwapDirection,
+    ) -> Option<((ByteIndex, ByteIndex), (ByteIndex, ByteIndex))> {
+        let byte_range = |node: Node| (ByteIndex(node.start_byte()), ByteIndex(node.end_byte()));
+
+        let mut node = tree.root_node().descendant_for_byte_range(start.0, end.0)?;
+        loop {
+            if node.is_named() && !is_field(node) {
+                let sibling = match direction {
+                    SwapDirection::Up => node.prev_named_sibling(),
+                    SwapDirection::Down => node.next_named_sibling(),
+                };
+
+                if let Some(sibling) = sibling {
+                    return Some((byte_range(node), byte_range(sibling)));
+                }
+            }
+
+            node = node.parent()?;
+        }
+    }
+
     #[allow(dead_code)]
     fn recursive_dbg(node: Option<Node>) {
         recursive_dbg_helper(node, 0)
@@ -2,6 +2,8 @@ use crate::{
     Parser,
     ParserKind,
     Parsers,
+    Rope,
+    RUST,
     Style,
     tree_sitter_rust,
     query::{self, *, sk},
@@ -9,6 +11,7 @@ use crate::{
 use macros::d;
 use platform_types::{spans_assert};
 use proptest::proptest;
+use tree_sitter::{InputEdit, Point};
 
 const SOME_AMOUNT: usize = 16;
 
@@ -88,10 +91,10 @@ fn get_rust_parser_and_query(query_source: &str) -> (Parser, Query) {
 }
 
 fn arbitary_span_kind_from_match(Match {    
-    pattern_index,
-    capture: _
+    capture_name: _,
+    capture,
 } : Match) -> SpanKind {
-    sk!(pattern_index.to_le_bytes()[0])
+    sk!(capture.index.to_le_bytes()[0])
 }
 
 macro_rules! parse_expect {
@@ -115,7 +118,7 @@ fn query_spans_for_produces_valid_rust_spans_on(
 
     let tree = parse_expect!(rust.parse(&code, None));
     
-    let spans = query::spans_for_inner(&tree, &query, code, arbitary_span_kind_from_match);
+    let spans = query::spans_for_inner(&tree, &query, code, None, arbitary_span_kind_from_match);
 
     spans_assert!(spans);
 }
@@ -167,12 +170,12 @@ fn query_spans_for_produces_the_right_result_on_this_multiple_match_case() {
     // As of this writing this only works if OTHER is the default.
     const OTHER: SpanKind = SpanKind::PLAIN; 
     fn span_kind_from_match_example(Match {    
-        pattern_index,
+        capture_name,
         ..
     } : Match) -> SpanKind {    
-        match pattern_index {
-            0 => OUTER,
-            1 => INNER,
+        match capture_name {
+            "p" => OUTER,
+            "t" => INNER,
             _ => OTHER,
         }
     }
@@ -187,7 +190,7 @@ fn query_spans_for_produces_the_right_result_on_this_multiple_match_case() {
 
     let tree = parse_expect!(rust.parse(foo, None));
     
-    let spans = query::spans_for_inner(&tree, &query, foo, span_kind_from_match_example);
+    let spans = query::spans_for_inner(&tree, &query, foo, None, span_kind_from_match_example);
 
     assert_eq!(
         spans,
@@ -197,6 +200,51 @@ fn query_spans_for_produces_the_right_result_on_this_multiple_match_case() {
             SpanView{ one_past_end: ByteIndex(11), kind: INNER},
             SpanView{ one_past_end: ByteIndex(12), kind: OUTER},
             SpanView{ one_past_end: ByteIndex(foo.len()), kind: OTHER},
+        ]
+    )
+}
+
+#[test]
+fn query_spans_for_ends_every_capture_that_ended_before_the_next_one_in_this_nested_case() {
+    const OUTER: SpanKind = sk!(1);
+    const INNER: SpanKind = sk!(2);
+    fn span_kind_from_match_example(Match {
+        capture_name,
+        ..
+    } : Match) -> SpanKind {
+        match capture_name {
+            "p" => OUTER,
+            "t" => INNER,
+            _ => PLAIN,
+        }
+    }
+
+    // The parameters of `f` and the type inside them both end before the parameters
+    // of `g` start, so the text between them should not get the kind of either.
+    let foo = "fn f(s: i32) {} fn g() -> u8 {}";
+    let query_source = "
+        (parameters) @p
+        (primitive_type) @t
+    ";
+
+    let (mut rust, query) = get_rust_parser_and_query(query_source);
+
+    let tree = parse_expect!(rust.parse(foo, None));
+
+    let spans = query::spans_for_inner(&tree, &query, foo, None, span_kind_from_match_example);
+
+    assert_eq!(
+        spans,
+        vec![
+            SpanView{ one_past_end: ByteIndex(4), kind: PLAIN},
+            SpanView{ one_past_end: ByteIndex(8), kind: OUTER},
+            SpanView{ one_past_end: ByteIndex(11), kind: INNER},
+            SpanView{ one_past_end: ByteIndex(12), kind: OUTER},
+            SpanView{ one_past_end: ByteIndex(20), kind: PLAIN},
+            SpanView{ one_past_end: ByteIndex(22), kind: OUTER},
+            SpanView{ one_past_end: ByteIndex(26), kind: PLAIN},
+            SpanView{ one_past_end: ByteIndex(28), kind: INNER},
+            SpanView{ one_past_end: ByteIndex(foo.len()), kind: PLAIN},
         ]
     )
 }
@@ -204,12 +252,12 @@ fn query_spans_for_produces_the_right_result_on_this_multiple_match_case() {
 #[test]
 fn query_spans_for_produces_the_right_result_on_this_basic_case() {
     fn span_kind_from_match_example(Match {    
-        pattern_index,
+        capture_name,
         ..
     } : Match) -> SpanKind {    
-        match pattern_index {
-            0 | 1 => COMMENT,
-            2 => STRING,
+        match capture_name {
+            "c1" | "c2" => COMMENT,
+            "s" => STRING,
             _ => PLAIN,
         }
     }
@@ -234,6 +282,7 @@ let hi = \"hi\";
         &tree,
         &query,
         foo,
+        None,
         span_kind_from_match_example
     );
 
@@ -252,12 +301,12 @@ let hi = \"hi\";
 #[test]
 fn query_spans_for_produces_the_right_result_on_this_less_basic_case() {
     fn span_kind_from_match_example(Match {    
-        pattern_index,
+        capture_name,
         ..
     } : Match) -> SpanKind {    
-        match pattern_index {
-            0 | 1 => COMMENT,
-            2 => STRING,
+        match capture_name {
+            "c1" | "c2" => COMMENT,
+            "s" => STRING,
             _ => PLAIN,
         }
     }
@@ -279,7 +328,7 @@ let yo = \"yo\";
 
     let tree = parse_expect!(rust.parse(foo, None));
     
-    let spans = query::spans_for_inner(&tree, &query, foo, span_kind_from_match_example);
+    let spans = query::spans_for_inner(&tree, &query, foo, None, span_kind_from_match_example);
 
     assert_eq!(
         spans,
@@ -303,7 +352,7 @@ fn totally_classified_spans_for_produces_valid_rust_spans_on(code: &str) {
     let tree = get_rust_tree!(code);
 
     let spans = query::totally_classified_spans_for_inner(
-        &tree,
+        query::nodes_from_tree(&tree),
         code,
         arbitary_span_kind_from_node,
     );
@@ -474,9 +523,10 @@ fn rust_extra_spans_should_not_give_paired_tokens_different_kinds_on(
 
     let buffer_name = d!();
     let spans = Parsers::default().get_spans_result(
-        code.into(), 
+        &Rope::from(code.as_str()),
+        0,
         &buffer_name,
-        ParserKind::Rust(Style::Extra),
+        ParserKind::Tree(RUST, Style::Extra),
     ).expect("inside rust_extra_spans_should_not_give_paired_tokens_different_kinds_on");
     
     spans_assert!(&spans);
@@ -567,9 +617,10 @@ fn rust_extra_spans_should_not_give_paired_tokens_different_kinds_on_this_reduce
 fn rust_extra_spans_produces_valid_spans_on(code: &str) {
     let buffer_name = d!();
     let spans = Parsers::default().get_spans_result(
-        code.into(),
+        &Rope::from(code),
+        0,
         &buffer_name,
-        ParserKind::Rust(Style::Extra),
+        ParserKind::Tree(RUST, Style::Extra),
     ).expect("inside rust_extra_spans_produces_valid_spans_on");
 
     spans_assert!(spans);
@@ -980,4 +1031,204 @@ fn filter_spans_produces_valid_spans_in_this_three_in_a_row_case() {
     filter_spans(&mut spans);
 
      spans_assert!(spans);
-}
\ No newline at end of file
+}
+#[test]
+fn fold_candidates_keeps_the_first_and_last_lines_of_these_items_visible() {
+    let code = "impl A {
+    fn f() {
+        1;
+        2;
+    }
+}
+";
+    let tree = get_rust_tree!(code);
+
+    assert_eq!(
+        fold_candidates(&tree),
+        vec![
+            Fold { first_line: 1, last_line: 4 },
+            Fold { first_line: 2, last_line: 3 },
+        ]
+    );
+}
+
+#[test]
+fn sibling_swap_finds_the_parameter_containing_the_cursor_and_the_next_one() {
+    let code = "fn f(a: A, b: B) {}";
+    let tree = get_rust_tree!(code);
+
+    let cursor = ByteIndex(code.find("A").unwrap());
+
+    assert_eq!(
+        sibling_swap(&tree, (cursor, cursor), SwapDirection::Down),
+        Some((
+            (ByteIndex(5), ByteIndex(9)),
+            (ByteIndex(11), ByteIndex(15)),
+        ))
+    );
+    assert_eq!(
+        sibling_swap(&tree, (cursor, cursor), SwapDirection::Up),
+        None
+    );
+}
+
+#[test]
+fn matching_bracket_finds_the_other_bracket_of_the_node_and_ignores_strings() {
+    let code = "fn f() { g(\")\") }";
+    let tree = get_rust_tree!(code);
+
+    let open = ByteIndex(code.find("g(").unwrap() + 1);
+    let close = ByteIndex(code.rfind(")").unwrap());
+    let in_string = ByteIndex(code.find("\")").unwrap() + 1);
+
+    assert_eq!(
+        matching_bracket(&tree, open, ('(', ')'), true),
+        TreeBracket::Matched(close)
+    );
+    assert_eq!(
+        matching_bracket(&tree, close, ('(', ')'), false),
+        TreeBracket::Matched(open)
+    );
+    assert_eq!(
+        matching_bracket(&tree, in_string, ('(', ')'), false),
+        TreeBracket::NotABracket
+    );
+}
+
+#[test]
+fn nesting_depth_counts_brackets_opened_on_the_same_line_once() {
+    let code = "fn f() {\n    h({\n        a\n    });\n}";
+    let tree = get_rust_tree!(code);
+
+    let depth = |s: &str| nesting_depth(&tree, ByteIndex(code.find(s).unwrap()));
+
+    assert_eq!(depth("fn"), Some(0));
+    assert_eq!(depth("h("), Some(1));
+    assert_eq!(depth("a"), Some(2));
+    assert_eq!(depth("})"), Some(1));
+}
+
+fn input_edit_for(code: &str, start: usize, old_end: usize, inserted: &str) -> InputEdit {
+    let point_at = |s: &str, byte: usize| {
+        let before = &s[..byte];
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        Point {
+            row: before.matches('\n').count(),
+            column: byte - line_start,
+        }
+    };
+
+    let new_end = start + inserted.len();
+    let mut edited = code.to_owned();
+    edited.replace_range(start..old_end, inserted);
+
+    InputEdit {
+        start_byte: start,
+        old_end_byte: old_end,
+        new_end_byte: new_end,
+        start_position: point_at(code, start),
+        old_end_position: point_at(code, old_end),
+        new_end_position: point_at(&edited, new_end),
+    }
+}
+
+/// Each edit is a byte to start at, a count of bytes to delete, and a string to insert,
+/// with the byte indexes moved back onto char boundaries.
+fn reused_spans_for_matches_the_spans_from_scratch_on(
+    code: &str,
+    edits: &[(usize, usize, &str)],
+) {
+    let (mut rust, query) = get_rust_parser_and_query(crate::LANGUAGES[0].highlight_query);
+
+    let old_tree = parse_expect!(rust.parse(code, None));
+    let old_basic = Vec::from(spans_for(&old_tree, &query, code));
+    let old_extra = Vec::from(totally_classified_spans_for(&old_tree, code));
+
+    let mut edited_tree = old_tree.clone();
+    let mut text = code.to_owned();
+    let mut input_edits = Vec::with_capacity(edits.len());
+    for &(start, delete_count, inserted) in edits {
+        let mut start = std::cmp::min(start, text.len());
+        while !text.is_char_boundary(start) {
+            start -= 1;
+        }
+        let mut old_end = std::cmp::min(start + delete_count, text.len());
+        while !text.is_char_boundary(old_end) {
+            old_end -= 1;
+        }
+
+        let input_edit = input_edit_for(&text, start, old_end, inserted);
+        edited_tree.edit(&input_edit);
+        input_edits.push(input_edit);
+
+        text.replace_range(start..old_end, inserted);
+    }
+
+    let tree = parse_expect!(rust.parse(&text, Some(&edited_tree)));
+
+    assert_eq!(
+        reused_spans_for(
+            &tree,
+            &edited_tree,
+            &input_edits,
+            &old_basic,
+            &text,
+            |range| spans_within(&tree, &query, &text, range),
+        ),
+        spans_for(&tree, &query, &text),
+        "basic spans differ for {:?}",
+        text
+    );
+
+    assert_eq!(
+        reused_spans_for(
+            &tree,
+            &edited_tree,
+            &input_edits,
+            &old_extra,
+            &text,
+            |range| totally_classified_spans_within(&tree, &text, range),
+        ),
+        totally_classified_spans_for(&tree, &text),
+        "extra spans differ for {:?}",
+        text
+    );
+}
+
+proptest!{
+    #[test]
+    fn reused_spans_for_matches_the_spans_from_scratch(
+        code in arb::rust_code(4),
+        edits in proptest::collection::vec(
+            (0..256usize, 0..8usize, "[a-z0-9 \n\"'/*(){};:]{0,6}"),
+            1..4
+        ),
+    ) {
+        let edits: Vec<_> = edits.iter()
+            .map(|(start, delete_count, inserted)| (*start, *delete_count, inserted.as_str()))
+            .collect();
+
+        reused_spans_for_matches_the_spans_from_scratch_on(&code, &edits);
+    }
+}
+
+#[test]
+fn reused_spans_for_matches_the_spans_from_scratch_when_a_comment_is_opened_above_a_function() {
+    let code = "fn f() {\n    a\n}\n\nfn g() -> &'static str {\n    \"b\"\n}\n";
+
+    reused_spans_for_matches_the_spans_from_scratch_on(
+        code,
+        &[(code.find("fn g").unwrap(), 0, "/*")]
+    );
+}
+
+#[test]
+fn reused_spans_for_matches_the_spans_from_scratch_when_typing_in_the_middle_function() {
+    let code = "fn f() {}\n\nfn g() {\n    let x = 1;\n}\n\nfn h() { \"s\" }\n";
+    let start = code.find("1;").unwrap();
+
+    reused_spans_for_matches_the_spans_from_scratch_on(
+        code,
+        &[(start, 0, "2"), (start + 1, 0, " + \"")]
+    );
+}
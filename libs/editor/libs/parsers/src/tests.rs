@@ -1,4 +1,5 @@
 use super::*;
+use platform_types::SpanView;
 
 // This test demonstrates what I believe is a bug in tree-sitter. We have an 
 // attempted workaround, in `parsers.rs` but if this test starts passing in the 
@@ -22,3 +23,196 @@ fn asking_to_parse_the_empty_string_twice_does_not_panic() {
 
     assert!(final_tree.is_some());
 }
+
+#[test]
+fn get_bracket_matches_uses_the_tree_and_skips_brackets_in_strings() {
+    u!{ParserKind}
+    let buffer_name = BufferName::Path("fakefile.rs".into());
+    let code = "fn f() { g(\"(\", [1]) }";
+    let rope = Rope::from(code);
+
+    let mut parsers = Parsers::default();
+
+    let offset = |s: &str| AbsoluteCharOffset(code.find(s).unwrap());
+
+    let matches = parsers.get_bracket_matches(
+        &buffer_name,
+        Tree(RUST, d!()),
+        &rope,
+        0,
+        &[offset("{"), offset(" }"), offset("\"(") + 1, offset("]")],
+    );
+
+    assert_eq!(
+        matches,
+        vec![
+            Some(BracketMatch { bracket: offset("{"), matching: Some(offset("}")) }),
+            Some(BracketMatch { bracket: offset(" }") - 1, matching: Some(offset("g(") + 1) }),
+            // Brackets in strings don't count.
+            None,
+            Some(BracketMatch { bracket: offset("]"), matching: Some(offset("[")) }),
+        ]
+    );
+}
+
+#[test]
+fn get_bracket_matches_scans_the_text_of_plaintext_buffers() {
+    u!{ParserKind}
+    let buffer_name = BufferName::Path("fakefile.txt".into());
+    let rope = Rope::from("(a [b) c(");
+
+    let mut parsers = Parsers::default();
+
+    let matches = parsers.get_bracket_matches(
+        &buffer_name,
+        Plaintext,
+        &rope,
+        0,
+        &[AbsoluteCharOffset(0), AbsoluteCharOffset(3), AbsoluteCharOffset(9), AbsoluteCharOffset(2)],
+    );
+
+    assert_eq!(
+        matches,
+        vec![
+            Some(BracketMatch { bracket: AbsoluteCharOffset(0), matching: Some(AbsoluteCharOffset(5)) }),
+            Some(BracketMatch { bracket: AbsoluteCharOffset(3), matching: None }),
+            Some(BracketMatch { bracket: AbsoluteCharOffset(8), matching: None }),
+            None,
+        ]
+    );
+}
+
+#[test]
+fn get_bracket_matches_finds_new_matches_when_the_offsets_or_the_version_change() {
+    u!{ParserKind}
+    let buffer_name = BufferName::Path("fakefile.txt".into());
+    let rope = Rope::from("(a) [b]");
+
+    let mut parsers = Parsers::default();
+
+    let mut matches = |rope: &Rope, version, offset| parsers.get_bracket_matches(
+        &buffer_name,
+        Plaintext,
+        rope,
+        version,
+        &[AbsoluteCharOffset(offset)],
+    );
+
+    assert_eq!(
+        matches(&rope, 0, 0),
+        vec![Some(BracketMatch { bracket: AbsoluteCharOffset(0), matching: Some(AbsoluteCharOffset(2)) })]
+    );
+    assert_eq!(
+        matches(&rope, 0, 4),
+        vec![Some(BracketMatch { bracket: AbsoluteCharOffset(4), matching: Some(AbsoluteCharOffset(6)) })]
+    );
+
+    let rope = Rope::from("(a)) [b]");
+
+    assert_eq!(
+        matches(&rope, 1, 4),
+        vec![Some(BracketMatch { bracket: AbsoluteCharOffset(3), matching: None })]
+    );
+}
+
+#[test]
+fn get_enclosing_brackets_gives_the_next_pair_out_if_the_contents_are_selected() {
+    u!{ParserKind}
+    let buffer_name = BufferName::Path("fakefile.rs".into());
+    let code = "fn f() { g(a[1], b) }";
+    let rope = Rope::from(code);
+
+    let mut parsers = Parsers::default();
+
+    let offset = |s: &str| AbsoluteCharOffset(code.find(s).unwrap());
+
+    let enclosing = parsers.get_enclosing_brackets(
+        &buffer_name,
+        Tree(RUST, d!()),
+        &rope,
+        0,
+        &[
+            (offset("1"), offset("1")),
+            (offset("1"), offset("]")),
+            (offset("b"), offset("b")),
+        ],
+    );
+
+    assert_eq!(
+        enclosing,
+        vec![
+            Some((offset("["), offset("]"))),
+            Some((offset("g(") + 1, offset(") }"))),
+            Some((offset("g(") + 1, offset(") }"))),
+        ]
+    );
+}
+
+#[test]
+fn get_enclosing_brackets_does_not_close_an_open_bracket_with_another_kind_of_bracket() {
+    u!{ParserKind}
+    let buffer_name = BufferName::Path("fakefile.txt".into());
+    let rope = Rope::from("(a] b)");
+
+    let mut parsers = Parsers::default();
+
+    let enclosing = parsers.get_enclosing_brackets(
+        &buffer_name,
+        Plaintext,
+        &rope,
+        0,
+        &[(AbsoluteCharOffset(4), AbsoluteCharOffset(4))],
+    );
+
+    assert_eq!(
+        enclosing,
+        vec![Some((AbsoluteCharOffset(0), AbsoluteCharOffset(5)))]
+    );
+}
+
+#[test]
+fn each_registered_language_is_picked_by_extension_and_highlights_strings() {
+    u!{ParserKind}
+    let examples = [
+        ("rs", "fn f() { \"s\" }"),
+        ("c", "char *s = \"s\";"),
+        ("json", "{\"a\": [1, \"s\"]}"),
+        ("md", "# a\n\nsome `code`\n"),
+        ("toml", "[a]\nb = \"s\"\n"),
+    ];
+    assert_eq!(examples.len(), LANGUAGES.len());
+
+    let mut parsers = Parsers::default();
+
+    for &(extension, code) in examples.iter() {
+        let buffer_name = BufferName::Path(format!("fakefile.{}", extension).into());
+
+        let id = match ParserKind::default_from_name(&buffer_name) {
+            Tree(id, _) => id,
+            Plaintext => panic!("no language for {}", extension),
+        };
+        assert!(id.spec().extensions.contains(&extension));
+
+        let spans = parsers.get_spans_result(&Rope::from(code), 0, &buffer_name, Tree(id, Style::Basic))
+            .unwrap_or_else(|e| panic!("{} for {}", e, extension));
+
+        assert!(
+            Vec::<SpanView>::from(spans).iter().any(|s| s.kind == SpanKind::STRING),
+            "no string spans for {}",
+            extension
+        );
+    }
+}
+
+#[test]
+fn parser_kind_iteration_goes_through_each_registered_language() {
+    u!{ParserKind}
+    let ids: Vec<LanguageId> = ParserKind::default()
+        .filter_map(|kind| match kind {
+            Tree(id, Style::Extra) => Some(id),
+            _ => None,
+        })
+        .collect();
+
+    assert_eq!(ids, LanguageId::all().collect::<Vec<_>>());
+}
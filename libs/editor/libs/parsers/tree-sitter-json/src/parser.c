@@ -0,0 +1,876 @@
+#include <tree_sitter/parser.h>
+
+#if defined(__GNUC__) || defined(__clang__)
+#pragma GCC diagnostic push
+#pragma GCC diagnostic ignored "-Wmissing-field-initializers"
+#endif
+
+#define LANGUAGE_VERSION 13
+#define STATE_COUNT 32
+#define LARGE_STATE_COUNT 2
+#define SYMBOL_COUNT 24
+#define ALIAS_COUNT 0
+#define TOKEN_COUNT 14
+#define EXTERNAL_TOKEN_COUNT 0
+#define FIELD_COUNT 2
+#define MAX_ALIAS_SEQUENCE_LENGTH 4
+#define PRODUCTION_ID_COUNT 2
+
+enum {
+  anon_sym_LBRACE = 1,
+  anon_sym_COMMA = 2,
+  anon_sym_RBRACE = 3,
+  anon_sym_COLON = 4,
+  anon_sym_LBRACK = 5,
+  anon_sym_RBRACK = 6,
+  anon_sym_DQUOTE = 7,
+  aux_sym_string_content_token1 = 8,
+  sym_escape_sequence = 9,
+  sym_number = 10,
+  sym_true = 11,
+  sym_false = 12,
+  sym_null = 13,
+  sym_document = 14,
+  sym__value = 15,
+  sym_object = 16,
+  sym_pair = 17,
+  sym_array = 18,
+  sym_string = 19,
+  sym_string_content = 20,
+  aux_sym_object_repeat1 = 21,
+  aux_sym_array_repeat1 = 22,
+  aux_sym_string_content_repeat1 = 23,
+};
+
+static const char * const ts_symbol_names[] = {
+  [ts_builtin_sym_end] = "end",
+  [anon_sym_LBRACE] = "{",
+  [anon_sym_COMMA] = ",",
+  [anon_sym_RBRACE] = "}",
+  [anon_sym_COLON] = ":",
+  [anon_sym_LBRACK] = "[",
+  [anon_sym_RBRACK] = "]",
+  [anon_sym_DQUOTE] = "\"",
+  [aux_sym_string_content_token1] = "string_content_token1",
+  [sym_escape_sequence] = "escape_sequence",
+  [sym_number] = "number",
+  [sym_true] = "true",
+  [sym_false] = "false",
+  [sym_null] = "null",
+  [sym_document] = "document",
+  [sym__value] = "_value",
+  [sym_object] = "object",
+  [sym_pair] = "pair",
+  [sym_array] = "array",
+  [sym_string] = "string",
+  [sym_string_content] = "string_content",
+  [aux_sym_object_repeat1] = "object_repeat1",
+  [aux_sym_array_repeat1] = "array_repeat1",
+  [aux_sym_string_content_repeat1] = "string_content_repeat1",
+};
+
+static const TSSymbol ts_symbol_map[] = {
+  [ts_builtin_sym_end] = ts_builtin_sym_end,
+  [anon_sym_LBRACE] = anon_sym_LBRACE,
+  [anon_sym_COMMA] = anon_sym_COMMA,
+  [anon_sym_RBRACE] = anon_sym_RBRACE,
+  [anon_sym_COLON] = anon_sym_COLON,
+  [anon_sym_LBRACK] = anon_sym_LBRACK,
+  [anon_sym_RBRACK] = anon_sym_RBRACK,
+  [anon_sym_DQUOTE] = anon_sym_DQUOTE,
+  [aux_sym_string_content_token1] = aux_sym_string_content_token1,
+  [sym_escape_sequence] = sym_escape_sequence,
+  [sym_number] = sym_number,
+  [sym_true] = sym_true,
+  [sym_false] = sym_false,
+  [sym_null] = sym_null,
+  [sym_document] = sym_document,
+  [sym__value] = sym__value,
+  [sym_object] = sym_object,
+  [sym_pair] = sym_pair,
+  [sym_array] = sym_array,
+  [sym_string] = sym_string,
+  [sym_string_content] = sym_string_content,
+  [aux_sym_object_repeat1] = aux_sym_object_repeat1,
+  [aux_sym_array_repeat1] = aux_sym_array_repeat1,
+  [aux_sym_string_content_repeat1] = aux_sym_string_content_repeat1,
+};
+
+static const TSSymbolMetadata ts_symbol_metadata[] = {
+  [ts_builtin_sym_end] = {
+    .visible = false,
+    .named = true,
+  },
+  [anon_sym_LBRACE] = {
+    .visible = true,
+    .named = false,
+  },
+  [anon_sym_COMMA] = {
+    .visible = true,
+    .named = false,
+  },
+  [anon_sym_RBRACE] = {
+    .visible = true,
+    .named = false,
+  },
+  [anon_sym_COLON] = {
+    .visible = true,
+    .named = false,
+  },
+  [anon_sym_LBRACK] = {
+    .visible = true,
+    .named = false,
+  },
+  [anon_sym_RBRACK] = {
+    .visible = true,
+    .named = false,
+  },
+  [anon_sym_DQUOTE] = {
+    .visible = true,
+    .named = false,
+  },
+  [aux_sym_string_content_token1] = {
+    .visible = false,
+    .named = false,
+  },
+  [sym_escape_sequence] = {
+    .visible = true,
+    .named = true,
+  },
+  [sym_number] = {
+    .visible = true,
+    .named = true,
+  },
+  [sym_true] = {
+    .visible = true,
+    .named = true,
+  },
+  [sym_false] = {
+    .visible = true,
+    .named = true,
+  },
+  [sym_null] = {
+    .visible = true,
+    .named = true,
+  },
+  [sym_document] = {
+    .visible = true,
+    .named = true,
+  },
+  [sym__value] = {
+    .visible = false,
+    .named = true,
+    .supertype = true,
+  },
+  [sym_object] = {
+    .visible = true,
+    .named = true,
+  },
+  [sym_pair] = {
+    .visible = true,
+    .named = true,
+  },
+  [sym_array] = {
+    .visible = true,
+    .named = true,
+  },
+  [sym_string] = {
+    .visible = true,
+    .named = true,
+  },
+  [sym_string_content] = {
+    .visible = true,
+    .named = true,
+  },
+  [aux_sym_object_repeat1] = {
+    .visible = false,
+    .named = false,
+  },
+  [aux_sym_array_repeat1] = {
+    .visible = false,
+    .named = false,
+  },
+  [aux_sym_string_content_repeat1] = {
+    .visible = false,
+    .named = false,
+  },
+};
+
+enum {
+  field_key = 1,
+  field_value = 2,
+};
+
+static const char * const ts_field_names[] = {
+  [0] = NULL,
+  [field_key] = "key",
+  [field_value] = "value",
+};
+
+static const TSFieldMapSlice ts_field_map_slices[PRODUCTION_ID_COUNT] = {
+  [1] = {.index = 0, .length = 2},
+};
+
+static const TSFieldMapEntry ts_field_map_entries[] = {
+  [0] =
+    {field_key, 0},
+    {field_value, 2},
+};
+
+static const TSSymbol ts_alias_sequences[PRODUCTION_ID_COUNT][MAX_ALIAS_SEQUENCE_LENGTH] = {
+  [0] = {0},
+};
+
+static const uint16_t ts_non_terminal_alias_map[] = {
+  0,
+};
+
+static bool ts_lex(TSLexer *lexer, TSStateId state) {
+  START_LEXER();
+  eof = lexer->eof(lexer);
+  switch (state) {
+    case 0:
+      if (eof) ADVANCE(22);
+      if (lookahead == '"') ADVANCE(29);
+      if (lookahead == '+' ||
+          lookahead == '-') ADVANCE(3);
+      if (lookahead == ',') ADVANCE(24);
+      if (lookahead == '.') ADVANCE(18);
+      if (lookahead == '0') ADVANCE(33);
+      if (lookahead == ':') ADVANCE(26);
+      if (lookahead == '[') ADVANCE(27);
+      if (lookahead == '\\') ADVANCE(17);
+      if (lookahead == ']') ADVANCE(28);
+      if (lookahead == 'f') ADVANCE(4);
+      if (lookahead == 'n') ADVANCE(13);
+      if (lookahead == 't') ADVANCE(10);
+      if (lookahead == '{') ADVANCE(23);
+      if (lookahead == '}') ADVANCE(25);
+      if (lookahead == '\t' ||
+          lookahead == '\n' ||
+          lookahead == '\r' ||
+          lookahead == ' ') SKIP(21)
+      if (('1' <= lookahead && lookahead <= '9')) ADVANCE(35);
+      END_STATE();
+    case 1:
+      if (lookahead == '\n') SKIP(2)
+      if (lookahead == '"') ADVANCE(29);
+      if (lookahead == '\\') ADVANCE(17);
+      if (lookahead == '\t' ||
+          lookahead == '\r' ||
+          lookahead == ' ') ADVANCE(30);
+      if (lookahead != 0) ADVANCE(31);
+      END_STATE();
+    case 2:
+      if (lookahead == '"') ADVANCE(29);
+      if (lookahead == '\t' ||
+          lookahead == '\n' ||
+          lookahead == '\r' ||
+          lookahead == ' ') SKIP(2)
+      END_STATE();
+    case 3:
+      if (lookahead == '0') ADVANCE(34);
+      if (('1' <= lookahead && lookahead <= '9')) ADVANCE(35);
+      END_STATE();
+    case 4:
+      if (lookahead == 'a') ADVANCE(7);
+      END_STATE();
+    case 5:
+      if (lookahead == 'e') ADVANCE(41);
+      END_STATE();
+    case 6:
+      if (lookahead == 'e') ADVANCE(42);
+      END_STATE();
+    case 7:
+      if (lookahead == 'l') ADVANCE(11);
+      END_STATE();
+    case 8:
+      if (lookahead == 'l') ADVANCE(43);
+      END_STATE();
+    case 9:
+      if (lookahead == 'l') ADVANCE(8);
+      END_STATE();
+    case 10:
+      if (lookahead == 'r') ADVANCE(12);
+      END_STATE();
+    case 11:
+      if (lookahead == 's') ADVANCE(6);
+      END_STATE();
+    case 12:
+      if (lookahead == 'u') ADVANCE(5);
+      END_STATE();
+    case 13:
+      if (lookahead == 'u') ADVANCE(9);
+      END_STATE();
+    case 14:
+      if (lookahead == '+' ||
+          lookahead == '-') ADVANCE(19);
+      if (('0' <= lookahead && lookahead <= '9')) ADVANCE(39);
+      END_STATE();
+    case 15:
+      if (lookahead == '0' ||
+          lookahead == '1') ADVANCE(37);
+      END_STATE();
+    case 16:
+      if (('0' <= lookahead && lookahead <= '7')) ADVANCE(38);
+      END_STATE();
+    case 17:
+      if (lookahead == '"' ||
+          lookahead == '/' ||
+          lookahead == '\\' ||
+          lookahead == 'b' ||
+          lookahead == 'f' ||
+          lookahead == 'n' ||
+          lookahead == 'r' ||
+          lookahead == 't' ||
+          lookahead == 'u') ADVANCE(32);
+      END_STATE();
+    case 18:
+      if (('0' <= lookahead && lookahead <= '9')) ADVANCE(36);
+      END_STATE();
+    case 19:
+      if (('0' <= lookahead && lookahead <= '9')) ADVANCE(39);
+      END_STATE();
+    case 20:
+      if (('0' <= lookahead && lookahead <= '9') ||
+          ('A' <= lookahead && lookahead <= 'F') ||
+          ('a' <= lookahead && lookahead <= 'f')) ADVANCE(40);
+      END_STATE();
+    case 21:
+      if (eof) ADVANCE(22);
+      if (lookahead == '"') ADVANCE(29);
+      if (lookahead == '+' ||
+          lookahead == '-') ADVANCE(3);
+      if (lookahead == ',') ADVANCE(24);
+      if (lookahead == '.') ADVANCE(18);
+      if (lookahead == '0') ADVANCE(33);
+      if (lookahead == ':') ADVANCE(26);
+      if (lookahead == '[') ADVANCE(27);
+      if (lookahead == ']') ADVANCE(28);
+      if (lookahead == 'f') ADVANCE(4);
+      if (lookahead == 'n') ADVANCE(13);
+      if (lookahead == 't') ADVANCE(10);
+      if (lookahead == '{') ADVANCE(23);
+      if (lookahead == '}') ADVANCE(25);
+      if (lookahead == '\t' ||
+          lookahead == '\n' ||
+          lookahead == '\r' ||
+          lookahead == ' ') SKIP(21)
+      if (('1' <= lookahead && lookahead <= '9')) ADVANCE(35);
+      END_STATE();
+    case 22:
+      ACCEPT_TOKEN(ts_builtin_sym_end);
+      END_STATE();
+    case 23:
+      ACCEPT_TOKEN(anon_sym_LBRACE);
+      END_STATE();
+    case 24:
+      ACCEPT_TOKEN(anon_sym_COMMA);
+      END_STATE();
+    case 25:
+      ACCEPT_TOKEN(anon_sym_RBRACE);
+      END_STATE();
+    case 26:
+      ACCEPT_TOKEN(anon_sym_COLON);
+      END_STATE();
+    case 27:
+      ACCEPT_TOKEN(anon_sym_LBRACK);
+      END_STATE();
+    case 28:
+      ACCEPT_TOKEN(anon_sym_RBRACK);
+      END_STATE();
+    case 29:
+      ACCEPT_TOKEN(anon_sym_DQUOTE);
+      END_STATE();
+    case 30:
+      ACCEPT_TOKEN(aux_sym_string_content_token1);
+      if (lookahead == '\t' ||
+          lookahead == '\r' ||
+          lookahead == ' ') ADVANCE(30);
+      if (lookahead != 0 &&
+          lookahead != '\n' &&
+          lookahead != '"' &&
+          lookahead != '\\') ADVANCE(31);
+      END_STATE();
+    case 31:
+      ACCEPT_TOKEN(aux_sym_string_content_token1);
+      if (lookahead != 0 &&
+          lookahead != '\n' &&
+          lookahead != '"' &&
+          lookahead != '\\') ADVANCE(31);
+      END_STATE();
+    case 32:
+      ACCEPT_TOKEN(sym_escape_sequence);
+      END_STATE();
+    case 33:
+      ACCEPT_TOKEN(sym_number);
+      if (lookahead == '.') ADVANCE(36);
+      if (lookahead == 'B' ||
+          lookahead == 'b') ADVANCE(15);
+      if (lookahead == 'E' ||
+          lookahead == 'e') ADVANCE(14);
+      if (lookahead == 'O' ||
+          lookahead == 'o') ADVANCE(16);
+      if (lookahead == 'X' ||
+          lookahead == 'x') ADVANCE(20);
+      END_STATE();
+    case 34:
+      ACCEPT_TOKEN(sym_number);
+      if (lookahead == '.') ADVANCE(36);
+      if (lookahead == 'E' ||
+          lookahead == 'e') ADVANCE(14);
+      END_STATE();
+    case 35:
+      ACCEPT_TOKEN(sym_number);
+      if (lookahead == '.') ADVANCE(36);
+      if (lookahead == 'E' ||
+          lookahead == 'e') ADVANCE(14);
+      if (('0' <= lookahead && lookahead <= '9')) ADVANCE(35);
+      END_STATE();
+    case 36:
+      ACCEPT_TOKEN(sym_number);
+      if (lookahead == 'E' ||
+          lookahead == 'e') ADVANCE(14);
+      if (('0' <= lookahead && lookahead <= '9')) ADVANCE(36);
+      END_STATE();
+    case 37:
+      ACCEPT_TOKEN(sym_number);
+      if (lookahead == '0' ||
+          lookahead == '1') ADVANCE(37);
+      END_STATE();
+    case 38:
+      ACCEPT_TOKEN(sym_number);
+      if (('0' <= lookahead && lookahead <= '7')) ADVANCE(38);
+      END_STATE();
+    case 39:
+      ACCEPT_TOKEN(sym_number);
+      if (('0' <= lookahead && lookahead <= '9')) ADVANCE(39);
+      END_STATE();
+    case 40:
+      ACCEPT_TOKEN(sym_number);
+      if (('0' <= lookahead && lookahead <= '9') ||
+          ('A' <= lookahead && lookahead <= 'F') ||
+          ('a' <= lookahead && lookahead <= 'f')) ADVANCE(40);
+      END_STATE();
+    case 41:
+      ACCEPT_TOKEN(sym_true);
+      END_STATE();
+    case 42:
+      ACCEPT_TOKEN(sym_false);
+      END_STATE();
+    case 43:
+      ACCEPT_TOKEN(sym_null);
+      END_STATE();
+    default:
+      return false;
+  }
+}
+
+static const TSLexMode ts_lex_modes[STATE_COUNT] = {
+  [0] = {.lex_state = 0},
+  [1] = {.lex_state = 0},
+  [2] = {.lex_state = 0},
+  [3] = {.lex_state = 0},
+  [4] = {.lex_state = 0},
+  [5] = {.lex_state = 0},
+  [6] = {.lex_state = 0},
+  [7] = {.lex_state = 1},
+  [8] = {.lex_state = 0},
+  [9] = {.lex_state = 0},
+  [10] = {.lex_state = 0},
+  [11] = {.lex_state = 1},
+  [12] = {.lex_state = 0},
+  [13] = {.lex_state = 0},
+  [14] = {.lex_state = 0},
+  [15] = {.lex_state = 0},
+  [16] = {.lex_state = 0},
+  [17] = {.lex_state = 1},
+  [18] = {.lex_state = 0},
+  [19] = {.lex_state = 0},
+  [20] = {.lex_state = 0},
+  [21] = {.lex_state = 0},
+  [22] = {.lex_state = 0},
+  [23] = {.lex_state = 0},
+  [24] = {.lex_state = 0},
+  [25] = {.lex_state = 0},
+  [26] = {.lex_state = 0},
+  [27] = {.lex_state = 0},
+  [28] = {.lex_state = 0},
+  [29] = {.lex_state = 0},
+  [30] = {.lex_state = 0},
+  [31] = {.lex_state = 0},
+};
+
+static const uint16_t ts_parse_table[LARGE_STATE_COUNT][SYMBOL_COUNT] = {
+  [0] = {
+    [ts_builtin_sym_end] = ACTIONS(1),
+    [anon_sym_LBRACE] = ACTIONS(1),
+    [anon_sym_COMMA] = ACTIONS(1),
+    [anon_sym_RBRACE] = ACTIONS(1),
+    [anon_sym_COLON] = ACTIONS(1),
+    [anon_sym_LBRACK] = ACTIONS(1),
+    [anon_sym_RBRACK] = ACTIONS(1),
+    [anon_sym_DQUOTE] = ACTIONS(1),
+    [sym_escape_sequence] = ACTIONS(1),
+    [sym_number] = ACTIONS(1),
+    [sym_true] = ACTIONS(1),
+    [sym_false] = ACTIONS(1),
+    [sym_null] = ACTIONS(1),
+  },
+  [1] = {
+    [sym_document] = STATE(28),
+    [sym__value] = STATE(31),
+    [sym_object] = STATE(12),
+    [sym_array] = STATE(12),
+    [sym_string] = STATE(12),
+    [anon_sym_LBRACE] = ACTIONS(3),
+    [anon_sym_LBRACK] = ACTIONS(5),
+    [anon_sym_DQUOTE] = ACTIONS(7),
+    [sym_number] = ACTIONS(9),
+    [sym_true] = ACTIONS(9),
+    [sym_false] = ACTIONS(9),
+    [sym_null] = ACTIONS(9),
+  },
+};
+
+static const uint16_t ts_small_parse_table[] = {
+  [0] = 7,
+    ACTIONS(3), 1,
+      anon_sym_LBRACE,
+    ACTIONS(5), 1,
+      anon_sym_LBRACK,
+    ACTIONS(7), 1,
+      anon_sym_DQUOTE,
+    ACTIONS(11), 1,
+      anon_sym_RBRACK,
+    STATE(21), 1,
+      sym__value,
+    STATE(12), 3,
+      sym_object,
+      sym_array,
+      sym_string,
+    ACTIONS(9), 4,
+      sym_number,
+      sym_true,
+      sym_false,
+      sym_null,
+  [27] = 6,
+    ACTIONS(3), 1,
+      anon_sym_LBRACE,
+    ACTIONS(5), 1,
+      anon_sym_LBRACK,
+    ACTIONS(7), 1,
+      anon_sym_DQUOTE,
+    STATE(27), 1,
+      sym__value,
+    STATE(12), 3,
+      sym_object,
+      sym_array,
+      sym_string,
+    ACTIONS(9), 4,
+      sym_number,
+      sym_true,
+      sym_false,
+      sym_null,
+  [51] = 6,
+    ACTIONS(3), 1,
+      anon_sym_LBRACE,
+    ACTIONS(5), 1,
+      anon_sym_LBRACK,
+    ACTIONS(7), 1,
+      anon_sym_DQUOTE,
+    STATE(25), 1,
+      sym__value,
+    STATE(12), 3,
+      sym_object,
+      sym_array,
+      sym_string,
+    ACTIONS(9), 4,
+      sym_number,
+      sym_true,
+      sym_false,
+      sym_null,
+  [75] = 1,
+    ACTIONS(13), 5,
+      ts_builtin_sym_end,
+      anon_sym_COMMA,
+      anon_sym_RBRACE,
+      anon_sym_COLON,
+      anon_sym_RBRACK,
+  [83] = 5,
+    ACTIONS(7), 1,
+      anon_sym_DQUOTE,
+    ACTIONS(15), 1,
+      anon_sym_RBRACE,
+    ACTIONS(17), 1,
+      sym_number,
+    STATE(20), 1,
+      sym_pair,
+    STATE(29), 1,
+      sym_string,
+  [99] = 4,
+    ACTIONS(19), 1,
+      anon_sym_DQUOTE,
+    STATE(11), 1,
+      aux_sym_string_content_repeat1,
+    STATE(30), 1,
+      sym_string_content,
+    ACTIONS(21), 2,
+      aux_sym_string_content_token1,
+      sym_escape_sequence,
+  [113] = 1,
+    ACTIONS(23), 5,
+      ts_builtin_sym_end,
+      anon_sym_COMMA,
+      anon_sym_RBRACE,
+      anon_sym_COLON,
+      anon_sym_RBRACK,
+  [121] = 1,
+    ACTIONS(25), 4,
+      ts_builtin_sym_end,
+      anon_sym_COMMA,
+      anon_sym_RBRACE,
+      anon_sym_RBRACK,
+  [128] = 1,
+    ACTIONS(27), 4,
+      ts_builtin_sym_end,
+      anon_sym_COMMA,
+      anon_sym_RBRACE,
+      anon_sym_RBRACK,
+  [135] = 3,
+    ACTIONS(29), 1,
+      anon_sym_DQUOTE,
+    STATE(17), 1,
+      aux_sym_string_content_repeat1,
+    ACTIONS(31), 2,
+      aux_sym_string_content_token1,
+      sym_escape_sequence,
+  [146] = 1,
+    ACTIONS(33), 4,
+      ts_builtin_sym_end,
+      anon_sym_COMMA,
+      anon_sym_RBRACE,
+      anon_sym_RBRACK,
+  [153] = 4,
+    ACTIONS(7), 1,
+      anon_sym_DQUOTE,
+    ACTIONS(17), 1,
+      sym_number,
+    STATE(26), 1,
+      sym_pair,
+    STATE(29), 1,
+      sym_string,
+  [166] = 1,
+    ACTIONS(35), 4,
+      ts_builtin_sym_end,
+      anon_sym_COMMA,
+      anon_sym_RBRACE,
+      anon_sym_RBRACK,
+  [173] = 1,
+    ACTIONS(37), 4,
+      ts_builtin_sym_end,
+      anon_sym_COMMA,
+      anon_sym_RBRACE,
+      anon_sym_RBRACK,
+  [180] = 1,
+    ACTIONS(39), 4,
+      ts_builtin_sym_end,
+      anon_sym_COMMA,
+      anon_sym_RBRACE,
+      anon_sym_RBRACK,
+  [187] = 3,
+    ACTIONS(41), 1,
+      anon_sym_DQUOTE,
+    STATE(17), 1,
+      aux_sym_string_content_repeat1,
+    ACTIONS(43), 2,
+      aux_sym_string_content_token1,
+      sym_escape_sequence,
+  [198] = 1,
+    ACTIONS(46), 4,
+      ts_builtin_sym_end,
+      anon_sym_COMMA,
+      anon_sym_RBRACE,
+      anon_sym_RBRACK,
+  [205] = 3,
+    ACTIONS(48), 1,
+      anon_sym_COMMA,
+    ACTIONS(51), 1,
+      anon_sym_RBRACE,
+    STATE(19), 1,
+      aux_sym_object_repeat1,
+  [215] = 3,
+    ACTIONS(53), 1,
+      anon_sym_COMMA,
+    ACTIONS(55), 1,
+      anon_sym_RBRACE,
+    STATE(23), 1,
+      aux_sym_object_repeat1,
+  [225] = 3,
+    ACTIONS(57), 1,
+      anon_sym_COMMA,
+    ACTIONS(59), 1,
+      anon_sym_RBRACK,
+    STATE(24), 1,
+      aux_sym_array_repeat1,
+  [235] = 3,
+    ACTIONS(61), 1,
+      anon_sym_COMMA,
+    ACTIONS(64), 1,
+      anon_sym_RBRACK,
+    STATE(22), 1,
+      aux_sym_array_repeat1,
+  [245] = 3,
+    ACTIONS(53), 1,
+      anon_sym_COMMA,
+    ACTIONS(66), 1,
+      anon_sym_RBRACE,
+    STATE(19), 1,
+      aux_sym_object_repeat1,
+  [255] = 3,
+    ACTIONS(57), 1,
+      anon_sym_COMMA,
+    ACTIONS(68), 1,
+      anon_sym_RBRACK,
+    STATE(22), 1,
+      aux_sym_array_repeat1,
+  [265] = 1,
+    ACTIONS(70), 2,
+      anon_sym_COMMA,
+      anon_sym_RBRACE,
+  [270] = 1,
+    ACTIONS(51), 2,
+      anon_sym_COMMA,
+      anon_sym_RBRACE,
+  [275] = 1,
+    ACTIONS(64), 2,
+      anon_sym_COMMA,
+      anon_sym_RBRACK,
+  [280] = 1,
+    ACTIONS(72), 1,
+      ts_builtin_sym_end,
+  [284] = 1,
+    ACTIONS(74), 1,
+      anon_sym_COLON,
+  [288] = 1,
+    ACTIONS(76), 1,
+      anon_sym_DQUOTE,
+  [292] = 1,
+    ACTIONS(78), 1,
+      ts_builtin_sym_end,
+};
+
+static const uint32_t ts_small_parse_table_map[] = {
+  [SMALL_STATE(2)] = 0,
+  [SMALL_STATE(3)] = 27,
+  [SMALL_STATE(4)] = 51,
+  [SMALL_STATE(5)] = 75,
+  [SMALL_STATE(6)] = 83,
+  [SMALL_STATE(7)] = 99,
+  [SMALL_STATE(8)] = 113,
+  [SMALL_STATE(9)] = 121,
+  [SMALL_STATE(10)] = 128,
+  [SMALL_STATE(11)] = 135,
+  [SMALL_STATE(12)] = 146,
+  [SMALL_STATE(13)] = 153,
+  [SMALL_STATE(14)] = 166,
+  [SMALL_STATE(15)] = 173,
+  [SMALL_STATE(16)] = 180,
+  [SMALL_STATE(17)] = 187,
+  [SMALL_STATE(18)] = 198,
+  [SMALL_STATE(19)] = 205,
+  [SMALL_STATE(20)] = 215,
+  [SMALL_STATE(21)] = 225,
+  [SMALL_STATE(22)] = 235,
+  [SMALL_STATE(23)] = 245,
+  [SMALL_STATE(24)] = 255,
+  [SMALL_STATE(25)] = 265,
+  [SMALL_STATE(26)] = 270,
+  [SMALL_STATE(27)] = 275,
+  [SMALL_STATE(28)] = 280,
+  [SMALL_STATE(29)] = 284,
+  [SMALL_STATE(30)] = 288,
+  [SMALL_STATE(31)] = 292,
+};
+
+static const TSParseActionEntry ts_parse_actions[] = {
+  [0] = {.entry = {.count = 0, .reusable = false}},
+  [1] = {.entry = {.count = 1, .reusable = false}}, RECOVER(),
+  [3] = {.entry = {.count = 1, .reusable = true}}, SHIFT(6),
+  [5] = {.entry = {.count = 1, .reusable = true}}, SHIFT(2),
+  [7] = {.entry = {.count = 1, .reusable = true}}, SHIFT(7),
+  [9] = {.entry = {.count = 1, .reusable = true}}, SHIFT(12),
+  [11] = {.entry = {.count = 1, .reusable = true}}, SHIFT(10),
+  [13] = {.entry = {.count = 1, .reusable = true}}, REDUCE(sym_string, 2),
+  [15] = {.entry = {.count = 1, .reusable = true}}, SHIFT(9),
+  [17] = {.entry = {.count = 1, .reusable = true}}, SHIFT(29),
+  [19] = {.entry = {.count = 1, .reusable = false}}, SHIFT(5),
+  [21] = {.entry = {.count = 1, .reusable = true}}, SHIFT(11),
+  [23] = {.entry = {.count = 1, .reusable = true}}, REDUCE(sym_string, 3),
+  [25] = {.entry = {.count = 1, .reusable = true}}, REDUCE(sym_object, 2),
+  [27] = {.entry = {.count = 1, .reusable = true}}, REDUCE(sym_array, 2),
+  [29] = {.entry = {.count = 1, .reusable = false}}, REDUCE(sym_string_content, 1),
+  [31] = {.entry = {.count = 1, .reusable = true}}, SHIFT(17),
+  [33] = {.entry = {.count = 1, .reusable = true}}, REDUCE(sym__value, 1),
+  [35] = {.entry = {.count = 1, .reusable = true}}, REDUCE(sym_object, 3),
+  [37] = {.entry = {.count = 1, .reusable = true}}, REDUCE(sym_array, 4),
+  [39] = {.entry = {.count = 1, .reusable = true}}, REDUCE(sym_array, 3),
+  [41] = {.entry = {.count = 1, .reusable = false}}, REDUCE(aux_sym_string_content_repeat1, 2),
+  [43] = {.entry = {.count = 2, .reusable = true}}, REDUCE(aux_sym_string_content_repeat1, 2), SHIFT_REPEAT(17),
+  [46] = {.entry = {.count = 1, .reusable = true}}, REDUCE(sym_object, 4),
+  [48] = {.entry = {.count = 2, .reusable = true}}, REDUCE(aux_sym_object_repeat1, 2), SHIFT_REPEAT(13),
+  [51] = {.entry = {.count = 1, .reusable = true}}, REDUCE(aux_sym_object_repeat1, 2),
+  [53] = {.entry = {.count = 1, .reusable = true}}, SHIFT(13),
+  [55] = {.entry = {.count = 1, .reusable = true}}, SHIFT(14),
+  [57] = {.entry = {.count = 1, .reusable = true}}, SHIFT(3),
+  [59] = {.entry = {.count = 1, .reusable = true}}, SHIFT(16),
+  [61] = {.entry = {.count = 2, .reusable = true}}, REDUCE(aux_sym_array_repeat1, 2), SHIFT_REPEAT(3),
+  [64] = {.entry = {.count = 1, .reusable = true}}, REDUCE(aux_sym_array_repeat1, 2),
+  [66] = {.entry = {.count = 1, .reusable = true}}, SHIFT(18),
+  [68] = {.entry = {.count = 1, .reusable = true}}, SHIFT(15),
+  [70] = {.entry = {.count = 1, .reusable = true}}, REDUCE(sym_pair, 3, .production_id = 1),
+  [72] = {.entry = {.count = 1, .reusable = true}},  ACCEPT_INPUT(),
+  [74] = {.entry = {.count = 1, .reusable = true}}, SHIFT(4),
+  [76] = {.entry = {.count = 1, .reusable = true}}, SHIFT(8),
+  [78] = {.entry = {.count = 1, .reusable = true}}, REDUCE(sym_document, 1),
+};
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+#ifdef _WIN32
+#define extern __declspec(dllexport)
+#endif
+
+extern const TSLanguage *tree_sitter_json(void) {
+  static const TSLanguage language = {
+    .version = LANGUAGE_VERSION,
+    .symbol_count = SYMBOL_COUNT,
+    .alias_count = ALIAS_COUNT,
+    .token_count = TOKEN_COUNT,
+    .external_token_count = EXTERNAL_TOKEN_COUNT,
+    .state_count = STATE_COUNT,
+    .large_state_count = LARGE_STATE_COUNT,
+    .production_id_count = PRODUCTION_ID_COUNT,
+    .field_count = FIELD_COUNT,
+    .max_alias_sequence_length = MAX_ALIAS_SEQUENCE_LENGTH,
+    .parse_table = &ts_parse_table[0][0],
+    .small_parse_table = ts_small_parse_table,
+    .small_parse_table_map = ts_small_parse_table_map,
+    .parse_actions = ts_parse_actions,
+    .symbol_names = ts_symbol_names,
+    .field_names = ts_field_names,
+    .field_map_slices = ts_field_map_slices,
+    .field_map_entries = ts_field_map_entries,
+    .symbol_metadata = ts_symbol_metadata,
+    .public_symbol_map = ts_symbol_map,
+    .alias_map = ts_non_terminal_alias_map,
+    .alias_sequences = &ts_alias_sequences[0][0],
+    .lex_modes = ts_lex_modes,
+    .lex_fn = ts_lex,
+  };
+  return &language;
+}
+#ifdef __cplusplus
+}
+#endif
@@ -1,4 +1,5 @@
 const PREC = {
+  range: 15,
   call: 14,
   field: 13,
   unary: 11,
@@ -11,7 +12,6 @@ const PREC = {
   comparative: 4,
   and: 3,
   or: 2,
-  range: 1,
   assign: 0,
   closure: -1,
 }
@@ -870,7 +870,8 @@ module.exports = grammar({
       $.while_expression,
       $.while_let_expression,
       $.loop_expression,
-      $.for_expression
+      $.for_expression,
+      $.const_block
     ),
 
     macro_invocation: $ => seq(
@@ -913,7 +914,10 @@ module.exports = grammar({
     ),
 
     range_expression: $ => prec.left(PREC.range, choice(
-      seq($._expression, choice('..', '...', '..='), $._expression),
+      prec.left(
+        PREC.range + 1,
+        seq($._expression, choice('..', '...', '..='), $._expression)
+      ),
       seq($._expression, '..'),
       seq('..', $._expression),
       '..'
@@ -1065,7 +1069,7 @@ module.exports = grammar({
       'if',
       field('condition', $._expression),
       field('consequence', $.block),
-      optional($._else_tail)
+      optional(field("alternative", $.else_clause))
     ),
 
     if_let_expression: $ => seq(
@@ -1075,16 +1079,16 @@ module.exports = grammar({
       '=',
       field('value', $._expression),
       field('consequence', $.block),
-      optional($._else_tail)
+      optional(field('alternative', $.else_clause))
     ),
 
-    _else_tail: $ => seq(
+    else_clause: $ => seq(
       'else',
-      field('alternative', choice(
+      choice(
         $.block,
         $.if_expression,
         $.if_let_expression
-      ))
+      )
     ),
 
     match_expression: $ => seq(
@@ -1160,6 +1164,11 @@ module.exports = grammar({
       field('body', $.block)
     ),
 
+    const_block: $ => seq(
+      'const',
+      field('body', $.block)
+    ),
+
     closure_expression: $ => prec(PREC.closure, seq(
       optional('move'),
       field('parameters', $.closure_parameters),
@@ -1240,6 +1249,7 @@ module.exports = grammar({
       $.mut_pattern,
       $.range_pattern,
       $.or_pattern,
+      $.const_block,
       '_'
     ),
 
@@ -1422,7 +1432,7 @@ module.exports = grammar({
       $.scoped_identifier
     ),
 
-    identifier: $ => /(r#)?[a-zA-Zα-ωΑ-Ωµ_][a-zA-Zα-ωΑ-Ωµ\d_]*/,
+    identifier: $ => /(r#)?[_\p{XID_Start}][_\p{XID_Continue}]*/,
 
     _reserved_identifier: $ => alias(choice(
       'default',
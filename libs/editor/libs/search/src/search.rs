@@ -1,45 +1,126 @@
 #![deny(unused_must_use)]
-use macros::{d, fmt_debug, SaturatingAdd, SaturatingSub};
-use panic_safe_rope::{Rope, RopeSlice, RopeSliceTrait};
+use macros::{d, fmt_debug, some_if, some_or, SaturatingAdd, SaturatingSub};
+use panic_safe_rope::{ByteIndex, Rope, RopeSlice, RopeSliceTrait};
 use editor_types::{Position, CharOffset, AbsoluteCharOffset};
-use rope_pos::{char_offset_to_pos, AbsoluteCharOffsetRange};
+use platform_types::{SearchKind, SearchOptions};
+use rope_pos::{char_offset_to_pos, pos_to_char_offset, AbsoluteCharOffsetRange};
+use unicode_segmentation::UnicodeSegmentation;
+
+use std::rc::Rc;
 
 #[derive(Clone, Default, Hash, PartialEq, Eq)]
 pub struct SearchResults {
     pub needle: String,
+    pub options: SearchOptions,
+    /// The sorted, non-overlapping ranges that the search is restricted to, if
+    /// `options.in_selection` is set.
+    pub selections: Vec<AbsoluteCharOffsetRange>,
     pub ranges: Vec<(Position, Position)>,
     pub current_range: usize,
 }
 
 fmt_debug!(collapse default for SearchResults : me {
     blank_if_default!(needle);
+    blank_if_default!(options);
+    blank_if_default!(selections);
     blank_if_default!(ranges);
     blank_if_default!(current_range);
 });
 
 impl SearchResults {
+    pub fn new(needle: RopeSlice, options: SearchOptions, haystack: &Rope) -> SearchResults {
+        Self::new_in_selections(needle, options, d!(), haystack)
+    }
+
+    /// Like `new`, except that if `options.in_selection` is set, and `selections` is
+    /// not empty, then only the text inside `selections` is searched.
     #[perf_viz::record]
-    pub fn new(needle: RopeSlice, haystack: &Rope) -> SearchResults {
-        let ranges = get_ranges(
-            needle,
-            haystack,
-            d!(),
-            d!(),
-        );
+    pub fn new_in_selections(
+        needle: RopeSlice,
+        options: SearchOptions,
+        selections: Vec<AbsoluteCharOffsetRange>,
+        haystack: &Rope,
+    ) -> SearchResults {
+        let needle_str = needle.as_cow_str();
+        let case_sensitive = options.case.is_sensitive_for(&needle_str);
+
+        let haystack_ranges: Vec<Option<AbsoluteCharOffsetRange>> =
+            if options.in_selection && !selections.is_empty() {
+                // The selections may have been captured before some edits, so we
+                // make sure they are still inside the haystack. Otherwise the whole
+                // haystack would be searched.
+                let len = haystack.len_chars();
+                selections
+                    .iter()
+                    .map(|s| Some(AbsoluteCharOffsetRange::new(
+                        std::cmp::min(s.min(), len),
+                        std::cmp::min(s.max(), len),
+                    )))
+                    .collect()
+            } else {
+                vec![None]
+            };
+
+        let mut ranges = Vec::new();
+        for haystack_range in haystack_ranges {
+            ranges.extend(match (options.kind, case_sensitive) {
+                (SearchKind::Plain, true) => {
+                    let ranges = get_ranges(
+                        needle,
+                        haystack,
+                        haystack_range,
+                        d!(),
+                    );
+                    // The two-way matcher finds every match, including overlapping
+                    // ones, so filtering afterwards cannot hide a whole word match.
+                    if options.whole_word {
+                        filter_to_whole_words(haystack, ranges)
+                    } else {
+                        ranges
+                    }
+                }
+                // The two-way matcher compares chars exactly, so we let the regex
+                // engine handle the case folding.
+                (SearchKind::Plain, false) => get_regex_ranges_with_case(
+                    &regex::escape(&needle_str),
+                    false,
+                    options.whole_word,
+                    haystack,
+                    haystack_range,
+                    d!(),
+                ),
+                (SearchKind::Regex, case_sensitive) => get_regex_ranges_with_case(
+                    &needle_str,
+                    case_sensitive,
+                    options.whole_word,
+                    haystack,
+                    haystack_range,
+                    d!(),
+                ),
+            });
+        }
 
         SearchResults {
             needle: {
                 perf_viz::record_guard!("SearchResults needle.into()");
                 needle.into()
             },
+            options,
+            selections,
             ranges,
             current_range: 0,
         }
     }
 
     #[perf_viz::record]
-    pub fn refresh(&mut self, needle: RopeSlice, haystack: &Rope) {
-        let mut new = Self::new(needle, haystack);
+    pub fn refresh(
+        &mut self,
+        needle: RopeSlice,
+        options: SearchOptions,
+        selections: Vec<AbsoluteCharOffsetRange>,
+        haystack: &Rope,
+    ) {
+        let mut new = Self::new_in_selections(needle, options, selections, haystack);
 
         let old_range = self.ranges.get(self.current_range);
         let overlapping_range = old_range.map(|(old_start, old_end)| {
@@ -62,6 +143,119 @@ impl SearchResults {
 
         *self = new
     }
+
+    /// Returns the text that each of the given ranges of `haystack` should be replaced
+    /// with, given the `replacement` the user entered. For `SearchKind::Regex` searches,
+    /// `$1` style references to capture groups in `replacement` are expanded, using the
+    /// text in each range.
+    pub fn get_replacements(
+        &self,
+        haystack: &Rope,
+        ranges: &[(Position, Position)],
+        replacement: &str,
+    ) -> Vec<String> {
+        match self.options.kind {
+            SearchKind::Plain => {
+                ranges.iter().map(|_| replacement.to_owned()).collect()
+            }
+            SearchKind::Regex => {
+                // The ranges were found by the DFA, which doesn't know about capture
+                // groups, so we run a regex that can see them on just the matched text.
+                let captures_regex = regex::RegexBuilder::new(&format!("^(?:{})$", self.needle))
+                    .case_insensitive(!self.options.case.is_sensitive_for(&self.needle))
+                    .build()
+                    .ok();
+
+                ranges.iter().map(|&(start, end)| {
+                    let captures_regex = some_or!(
+                        captures_regex.as_ref(),
+                        return replacement.to_owned()
+                    );
+                    let matched: String = some_or!(
+                        pos_to_char_offset(haystack, &start)
+                            .and_then(|s| pos_to_char_offset(haystack, &end).map(|e| (s, e)))
+                            .and_then(|(s, e)| haystack.slice(s..e)),
+                        return replacement.to_owned()
+                    ).into();
+
+                    let mut output = String::with_capacity(replacement.len());
+                    match captures_regex.captures(&matched) {
+                        Some(captures) => captures.expand(replacement, &mut output),
+                        None => output.push_str(replacement),
+                    }
+                    output
+                }).collect()
+            }
+        }
+    }
+}
+
+/// Returns the ranges that a search with `in_selection` set should be restricted to,
+/// given pairs of positions in `haystack`. Empty ranges are dropped and overlapping
+/// ranges are merged, so the result is sorted and non-overlapping.
+pub fn selection_ranges<I>(haystack: &Rope, selections: I) -> Vec<AbsoluteCharOffsetRange>
+where
+    I: IntoIterator<Item = (Position, Position)>,
+{
+    let mut offsets: Vec<AbsoluteCharOffsetRange> = selections
+        .into_iter()
+        .filter_map(|(p1, p2)| {
+            let o1 = pos_to_char_offset(haystack, &p1)?;
+            let o2 = pos_to_char_offset(haystack, &p2)?;
+            some_if!(o1 != o2 => AbsoluteCharOffsetRange::new(o1, o2))
+        })
+        .collect();
+    offsets.sort_by_key(|r| r.min());
+
+    let mut selections: Vec<AbsoluteCharOffsetRange> = Vec::with_capacity(offsets.len());
+    for range in offsets {
+        match selections.last_mut() {
+            Some(last) if range.min() <= last.max() => {
+                *last = AbsoluteCharOffsetRange::new(
+                    last.min(),
+                    std::cmp::max(last.max(), range.max())
+                );
+            }
+            _ => selections.push(range),
+        }
+    }
+
+    selections
+}
+
+/// Updates `selections` for an edit that removed `removed` chars starting at `at`, and
+/// then inserted `added` chars there, so they still cover the same text. Text inserted
+/// at the edge of a selection counts as part of it, and selections that the removed
+/// text overlapped shrink accordingly.
+pub fn adjust_selections_for_edit(
+    selections: &mut [AbsoluteCharOffsetRange],
+    at: AbsoluteCharOffset,
+    removed: usize,
+    added: usize,
+) {
+    let removed_end = at.0.saturating_add(removed);
+    let shift = |offset: AbsoluteCharOffset, is_min: bool| {
+        let offset = if offset.0 <= at.0 {
+            offset.0
+        } else if offset.0 <= removed_end {
+            at.0
+        } else {
+            offset.0 - removed
+        };
+
+        AbsoluteCharOffset(if offset < at.0 || (is_min && offset == at.0) {
+            offset
+        } else {
+            offset.saturating_add(added)
+        })
+    };
+
+    for selection in selections.iter_mut() {
+        *selection = AbsoluteCharOffsetRange::new(
+            shift(selection.min(), true),
+            shift(selection.max(), false),
+        );
+    }
 }
 
 /// A `haystack_range` of `None` means use the whole haystack. AKA no limit.
@@ -86,6 +280,61 @@ pub fn get_ranges(
 
     let offsets = get_ranges_impl(needle, slice, max_needed);
 
+    offsets_to_positions(haystack, min, offsets)
+}
+
+/// Like `get_ranges` except `pattern` is interpreted as a regular expression. See
+/// `SearchKind::Regex` for which syntax is supported. If `pattern` is not a
+/// supported regex then no ranges are returned. Empty matches are skipped, since
+/// there would be nothing to highlight or replace.
+pub fn get_regex_ranges(
+    pattern: &str,
+    haystack: &Rope,
+    haystack_range: Option<AbsoluteCharOffsetRange>,
+    max_needed: Option<std::num::NonZeroUsize>,
+) -> Vec<(Position, Position)> {
+    get_regex_ranges_with_case(pattern, true, false, haystack, haystack_range, max_needed)
+}
+
+/// If `whole_word` is set, matches that do not start and end on a word boundary are
+/// skipped, and the search continues from just after where they started, so they
+/// cannot hide a whole word match that overlaps them.
+fn get_regex_ranges_with_case(
+    pattern: &str,
+    case_sensitive: bool,
+    whole_word: bool,
+    haystack: &Rope,
+    haystack_range: Option<AbsoluteCharOffsetRange>,
+    max_needed: Option<std::num::NonZeroUsize>,
+) -> Vec<(Position, Position)> {
+    perf_viz::record_guard!("get_regex_ranges");
+
+    if pattern.is_empty() {
+        return d!();
+    }
+
+    let regex = some_or!(compile_regex(pattern, case_sensitive), return d!());
+
+    let (min, slice) = haystack_range
+        .and_then(|r| haystack.slice(r.range()).map(|s| (r.min().0, s)))
+        .unwrap_or_else(|| (0, haystack.full_slice()));
+
+    let mut whole_words = WholeWords::new(haystack);
+    let offsets = get_regex_ranges_impl(&regex, slice, max_needed, |start, end| {
+        !whole_word || whole_words.is_whole_word(
+            AbsoluteCharOffset(start.0 + min),
+            AbsoluteCharOffset(end.0 + min),
+        )
+    });
+
+    offsets_to_positions(haystack, min, offsets)
+}
+
+fn offsets_to_positions(
+    haystack: &Rope,
+    min: usize,
+    offsets: Vec<(CharOffset, CharOffset)>
+) -> Vec<(Position, Position)> {
     perf_viz::record_guard!("offsets.into_iter() char_offset_to_pos");
     offsets
         .into_iter()
@@ -99,6 +348,237 @@ pub fn get_ranges(
         .collect()
 }
 
+type Regex = regex_automata::Regex;
+
+thread_local! {
+    // Compiling the DFAs can take long enough to be noticeable, and we search again
+    // after every edit, so we keep the most recently used one around.
+    static LAST_REGEX: std::cell::RefCell<Option<(String, bool, Rc<Regex>)>> = d!();
+}
+
+fn compile_regex(pattern: &str, case_sensitive: bool) -> Option<Rc<Regex>> {
+    perf_viz::record_guard!("compile_regex");
+    LAST_REGEX.with(|last| {
+        let mut last = last.borrow_mut();
+        match &*last {
+            Some((last_pattern, last_case_sensitive, regex))
+            if last_pattern == pattern && *last_case_sensitive == case_sensitive => {
+                return Some(Rc::clone(regex));
+            }
+            _ => {}
+        }
+
+        let regex = Rc::new(
+            regex_automata::RegexBuilder::new()
+                .case_insensitive(!case_sensitive)
+                .build(pattern)
+                .ok()?
+        );
+        *last = Some((pattern.to_owned(), case_sensitive, Rc::clone(&regex)));
+        Some(regex)
+    })
+}
+
+/// Keeps only the ranges that both start and end on a word boundary. See `WholeWords`.
+fn filter_to_whole_words(
+    haystack: &Rope,
+    ranges: Vec<(Position, Position)>,
+) -> Vec<(Position, Position)> {
+    perf_viz::record_guard!("filter_to_whole_words");
+    let mut whole_words = WholeWords::new(haystack);
+
+    ranges.into_iter().filter(|(start, end)| {
+        match (pos_to_char_offset(haystack, start), pos_to_char_offset(haystack, end)) {
+            (Some(start), Some(end)) => whole_words.is_whole_word(start, end),
+            _ => false,
+        }
+    }).collect()
+}
+
+/// Tells whether ranges both start and end on a word boundary, as defined by
+/// [UAX #29](http://www.unicode.org/reports/tr29/#Word_Boundaries).
+struct WholeWords<'haystack> {
+    haystack: &'haystack Rope,
+    // Word boundaries never depend on text past a line break, so we only need to look
+    // at the lines each range touches. Many ranges are often on the same lines, so we
+    // keep the first and last line we looked at, the offset of the start of the first
+    // one, and the boundaries in them.
+    cached: Option<(usize, usize, usize, Vec<usize>)>,
+}
+
+impl <'haystack> WholeWords<'haystack> {
+    fn new(haystack: &'haystack Rope) -> Self {
+        WholeWords {
+            haystack,
+            cached: None,
+        }
+    }
+
+    fn is_whole_word(&mut self, start: AbsoluteCharOffset, end: AbsoluteCharOffset) -> bool {
+        let haystack = self.haystack;
+        let first_line = some_or!(haystack.char_to_line(start), return false).0;
+        let last_line = some_or!(haystack.char_to_line(end), return false).0;
+
+        let is_cached = match self.cached {
+            Some((first, last, _, _)) => first == first_line && last == last_line,
+            None => false,
+        };
+        if !is_cached {
+            self.cached = word_boundaries(haystack, first_line, last_line)
+                .map(|(lines_start, boundaries)| (first_line, last_line, lines_start, boundaries));
+        }
+
+        let (_, _, lines_start, boundaries) = some_or!(self.cached.as_ref(), return false);
+
+        let is_boundary = |o: AbsoluteCharOffset| {
+            o.0.checked_sub(*lines_start)
+                .map(|o| boundaries.binary_search(&o).is_ok())
+                .unwrap_or(false)
+        };
+
+        is_boundary(start) && is_boundary(end)
+    }
+}
+
+/// Returns the absolute char offset of the start of `first_line` and the sorted char
+/// offsets, relative to that start, of the word boundaries in the given lines.
+fn word_boundaries(
+    haystack: &Rope,
+    first_line: usize,
+    last_line: usize,
+) -> Option<(usize, Vec<usize>)> {
+    use panic_safe_rope::LineIndex;
+    let lines_start = haystack.line_to_char(LineIndex(first_line))?;
+    let lines_end = haystack.line_to_char(LineIndex(last_line + 1))
+        .unwrap_or_else(|| haystack.len_chars());
+    let lines: String = haystack.slice(lines_start..lines_end)?.into();
+
+    let mut boundaries = vec![0];
+    let mut offset = 0;
+    for word in lines.split_word_bounds() {
+        offset += word.chars().count();
+        boundaries.push(offset);
+    }
+
+    Some((lines_start.0, boundaries))
+}
+
+/// This walks the haystack's bytes in place, chunk by chunk, so we never need to copy
+/// the whole haystack into a single `String`. Matches that `is_accepted` returns false
+/// for are skipped, and the search continues from the char after where they started.
+fn get_regex_ranges_impl(
+    regex: &Regex,
+    haystack: RopeSlice,
+    max_needed: Option<std::num::NonZeroUsize>,
+    mut is_accepted: impl FnMut(CharOffset, CharOffset) -> bool,
+) -> Vec<(CharOffset, CharOffset)> {
+    perf_viz::record_guard!("get_regex_ranges_impl");
+    let max_needed = max_needed.map(|n| n.get()).unwrap_or(usize::max_value());
+
+    let mut output = Vec::new();
+
+    let mut search_start = 0;
+    while output.len() < max_needed {
+        let end = some_or!(find_match_end(regex.forward(), haystack, search_start), break);
+        // If the forward search matched, then the reverse search always will, so this
+        // fallback should never actually be used.
+        let start = find_match_start(regex.reverse(), haystack, search_start, end)
+            .unwrap_or(end);
+
+        let start_char = haystack.byte_to_char(ByteIndex(start));
+        let end_char = haystack.byte_to_char(ByteIndex(end));
+
+        if start == end {
+            // Move past the empty match so we don't find it again.
+            search_start = some_or!(
+                end_char.and_then(|o| haystack.char_to_byte(o + 1)),
+                break
+            ).0;
+            continue;
+        }
+
+        if let (Some(start_char), Some(end_char)) = (start_char, end_char) {
+            if !is_accepted(start_char, end_char) {
+                search_start = some_or!(
+                    haystack.char_to_byte(start_char + 1),
+                    break
+                ).0;
+                continue;
+            }
+            output.push((start_char, end_char));
+        }
+
+        search_start = end;
+    }
+
+    output
+}
+
+/// Returns the byte index just past the end of the leftmost match that starts at or
+/// after `start`.
+fn find_match_end<D: regex_automata::DFA>(
+    dfa: &D,
+    haystack: RopeSlice,
+    start: usize,
+) -> Option<usize> {
+    let mut state = dfa.start_state();
+    let mut last_match = if dfa.is_dead_state(state) {
+        return None;
+    } else if dfa.is_match_state(state) {
+        Some(start)
+    } else {
+        None
+    };
+
+    let mut i = start;
+    for byte in haystack.bytes_at(ByteIndex(start))? {
+        state = dfa.next_state(state, byte);
+        i += 1;
+        if dfa.is_match_or_dead_state(state) {
+            if dfa.is_dead_state(state) {
+                return last_match;
+            }
+            last_match = Some(i);
+        }
+    }
+
+    last_match
+}
+
+/// Runs the reverse DFA backwards from `end`, without going past `min`, to find where
+/// the match ending at `end` starts.
+fn find_match_start<D: regex_automata::DFA>(
+    dfa: &D,
+    haystack: RopeSlice,
+    min: usize,
+    end: usize,
+) -> Option<usize> {
+    let mut state = dfa.start_state();
+    let mut last_match = if dfa.is_dead_state(state) {
+        return None;
+    } else if dfa.is_match_state(state) {
+        Some(end)
+    } else {
+        None
+    };
+
+    let mut bytes = haystack.bytes_at(ByteIndex(end))?;
+    let mut i = end;
+    while i > min {
+        let byte = bytes.prev()?;
+        state = dfa.next_state(state, byte);
+        i -= 1;
+        if dfa.is_match_or_dead_state(state) {
+            if dfa.is_dead_state(state) {
+                return last_match;
+            }
+            last_match = Some(i);
+        }
+    }
+
+    last_match
+}
+
 // TODO benchmark with previous version used in tests
 fn get_ranges_impl(
     needle: RopeSlice,
@@ -1,5 +1,6 @@
 use super::*;
 
+use platform_types::{pos, CaseSensitivity};
 use proptest::proptest;
 
 mod arb {
@@ -486,3 +487,479 @@ fn get_ranges_slow_returns_only_locations_that_match_the_needle_in_this_overlapp
     get_ranges_slow_returns_only_locations_that_match_the_needle_on(&needle, &haystack);
 }
 
+fn regex_crate_ranges(pattern: &str, haystack: &Rope) -> Vec<(Position, Position)> {
+    let haystack_string: String = haystack.into();
+    regex::Regex::new(pattern)
+        .unwrap()
+        .find_iter(&haystack_string)
+        .filter(|m| m.start() != m.end())
+        .map(|m| {
+            let to_pos = |byte_index| {
+                let offset = haystack.byte_to_char(ByteIndex(byte_index)).unwrap();
+                char_offset_to_pos(haystack, offset).unwrap()
+            };
+            (to_pos(m.start()), to_pos(m.end()))
+        })
+        .collect()
+}
+
+proptest! {
+    #[test]
+    fn get_regex_ranges_matches_the_regex_crate(
+        pattern in "(a|b|ab|a+|b\\*|\\[ab\\]+c\\?|\\.|\\.\\+c)",
+        haystack in "[abcȺ\n ]{0,64}",
+    ) {
+        let haystack: Rope = haystack.into();
+        assert_eq!(
+            get_regex_ranges(&pattern, &haystack, None, None),
+            regex_crate_ranges(&pattern, &haystack)
+        );
+    }
+}
+
+#[test]
+fn get_regex_ranges_finds_matches_that_cross_chunk_boundaries() {
+    // This is long enough that the rope will be split into several chunks, and the
+    // lengths are relatively prime to common chunk sizes, so some matches will cross
+    // chunk boundaries.
+    let haystack: Rope = "xyzzy abbbc Ⱥ ".repeat(2000).into();
+
+    assert!(haystack.chunks().count() > 1);
+
+    let ranges = get_regex_ranges("ab+c", &haystack, None, None);
+
+    assert_eq!(ranges.len(), 2000);
+    assert_eq!(ranges, regex_crate_ranges("ab+c", &haystack));
+}
+
+#[test]
+fn get_regex_ranges_respects_max_needed() {
+    let haystack: Rope = "a1 b2 c3 d4".into();
+
+    assert_eq!(
+        get_regex_ranges("[a-z][0-9]", &haystack, None, std::num::NonZeroUsize::new(2)),
+        vec![
+            (pos!{l 0 o 0}, pos!{l 0 o 2}),
+            (pos!{l 0 o 3}, pos!{l 0 o 5}),
+        ]
+    );
+}
+
+#[test]
+fn get_regex_ranges_returns_nothing_for_an_invalid_pattern() {
+    let haystack: Rope = "a(b".into();
+
+    assert_eq!(get_regex_ranges("a(b", &haystack, None, None), vec![]);
+}
+
+#[test]
+fn get_regex_ranges_skips_empty_matches() {
+    let haystack: Rope = "baab".into();
+
+    assert_eq!(
+        get_regex_ranges("a*", &haystack, None, None),
+        vec![(pos!{l 0 o 1}, pos!{l 0 o 3})]
+    );
+}
+
+fn options(kind: SearchKind) -> SearchOptions {
+    SearchOptions {
+        kind,
+        ..d!()
+    }
+}
+
+#[test]
+fn search_results_new_finds_regex_matches_on_multiple_lines() {
+    let haystack: Rope = "fn a() {}\nfn bc() {}\n".into();
+    let needle: Rope = "fn [a-z]+".into();
+
+    let results = SearchResults::new(needle.full_slice(), options(SearchKind::Regex), &haystack);
+
+    assert_eq!(
+        results.ranges,
+        vec![
+            (pos!{l 0 o 0}, pos!{l 0 o 4}),
+            (pos!{l 1 o 0}, pos!{l 1 o 5}),
+        ]
+    );
+}
+
+#[test]
+fn search_results_new_treats_regex_syntax_literally_in_plain_mode() {
+    let haystack: Rope = "a+ aa".into();
+    let needle: Rope = "a+".into();
+
+    let results = SearchResults::new(needle.full_slice(), options(SearchKind::Plain), &haystack);
+
+    assert_eq!(results.ranges, vec![(pos!{l 0 o 0}, pos!{l 0 o 2})]);
+}
+
+#[test]
+fn get_replacements_expands_capture_groups_for_regex_searches() {
+    let haystack: Rope = "a = 1;\nbc = 23;".into();
+    let needle: Rope = "([a-z]+) = ([0-9]+)".into();
+
+    let results = SearchResults::new(needle.full_slice(), options(SearchKind::Regex), &haystack);
+
+    assert_eq!(
+        results.get_replacements(&haystack, &results.ranges, "$2 = $1"),
+        vec!["1 = a".to_owned(), "23 = bc".to_owned()]
+    );
+}
+
+#[test]
+fn get_replacements_does_not_expand_capture_groups_for_plain_searches() {
+    let haystack: Rope = "a = 1;".into();
+    let needle: Rope = "a".into();
+
+    let results = SearchResults::new(needle.full_slice(), options(SearchKind::Plain), &haystack);
+
+    assert_eq!(
+        results.get_replacements(&haystack, &results.ranges, "$1"),
+        vec!["$1".to_owned()]
+    );
+}
+
+#[test]
+fn search_results_new_ignores_case_in_plain_mode_when_case_insensitive() {
+    let haystack: Rope = "Foo foo FOO f.o".into();
+    let needle: Rope = "foo".into();
+
+    let results = SearchResults::new(
+        needle.full_slice(),
+        SearchOptions {
+            case: CaseSensitivity::Insensitive,
+            ..d!()
+        },
+        &haystack
+    );
+
+    assert_eq!(
+        results.ranges,
+        vec![
+            (pos!{l 0 o 0}, pos!{l 0 o 3}),
+            (pos!{l 0 o 4}, pos!{l 0 o 7}),
+            (pos!{l 0 o 8}, pos!{l 0 o 11}),
+        ]
+    );
+}
+
+#[test]
+fn search_results_new_ignores_case_in_regex_mode_when_case_insensitive() {
+    let haystack: Rope = "ab AB aB".into();
+    let needle: Rope = "a[b]".into();
+
+    let results = SearchResults::new(
+        needle.full_slice(),
+        SearchOptions {
+            kind: SearchKind::Regex,
+            case: CaseSensitivity::Insensitive,
+            ..d!()
+        },
+        &haystack
+    );
+
+    assert_eq!(results.ranges.len(), 3);
+}
+
+#[test]
+fn search_results_new_with_smart_case_ignores_case_for_an_all_lowercase_needle() {
+    let haystack: Rope = "Foo foo".into();
+    let needle: Rope = "foo".into();
+
+    let results = SearchResults::new(
+        needle.full_slice(),
+        SearchOptions {
+            case: CaseSensitivity::Smart,
+            ..d!()
+        },
+        &haystack
+    );
+
+    assert_eq!(results.ranges.len(), 2);
+}
+
+#[test]
+fn search_results_new_with_smart_case_respects_case_for_a_needle_with_an_uppercase_letter() {
+    let haystack: Rope = "Foo foo".into();
+    let needle: Rope = "Foo".into();
+
+    let results = SearchResults::new(
+        needle.full_slice(),
+        SearchOptions {
+            case: CaseSensitivity::Smart,
+            ..d!()
+        },
+        &haystack
+    );
+
+    assert_eq!(results.ranges, vec![(pos!{l 0 o 0}, pos!{l 0 o 3})]);
+}
+
+#[test]
+fn search_results_new_with_whole_word_skips_matches_inside_words() {
+    let haystack: Rope = "cat concat cat_s\ncats cat.".into();
+    let needle: Rope = "cat".into();
+
+    let results = SearchResults::new(
+        needle.full_slice(),
+        SearchOptions {
+            whole_word: true,
+            ..d!()
+        },
+        &haystack
+    );
+
+    assert_eq!(
+        results.ranges,
+        vec![
+            (pos!{l 0 o 0}, pos!{l 0 o 3}),
+            (pos!{l 1 o 5}, pos!{l 1 o 8}),
+        ]
+    );
+}
+
+#[test]
+fn search_results_new_with_whole_word_uses_unicode_word_boundaries() {
+    let haystack: Rope = "naïve naï".into();
+    let needle: Rope = "naï".into();
+
+    let results = SearchResults::new(
+        needle.full_slice(),
+        SearchOptions {
+            whole_word: true,
+            ..d!()
+        },
+        &haystack
+    );
+
+    assert_eq!(results.ranges, vec![(pos!{l 0 o 6}, pos!{l 0 o 9})]);
+}
+
+#[test]
+fn search_results_new_with_whole_word_works_with_regexes_and_ignored_case() {
+    let haystack: Rope = "Ab ab abc".into();
+    let needle: Rope = "a.".into();
+
+    let results = SearchResults::new(
+        needle.full_slice(),
+        SearchOptions {
+            kind: SearchKind::Regex,
+            case: CaseSensitivity::Insensitive,
+            whole_word: true,
+            in_selection: false,
+        },
+        &haystack
+    );
+
+    assert_eq!(
+        results.ranges,
+        vec![
+            (pos!{l 0 o 0}, pos!{l 0 o 2}),
+            (pos!{l 0 o 3}, pos!{l 0 o 5}),
+        ]
+    );
+}
+
+#[test]
+fn search_results_new_with_whole_word_finds_a_match_overlapping_a_skipped_one_in_every_mode() {
+    let haystack: Rope = "aa a a".into();
+    let needle: Rope = "a a".into();
+
+    for &kind in &[SearchKind::Plain, SearchKind::Regex] {
+        for &case in &[CaseSensitivity::Sensitive, CaseSensitivity::Insensitive] {
+            let results = SearchResults::new(
+                needle.full_slice(),
+                SearchOptions {
+                    kind,
+                    case,
+                    whole_word: true,
+                    in_selection: false,
+                },
+                &haystack
+            );
+
+            assert_eq!(
+                results.ranges,
+                vec![(pos!{l 0 o 3}, pos!{l 0 o 6})],
+                "{:?} {:?}",
+                kind,
+                case
+            );
+        }
+    }
+}
+
+#[test]
+fn get_replacements_expands_capture_groups_for_case_insensitive_regex_searches() {
+    let haystack: Rope = "A = 1;".into();
+    let needle: Rope = "([a-z]+) = ([0-9]+)".into();
+
+    let results = SearchResults::new(
+        needle.full_slice(),
+        SearchOptions {
+            kind: SearchKind::Regex,
+            case: CaseSensitivity::Insensitive,
+            ..d!()
+        },
+        &haystack
+    );
+
+    assert_eq!(
+        results.get_replacements(&haystack, &results.ranges, "$2 = $1"),
+        vec!["1 = A".to_owned()]
+    );
+}
+
+fn in_selection() -> SearchOptions {
+    SearchOptions {
+        in_selection: true,
+        ..d!()
+    }
+}
+
+#[test]
+fn search_results_new_in_selections_only_finds_results_inside_the_selections() {
+    let haystack: Rope = "ab ab\nab ab ab".into();
+    let needle: Rope = "ab".into();
+
+    let results = SearchResults::new_in_selections(
+        needle.full_slice(),
+        in_selection(),
+        vec![
+            AbsoluteCharOffsetRange::new_usize(2, 7),
+            AbsoluteCharOffsetRange::new_usize(8, 15),
+        ],
+        &haystack
+    );
+
+    assert_eq!(
+        results.ranges,
+        vec![
+            (pos!{l 0 o 3}, pos!{l 0 o 5}),
+            (pos!{l 1 o 3}, pos!{l 1 o 5}),
+            (pos!{l 1 o 6}, pos!{l 1 o 8}),
+        ]
+    );
+}
+
+#[test]
+fn search_results_new_in_selections_does_not_find_results_that_cross_a_selection_edge() {
+    let haystack: Rope = "abab".into();
+    let needle: Rope = "ab".into();
+
+    let results = SearchResults::new_in_selections(
+        needle.full_slice(),
+        in_selection(),
+        vec![AbsoluteCharOffsetRange::new_usize(1, 4)],
+        &haystack
+    );
+
+    assert_eq!(results.ranges, vec![(pos!{l 0 o 2}, pos!{l 0 o 4})]);
+}
+
+#[test]
+fn search_results_new_in_selections_searches_everything_if_in_selection_is_not_set() {
+    let haystack: Rope = "ab ab".into();
+    let needle: Rope = "ab".into();
+
+    let results = SearchResults::new_in_selections(
+        needle.full_slice(),
+        d!(),
+        vec![AbsoluteCharOffsetRange::new_usize(0, 2)],
+        &haystack
+    );
+
+    assert_eq!(results.ranges.len(), 2);
+}
+
+#[test]
+fn search_results_new_in_selections_searches_everything_if_there_are_no_selections() {
+    let haystack: Rope = "ab ab".into();
+    let needle: Rope = "ab".into();
+
+    let results = SearchResults::new_in_selections(
+        needle.full_slice(),
+        in_selection(),
+        vec![],
+        &haystack
+    );
+
+    assert_eq!(results.ranges.len(), 2);
+}
+
+#[test]
+fn search_results_new_in_selections_does_not_search_everything_if_the_selections_are_out_of_bounds() {
+    let haystack: Rope = "ab ab".into();
+    let needle: Rope = "ab".into();
+
+    let results = SearchResults::new_in_selections(
+        needle.full_slice(),
+        SearchOptions {
+            kind: SearchKind::Regex,
+            in_selection: true,
+            ..d!()
+        },
+        vec![AbsoluteCharOffsetRange::new_usize(8, 16)],
+        &haystack
+    );
+
+    assert_eq!(results.ranges, vec![]);
+}
+
+#[test]
+fn selection_ranges_merges_overlapping_selections_and_drops_empty_ones() {
+    let haystack: Rope = "abcdef\nghi".into();
+
+    let selections = selection_ranges(
+        &haystack,
+        vec![
+            (pos!{l 1 o 2}, pos!{l 0 o 4}),
+            (pos!{l 0 o 1}, pos!{l 0 o 1}),
+            (pos!{l 0 o 0}, pos!{l 0 o 2}),
+            (pos!{l 0 o 5}, pos!{l 0 o 3}),
+        ]
+    );
+
+    assert_eq!(
+        selections,
+        vec![
+            AbsoluteCharOffsetRange::new_usize(0, 2),
+            AbsoluteCharOffsetRange::new_usize(3, 9),
+        ]
+    );
+}
+
+#[test]
+fn adjust_selections_for_edit_keeps_the_selections_around_the_same_text() {
+    // "a a a a" with the last "a" removed, then the first "a" replaced with "bbb".
+    let mut selections = vec![
+        AbsoluteCharOffsetRange::new_usize(0, 3),
+        AbsoluteCharOffsetRange::new_usize(4, 7),
+    ];
+
+    adjust_selections_for_edit(&mut selections, AbsoluteCharOffset(6), 1, 0);
+    adjust_selections_for_edit(&mut selections, AbsoluteCharOffset(0), 1, 3);
+
+    assert_eq!(
+        selections,
+        vec![
+            AbsoluteCharOffsetRange::new_usize(0, 5),
+            AbsoluteCharOffsetRange::new_usize(6, 8),
+        ]
+    );
+}
+
+#[test]
+fn adjust_selections_for_edit_grows_selections_for_typing_and_shrinks_them_for_deletions() {
+    let mut selections = vec![AbsoluteCharOffsetRange::new_usize(2, 5)];
+
+    adjust_selections_for_edit(&mut selections, AbsoluteCharOffset(3), 0, 2);
+
+    assert_eq!(selections, vec![AbsoluteCharOffsetRange::new_usize(2, 7)]);
+
+    adjust_selections_for_edit(&mut selections, AbsoluteCharOffset(0), 3, 0);
+
+    assert_eq!(selections, vec![AbsoluteCharOffsetRange::new_usize(0, 4)]);
+}
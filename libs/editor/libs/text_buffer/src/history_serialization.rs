@@ -0,0 +1,386 @@
+//! A plain text format for the parts of a `TextBuffer` that are not the text itself,
+//! so they can survive the editor being restarted. The text is stored separately, so
+//! we only store a hash of it, to detect when the history does not belong to it.
+use super::*;
+use edit::{RangeEdit, RangeEdits};
+use undo_tree::UndoTreeParts;
+
+/// This should be changed whenever the format changes, so that histories in an older
+/// format are discarded instead of being misread.
+const HEADER: &str = "rote text buffer history v3\n";
+
+const NONE_TOKEN: &str = "-";
+const SOME_TOKEN: &str = "+";
+
+const ALL_MOVES: [Move; 10] = {
+    u!{Move}
+    [
+        Up,
+        Down,
+        Left,
+        Right,
+        ToLineStart,
+        ToLineEnd,
+        ToBufferStart,
+        ToBufferEnd,
+        ToPreviousLikelyEditLocation,
+        ToNextLikelyEditLocation,
+    ]
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RestoreHistoryError {
+    UnknownVersion,
+    Malformed,
+    /// The history was for a different text than the one in the buffer.
+    TextMismatch,
+}
+
+impl TextBuffer {
+    pub fn serialize_history(&self) -> String {
+        let mut output = String::with_capacity(HEADER.len());
+        output.push_str(HEADER);
+
+        write_u64(&mut output, self.text_hash());
+        write_u64(&mut output, self.unedited_hash);
+        write_usize(&mut output, self.unedited_len);
+        write_cursors(&mut output, &self.cursors);
+
+        let UndoTreeParts { nodes, current, root_redo_child } = self.history.parts();
+        write_usize(&mut output, nodes.len());
+        for (edit, parent, redo_child) in nodes {
+            write_option_usize(&mut output, parent);
+            write_option_usize(&mut output, redo_child);
+            write_edit(&mut output, edit);
+        }
+        write_option_usize(&mut output, current);
+        write_option_usize(&mut output, root_redo_child);
+
+        output
+    }
+
+    /// Replaces the history, the unedited text's hash and length, and the cursors with
+    /// the ones from `serialized`, which should have come from `serialize_history`. If
+    /// an error is returned then the buffer is left as it was.
+    pub fn restore_history(
+        &mut self,
+        serialized: &str,
+    ) -> Result<PossibleEditedTransition, RestoreHistoryError> {
+        u!{RestoreHistoryError}
+        if !serialized.starts_with(HEADER) {
+            return Err(UnknownVersion);
+        }
+
+        let mut reader = Reader(&serialized[HEADER.len()..]);
+
+        if reader.u64().ok_or(Malformed)? != self.text_hash() {
+            return Err(TextMismatch);
+        }
+
+        let (unedited_hash, unedited_len, cursors, history) = read_history_parts(&mut reader)
+            .ok_or(Malformed)?;
+
+        if !reader.0.is_empty() || !history_fits(&self.rope, &history) {
+            return Err(Malformed);
+        }
+
+        let old_editedness = self.editedness();
+
+        self.unedited_hash = unedited_hash;
+        self.unedited_len = unedited_len;
+        self.cursors = Cursors::new(&self.rope, cursors);
+        self.history = history;
+
+        Ok(HistoryNavOutcome::from(change!(old_editedness, self.editedness())).into())
+    }
+}
+
+fn read_history_parts(
+    reader: &mut Reader,
+) -> Option<(u64, usize, Vec1<Cursor>, UndoTree<Edit>)> {
+    let unedited_hash = reader.u64()?;
+    let unedited_len = reader.usize()?;
+    let cursors = read_cursors(reader)?;
+
+    let len = reader.usize()?;
+    // The length came from a file, so we don't trust it enough to preallocate with it.
+    let mut nodes = Vec::new();
+    for _ in 0..len {
+        let parent = reader.option_usize()?;
+        let redo_child = reader.option_usize()?;
+        let edit = read_edit(reader)?;
+        nodes.push((edit, parent, redo_child));
+    }
+    let current = reader.option_usize()?;
+    let root_redo_child = reader.option_usize()?;
+
+    let history = UndoTree::from_parts(UndoTreeParts {
+        nodes,
+        current,
+        root_redo_child,
+    })?;
+
+    Some((unedited_hash, unedited_len, cursors, history))
+}
+
+/// The history came from outside the editor, so before using it we check that every
+/// edit in it fits the text that it would be applied to, by replaying the whole tree,
+/// starting from the current text. Otherwise undoing or redoing could panic, or
+/// silently do the wrong thing.
+fn history_fits(rope: &Rope, history: &UndoTree<Edit>) -> bool {
+    let UndoTreeParts { nodes, current, .. } = history.parts();
+
+    let mut root_rope = rope.clone();
+    let mut node = current;
+    while let Some(index) = node {
+        let (edit, parent, _) = nodes[index];
+        root_rope = some_or!(apply_if_fits(&root_rope, &!edit.clone()), return false);
+        node = parent;
+    }
+
+    // Parents come before their children, so the text before each edit is known by
+    // the time we get to it.
+    let mut ropes: Vec<Rope> = Vec::with_capacity(nodes.len());
+    for &(edit, parent, _) in nodes.iter() {
+        let parent_rope = match parent {
+            Some(index) => &ropes[index],
+            None => &root_rope,
+        };
+        let rope = some_or!(apply_if_fits(parent_rope, edit), return false);
+        ropes.push(rope);
+    }
+
+    true
+}
+
+/// Returns what `rope` would be after `edit`, if `edit` fits `rope`. That is, if each
+/// range it deletes is inside the text and as long as the chars it says are there, each
+/// insertion point is inside the text, and the cursors are inside the text before and
+/// after the edit.
+fn apply_if_fits(rope: &Rope, edit: &Edit) -> Option<Rope> {
+    let cursors_fit = |rope: &Rope, cursors: &Cursors| cursors.iter().all(|c| {
+        in_cursor_bounds(rope, c.get_position())
+        && in_cursor_bounds(rope, c.get_highlight_position_or_position())
+    });
+
+    let Change { old, new } = edit.cursors();
+    if !cursors_fit(rope, old) {
+        return None;
+    }
+
+    let mut rope = rope.clone();
+    for range_edits in edit.range_edits().iter() {
+        if let Some(RangeEdit { chars, range }) = &range_edits.delete_range {
+            if range.max() > rope.len_chars()
+            || range.max().0 - range.min().0 != chars.chars().count() {
+                return None;
+            }
+        }
+        if let Some(RangeEdit { range, .. }) = &range_edits.insert_range {
+            if range.min() > rope.len_chars() {
+                return None;
+            }
+        }
+
+        range_edits.apply(&mut rope);
+    }
+
+    if !cursors_fit(&rope, new) {
+        return None;
+    }
+
+    Some(rope)
+}
+
+//
+// Writing. Every token is followed by a space, including the last one.
+//
+
+fn write_usize(output: &mut String, n: usize) {
+    use std::fmt::Write;
+    let _cannot_actually_fail = write!(output, "{} ", n);
+}
+
+fn write_u64(output: &mut String, n: u64) {
+    use std::fmt::Write;
+    let _cannot_actually_fail = write!(output, "{} ", n);
+}
+
+fn write_option_usize(output: &mut String, op: Option<usize>) {
+    match op {
+        Some(n) => write_usize(output, n),
+        None => {
+            output.push_str(NONE_TOKEN);
+            output.push(' ');
+        }
+    }
+}
+
+/// Strings are prefixed with their length in bytes, so they can contain spaces.
+fn write_str(output: &mut String, s: &str) {
+    use std::fmt::Write;
+    let _cannot_actually_fail = write!(output, "{}:{} ", s.len(), s);
+}
+
+fn write_position(output: &mut String, position: Position) {
+    write_usize(output, position.line);
+    write_usize(output, position.offset.0);
+}
+
+fn write_cursor(output: &mut String, cursor: &Cursor) {
+    write_position(output, cursor.get_position());
+    write_position(output, cursor.get_highlight_position_or_position());
+    write_usize(output, cursor.sticky_offset.0);
+    match cursor.state {
+        CursorState::None => output.push_str(NONE_TOKEN),
+        // None of the `Move` display strings contain spaces.
+        CursorState::PressedAgainstWall(r#move) => output.push_str(&r#move.to_string()),
+    }
+    output.push(' ');
+}
+
+fn write_cursors(output: &mut String, cursors: &Cursors) {
+    write_usize(output, cursors.len());
+    for cursor in cursors.iter() {
+        write_cursor(output, cursor);
+    }
+}
+
+fn write_range_edit(output: &mut String, range_edit: &Option<RangeEdit>) {
+    match range_edit {
+        Some(RangeEdit { chars, range }) => {
+            output.push_str(SOME_TOKEN);
+            output.push(' ');
+            write_usize(output, range.min().0);
+            write_usize(output, range.max().0);
+            write_str(output, chars);
+        }
+        None => {
+            output.push_str(NONE_TOKEN);
+            output.push(' ');
+        }
+    }
+}
+
+fn write_edit(output: &mut String, edit: &Edit) {
+    let range_edits = edit.range_edits();
+    write_usize(output, range_edits.len());
+    for RangeEdits { insert_range, delete_range } in range_edits.iter() {
+        write_range_edit(output, insert_range);
+        write_range_edit(output, delete_range);
+    }
+
+    let Change { old, new } = edit.cursors();
+    write_cursors(output, old);
+    write_cursors(output, new);
+}
+
+//
+// Reading
+//
+
+struct Reader<'s>(&'s str);
+
+impl <'s> Reader<'s> {
+    fn token(&mut self) -> Option<&'s str> {
+        let space_index = self.0.find(' ')?;
+        let token = &self.0[..space_index];
+        self.0 = &self.0[space_index + 1..];
+        Some(token)
+    }
+
+    fn usize(&mut self) -> Option<usize> {
+        self.token()?.parse().ok()
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        self.token()?.parse().ok()
+    }
+
+    fn option_usize(&mut self) -> Option<Option<usize>> {
+        match self.token()? {
+            NONE_TOKEN => Some(None),
+            token => token.parse().ok().map(Some),
+        }
+    }
+
+    fn str(&mut self) -> Option<&'s str> {
+        let colon_index = self.0.find(':')?;
+        let len: usize = self.0[..colon_index].parse().ok()?;
+        let rest = &self.0[colon_index + 1..];
+
+        let s = rest.get(..len)?;
+        let rest = rest.get(len..)?;
+        if !rest.starts_with(' ') {
+            return None;
+        }
+        self.0 = &rest[1..];
+
+        Some(s)
+    }
+}
+
+fn read_position(reader: &mut Reader) -> Option<Position> {
+    Some(Position {
+        line: reader.usize()?,
+        offset: CharOffset(reader.usize()?),
+    })
+}
+
+fn read_cursor(reader: &mut Reader) -> Option<Cursor> {
+    let position = read_position(reader)?;
+    let highlight_position = read_position(reader)?;
+    let mut cursor = Cursor::new_with_highlight(position, highlight_position);
+    cursor.sticky_offset = CharOffset(reader.usize()?);
+    cursor.state = match reader.token()? {
+        NONE_TOKEN => CursorState::None,
+        token => CursorState::PressedAgainstWall(
+            *ALL_MOVES.iter().find(|r#move| r#move.to_string() == token)?
+        ),
+    };
+    Some(cursor)
+}
+
+fn read_cursors(reader: &mut Reader) -> Option<Vec1<Cursor>> {
+    let len = reader.usize()?;
+    let mut cursors = Vec::new();
+    for _ in 0..len {
+        cursors.push(read_cursor(reader)?);
+    }
+    Vec1::try_from_vec(cursors).ok()
+}
+
+fn read_range_edit(reader: &mut Reader) -> Option<Option<RangeEdit>> {
+    match reader.token()? {
+        NONE_TOKEN => Some(None),
+        SOME_TOKEN => {
+            let min = reader.usize()?;
+            let max = reader.usize()?;
+            if min > max {
+                return None;
+            }
+            let chars = reader.str()?.to_owned();
+            Some(Some(RangeEdit {
+                chars,
+                range: AbsoluteCharOffsetRange::new_usize(min, max),
+            }))
+        }
+        _ => None,
+    }
+}
+
+fn read_edit(reader: &mut Reader) -> Option<Edit> {
+    let len = reader.usize()?;
+    let mut range_edits = Vec::new();
+    for _ in 0..len {
+        let insert_range = read_range_edit(reader)?;
+        let delete_range = read_range_edit(reader)?;
+        range_edits.push(RangeEdits { insert_range, delete_range });
+    }
+    let range_edits = Vec1::try_from_vec(range_edits).ok()?;
+
+    let old = Cursors::new_unchecked(read_cursors(reader)?);
+    let new = Cursors::new_unchecked(read_cursors(reader)?);
+
+    Some(Edit::from_parts(range_edits, Change { old, new }))
+}
@@ -851,6 +851,25 @@ fn inserting_then_deleting_preserves_editedness_in_this_minimal_example() {
     );
 }
 
+#[test]
+fn the_version_is_new_after_each_edit_even_if_the_text_is_the_same_as_before() {
+    let mut buffer: TextBuffer = d!();
+    let other: TextBuffer = d!();
+    let mut versions = vec![buffer.version(), other.version()];
+
+    buffer.insert('a', None);
+    versions.push(buffer.version());
+    buffer.delete(None);
+    versions.push(buffer.version());
+    let _ = buffer.undo(None);
+    versions.push(buffer.version());
+
+    let mut deduped = versions.clone();
+    deduped.sort();
+    deduped.dedup();
+    assert_eq!(deduped.len(), versions.len(), "{:?}", versions);
+}
+
 fn calling_set_unedited_acts_as_expected_after_a_second_insertion_on(
     mut buffer: TextBuffer,
     ch1: char,
@@ -903,7 +922,290 @@ fn calling_set_unedited_acts_as_expected_after_a_second_insertion_works_on_an_em
     );
 }
 
+#[test]
+fn reload_keeps_the_cursor_where_it_was_and_is_undoable() {
+    u!{Editedness}
+    let mut buffer = t_b!("000\n111\n222\n");
+    buffer.set_cursor(pos! {l 1 o 2}, ReplaceOrAdd::Replace);
+    buffer.insert('a', None);
+    assert_eq!(buffer.editedness(), Edited, "precondition_failure");
+
+    let transition = buffer.reload("000\n1111111\n".to_owned(), None);
+
+    assert_eq!(transition, Some(EditedTransition::ToUnedited));
+    assert_eq!(buffer.editedness(), Unedited);
+    assert_eq!(String::from(&buffer), "000\n1111111\n");
+    assert_eq!(single_cursor(&buffer).get_position(), pos! {l 1 o 3});
+
+    buffer.undo(None);
+
+    assert_eq!(String::from(&buffer), "000\n11a1\n222\n");
+    assert_eq!(buffer.editedness(), Edited);
+}
+
+#[test]
+fn reload_clamps_cursors_past_the_end_of_the_new_text() {
+    let mut buffer = t_b!("000\n111\n222");
+    buffer.set_cursor(pos! {l 2 o 3}, ReplaceOrAdd::Replace);
+
+    buffer.reload("0".to_owned(), None);
+
+    assert_eq!(String::from(&buffer), "0");
+    assert!(buffer.in_bounds(single_cursor(&buffer).get_position()));
+}
+
+#[test]
+fn typing_out_the_changed_on_disk_text_makes_the_buffer_unedited() {
+    u!{Editedness, EditedTransition}
+    let mut buffer = t_b!("abc");
+    buffer.move_all_cursors(Move::ToBufferEnd);
+
+    let transition = buffer.set_unedited_text("abcd");
+
+    assert_eq!(transition, Some(ToEdited));
+    assert_eq!(buffer.editedness(), Edited);
+
+    let transition = buffer.insert('d', None);
+
+    assert_eq!(transition, Some(ToUnedited));
+    assert_eq!(buffer.editedness(), Unedited);
+}
+
+#[test]
+fn a_buffer_with_the_same_length_as_the_unedited_text_but_different_text_is_edited() {
+    u!{Editedness, EditedTransition}
+    let mut buffer = t_b!("ab");
+
+    let transition = buffer.set_unedited_text("ba");
+
+    assert_eq!(transition, Some(ToEdited));
+    assert_eq!(buffer.editedness(), Edited);
+}
+
+#[test]
+fn moving_down_past_a_fold_skips_the_hidden_lines_and_unfolding_shows_them_again() {
+    let mut buffer = t_b!("fn f() {\n    a;\n    b;\n}\nc");
+    let candidate = Fold { first_line: 1, last_line: 2 };
+
+    buffer.fold(&[candidate]);
+
+    assert_eq!(buffer.shown_folds(), vec![candidate]);
+
+    buffer.move_all_cursors(Move::Down);
+
+    assert_eq!(single_cursor(&buffer).get_position(), pos!{l 3 o 0});
+
+    buffer.unfold();
+
+    assert_eq!(buffer.shown_folds(), vec![]);
+
+    buffer.move_all_cursors(Move::Up);
+
+    assert_eq!(single_cursor(&buffer).get_position(), pos!{l 2 o 0});
+}
+
+/// Owns what a `ParserEditListener` borrows, so tests can make a fresh listener
+/// for each call with `listener()`.
+struct ListenerParts {
+    buffer_name: BufferName,
+    parser_kind: ParserKind,
+    parsers: Parsers,
+}
+
+impl ListenerParts {
+    fn rust() -> Self {
+        ListenerParts {
+            buffer_name: BufferName::Path("fakefile.rs".into()),
+            parser_kind: ParserKind::Tree(parsers::RUST, d!()),
+            parsers: d!(),
+        }
+    }
+
+    fn plaintext() -> Self {
+        ListenerParts {
+            buffer_name: BufferName::Path("fakefile.txt".into()),
+            parser_kind: ParserKind::Plaintext,
+            parsers: d!(),
+        }
+    }
+
+    fn listener(&mut self) -> PossibleParserEditListener<'_, '_> {
+        Some(ParserEditListener {
+            buffer_name: &self.buffer_name,
+            parser_kind: self.parser_kind,
+            parsers: &mut self.parsers,
+        })
+    }
+}
+
+#[test]
+fn jumping_to_matching_brackets_twice_goes_back_to_where_each_cursor_started() {
+    let mut parts = ListenerParts::rust();
+
+    let mut buffer = t_b!("fn f() {\n    g(a);\n}", vec1![
+        cur!{l 0 o 7},
+        cur!{l 1 o 8},
+    ]);
+
+    buffer.jump_to_matching_brackets(parts.listener());
+
+    let cursors = buffer.borrow_cursors().get_cloned_cursors();
+    assert_eq!(cursors.len(), 2);
+    assert_eq!(cursors[0].get_position(), pos!{l 2 o 0});
+    assert_eq!(cursors[1].get_position(), pos!{l 1 o 6});
+
+    buffer.jump_to_matching_brackets(parts.listener());
+
+    let cursors = buffer.borrow_cursors().get_cloned_cursors();
+    assert_eq!(cursors[0].get_position(), pos!{l 1 o 8});
+    assert_eq!(cursors[1].get_position(), pos!{l 0 o 7});
+}
+
+#[test]
+fn selecting_inside_brackets_again_selects_inside_the_next_pair_out() {
+    let mut parts = ListenerParts::plaintext();
+
+    let mut buffer = t_b!("a(b[c]d)", vec1![cur!{l 0 o 5}]);
+
+    buffer.select_inside_brackets(parts.listener());
+
+    assert_eq!(single_cursor(&buffer), cur!{l 0 o 5 h l 0 o 4});
+
+    buffer.select_inside_brackets(parts.listener());
+
+    assert_eq!(single_cursor(&buffer), cur!{l 0 o 7 h l 0 o 2});
+}
+
+#[test]
+fn inserting_new_lines_indents_them_by_the_brackets_around_each_cursor() {
+    let mut parts = ListenerParts::rust();
+
+    let mut buffer = t_b!("fn f() {\n    g(a);\n    h({});\n}", vec1![
+        cur!{l 0 o 8},
+        cur!{l 1 o 9},
+        cur!{l 2 o 7},
+    ]);
+
+    buffer.insert_new_line(parts.listener());
+
+    let s: String = buffer.rope.clone().into();
+    assert_eq!(s, "fn f() {\n    \n    g(a);\n    \n    h({\n    });\n}");
+    let cursors = buffer.borrow_cursors().get_cloned_cursors();
+    assert_eq!(cursors.len(), 3);
+    assert_eq!(cursors[0].get_position(), pos!{l 5 o 4});
+    assert_eq!(cursors[1].get_position(), pos!{l 3 o 4});
+    assert_eq!(cursors[2].get_position(), pos!{l 1 o 4});
+
+    // Without a listener, as in the menus, just a line break is inserted.
+    let mut buffer = t_b!("fn f() {", vec1![cur!{l 0 o 8}]);
+
+    buffer.insert_new_line(None);
+
+    let s: String = buffer.rope.into();
+    assert_eq!(s, "fn f() {\n");
+}
+
+#[test]
+fn tabbing_in_indents_lines_to_where_the_lines_above_suggest_then_further() {
+    let mut parts = ListenerParts::plaintext();
+
+    let mut buffer = t_b!("{\nto tab\n  also\n}", vec1![cur!{l 1 o 0 h l 2 o 6}]);
+
+    buffer.tab_in(parts.listener());
+
+    let s: String = buffer.rope.clone().into();
+    assert_eq!(s, "{\n    to tab\n      also\n}");
+    assert_eq!(single_cursor(&buffer), cur!{l 1 o 4 h l 2 o 10});
+
+    buffer.tab_in(parts.listener());
+
+    let s: String = buffer.rope.clone().into();
+    assert_eq!(s, "{\n        to tab\n          also\n}");
+
+    // A cursor in the indentation of a line counts as selecting it.
+    let mut buffer = t_b!("    a = 1;\nb", vec1![cur!{l 1 o 0}]);
+
+    buffer.tab_in(parts.listener());
+
+    let s: String = buffer.rope.clone().into();
+    assert_eq!(s, "    a = 1;\n    b");
+    assert_eq!(single_cursor(&buffer), cur!{l 1 o 4});
+
+    // A cursor after the indentation gets a tab inserted, like without a listener.
+    let mut buffer = t_b!("a\nbc", vec1![cur!{l 1 o 1}]);
+
+    buffer.tab_in(parts.listener());
+
+    let s: String = buffer.rope.into();
+    assert_eq!(s, "a\nb    c");
+}
+
+#[test]
+fn tabbing_in_with_a_parse_tree_indents_by_the_enclosing_brackets() {
+    let mut parts = ListenerParts::rust();
+
+    // The line above ends with a bracket, but it is closed again on a later line.
+    let mut buffer = t_b!("fn f() {\n    g(a, b(\n    ));\nh();\n}", vec1![cur!{l 3 o 0}]);
+
+    buffer.tab_in(parts.listener());
+
+    let s: String = buffer.rope.into();
+    assert_eq!(s, "fn f() {\n    g(a, b(\n    ));\n    h();\n}");
+}
+
+#[test]
+fn extracting_a_function_selects_the_name_at_the_call_and_the_definition() {
+    let mut parts = ListenerParts::rust();
+
+    let mut buffer = t_b!("fn f(a: u8) -> u8 {\n    a + 1\n}", vec1![cur!{l 1 o 9 h l 1 o 4}]);
+
+    buffer.extract_function(parts.listener());
+
+    let s: String = buffer.rope.clone().into();
+    assert_eq!(
+        s,
+        "fn f(a: u8) -> u8 {\n    funcName(a)\n}\n\nfn funcName(a: u8) -> TypeName {\n    a + 1\n}"
+    );
+
+    let cursors: Vec<_> = buffer.cursors.iter().cloned().collect();
+    assert_eq!(cursors.len(), 2, "{:?}", cursors);
+    assert!(cursors.contains(&cur!{l 1 o 12 h l 1 o 4}), "{:?}", cursors);
+    assert!(cursors.contains(&cur!{l 4 o 11 h l 4 o 3}), "{:?}", cursors);
+
+    buffer.undo(parts.listener());
+
+    let s: String = buffer.rope.into();
+    assert_eq!(s, "fn f(a: u8) -> u8 {\n    a + 1\n}");
+}
+
+#[test]
+fn extracting_a_variable_everywhere_selects_the_name_at_the_binding_and_each_use() {
+    let mut parts = ListenerParts::rust();
+
+    let mut buffer = t_b!("fn f(a: u8) {\n    g(a + 1);\n    h(a + 1);\n}", vec1![cur!{l 1 o 11 h l 1 o 6}]);
+
+    buffer.extract_variable(true, parts.listener());
+
+    let s: String = buffer.rope.clone().into();
+    assert_eq!(
+        s,
+        "fn f(a: u8) {\n    let varName = a + 1;\n    g(varName);\n    h(varName);\n}"
+    );
+
+    let cursors: Vec<_> = buffer.cursors.iter().cloned().collect();
+    assert_eq!(cursors.len(), 3, "{:?}", cursors);
+    assert!(cursors.contains(&cur!{l 1 o 15 h l 1 o 8}), "{:?}", cursors);
+    assert!(cursors.contains(&cur!{l 2 o 13 h l 2 o 6}), "{:?}", cursors);
+    assert!(cursors.contains(&cur!{l 3 o 13 h l 3 o 6}), "{:?}", cursors);
+
+    buffer.undo(parts.listener());
+
+    let s: String = buffer.rope.into();
+    assert_eq!(s, "fn f(a: u8) {\n    g(a + 1);\n    h(a + 1);\n}");
+}
+
 pub mod arb;
 mod cursor_manipulation;
 mod edit_tests;
+mod history_serialization;
 mod inserting_then_deleting;
@@ -209,9 +209,14 @@ pub enum TestEdit {
     InsertNumbersAtCursors,
     TabIn,
     TabOut,
+    SwapLinesUp,
+    SwapLinesDown,
+    ToggleComments,
 }
 
 pub type CountNumber = usize;
+
+pub const TEST_COMMENT_START: &str = "//";
 
 pub type Counts = HashMap<char, CountNumber>;
 
@@ -295,6 +300,9 @@ impl TestEdit {
             InsertNumbersAtCursors => { buffer.insert_at_each_cursor(|i| i.to_string(), None); },
             TabIn => { buffer.tab_in(None); },
             TabOut => { buffer.tab_out(None); },
+            SwapLinesUp => { buffer.swap_lines(edit::SwapDirection::Up, None); },
+            SwapLinesDown => { buffer.swap_lines(edit::SwapDirection::Down, None); },
+            ToggleComments => { buffer.toggle_comments(TEST_COMMENT_START, None); },
         };
     }
 
@@ -351,6 +359,9 @@ impl TestEdit {
             MoveAllCursors(_) | ExtendSelectionForAllCursors(_) | MoveCursors(_, _)
             | ExtendSelection(_, _) | SetCursor(_, _) | DragCursors(_)
             | SelectCharTypeGrouping(_, _) | SelectAll => {},
+            // The lines move around, but the characters in them, and the line
+            // endings, all stay in the buffer.
+            SwapLinesUp | SwapLinesDown => {},
             InsertNumbersAtCursors => {
                 decrement_strings(counts, &buffer.copy_selections());
                 for i in 0..buffer.borrow_cursors().len() {
@@ -424,6 +435,33 @@ impl TestEdit {
 
                 *counts = clone_counts;
             },
+            ToggleComments => {
+                let mut clone = deep_clone(&buffer);
+                clone.toggle_comments(TEST_COMMENT_START, None);
+                let clone_counts = get_counts(&clone);
+
+                // Only the comment starts, and the spaces after them, should change.
+                let key_set: std::collections::HashSet<&char> =
+                    clone_counts.keys()
+                    .chain(counts.keys())
+                    .collect();
+                for key in key_set {
+                    assert!(
+                        TEST_COMMENT_START.contains(*key)
+                        || *key == ' '
+                        || match (clone_counts.get(key), counts.get(key)) {
+                            (Some(0), None)|(None, Some(0)) => true,
+                            (clone_count, count) => clone_count == count
+                        },
+                        "key: {:?}\n clone_counts.get(key): {:?}\n counts.get(key): {:?}",
+                        key,
+                        clone_counts.get(key),
+                        counts.get(key)
+                    );
+                }
+
+                *counts = clone_counts;
+            },
         }
         Self::apply_ref(buffer, edit);
     }
@@ -438,7 +476,8 @@ impl TestEdit {
                 false
             }
             Insert(_) | InsertString(_) | Delete | DeleteLines | Cut 
-            | InsertNumbersAtCursors | TabIn | TabOut => {
+            | InsertNumbersAtCursors | TabIn | TabOut | SwapLinesUp | SwapLinesDown
+            | ToggleComments => {
                 true
             }
         }
@@ -531,6 +570,25 @@ pub fn test_edit_delete_lines_heavy() -> impl Strategy<Value = TestEdit> {
     ]
 }
 
+pub fn test_edit_swap_lines_heavy() -> impl Strategy<Value = TestEdit> {
+    use TestEdit::*;
+    prop_oneof![
+        9 => Just(SwapLinesUp),
+        9 => Just(SwapLinesDown),
+        5 => test_edit_selection_changes(), // Make sure there can be selections
+        1 => test_edit()
+    ]
+}
+
+pub fn test_edit_toggle_comments_heavy() -> impl Strategy<Value = TestEdit> {
+    use TestEdit::*;
+    prop_oneof![
+        9 => Just(ToggleComments),
+        5 => test_edit_selection_changes(), // Make sure there can be selections
+        1 => test_edit()
+    ]
+}
+
 // Generates only cursor movement and selection edits. Intended for use as a part of larger
 pub fn test_edit_selection_changes() -> impl Strategy<Value = TestEdit> {
     use TestEdit::*;
@@ -566,6 +624,8 @@ pub enum TestEditSpec {
     DeleteAndTabInOutHeavy,
     SelectionChanges,
     DeleteLinesHeavy,
+    SwapLinesHeavy,
+    ToggleCommentsHeavy,
 }
 
 pub fn test_edits(max_len: usize, spec: TestEditSpec) -> impl Strategy<Value = Vec<TestEdit>> {
@@ -580,6 +640,8 @@ pub fn test_edits(max_len: usize, spec: TestEditSpec) -> impl Strategy<Value = V
             DeleteAndTabInOutHeavy => test_edit_delete_and_tab_in_out_heavy().boxed(),
             SelectionChanges => test_edit_selection_changes().boxed(),
             DeleteLinesHeavy => test_edit_delete_lines_heavy().boxed(),
+            SwapLinesHeavy => test_edit_swap_lines_heavy().boxed(),
+            ToggleCommentsHeavy => test_edit_toggle_comments_heavy().boxed(),
         },
         0..max_len,
     )
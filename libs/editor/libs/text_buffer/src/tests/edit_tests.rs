@@ -337,6 +337,90 @@ fn delete_lines_deletes_everything_in_this_two_line_case() {
     TestEdit::apply(&mut buffer, TestEdit::DeleteLines);
 
     assert_eq!(buffer.rope.len_chars(), 0);
+}
+
+#[test]
+fn swap_lines_up_moves_each_selection_past_the_line_above_as_one_undoable_edit() {
+    let text = "a\nb\nc\nd\ne\nf";
+    let mut buffer = t_b!(text, vec1![
+        cur!{l 1 o 0 h l 3 o 0},
+        cur!{l 4 o 1},
+    ]);
+
+    buffer.swap_lines(SwapDirection::Up, None);
+
+    let s: String = buffer.rope.clone().into();
+    assert_eq!(s, "b\nc\na\ne\nd\nf");
+    let cursors = buffer.borrow_cursors().get_cloned_cursors();
+    // The cursors are kept sorted from the end of the buffer to the start.
+    assert_eq!(cursors.len(), 2);
+    assert_eq!(cursors[0].get_position(), pos!{l 3 o 1});
+    assert_eq!(cursors[1].get_position(), pos!{l 0 o 0});
+    assert_eq!(cursors[1].get_highlight_position(), Some(pos!{l 2 o 0}));
+
+    buffer.undo(None);
+
+    let s: String = buffer.rope.into();
+    assert_eq!(s, text);
+}
+
+#[test]
+fn swap_lines_down_keeps_the_line_endings_in_place_at_the_end_of_the_buffer() {
+    let mut buffer = t_b!("a\r\nb\nc", vec1![cur!{l 1 o 1}]);
+
+    buffer.swap_lines(SwapDirection::Down, None);
+
+    let s: String = buffer.rope.clone().into();
+    assert_eq!(s, "a\r\nc\nb");
+    assert_eq!(buffer.borrow_cursors().first().get_position(), pos!{l 2 o 1});
+
+    buffer.swap_lines(SwapDirection::Down, None);
+
+    let s: String = buffer.rope.into();
+    assert_eq!(s, "a\r\nc\nb");
+}
+
+#[test]
+fn toggle_comments_comments_at_the_smallest_indent_and_skips_blank_lines() {
+    let text = "fn f() {\n    if a {\n\n        b();\n    }\n}";
+    let mut buffer = t_b!(text, vec1![cur!{l 1 o 6 h l 4 o 1}]);
+
+    buffer.toggle_comments("//", None);
+
+    let s: String = buffer.rope.clone().into();
+    assert_eq!(s, "fn f() {\n    // if a {\n\n    //     b();\n    // }\n}");
+    let cursor = buffer.borrow_cursors().first().clone();
+    assert_eq!(cursor.get_position(), pos!{l 1 o 9});
+    assert_eq!(cursor.get_highlight_position(), Some(pos!{l 4 o 1}));
+
+    buffer.undo(None);
+
+    let s: String = buffer.rope.into();
+    assert_eq!(s, text);
+}
+
+#[test]
+fn toggle_comments_only_uncomments_when_every_line_is_commented() {
+    // Cursors on touching lines are toggled together, so the `d` line keeps these apart.
+    let mut buffer = t_b!("// a\n  //b\nd\nc", vec1![
+        cur!{l 0 o 0 h l 1 o 4},
+        cur!{l 3 o 1},
+    ]);
+
+    buffer.toggle_comments("//", None);
+
+    let s: String = buffer.rope.clone().into();
+    assert_eq!(s, "a\n  b\nd\n// c");
+    let cursors = buffer.borrow_cursors().get_cloned_cursors();
+    assert_eq!(cursors.len(), 2);
+    assert_eq!(cursors[0].get_position(), pos!{l 3 o 4});
+    assert_eq!(cursors[1].get_position(), pos!{l 0 o 0});
+    assert_eq!(cursors[1].get_highlight_position(), Some(pos!{l 1 o 2}));
+
+    buffer.toggle_comments("//", None);
+
+    let s: String = buffer.rope.into();
+    assert_eq!(s, "// a\n//   b\nd\nc");
 }
 
 #[test]
@@ -1206,6 +1290,16 @@ proptest! {
         does_not_lose_characters_on(buffer, edits);
     }
 
+    #[test]
+    fn does_not_lose_characters_on_swap_lines_heavy((buffer, edits) in arb::text_buffer_and_test_edits(SOME_AMOUNT, TestEditSpec::SwapLinesHeavy)) {
+        does_not_lose_characters_on(buffer, edits);
+    }
+
+    #[test]
+    fn does_not_lose_characters_on_toggle_comments_heavy((buffer, edits) in arb::text_buffer_and_test_edits(SOME_AMOUNT, TestEditSpec::ToggleCommentsHeavy)) {
+        does_not_lose_characters_on(buffer, edits);
+    }
+
     #[test]
     fn does_not_lose_characters_on_delete_then_tab_out(buffer in arb::text_buffer_with_valid_cursors(), edits in arb::test_edit_delete_then_tab_out_vec()) {
         does_not_lose_characters_on(buffer, edits);
@@ -1935,5 +2029,100 @@ fn delete_lines_deletes_the_expected_amount_of_lines_in_this_reduced_less_small_
     );
 }
 
+#[test]
+fn replace_ranges_replaces_every_range_in_this_small_case() {
+    let mut buffer = t_b!("abc abc\nabc", vec1![cur!{l 0 o 0}]);
+
+    buffer.replace_ranges(
+        vec1![
+            (pos!{l 0 o 0}, pos!{l 0 o 3}),
+            (pos!{l 0 o 4}, pos!{l 0 o 7}),
+            (pos!{l 1 o 0}, pos!{l 1 o 3}),
+        ],
+        "xy".to_owned(),
+        None
+    );
+
+    assert_eq!(String::from(&buffer), "xy xy\nxy");
+    assert_eq!(buffer.cursors.len(), 3);
+}
+
+#[test]
+fn replace_ranges_can_be_undone_in_a_single_step_in_this_small_case() {
+    let initial = "abc abc\nabc";
+    let mut buffer = t_b!(initial, vec1![cur!{l 0 o 0}]);
+
+    buffer.replace_ranges(
+        vec1![
+            (pos!{l 0 o 0}, pos!{l 0 o 3}),
+            (pos!{l 0 o 4}, pos!{l 0 o 7}),
+            (pos!{l 1 o 0}, pos!{l 1 o 3}),
+        ],
+        "xy".to_owned(),
+        None
+    );
+
+    buffer.undo(None);
+
+    assert_eq!(String::from(&buffer), initial);
+    assert_eq!(buffer.cursors, curs!(buffer.rope, cur!{l 0 o 0}));
+}
+
+#[test]
+fn replace_ranges_replaces_touching_ranges_separately_in_this_small_case() {
+    let mut buffer = t_b!("abab", vec1![cur!{l 0 o 0}]);
+
+    buffer.replace_ranges(
+        vec1![
+            (pos!{l 0 o 0}, pos!{l 0 o 2}),
+            (pos!{l 0 o 2}, pos!{l 0 o 4}),
+        ],
+        "xyz".to_owned(),
+        None
+    );
+
+    assert_eq!(String::from(&buffer), "xyzxyz");
+    assert_eq!(
+        buffer.cursors,
+        Cursors::new(&buffer.rope, vec1![cur!{l 0 o 3}, cur!{l 0 o 6}])
+    );
+}
+
+#[test]
+fn get_replace_ranges_edit_has_a_range_edit_for_each_cursor_with_more_cursors_than_ranges() {
+    let buffer = t_b!(
+        "abc abc\nabc",
+        vec1![cur!{l 0 o 1}, cur!{l 0 o 5}, cur!{l 1 o 2}]
+    );
+
+    let edit = edit::get_replace_ranges_edit(
+        &buffer.rope,
+        &buffer.cursors,
+        vec1![(pos!{l 0 o 4}, pos!{l 0 o 7})],
+        |_| "xy".to_owned(),
+    );
+
+    assert_eq!(edit.cursors().old.len(), 3);
+    assert!(edit.range_edits().len() >= edit.cursors().old.len());
+}
+
+#[test]
+fn replace_ranges_does_nothing_if_any_range_is_out_of_bounds_in_this_small_case() {
+    let initial = "abc abc";
+    let mut buffer = t_b!(initial, vec1![cur!{l 0 o 0}]);
+
+    buffer.replace_ranges_with(
+        vec1![
+            (pos!{l 0 o 0}, pos!{l 0 o 3}),
+            (pos!{l 5 o 0}, pos!{l 5 o 3}),
+            (pos!{l 0 o 4}, pos!{l 0 o 7}),
+        ],
+        |index| index.to_string(),
+        None
+    );
+
+    assert_eq!(String::from(&buffer), initial);
+}
+
 mod included_files;
 mod undo_redo;
@@ -499,6 +499,16 @@ proptest! {
     fn undo_redo_works_with_heavy_delete_lines_regarding_ropes((edits, index) in arb::test_edits_and_index(SOME_AMOUNT, TestEditSpec::DeleteLinesHeavy)) {
         undo_redo_works_on_these_edits_and_index_regarding_ropes(edits, index);
     }
+
+    #[test]
+    fn undo_redo_works_with_heavy_swap_lines_regarding_ropes((edits, index) in arb::test_edits_and_index(SOME_AMOUNT, TestEditSpec::SwapLinesHeavy)) {
+        undo_redo_works_on_these_edits_and_index_regarding_ropes(edits, index);
+    }
+
+    #[test]
+    fn undo_redo_works_with_heavy_toggle_comments_regarding_ropes((edits, index) in arb::test_edits_and_index(SOME_AMOUNT, TestEditSpec::ToggleCommentsHeavy)) {
+        undo_redo_works_on_these_edits_and_index_regarding_ropes(edits, index);
+    }
 }
 
 #[test]
@@ -814,6 +824,50 @@ fn undo_redo_works_in_this_familiar_scenario() {
     assert_text_buffer_eq_ignoring_history!(buffer, initial_buffer);
 }
 
+#[test]
+fn switching_undo_branches_works_in_this_familiar_scenario() {
+    let initial_buffer: TextBuffer = d!();
+    let mut buffer: TextBuffer = deep_clone(&initial_buffer);
+
+    TestEdit::apply(&mut buffer, TestEdit::Insert('1'));
+
+    let buffer_with_1 = deep_clone(&buffer);
+
+    TestEdit::apply(&mut buffer, TestEdit::Insert('2'));
+
+    let buffer_with_12 = deep_clone(&buffer);
+
+    buffer.undo(None);
+
+    TestEdit::apply(&mut buffer, TestEdit::Insert('3'));
+
+    let buffer_with_13 = deep_clone(&buffer);
+
+    assert!(!buffer.previous_undo_branch(None).ran_out_of_history());
+
+    assert_text_buffer_eq_ignoring_history!(buffer, buffer_with_12);
+
+    assert!(!buffer.next_undo_branch(None).ran_out_of_history());
+
+    assert_text_buffer_eq_ignoring_history!(buffer, buffer_with_13);
+
+    buffer.undo(None);
+
+    assert_text_buffer_eq_ignoring_history!(buffer, buffer_with_1);
+
+    // There is only one branch starting from the initial state.
+    buffer.undo(None);
+
+    assert!(buffer.next_undo_branch(None).ran_out_of_history());
+
+    assert_text_buffer_eq_ignoring_history!(buffer, initial_buffer);
+
+    buffer.redo(None);
+    buffer.redo(None);
+
+    assert_text_buffer_eq_ignoring_history!(buffer, buffer_with_13);
+}
+
 #[test]
 fn undo_redo_works_on_this_backslash_example() {
     /* we want the text buffer to be like this just before the delete lines:
@@ -860,7 +914,7 @@ fn undo_redo_works_on_this_reduced_simple_insert_delete_case() {
     dbg!();
     let delete_edit = buffer
         .history
-        .get(buffer.history_index.checked_sub(1).unwrap())
+        .current()
         .unwrap();
 
     match &delete_edit.range_edits().first().delete_range {
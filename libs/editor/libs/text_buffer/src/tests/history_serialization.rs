@@ -0,0 +1,159 @@
+use super::*;
+use arb::{TestEdit, TestEditSpec};
+use pretty_assertions::assert_eq;
+
+fn restored_buffer(buffer: &TextBuffer) -> TextBuffer {
+    let serialized = buffer.serialize_history();
+
+    let mut restored: TextBuffer = String::from(buffer).into();
+    restored.scroll = buffer.scroll;
+    restored.restore_history(&serialized)
+        .expect("restore_history did not work on the output of serialize_history");
+
+    restored
+}
+
+fn restoring_a_serialized_history_gives_the_same_buffer_on(
+    mut buffer: TextBuffer,
+    edits: Vec<TestEdit>,
+    undo_count: usize,
+) {
+    for edit in edits {
+        TestEdit::apply(&mut buffer, edit);
+    }
+
+    for _ in 0..undo_count {
+        buffer.undo(None);
+    }
+
+    assert_eq!(restored_buffer(&buffer), buffer);
+}
+
+proptest! {
+    #[test]
+    fn restoring_a_serialized_history_gives_the_same_buffer(
+        (buffer, edits) in arb::text_buffer_and_test_edits(SOME_AMOUNT, TestEditSpec::All),
+        undo_count in 0..SOME_AMOUNT,
+    ) {
+        restoring_a_serialized_history_gives_the_same_buffer_on(buffer, edits, undo_count);
+    }
+}
+
+#[test]
+fn restoring_a_serialized_history_keeps_undone_branches() {
+    let mut buffer: TextBuffer = d!();
+
+    TestEdit::apply(&mut buffer, TestEdit::Insert('1'));
+    TestEdit::apply(&mut buffer, TestEdit::Insert('2'));
+
+    let buffer_with_12 = buffer.clone();
+
+    buffer.undo(None);
+    TestEdit::apply(&mut buffer, TestEdit::Insert('3'));
+
+    let mut restored = restored_buffer(&buffer);
+
+    assert_eq!(restored.editedness(), Editedness::Edited);
+
+    restored.previous_undo_branch(None);
+
+    assert_text_buffer_eq_ignoring_history!(restored, buffer_with_12);
+}
+
+#[test]
+fn restoring_a_serialized_history_does_nothing_if_the_text_does_not_match() {
+    let mut buffer: TextBuffer = d!();
+    TestEdit::apply(&mut buffer, TestEdit::Insert('a'));
+
+    let serialized = buffer.serialize_history();
+
+    let mut other: TextBuffer = "b".into();
+    let expected = other.clone();
+
+    assert_eq!(
+        other.restore_history(&serialized),
+        Err(RestoreHistoryError::TextMismatch)
+    );
+    assert_eq!(other, expected);
+}
+
+#[test]
+fn restoring_a_serialized_history_does_nothing_if_the_version_does_not_match() {
+    let mut buffer: TextBuffer = d!();
+    TestEdit::apply(&mut buffer, TestEdit::Insert('a'));
+
+    let serialized = buffer
+        .serialize_history()
+        .replacen("v3", "v2", 1);
+
+    let mut other: TextBuffer = "a".into();
+    let expected = other.clone();
+
+    assert_eq!(
+        other.restore_history(&serialized),
+        Err(RestoreHistoryError::UnknownVersion)
+    );
+    assert_eq!(other, expected);
+}
+
+#[test]
+fn restoring_a_truncated_serialized_history_does_nothing() {
+    let mut buffer: TextBuffer = d!();
+    TestEdit::apply(&mut buffer, TestEdit::Insert('a'));
+    TestEdit::apply(&mut buffer, TestEdit::Insert('b'));
+
+    let serialized = buffer.serialize_history();
+    let truncated = &serialized[..serialized.len() - 3];
+
+    let mut other: TextBuffer = "ab".into();
+    let expected = other.clone();
+
+    assert_eq!(
+        other.restore_history(truncated),
+        Err(RestoreHistoryError::Malformed)
+    );
+    assert_eq!(other, expected);
+}
+
+#[test]
+fn restoring_a_serialized_history_with_an_edit_outside_the_text_does_nothing() {
+    let mut buffer: TextBuffer = d!();
+    TestEdit::apply(&mut buffer, TestEdit::Insert('a'));
+
+    let serialized = buffer.serialize_history();
+    let out_of_bounds = serialized.replacen("+ 0 1 1:a ", "+ 5 6 1:a ", 1);
+    assert_ne!(out_of_bounds, serialized, "precondition failure");
+
+    let mut other: TextBuffer = "a".into();
+    let expected = other.clone();
+
+    assert_eq!(
+        other.restore_history(&out_of_bounds),
+        Err(RestoreHistoryError::Malformed)
+    );
+    assert_eq!(other, expected);
+}
+
+#[test]
+fn restoring_a_serialized_history_with_an_edit_cursor_outside_the_text_does_nothing() {
+    let mut buffer: TextBuffer = d!();
+    TestEdit::apply(&mut buffer, TestEdit::Insert('a'));
+
+    let serialized = buffer.serialize_history();
+    // The last cursors are the ones after the edit.
+    let index = serialized.rfind("1 0 1 0 1 ").expect("precondition failure");
+    let out_of_bounds = format!(
+        "{}1 0 9 0 9 {}",
+        &serialized[..index],
+        &serialized[index + "1 0 1 0 1 ".len()..]
+    );
+
+    let mut other: TextBuffer = "a".into();
+    let expected = other.clone();
+
+    assert_eq!(
+        other.restore_history(&out_of_bounds),
+        Err(RestoreHistoryError::Malformed)
+    );
+    assert_eq!(other, expected);
+}
@@ -1,23 +1,26 @@
 #![deny(unused)]
 use cursors::Cursors;
 use edit::{Applier, Change, Edit, change};
+pub use edit::SwapDirection;
 use editor_types::{Cursor, SetPositionAction};
-use macros::{d, dbg, u};
+use macros::{d, dbg, some_or, u};
 use move_cursor::{forward, get_next_selection_point, get_previous_selection_point};
 use panic_safe_rope::{ByteIndex, Rope, RopeSlice};
-use parsers::{Parsers, ParserKind};
+use parsers::{Extraction, Parsers, ParserKind};
 use platform_types::{*, screen_positioning::*};
 use rope_pos::{
     AbsoluteCharOffsetRange,
+    char_offset_to_pos,
     in_cursor_bounds,
     pos_to_char_offset,
     offset_pair,
     nearest_valid_position_on_same_line,
 };
+use undo_tree::{BranchDirection, UndoTree};
 
 use std::{
     borrow::Borrow,
-    collections::VecDeque,
+    cmp::{max, min},
 };
 
 #[derive(Clone, Debug, PartialEq)]
@@ -27,20 +30,67 @@ pub struct TextBuffer {
     /// to the rope.
     rope: Rope,
     cursors: Cursors,
-    history: VecDeque<Edit>,
-    history_index: usize,
-    unedited: Rope,
+    history: UndoTree<Edit>,
+    /// The `content_hash` of the text that the buffer counts as unedited with. For a
+    /// buffer with a path, this is kept matching what is on disk at that path.
+    unedited_hash: u64,
+    /// The length in bytes of the text that the buffer counts as unedited with. This
+    /// lets us usually skip hashing the text when checking if the buffer is edited.
+    unedited_len: usize,
+    /// Every fold that is currently folded, including ones inside of others.
+    folds: Vec<Fold>,
+    /// The sorted, non-overlapping ranges that searches with `in_selection` set are
+    /// restricted to. These are kept covering the same text as the buffer is edited.
+    search_selections: Vec<AbsoluteCharOffsetRange>,
+    /// Changes whenever the text does. See `version`.
+    version: Version,
+    text_hash_cache: TextHashCache,
     pub scroll: ScrollXY,
+}
+
+/// A number that is given to at most one text. It is ignored when comparing buffers,
+/// since buffers with the same text can have different versions.
+#[derive(Clone, Copy, Debug)]
+struct Version(u64);
+
+impl Version {
+    fn next() -> Self {
+        use std::sync::atomic::{AtomicU64, Ordering};
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+
+        Version(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
 }
 
+/// A `Version` of the text, along with the text's `content_hash`, so it only needs to
+/// be computed once per version. Like `Version`, this is ignored when comparing buffers.
+#[derive(Clone, Debug, Default)]
+struct TextHashCache(std::cell::Cell<Option<(u64, u64)>>);
+
+impl PartialEq for TextHashCache {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
 d!(for TextBuffer: {
     let rope: Rope = d!();
     TextBuffer {
-        unedited: rope.clone(),
+        unedited_hash: content_hash_chunks(rope.chunks()),
+        unedited_len: rope.len_bytes().0,
         rope,
         cursors: d!(),
         history: d!(),
-        history_index: d!(),
+        folds: d!(),
+        search_selections: d!(),
+        version: Version::next(),
+        text_hash_cache: d!(),
         scroll: d!(),
     }
 });
@@ -53,7 +103,8 @@ impl TextBuffer {
         perf_viz::start_record!("history hash");
         self.history.hash(state);
         perf_viz::end_record!("history hash");
-        self.history_index.hash(state);
+        self.folds.hash(state);
+        self.search_selections.hash(state);
         self.scroll.hash(state);
     }
 
@@ -65,9 +116,8 @@ impl TextBuffer {
         }
 
         // TODO is it worth it to have a third hash level?
-        for c in self.unedited.chunks() {
-            c.hash(state);    
-        }
+        self.unedited_hash.hash(state);
+        self.unedited_len.hash(state);
     }
 }
 
@@ -103,6 +153,14 @@ impl TextBuffer {
         &self.cursors
     }
 
+    /// Returns a number that changes whenever the text does, and that no other text,
+    /// in this buffer or any other, has had. So if this returns the same value twice,
+    /// the text did not change in between.
+    #[must_use]
+    pub fn version(&self) -> u64 {
+        self.version.0
+    }
+
     pub fn reset_cursor_states(&mut self) {
         self.cursors.reset_states();
     }
@@ -113,6 +171,7 @@ impl TextBuffer {
     ) -> VisibilityAttemptResult {
         u!{ScrollAdjustSpec, VisibilityAttemptResult};
 
+        let shown_folds = self.shown_folds();
         let scroll = &mut self.scroll; 
         
         match spec {
@@ -121,7 +180,9 @@ impl TextBuffer {
                 Succeeded
             }
             Calculate(char_dim, xywh) => {
-                let text_space = position_to_text_space(dbg!(self.cursors.last().get_position()), char_dim);
+                let mut position = self.cursors.last().get_position();
+                position.line = folds::display_line(&shown_folds, position.line);
+                let text_space = position_to_text_space(dbg!(position), char_dim);
 
                 // We try first with this smaller xywh to make the cursor appear
                 // in the center more often.
@@ -169,6 +230,7 @@ impl From<String> for TextBuffer {
         let mut output: Self = d!();
 
         output.rope = Rope::from(s);
+        output.version = Version::next();
         output.set_unedited();
 
         output
@@ -180,6 +242,7 @@ impl From<&str> for TextBuffer {
         let mut output: Self = d!();
 
         output.rope = Rope::from(s);
+        output.version = Version::next();
         output.set_unedited();
 
         output
@@ -333,6 +396,36 @@ impl TextBuffer {
         )
     }
 
+    /// Replaces the text in each of the ranges with `s`, as a single undoable edit.
+    #[perf_viz::record]
+    pub fn replace_ranges(
+        &mut self,
+        ranges: Vec1<(Position, Position)>,
+        s: String,
+        listener: ppel!()
+    ) -> PossibleEditedTransition {
+        self.replace_ranges_with(ranges, |_| s.clone(), listener)
+    }
+
+    /// Replaces the text in each of the ranges with the string `func` returns for the index
+    /// of that range, as a single undoable edit. The indexes count up from the range nearest
+    /// the start of the buffer.
+    #[perf_viz::record]
+    pub fn replace_ranges_with<F>(
+        &mut self,
+        ranges: Vec1<(Position, Position)>,
+        func: F,
+        listener: ppel!()
+    ) -> PossibleEditedTransition
+    where
+        F: Fn(usize) -> String,
+    {
+        self.record_edit(
+            edit::get_replace_ranges_edit(&self.rope, &self.cursors, ranges, func),
+            listener,
+        )
+    }
+
     pub fn move_all_cursors(&mut self, r#move: Move) {
         self.move_cursors(
             CursorMoveSpec {
@@ -403,6 +496,21 @@ impl TextBuffer {
             (None, _) => None,
         };
 
+        let cursor = cursor.map(|mut c| {
+            if !self.folds.is_empty() {
+                // Ending up at the end of the line before a fold seems like the
+                // least surprising thing when clicking on its placeholder.
+                move_cursor::out_of_folds(
+                    &self.rope,
+                    &mut c,
+                    Move::Left,
+                    &self.shown_folds(),
+                    SetPositionAction::ClearHighlightOnlyIfItMatchesNewPosition,
+                );
+            }
+            c
+        });
+
         cursor.map(|c| match replace_or_add {
             ReplaceOrAdd::Replace => Vec1::new(c),
             ReplaceOrAdd::Add => {
@@ -431,13 +539,26 @@ impl TextBuffer {
         char_dim: CharDim,
         xy: TextBoxSpaceXY,
     ) -> Position {
-        text_space_to_position(
+        self.display_to_text_position(text_space_to_position(
             text_box_to_text(xy, self.scroll),
             char_dim,
             // We want different rounding for selections so that if we trigger a selection on the
             // right side of a character, we select that character rather than the next character.
             PositionRound::TowardsZero,
-        )
+        ))
+    }
+
+    /// Converts a position where the line is counted in lines as they are shown, with
+    /// each fold taking up one line, into a position in the text.
+    pub fn display_to_text_position(&self, position: Position) -> Position {
+        if self.folds.is_empty() {
+            return position;
+        }
+
+        Position {
+            line: folds::text_line(&self.shown_folds(), position.line),
+            ..position
+        }
     }
 
     /// Selects a grouping of characters with a single character type, where the character types
@@ -519,22 +640,136 @@ impl TextBuffer {
         }
     }
 
+    /// Returns the position of each bracket next to a cursor, along with the position
+    /// of the bracket that matches it, if there is one.
+    pub fn bracket_match_positions(
+        &self,
+        parsers: &mut Parsers,
+        buffer_name: &BufferName,
+        parser_kind: ParserKind,
+    ) -> Vec<(Position, Option<Position>)> {
+        let offsets: Vec<_> = self.cursors.iter()
+            .filter_map(|c| offset_pair(&self.rope, c).0)
+            .collect();
+
+        parsers.get_bracket_matches(buffer_name, parser_kind, &self.rope, self.version(), &offsets)
+            .into_iter()
+            .flatten()
+            .filter_map(|m| Some((
+                char_offset_to_pos(&self.rope, m.bracket)?,
+                m.matching.and_then(|o| char_offset_to_pos(&self.rope, o)),
+            )))
+            .collect()
+    }
+
+    /// Moves each cursor that is next to a bracket to the bracket that matches it, on
+    /// the same side of the bracket that the cursor started on, so that jumping again
+    /// goes back. This does nothing without a parser to ask where the brackets are.
+    pub fn jump_to_matching_brackets(&mut self, listener: ppel!()) {
+        let l = some_or!(listener, return);
+        let cursors = self.cursors.get_cloned_cursors();
+
+        let offsets: Vec<_> = cursors.iter()
+            .filter_map(|c| offset_pair(&self.rope, c).0)
+            .collect();
+        let mut matches = l.parsers.get_bracket_matches(
+            l.buffer_name,
+            l.parser_kind,
+            &self.rope,
+            self.version(),
+            &offsets,
+        ).into_iter();
+
+        let rope = &self.rope;
+        let new = cursors.mapped_ref(|c| {
+            let offset = some_or!(offset_pair(rope, c).0, return c.clone());
+            let bracket_match = some_or!(matches.next().flatten(), return c.clone());
+            let matching = some_or!(bracket_match.matching, return c.clone());
+
+            let target = if bracket_match.bracket == offset {
+                Some(matching)
+            } else {
+                matching.checked_add_one()
+            };
+
+            target.and_then(|o| char_offset_to_pos(rope, o))
+                .map(Cursor::new)
+                .unwrap_or_else(|| c.clone())
+        });
+
+        self.apply_cursor_only_edit(new);
+    }
+
+    /// Selects the contents of the closest pair of brackets around each selection, or
+    /// the pair around that, if the contents are already selected. This does nothing
+    /// without a parser to ask where the brackets are.
+    pub fn select_inside_brackets(&mut self, listener: ppel!()) {
+        let l = some_or!(listener, return);
+        let cursors = self.cursors.get_cloned_cursors();
+
+        let selections: Vec<_> = cursors.iter()
+            .filter_map(|c| match offset_pair(&self.rope, c) {
+                (Some(o), highlight) => Some((o, highlight.unwrap_or(o))),
+                (None, _) => None,
+            })
+            .collect();
+        let mut enclosing = l.parsers.get_enclosing_brackets(
+            l.buffer_name,
+            l.parser_kind,
+            &self.rope,
+            self.version(),
+            &selections,
+        ).into_iter();
+
+        let rope = &self.rope;
+        let new = cursors.mapped_ref(|c| {
+            if offset_pair(rope, c).0.is_none() {
+                return c.clone();
+            }
+            let (open, close) = some_or!(enclosing.next().flatten(), return c.clone());
+
+            match (
+                open.checked_add_one().and_then(|o| char_offset_to_pos(rope, o)),
+                char_offset_to_pos(rope, close),
+            ) {
+                (Some(start), Some(end)) => Cursor::new_with_highlight(end, start),
+                _ => c.clone(),
+            }
+        });
+
+        self.apply_cursor_only_edit(new);
+    }
+
     fn move_cursors(&mut self, spec: CursorMoveSpec, r#move: Move) -> Option<()> {
         let mut new = self.cursors.get_cloned_cursors();
 
-        let action: for<'r, 's> fn(&'r Rope, &'s mut Cursor, Move) = match spec.what {
-            MoveOrSelect::Move => move_cursor::or_clear_highlights,
-            MoveOrSelect::Select => move_cursor::and_extend_selection,
+        let (action, set_position_action): (for<'r, 's> fn(&'r Rope, &'s mut Cursor, Move), _) =
+            match spec.what {
+                MoveOrSelect::Move => (
+                    move_cursor::or_clear_highlights,
+                    SetPositionAction::ClearHighlight,
+                ),
+                MoveOrSelect::Select => (
+                    move_cursor::and_extend_selection,
+                    SetPositionAction::ClearHighlightOnlyIfItMatchesNewPosition,
+                ),
+            };
+
+        let shown_folds = self.shown_folds();
+        let rope = &self.rope;
+        let move_cursor = |cursor: &mut Cursor| {
+            action(rope, cursor, r#move);
+            move_cursor::out_of_folds(rope, cursor, r#move, &shown_folds, set_position_action);
         };
 
         match spec.how_many {
             AllOrOne::All => {
                 for cursor in new.iter_mut() {
-                    action(&self.rope, cursor, r#move);
+                    move_cursor(cursor);
                 }
             }
             AllOrOne::Index(index) => {
-                action(&self.rope, new.get_mut(index)?, r#move);
+                move_cursor(new.get_mut(index)?);
             }
         };
 
@@ -565,11 +800,47 @@ impl TextBuffer {
         );
     }
 
-    pub fn tab_in(&mut self, listener: PossibleParserEditListener) -> PossibleEditedTransition {
-        self.record_edit(
-            edit::get_tab_in_edit(&self.rope, &self.cursors),
-            listener
-        )
+    /// With a listener, lines are indented to where the lines above and the parse tree
+    /// suggest they should be, as described in `edit::get_auto_indent_tab_in_edit`.
+    /// Buffers without a listener, like the ones in menus, always get a `TAB_STR`.
+    pub fn tab_in(&mut self, mut listener: PossibleParserEditListener) -> PossibleEditedTransition {
+        let edit = match listener {
+            Some(ref mut l) => edit::get_auto_indent_tab_in_edit(
+                &self.rope,
+                &self.cursors,
+                l.parsers.get_nesting_depth_fn(
+                    l.buffer_name,
+                    l.parser_kind,
+                    &self.rope,
+                    self.version(),
+                ),
+            ),
+            None => edit::get_tab_in_edit(&self.rope, &self.cursors),
+        };
+
+        self.record_edit(edit, listener)
+    }
+
+    /// Inserts a line break at each cursor. With a listener, the line break is followed
+    /// by the indentation the new line is expected to have, judging by the line the cursor
+    /// was on and the parse tree, as described in `edit::get_new_line_edit`. Buffers
+    /// without a listener, like the ones in menus, just get the line break.
+    pub fn insert_new_line(&mut self, mut listener: ppel!()) -> PossibleEditedTransition {
+        let edit = match listener {
+            Some(ref mut l) => edit::get_new_line_edit(
+                &self.rope,
+                &self.cursors,
+                l.parsers.get_nesting_depth_fn(
+                    l.buffer_name,
+                    l.parser_kind,
+                    &self.rope,
+                    self.version(),
+                ),
+            ),
+            None => edit::get_insert_edit(&self.rope, &self.cursors, |_| "\n".to_owned()),
+        };
+
+        self.record_edit(edit, listener)
     }
 
     pub fn tab_out(&mut self, listener: PossibleParserEditListener) -> PossibleEditedTransition {
@@ -579,6 +850,122 @@ impl TextBuffer {
         )
     }
 
+    /// Comments out the lines each cursor is on with `comment_start`, or uncomments them
+    /// if they are all commented out already.
+    pub fn toggle_comments(
+        &mut self,
+        comment_start: &str,
+        listener: ppel!()
+    ) -> PossibleEditedTransition {
+        self.record_edit(
+            edit::get_toggle_comment_edit(&self.rope, &self.cursors, comment_start),
+            listener,
+        )
+    }
+
+    /// Swaps the lines each cursor is on with the line above or below them.
+    pub fn swap_lines(
+        &mut self,
+        direction: SwapDirection,
+        listener: ppel!()
+    ) -> PossibleEditedTransition {
+        self.record_edit(
+            edit::get_swap_lines_edit(&self.rope, &self.cursors, direction),
+            listener,
+        )
+    }
+
+    /// Moves the smallest syntax node containing each selection past its previous or
+    /// next sibling. This does nothing without a parser to tell us where the nodes are.
+    pub fn swap_syntax_nodes(
+        &mut self,
+        direction: SwapDirection,
+        listener: ppel!()
+    ) -> PossibleEditedTransition {
+        let swaps = match listener.as_ref() {
+            Some(l) => {
+                let selections: Vec<_> = self.cursors.iter()
+                    .filter_map(|c| match offset_pair(&self.rope, c) {
+                        (Some(o), highlight) => Some((o, highlight.unwrap_or(o))),
+                        (None, _) => None,
+                    })
+                    .collect();
+
+                l.parsers.get_sibling_swaps(
+                    l.buffer_name,
+                    l.parser_kind,
+                    &self.rope,
+                    &selections,
+                    direction,
+                )
+            }
+            None => return None,
+        };
+
+        self.record_edit(
+            edit::get_swap_ranges_edit(&self.rope, &self.cursors, swaps),
+            listener,
+        )
+    }
+
+    /// Moves the code selected by the first cursor into a new function, and calls that
+    /// function in its place. Both the call and the definition have the function's
+    /// name selected afterwards, so it can be renamed by typing. This does nothing
+    /// without a parser to tell us what the selected code is, or if the code would not
+    /// work in a function of its own, like when it returns from the one it is in.
+    pub fn extract_function(&mut self, listener: ppel!()) -> PossibleEditedTransition {
+        self.extract(Extraction::Function, listener)
+    }
+
+    /// Moves the expression selected by the first cursor into a new variable, declared
+    /// on the line before the statement it is in, and uses that variable in its place.
+    /// If `every_occurrence` is true, then the identical expressions in the same block
+    /// are replaced too. The variable's name is selected everywhere it appears
+    /// afterwards, so it can be renamed by typing. This does nothing without a parser to
+    /// tell us what the selected code is, or if the expression would be evaluated at a
+    /// different time before the statement, like when it is on the right of a `&&`.
+    pub fn extract_variable(
+        &mut self,
+        every_occurrence: bool,
+        listener: ppel!()
+    ) -> PossibleEditedTransition {
+        self.extract(Extraction::Variable { every_occurrence }, listener)
+    }
+
+    fn extract(
+        &mut self,
+        extraction: Extraction,
+        mut listener: ppel!()
+    ) -> PossibleEditedTransition {
+        let replacements = match listener.as_mut() {
+            Some(l) => {
+                let selection = match offset_pair(&self.rope, self.cursors.first()) {
+                    (Some(o), highlight) => (o, highlight.unwrap_or(o)),
+                    (None, _) => return None,
+                };
+
+                l.parsers.get_extraction(
+                    l.buffer_name,
+                    l.parser_kind,
+                    &self.rope,
+                    self.version(),
+                    selection,
+                    extraction,
+                )
+            }
+            None => return None,
+        };
+
+        if replacements.is_empty() {
+            return None;
+        }
+
+        self.record_edit(
+            edit::get_range_replacements_edit(&self.rope, &self.cursors, replacements),
+            listener,
+        )
+    }
+
     #[perf_viz::record]
     fn record_edit(&mut self, edit: Edit, listener: PossibleParserEditListener) -> PossibleEditedTransition {
         u!{Editedness, EditedTransition}
@@ -602,6 +989,29 @@ impl TextBuffer {
         kind: ApplyKind,
         listener: ppel!(),
     ) {
+        if !self.folds.is_empty() {
+            self.adjust_folds_for_edit(&edit);
+        }
+
+        for range_edits in edit.range_edits().iter() {
+            if let Some(edit::RangeEdit { chars, range }) = &range_edits.delete_range {
+                search::adjust_selections_for_edit(
+                    &mut self.search_selections,
+                    range.min(),
+                    chars.chars().count(),
+                    0,
+                );
+            }
+            if let Some(edit::RangeEdit { chars, range }) = &range_edits.insert_range {
+                search::adjust_selections_for_edit(
+                    &mut self.search_selections,
+                    range.min(),
+                    0,
+                    chars.chars().count(),
+                );
+            }
+        }
+
         if let Some(listener) = listener {
             listener.parsers.acknowledge_edit(
                 listener.buffer_name,
@@ -616,17 +1026,38 @@ impl TextBuffer {
             &mut self.cursors
         );
         edit::apply(applier, &edit);
+        self.version = Version::next();
 
         match kind {
             ApplyKind::Record => {
-                self.history.truncate(self.history_index);
-                self.history.push_back(edit);
-                self.history_index += 1;
+                self.history.push(edit);
             }
             ApplyKind::Playback => {}
         }
     }
 
+    /// This should be called before the edit is applied.
+    fn adjust_folds_for_edit(&mut self, edit: &Edit) {
+        // We need to know which lines each range edit happens on, and each one is
+        // relative to the rope after the previous ones, so we apply them to a copy.
+        let mut rope = self.rope.clone();
+
+        for range_edits in edit.range_edits().iter() {
+            if let Some(edit::RangeEdit { chars, range }) = &range_edits.delete_range {
+                if let Some(line) = rope.char_to_line(range.min()) {
+                    folds::adjust_for_edit(&mut self.folds, line.0, line_break_count(chars), 0);
+                }
+            }
+            if let Some(edit::RangeEdit { chars, range }) = &range_edits.insert_range {
+                if let Some(line) = rope.char_to_line(range.min()) {
+                    folds::adjust_for_edit(&mut self.folds, line.0, 0, line_break_count(chars));
+                }
+            }
+
+            range_edits.apply(&mut rope);
+        }
+    }
+
     // some of these are convenience methods for tests
     #[allow(dead_code)]
     fn set_cursors(&mut self, new: Cursors) {
@@ -638,6 +1069,117 @@ impl TextBuffer {
         self.cursors = Cursors::new(&self.rope, cursors);
     }
 }
+
+fn line_break_count(s: &str) -> usize {
+    s.chars().filter(|&c| c == '\n').count()
+}
+
+impl TextBuffer {
+    /// Remembers what the cursors currently have selected, so that searches with
+    /// `in_selection` set can be restricted to that text.
+    pub fn capture_search_selections(&mut self) {
+        let selections = self.cursors.iter().filter_map(|c| {
+            c.get_highlight_position().map(|h| (c.get_position(), h))
+        });
+        self.search_selections = search::selection_ranges(&self.rope, selections);
+    }
+
+    #[must_use]
+    pub fn search_selections(&self) -> &[AbsoluteCharOffsetRange] {
+        &self.search_selections
+    }
+}
+
+impl TextBuffer {
+    /// The folds as returned by `folds::shown`.
+    pub fn shown_folds(&self) -> Vec<Fold> {
+        folds::shown(&self.folds)
+    }
+
+    /// Folds the lines selected by each cursor that has more than one line selected.
+    /// If there are no such cursors, then for each cursor this folds the smallest of
+    /// the `candidates` that the cursor is at, that is not already folded. Cursors that
+    /// end up on hidden lines are moved to the line before the fold.
+    pub fn fold(&mut self, candidates: &[Fold]) {
+        let line_count = self.rope.len_lines().0;
+        // We don't want to hide every line, since then there is nowhere for the
+        // cursors to go.
+        let hides_everything = |f: &Fold| f.first_line == 0 && f.last_line + 1 >= line_count;
+
+        let mut new_folds: Vec<Fold> = self.cursors.iter()
+            .filter_map(|c| {
+                let position = c.get_position();
+                let highlight_position = c.get_highlight_position()?;
+                let (first, last) = (
+                    min(position, highlight_position),
+                    max(position, highlight_position),
+                );
+
+                if first.line < last.line {
+                    Some(Fold { first_line: first.line, last_line: last.line })
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        if new_folds.is_empty() {
+            for c in self.cursors.iter() {
+                let line = c.get_position().line;
+                let smallest = candidates.iter()
+                    .filter(|f| f.region_contains_line(line) && !self.folds.contains(f))
+                    .min_by_key(|f| f.line_count());
+
+                if let Some(fold) = smallest {
+                    new_folds.push(*fold);
+                }
+            }
+        }
+
+        for fold in new_folds {
+            if !hides_everything(&fold) && !self.folds.contains(&fold) {
+                self.folds.push(fold);
+            }
+        }
+
+        let shown = self.shown_folds();
+        let mut cursors = self.cursors.get_cloned_cursors();
+        for c in cursors.iter_mut() {
+            move_cursor::out_of_folds(
+                &self.rope,
+                c,
+                Move::Left,
+                &shown,
+                SetPositionAction::ClearHighlight,
+            );
+        }
+        self.apply_cursor_only_edit(cursors);
+    }
+
+    /// For each cursor, unfolds the smallest fold that the cursor is at, out of the
+    /// ones that are not inside of another fold.
+    pub fn unfold(&mut self) {
+        let is_outermost = |f: &Fold| !self.folds.iter().any(|other|
+            other != f
+            && other.first_line <= f.first_line
+            && f.last_line <= other.last_line
+        );
+
+        let mut to_remove: Vec<Fold> = Vec::with_capacity(self.cursors.len());
+        for c in self.cursors.iter() {
+            let line = c.get_position().line;
+            let smallest = self.folds.iter()
+                .filter(|f| f.region_contains_line(line) && is_outermost(f))
+                .min_by_key(|f| f.line_count());
+
+            if let Some(fold) = smallest {
+                to_remove.push(*fold);
+            }
+        }
+
+        self.folds.retain(|f| !to_remove.contains(f));
+    }
+}
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Editedness {
@@ -649,16 +1191,66 @@ impl TextBuffer {
     pub fn editedness(&self) -> Editedness {
         u!{Editedness}
 
-        if self.unedited == self.rope {
+        if self.rope.len_bytes().0 == self.unedited_len
+        && self.text_hash() == self.unedited_hash {
             Unedited
         } else {
             Edited
         }
     }
 
-    pub fn set_unedited(&mut self) {
-        self.unedited = self.rope.clone();
+    /// The `content_hash` of the text.
+    fn text_hash(&self) -> u64 {
+        let cache = &self.text_hash_cache.0;
+        match cache.get() {
+            Some((version, hash)) if version == self.version.0 => hash,
+            _ => {
+                let hash = content_hash_chunks(self.rope.chunks());
+                cache.set(Some((self.version.0, hash)));
+                hash
+            }
+        }
+    }
+
+    pub fn set_unedited(&mut self) {
+        self.unedited_hash = self.text_hash();
+        self.unedited_len = self.rope.len_bytes().0;
+    }
+
+    /// Makes the buffer count as unedited exactly when its text is `s`. This is how we
+    /// find out the file on disk has changed.
+    pub fn set_unedited_text(&mut self, s: &str) -> PossibleEditedTransition {
+        let old_editedness = self.editedness();
+
+        self.unedited_hash = content_hash(s);
+        self.unedited_len = s.len();
+
+        HistoryNavOutcome::from(change!(old_editedness, self.editedness())).into()
     }
+
+    /// Replaces all the text with `s`, which is expected to be what is now on disk, as
+    /// a single undoable edit, and then considers `s` to be the unedited text. The
+    /// cursors are put back where they were, or as close as the new text allows.
+    pub fn reload(&mut self, s: String, listener: ppel!()) -> PossibleEditedTransition {
+        u!{Editedness, EditedTransition}
+        let old_editedness = self.editedness();
+        let old_cursors = self.cursors.get_cloned_cursors();
+
+        self.select_all();
+        self.apply_edit(
+            edit::get_insert_edit(&self.rope, &self.cursors, |_| s.clone()),
+            ApplyKind::Record,
+            listener,
+        );
+        self.set_unedited();
+
+        self.apply_cursor_only_edit(old_cursors);
+
+        match old_editedness {
+            Edited => Some(ToUnedited),
+            Unedited => None,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -701,9 +1293,8 @@ impl TextBuffer {
         u!{HistoryNavOutcome}
         let old_editedness = self.editedness();
 
-        if let Some(edit) = self.history.get(self.history_index).cloned() {
+        if let Some(edit) = self.history.redo().cloned() {
             self.apply_edit(edit, ApplyKind::Playback, listener);
-            self.history_index += 1;
 
             change!(old_editedness, self.editedness()).into()
         } else {
@@ -715,19 +1306,60 @@ impl TextBuffer {
         u!{HistoryNavOutcome}
         let old_editedness = self.editedness();
 
-        let opt = self.history_index.checked_sub(1)
-            .and_then(|new_index|
-                self.history.get(new_index).cloned().map(|e| (new_index, e))
-            );
-
-        if let Some((new_index, edit)) = opt {
+        if let Some(edit) = self.history.undo().cloned() {
             self.apply_edit(!edit, ApplyKind::Playback, listener);
-            self.history_index = new_index;
 
             change!(old_editedness, self.editedness()).into()
         } else {
             RanOutOfHistory
         } 
+    }
+
+    /// Undoes the most recent edit, and then redoes the edit that was made after the
+    /// same state before that one, if there is one.
+    pub fn previous_undo_branch(&mut self, listener: ppel!()) -> HistoryNavOutcome {
+        self.switch_undo_branch(BranchDirection::Previous, listener)
+    }
+
+    /// Undoes the most recent edit, and then redoes the edit that was made after the
+    /// same state after that one, if there is one.
+    pub fn next_undo_branch(&mut self, listener: ppel!()) -> HistoryNavOutcome {
+        self.switch_undo_branch(BranchDirection::Next, listener)
+    }
+
+    fn switch_undo_branch(
+        &mut self,
+        direction: BranchDirection,
+        mut listener: ppel!(),
+    ) -> HistoryNavOutcome {
+        u!{HistoryNavOutcome}
+        let old_editedness = self.editedness();
+
+        let opt = self.history.switch_branch(direction)
+            .map(|(undone, redone)| (undone.clone(), redone.clone()));
+
+        if let Some((undone, redone)) = opt {
+            // The listener needs to hear about both edits, so we reborrow it for the first one.
+            self.apply_edit(
+                !undone,
+                ApplyKind::Playback,
+                listener.as_mut().map(|l| ParserEditListener {
+                    buffer_name: l.buffer_name,
+                    parser_kind: l.parser_kind,
+                    parsers: &mut *l.parsers,
+                }),
+            );
+            self.apply_edit(redone, ApplyKind::Playback, listener);
+
+            change!(old_editedness, self.editedness()).into()
+        } else {
+            RanOutOfHistory
+        }
+    }
+
+    /// A plain text drawing of the undo tree, intended for debugging.
+    pub fn undo_tree_drawing(&self) -> String {
+        self.history.to_string()
     }
 
     pub fn has_no_edits(&self) -> bool {
@@ -736,11 +1368,13 @@ impl TextBuffer {
     }
 
     pub fn clear_history(&mut self) {
-        self.history.clear();
-        self.history_index = d!();
+        self.history = d!();
     }
 }
 
+mod history_serialization;
+pub use history_serialization::RestoreHistoryError;
+
 //
 // View rendering
 //
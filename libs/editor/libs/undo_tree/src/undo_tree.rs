@@ -0,0 +1,569 @@
+//! An undo tree keeps around the edits that were undone then replaced by other edits,
+//! instead of discarding them like an undo stack would. See the "Undo Tree" section of
+//! `design/IDEAS.md` for the background.
+#![deny(unused)]
+use macros::{d, u};
+use std::fmt;
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+struct UndoNode<T> {
+    value: T,
+    /// `None` means the parent is the root, which represents the state before any of
+    /// the edits in the tree were made.
+    parent: Option<usize>,
+    /// The child that `redo` moves to, which is the one that was visited most recently.
+    redo_child: Option<usize>,
+}
+
+/// The maximum amount of nodes kept by default. This must be a power of two.
+pub const DEFAULT_MAX_LEN: usize = 1 << 12;
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct UndoTree<T> {
+    /// Newer nodes are added to the end, so the age of each node is stored implicitly,
+    /// and a node's parent is always before it.
+    nodes: Vec<UndoNode<T>>,
+    /// The node that was applied most recently, or `None` if we are at the root.
+    current: Option<usize>,
+    root_redo_child: Option<usize>,
+    max_len: usize,
+}
+
+d!(<T> for UndoTree<T>: UndoTree::with_max_len(DEFAULT_MAX_LEN));
+
+/// A node's value, followed by the indexes of its parent and its redo child.
+pub type NodeParts<T> = (T, Option<usize>, Option<usize>);
+
+/// Everything needed to rebuild an `UndoTree` with `from_parts`, for example after
+/// it has been saved to disk.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UndoTreeParts<T> {
+    pub nodes: Vec<NodeParts<T>>,
+    pub current: Option<usize>,
+    pub root_redo_child: Option<usize>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BranchDirection {
+    /// Towards the older siblings.
+    Previous,
+    /// Towards the newer siblings.
+    Next,
+}
+
+impl<T> UndoTree<T> {
+    /// `max_len` is rounded up to a power of two, and is at least two.
+    pub fn with_max_len(max_len: usize) -> Self {
+        UndoTree {
+            nodes: Vec::new(),
+            current: None,
+            root_redo_child: None,
+            max_len: std::cmp::max(max_len, 2).next_power_of_two(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn parts(&self) -> UndoTreeParts<&T> {
+        UndoTreeParts {
+            nodes: self.nodes
+                .iter()
+                .map(|node| (&node.value, node.parent, node.redo_child))
+                .collect(),
+            current: self.current,
+            root_redo_child: self.root_redo_child,
+        }
+    }
+
+    /// Returns `None` if the parts do not describe a valid tree with at most
+    /// `DEFAULT_MAX_LEN` nodes.
+    pub fn from_parts(parts: UndoTreeParts<T>) -> Option<Self> {
+        let UndoTreeParts {
+            nodes,
+            current,
+            root_redo_child,
+        } = parts;
+
+        let len = nodes.len();
+        if len > DEFAULT_MAX_LEN {
+            return None;
+        }
+
+        let parents: Vec<Option<usize>> = nodes.iter().map(|&(_, parent, _)| parent).collect();
+
+        let is_child_of = |child: Option<usize>, parent: Option<usize>| match child {
+            None => true,
+            Some(c) => c < len && parents[c] == parent,
+        };
+
+        for (i, &(_, parent, redo_child)) in nodes.iter().enumerate() {
+            // Requiring that parents come before their children rules out cycles.
+            if parent.map(|p| p >= i).unwrap_or(false)
+            || !is_child_of(redo_child, Some(i)) {
+                return None;
+            }
+        }
+
+        if current.map(|c| c >= len).unwrap_or(false)
+        || !is_child_of(root_redo_child, None) {
+            return None;
+        }
+
+        Some(UndoTree {
+            nodes: nodes
+                .into_iter()
+                .map(|(value, parent, redo_child)| UndoNode {
+                    value,
+                    parent,
+                    redo_child,
+                })
+                .collect(),
+            current,
+            root_redo_child,
+            max_len: DEFAULT_MAX_LEN,
+        })
+    }
+
+    /// The value of the node that was applied most recently, if any.
+    pub fn current(&self) -> Option<&T> {
+        self.current.map(|i| &self.nodes[i].value)
+    }
+
+    /// Adds `value` as a new child of the current node, and makes it the current node.
+    pub fn push(&mut self, value: T) {
+        if self.nodes.len() >= self.max_len {
+            self.compact();
+        }
+
+        let index = self.nodes.len();
+        self.nodes.push(UndoNode {
+            value,
+            parent: self.current,
+            redo_child: None,
+        });
+        *self.redo_child_mut(self.current) = Some(index);
+        self.current = Some(index);
+    }
+
+    /// Moves to the parent of the current node. Returns the value of the node that was
+    /// current, which is the one that needs to be undone, if there was one.
+    pub fn undo(&mut self) -> Option<&T> {
+        let index = self.current?;
+        let parent = self.nodes[index].parent;
+
+        *self.redo_child_mut(parent) = Some(index);
+        self.current = parent;
+
+        Some(&self.nodes[index].value)
+    }
+
+    /// Moves to the most recently visited child of the current node. Returns the value
+    /// of that child, which is the one that needs to be redone, if there was one.
+    pub fn redo(&mut self) -> Option<&T> {
+        let index = self.redo_child(self.current)?;
+
+        self.current = Some(index);
+
+        Some(&self.nodes[index].value)
+    }
+
+    /// Moves from the current node to its next older or newer sibling, wrapping around.
+    /// Returns the value of the node that was current, which needs to be undone, and
+    /// the value of the sibling, which then needs to be redone. Returns `None` if the
+    /// current node has no siblings.
+    pub fn switch_branch(&mut self, direction: BranchDirection) -> Option<(&T, &T)> {
+        u!{BranchDirection}
+        let index = self.current?;
+        let parent = self.nodes[index].parent;
+
+        let siblings: Vec<usize> = self.children(parent).collect();
+        let len = siblings.len();
+        if len <= 1 {
+            return None;
+        }
+        let position = siblings.iter().position(|&i| i == index)?;
+
+        let sibling = siblings[match direction {
+            Previous => (position + len - 1) % len,
+            Next => (position + 1) % len,
+        }];
+
+        *self.redo_child_mut(parent) = Some(sibling);
+        self.current = Some(sibling);
+
+        Some((&self.nodes[index].value, &self.nodes[sibling].value))
+    }
+
+    /// The children of the node at `parent`, or of the root if that is `None`, from
+    /// oldest to newest.
+    fn children(&self, parent: Option<usize>) -> impl Iterator<Item = usize> + '_ {
+        let start = parent.map(|p| p + 1).unwrap_or(0);
+        self.nodes[start..]
+            .iter()
+            .enumerate()
+            .filter(move |(_, node)| node.parent == parent)
+            .map(move |(i, _)| start + i)
+    }
+
+    fn redo_child(&self, parent: Option<usize>) -> Option<usize> {
+        match parent {
+            None => self.root_redo_child,
+            Some(i) => self.nodes[i].redo_child,
+        }
+    }
+
+    fn redo_child_mut(&mut self, parent: Option<usize>) -> &mut Option<usize> {
+        match parent {
+            None => &mut self.root_redo_child,
+            Some(i) => &mut self.nodes[i].redo_child,
+        }
+    }
+
+    /// Removes the older half of the nodes. The newest removed node on the path from
+    /// the current node to the root becomes the new root. Nodes from the newer half
+    /// that are no longer connected to that root are orphaned, and removed as well,
+    /// since the state they apply to can no longer be reached.
+    fn compact(&mut self) {
+        let half = self.nodes.len() / 2;
+
+        let mut new_root = self.current;
+        while let Some(i) = new_root {
+            if i < half {
+                break;
+            }
+            new_root = self.nodes[i].parent;
+        }
+
+        let root_redo_child = match new_root {
+            None => self.root_redo_child,
+            Some(i) => self.nodes[i].redo_child,
+        };
+
+        // Since parents are always before their children, we know the new index of
+        // any parent that was kept by the time we get to its children.
+        let mut new_indexes: Vec<Option<usize>> = vec![None; self.nodes.len()];
+        let mut kept = Vec::with_capacity(self.max_len);
+        for (i, mut node) in self.nodes.drain(..).enumerate().skip(half) {
+            let parent = if node.parent == new_root {
+                Some(None)
+            } else {
+                match node.parent {
+                    Some(p) if p >= half => new_indexes[p].map(Some),
+                    _ => None,
+                }
+            };
+
+            if let Some(parent) = parent {
+                new_indexes[i] = Some(kept.len());
+                node.parent = parent;
+                kept.push(node);
+            }
+        }
+
+        for node in kept.iter_mut() {
+            node.redo_child = node.redo_child.and_then(|c| new_indexes[c]);
+        }
+
+        self.nodes = kept;
+        self.root_redo_child = root_redo_child.and_then(|c| new_indexes[c]);
+        self.current = self.current.and_then(|c| new_indexes[c]);
+    }
+}
+
+/// The maximum amount of nodes shown in a row before some are elided.
+const MAX_DRAWN_CHAIN_LEN: usize = 8;
+
+/// Draws the tree with ASCII characters, one branch per line. Each node is labeled
+/// with its age, (lower is older,) and the current node is surrounded by brackets.
+/// Nodes with only one child are drawn on the same line as that child.
+impl<T> fmt::Display for UndoTree<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut children: Vec<Vec<usize>> = vec![Vec::new(); self.nodes.len()];
+        let mut root_children = Vec::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            match node.parent {
+                None => root_children.push(i),
+                Some(p) => children[p].push(i),
+            }
+        }
+
+        let label = |i: usize| if self.current == Some(i) {
+            format!("[{}]", i)
+        } else {
+            i.to_string()
+        };
+
+        if self.current.is_none() {
+            write!(f, "[root]")?;
+        } else {
+            write!(f, "root")?;
+        }
+
+        // We use an explicit stack since the tree can be deep enough that recursion
+        // would be a problem.
+        let mut stack: Vec<(String, bool, usize)> = root_children
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(i, &c)| (String::new(), i + 1 == root_children.len(), c))
+            .collect();
+
+        while let Some((prefix, is_last, start)) = stack.pop() {
+            let mut chain = vec![start];
+            let mut end = start;
+            while let [only_child] = children[end][..] {
+                chain.push(only_child);
+                end = only_child;
+            }
+
+            write!(f, "\n{}{}", prefix, if is_last { "`- " } else { "+- " })?;
+
+            let mut labels: Vec<String> = Vec::with_capacity(MAX_DRAWN_CHAIN_LEN);
+            if chain.len() <= MAX_DRAWN_CHAIN_LEN {
+                labels.extend(chain.iter().map(|&i| label(i)));
+            } else {
+                const ENDS_LEN: usize = MAX_DRAWN_CHAIN_LEN / 2 - 1;
+                let elided = &chain[ENDS_LEN..chain.len() - ENDS_LEN];
+
+                labels.extend(chain[..ENDS_LEN].iter().map(|&i| label(i)));
+                labels.push("...".to_owned());
+                if let Some(&current) = elided.iter().find(|&&i| self.current == Some(i)) {
+                    labels.push(label(current));
+                    labels.push("...".to_owned());
+                }
+                labels.extend(chain[chain.len() - ENDS_LEN..].iter().map(|&i| label(i)));
+            }
+            write!(f, "{}", labels.join(" "))?;
+
+            let child_prefix = format!("{}{}", prefix, if is_last { "   " } else { "|  " });
+            let branches = &children[end];
+            for (i, &c) in branches.iter().enumerate().rev() {
+                stack.push((child_prefix.clone(), i + 1 == branches.len(), c));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn undo_all(tree: &mut UndoTree<u32>) -> Vec<u32> {
+        let mut undone = Vec::new();
+        while let Some(&v) = tree.undo() {
+            undone.push(v);
+        }
+        undone
+    }
+
+    fn redo_all(tree: &mut UndoTree<u32>) -> Vec<u32> {
+        let mut redone = Vec::new();
+        while let Some(&v) = tree.redo() {
+            redone.push(v);
+        }
+        redone
+    }
+
+    #[test]
+    fn undo_then_redo_works_like_a_stack_when_there_are_no_branches() {
+        let mut tree = UndoTree::default();
+        tree.push(1);
+        tree.push(2);
+        tree.push(3);
+
+        assert_eq!(undo_all(&mut tree), vec![3, 2, 1]);
+        assert_eq!(redo_all(&mut tree), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn pushing_after_an_undo_keeps_the_undone_branch() {
+        let mut tree = UndoTree::default();
+        tree.push(1);
+        tree.push(2);
+        tree.undo();
+        tree.push(3);
+
+        assert_eq!(tree.len(), 3);
+        assert_eq!(tree.switch_branch(BranchDirection::Previous), Some((&3, &2)));
+        assert_eq!(undo_all(&mut tree), vec![2, 1]);
+    }
+
+    #[test]
+    fn redo_follows_the_most_recently_visited_branch() {
+        let mut tree = UndoTree::default();
+        tree.push(1);
+        tree.undo();
+        tree.push(2);
+        tree.switch_branch(BranchDirection::Previous);
+        tree.undo();
+
+        assert_eq!(redo_all(&mut tree), vec![1]);
+    }
+
+    #[test]
+    fn switching_branches_wraps_around() {
+        let mut tree = UndoTree::default();
+        tree.push(1);
+        tree.undo();
+        tree.push(2);
+        tree.undo();
+        tree.push(3);
+
+        assert_eq!(tree.switch_branch(BranchDirection::Next), Some((&3, &1)));
+        assert_eq!(tree.switch_branch(BranchDirection::Next), Some((&1, &2)));
+        assert_eq!(tree.switch_branch(BranchDirection::Previous), Some((&2, &1)));
+        assert_eq!(tree.switch_branch(BranchDirection::Previous), Some((&1, &3)));
+    }
+
+    #[test]
+    fn switching_branches_does_nothing_without_siblings() {
+        let mut tree = UndoTree::default();
+        assert_eq!(tree.switch_branch(BranchDirection::Next), None);
+
+        tree.push(1);
+        tree.push(2);
+        assert_eq!(tree.switch_branch(BranchDirection::Next), None);
+        assert_eq!(undo_all(&mut tree), vec![2, 1]);
+    }
+
+    #[test]
+    fn compacting_keeps_the_newer_half_of_a_linear_history() {
+        let mut tree = UndoTree::with_max_len(4);
+        for i in 0..5 {
+            tree.push(i);
+        }
+
+        assert_eq!(tree.len(), 3);
+        assert_eq!(undo_all(&mut tree), vec![4, 3, 2]);
+        assert_eq!(redo_all(&mut tree), vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn compacting_removes_branches_that_are_no_longer_connected_to_the_root() {
+        let mut tree = UndoTree::with_max_len(4);
+        tree.push(0);
+        tree.push(1);
+        // This branch starts from the state after 0, which will be removed.
+        tree.undo();
+        tree.push(2);
+        // This one starts from the state after 1, which will be the new root.
+        tree.switch_branch(BranchDirection::Previous);
+        tree.push(3);
+        tree.push(4);
+
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree.switch_branch(BranchDirection::Next), None);
+        assert_eq!(undo_all(&mut tree), vec![4, 3]);
+    }
+
+    #[test]
+    fn compacting_when_the_current_node_is_old_makes_it_the_new_root() {
+        let mut tree = UndoTree::with_max_len(4);
+        for i in 0..4 {
+            tree.push(i);
+        }
+        tree.undo();
+        tree.undo();
+        tree.undo();
+        tree.push(4);
+
+        assert_eq!(tree.len(), 1);
+        assert_eq!(undo_all(&mut tree), vec![4]);
+    }
+
+    fn parts_to_owned(tree: &UndoTree<u32>) -> UndoTreeParts<u32> {
+        let UndoTreeParts { nodes, current, root_redo_child } = tree.parts();
+        UndoTreeParts {
+            nodes: nodes.into_iter().map(|(&v, p, r)| (v, p, r)).collect(),
+            current,
+            root_redo_child,
+        }
+    }
+
+    #[test]
+    fn from_parts_round_trips_a_tree_with_branches() {
+        let mut tree = UndoTree::default();
+        tree.push(0);
+        tree.push(1);
+        tree.undo();
+        tree.push(2);
+        tree.undo();
+
+        let rebuilt = UndoTree::from_parts(parts_to_owned(&tree));
+
+        assert_eq!(rebuilt, Some(tree));
+    }
+
+    #[test]
+    fn from_parts_rejects_cycles() {
+        let parts = UndoTreeParts {
+            nodes: vec![(0, Some(1), None), (1, Some(0), None)],
+            current: Some(1),
+            root_redo_child: None,
+        };
+
+        assert_eq!(UndoTree::from_parts(parts), None);
+    }
+
+    #[test]
+    fn from_parts_rejects_redo_children_that_are_not_children() {
+        let parts = UndoTreeParts {
+            nodes: vec![(0, None, None), (1, None, None)],
+            current: Some(0),
+            root_redo_child: None,
+        };
+        assert!(UndoTree::from_parts(parts.clone()).is_some());
+
+        let mut bad_parts = parts;
+        bad_parts.nodes[0].2 = Some(1);
+        assert_eq!(UndoTree::from_parts(bad_parts), None);
+    }
+
+    #[test]
+    fn draws_branches_on_separate_lines() {
+        let mut tree = UndoTree::default();
+        tree.push(0);
+        tree.push(1);
+        tree.undo();
+        tree.push(2);
+        tree.push(3);
+
+        assert_eq!(
+            tree.to_string(),
+            "root\n`- 0\n   +- 1\n   `- 2 [3]"
+        );
+    }
+
+    #[test]
+    fn draws_the_root_as_current_when_everything_has_been_undone() {
+        let mut tree = UndoTree::default();
+        tree.push(0);
+        tree.undo();
+
+        assert_eq!(tree.to_string(), "[root]\n`- 0");
+    }
+
+    #[test]
+    fn draws_long_chains_with_the_middle_elided_except_for_the_current_node() {
+        let mut tree = UndoTree::default();
+        for i in 0..20 {
+            tree.push(i);
+        }
+        for _ in 0..10 {
+            tree.undo();
+        }
+
+        assert_eq!(
+            tree.to_string(),
+            "root\n`- 0 1 2 ... [9] ... 17 18 19"
+        );
+    }
+}
@@ -2,6 +2,7 @@
 /// The code is licensed under the Apache 2.0 license, and/or the MIT Licencse as described in the
 /// license files included in this crate. This file has been modified from its original form.
 use grapheme::GraphemeCat;
+use word::WordCat;
 
 pub mod grapheme {
     use std::result::Result::{Err, Ok};
@@ -581,9 +582,10 @@ pub mod grapheme {
         ('\u{e0020}', '\u{e007f}', GC_Extend), ('\u{e0080}', '\u{e00ff}', GC_Control), ('\u{e0100}',
         '\u{e01ef}', GC_Extend), ('\u{e01f0}', '\u{e0fff}', GC_Control)
     ];
-
 }
 
+pub mod word;
+
 #[derive(Clone)]
 pub struct GraphemeIndices<'a> {
     start_offset: usize,
@@ -927,10 +929,358 @@ impl GraphemeCursor {
     }
 }
 
+/// External iterator for a string's
+/// [word boundaries](http://www.unicode.org/reports/tr29/#Word_Boundaries).
+#[derive(Clone)]
+pub struct UWordBounds<'a> {
+    string: &'a str,
+    cat: Option<WordCat>,
+}
+
+/// External iterator for word boundaries and byte offsets.
+#[derive(Clone)]
+pub struct UWordBoundIndices<'a> {
+    start_offset: usize,
+    iter: UWordBounds<'a>,
+}
+
+impl<'a> UWordBoundIndices<'a> {
+    #[inline]
+    /// View the underlying data (the part yet to be iterated) as a slice of the original string.
+    pub fn as_str(&self) -> &'a str {
+        self.iter.as_str()
+    }
+}
+
+impl<'a> Iterator for UWordBoundIndices<'a> {
+    type Item = (usize, &'a str);
+
+    #[inline]
+    fn next(&mut self) -> Option<(usize, &'a str)> {
+        self.iter
+            .next()
+            .map(|s| (s.as_ptr() as usize - self.start_offset, s))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+// state machine for word boundary rules
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum UWordBoundsState {
+    Start,
+    Letter,
+    HLetter,
+    Numeric,
+    Katakana,
+    ExtendNumLet,
+    Regional(RegionalState),
+    FormatExtend(FormatExtendType),
+    Zwj,
+    Emoji,
+}
+
+// subtypes for FormatExtend state in UWordBoundsState
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum FormatExtendType {
+    AcceptNone,
+    RequireLetter,
+    RequireHLetter,
+    AcceptQLetter,
+    RequireNumeric,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum RegionalState {
+    Half,
+    Full,
+}
+
+impl<'a> Iterator for UWordBounds<'a> {
+    type Item = &'a str;
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let slen = self.string.len();
+        (std::cmp::min(slen, 1), Some(slen))
+    }
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a str> {
+        use self::FormatExtendType::*;
+        use self::UWordBoundsState::*;
+        use crate::word as wd;
+        if self.string.is_empty() {
+            return None;
+        }
+
+        let mut take_curr = true;
+        let mut take_cat = true;
+        let mut idx = 0;
+        let mut saveidx = 0;
+        let mut state = Start;
+        let mut cat = wd::WC_Any;
+        let mut savecat = wd::WC_Any;
+
+        // Whether or not the previous category was ZWJ
+        // ZWJs get collapsed, so this handles precedence of WB3c over WB4
+        let mut prev_zwj;
+        for (curr, ch) in self.string.char_indices() {
+            idx = curr;
+            prev_zwj = cat == wd::WC_ZWJ;
+            // if there's a category cached, grab it
+            cat = match self.cat.take() {
+                None => wd::word_category(ch),
+                Some(cat) => cat,
+            };
+            take_cat = true;
+
+            // handle rule WB4
+            // just skip all format, extend, and zwj chars
+            // note that Start is a special case: if there's a bunch of Format | Extend
+            // characters at the beginning of a block of text, dump them out as one unit.
+            //
+            // (This is not obvious from the wording of UAX#29, but if you look at the
+            // test cases http://www.unicode.org/Public/UNIDATA/auxiliary/WordBreakTest.txt
+            // then the "correct" interpretation of WB4 becomes apparent.)
+            if state != Start {
+                match cat {
+                    wd::WC_Extend | wd::WC_Format | wd::WC_ZWJ => continue,
+                    _ => {}
+                }
+            }
+
+            // rule WB3c
+            // WB4 makes all ZWJs collapse into the previous state
+            // but you can still be in a Zwj state if you started with Zwj
+            //
+            // This means that Zwj + Extend will collapse into Zwj, which is wrong,
+            // since Extend has a boundary with following EBG/GAZ chars but ZWJ doesn't,
+            // and that rule (WB3c) has higher priority
+            //
+            // Additionally, Emoji_Base+ZWJ+(EBG/GAZ) will collapse into Emoji_Base+EBG/GAZ
+            // which won't have a boundary even though EB+ZWJ+GAZ should have a boundary.
+            //
+            // Thus, we separately keep track of whether or not the last character
+            // was a ZWJ. This is an additional bit of state tracked outside of the
+            // state enum; the state enum represents the last non-zwj state encountered.
+            // When prev_zwj is true, for the purposes of WB3c, we are in the Zwj state,
+            // however we are in the previous state for the purposes of all other rules.
+            if prev_zwj {
+                match cat {
+                    wd::WC_Glue_After_Zwj => continue,
+                    wd::WC_E_Base_GAZ => {
+                        state = Emoji;
+                        continue;
+                    }
+                    _ => (),
+                }
+            }
+            // Don't use `continue` in this match without updating `cat`
+            state = match state {
+                Start if cat == wd::WC_CR => {
+                    idx += match self.get_next_cat(idx) {
+                        Some(ncat) if ncat == wd::WC_LF => 1, // rule WB3
+                        _ => 0,
+                    };
+                    break; // rule WB3a
+                }
+                Start => match cat {
+                    wd::WC_ALetter => Letter,            // rule WB5, WB6, WB9, WB13a
+                    wd::WC_Hebrew_Letter => HLetter,     // rule WB5, WB6, WB7a, WB7b, WB9, WB13a
+                    wd::WC_Numeric => Numeric,           // rule WB8, WB10, WB12, WB13a
+                    wd::WC_Katakana => Katakana,         // rule WB13, WB13a
+                    wd::WC_ExtendNumLet => ExtendNumLet, // rule WB13a, WB13b
+                    wd::WC_Regional_Indicator => Regional(RegionalState::Half), // rule WB13c
+                    wd::WC_LF | wd::WC_Newline => break, // rule WB3a
+                    wd::WC_ZWJ => Zwj,                   // rule WB3c
+                    wd::WC_E_Base | wd::WC_E_Base_GAZ => Emoji, // rule WB14
+                    _ => {
+                        if let Some(ncat) = self.get_next_cat(idx) {
+                            // rule WB4
+                            if ncat == wd::WC_Format || ncat == wd::WC_Extend || ncat == wd::WC_ZWJ
+                            {
+                                state = FormatExtend(AcceptNone);
+                                self.cat = Some(ncat);
+                                continue;
+                            }
+                        }
+                        break; // rule WB999
+                    }
+                },
+                Zwj => {
+                    // We already handle WB3c above. At this point,
+                    // the current category is not GAZ or EBG,
+                    // or the previous character was not actually a ZWJ
+                    take_curr = false;
+                    break;
+                }
+                Letter | HLetter => match cat {
+                    wd::WC_ALetter => Letter,            // rule WB5
+                    wd::WC_Hebrew_Letter => HLetter,     // rule WB5
+                    wd::WC_Numeric => Numeric,           // rule WB9
+                    wd::WC_ExtendNumLet => ExtendNumLet, // rule WB13a
+                    wd::WC_Double_Quote if state == HLetter => {
+                        savecat = cat;
+                        saveidx = idx;
+                        FormatExtend(RequireHLetter) // rule WB7b
+                    }
+                    wd::WC_Single_Quote if state == HLetter => {
+                        FormatExtend(AcceptQLetter) // rule WB7a
+                    }
+                    wd::WC_MidLetter | wd::WC_MidNumLet | wd::WC_Single_Quote => {
+                        savecat = cat;
+                        saveidx = idx;
+                        FormatExtend(RequireLetter) // rule WB6
+                    }
+                    _ => {
+                        take_curr = false;
+                        break;
+                    }
+                },
+                Numeric => match cat {
+                    wd::WC_Numeric => Numeric,           // rule WB8
+                    wd::WC_ALetter => Letter,            // rule WB10
+                    wd::WC_Hebrew_Letter => HLetter,     // rule WB10
+                    wd::WC_ExtendNumLet => ExtendNumLet, // rule WB13a
+                    wd::WC_MidNum | wd::WC_MidNumLet | wd::WC_Single_Quote => {
+                        savecat = cat;
+                        saveidx = idx;
+                        FormatExtend(RequireNumeric) // rule WB12
+                    }
+                    _ => {
+                        take_curr = false;
+                        break;
+                    }
+                },
+                Katakana => match cat {
+                    wd::WC_Katakana => Katakana,         // rule WB13
+                    wd::WC_ExtendNumLet => ExtendNumLet, // rule WB13a
+                    _ => {
+                        take_curr = false;
+                        break;
+                    }
+                },
+                ExtendNumLet => match cat {
+                    wd::WC_ExtendNumLet => ExtendNumLet, // rule WB13a
+                    wd::WC_ALetter => Letter,            // rule WB13b
+                    wd::WC_Hebrew_Letter => HLetter,     // rule WB13b
+                    wd::WC_Numeric => Numeric,           // rule WB13b
+                    wd::WC_Katakana => Katakana,         // rule WB13b
+                    _ => {
+                        take_curr = false;
+                        break;
+                    }
+                },
+                Regional(RegionalState::Full) => {
+                    // if it reaches here we've gone too far,
+                    // a full flag can only compose with ZWJ/Extend/Format
+                    // proceeding it.
+                    take_curr = false;
+                    break;
+                }
+                Regional(RegionalState::Half) => match cat {
+                    wd::WC_Regional_Indicator => Regional(RegionalState::Full), // rule WB13c
+                    _ => {
+                        take_curr = false;
+                        break;
+                    }
+                },
+                Emoji => match cat {
+                    // rule WB14
+                    wd::WC_E_Modifier => state,
+                    _ => {
+                        take_curr = false;
+                        break;
+                    }
+                },
+                FormatExtend(t) => match t {
+                    // handle FormatExtends depending on what type
+                    RequireNumeric if cat == wd::WC_Numeric => Numeric, // rule WB11
+                    RequireLetter | AcceptQLetter if cat == wd::WC_ALetter => Letter, // rule WB7
+                    RequireLetter | AcceptQLetter if cat == wd::WC_Hebrew_Letter => HLetter, // WB7a
+                    RequireHLetter if cat == wd::WC_Hebrew_Letter => HLetter, // rule WB7b
+                    AcceptNone | AcceptQLetter => {
+                        take_curr = false; // emit all the Format|Extend characters
+                        take_cat = false;
+                        break;
+                    }
+                    _ => break, // rewind (in if statement below)
+                },
+            }
+        }
+
+        if let FormatExtend(t) = state {
+            // we were looking for something and didn't find it; we have to back up
+            if t == RequireLetter || t == RequireHLetter || t == RequireNumeric {
+                idx = saveidx;
+                cat = savecat;
+                take_curr = false;
+            }
+        }
+
+        self.cat = if take_curr {
+            idx = idx + self.string[idx..].chars().next().unwrap().len_utf8();
+            None
+        } else if take_cat {
+            Some(cat)
+        } else {
+            None
+        };
+
+        let retstr = &self.string[..idx];
+        self.string = &self.string[idx..];
+        Some(retstr)
+    }
+}
+
+impl<'a> UWordBounds<'a> {
+    #[inline]
+    /// View the underlying data (the part yet to be iterated) as a slice of the original string.
+    pub fn as_str(&self) -> &'a str {
+        self.string
+    }
+
+    #[inline]
+    fn get_next_cat(&self, idx: usize) -> Option<WordCat> {
+        use crate::word as wd;
+        let nidx = idx + self.string[idx..].chars().next().unwrap().len_utf8();
+        if nidx < self.string.len() {
+            let nch = self.string[nidx..].chars().next().unwrap();
+            Some(wd::word_category(nch))
+        } else {
+            None
+        }
+    }
+}
+
+#[inline]
+pub fn new_word_bounds<'b>(s: &'b str) -> UWordBounds<'b> {
+    UWordBounds {
+        string: s,
+        cat: None,
+    }
+}
+
+#[inline]
+pub fn new_word_bound_indices<'b>(s: &'b str) -> UWordBoundIndices<'b> {
+    UWordBoundIndices {
+        start_offset: s.as_ptr() as usize,
+        iter: new_word_bounds(s),
+    }
+}
+
 pub trait UnicodeSegmentation {
     fn graphemes<'a>(&'a self) -> Graphemes<'a>;
 
     fn grapheme_indices<'a>(&'a self) -> GraphemeIndices<'a>;
+
+    fn split_word_bounds<'a>(&'a self) -> UWordBounds<'a>;
+
+    fn split_word_bound_indices<'a>(&'a self) -> UWordBoundIndices<'a>;
 }
 
 impl UnicodeSegmentation for str {
@@ -943,4 +1293,14 @@ impl UnicodeSegmentation for str {
     fn grapheme_indices(&self) -> GraphemeIndices {
         new_grapheme_indices(self)
     }
+
+    #[inline]
+    fn split_word_bounds(&self) -> UWordBounds {
+        new_word_bounds(self)
+    }
+
+    #[inline]
+    fn split_word_bound_indices(&self) -> UWordBoundIndices {
+        new_word_bound_indices(self)
+    }
 }
@@ -1,4 +1,4 @@
-use macros::{d, dbg, fmt_debug, u, SaturatingSub};
+use macros::{d, dbg, fmt_debug, some_or, u, SaturatingSub};
 use platform_types::{screen_positioning::*, *};
 use parsers::{Parsers, ParserKind};
 
@@ -10,6 +10,7 @@ use text_buffer::{
     Editedness,
     PossibleEditedTransition,
     ScrollAdjustSpec,
+    SwapDirection,
     TextBuffer,
     ppel,
 };
@@ -18,16 +19,21 @@ mod editor_view;
 mod editor_buffers;
 use editor_buffers::{
     EditorBuffers, 
-    EditorBuffer
+    EditorBuffer,
+    FindInPathsKey,
+    find_in_texts,
 };
 
 mod clipboard_history {
     use super::*;
     use std::collections::VecDeque;
 
+    /// Each entry holds the selection from each cursor that was copied, in the
+    /// order the cursors appear in the buffer, so that pasting with the same
+    /// amount of cursors can put each selection back at its own cursor.
     #[derive(Debug, Default, PartialEq, Eq)]
     pub struct ClipboardHistory {
-        entries: VecDeque<String>,
+        entries: VecDeque<Vec<String>>,
         index: usize,
     }
     
@@ -52,11 +58,23 @@ mod clipboard_history {
             let mut output = None;
     
             if let Some(s) = possible_string {
-                self.push_if_does_not_match_top(s)
+                // If the string is what we last put on the clipboard, then we keep
+                // the selections it was joined from, instead of treating it as a
+                // single selection.
+                let matches_top = self.entries.get(self.index)
+                    .map(|pieces| join(pieces) == s)
+                    .unwrap_or(false);
+                if !matches_top {
+                    self.push_if_does_not_match_top(vec![s])
+                }
             }
     
-            if let Some(s) = self.entries.get(self.index) {
-                output = buffer.insert_string(s.to_owned(), listener);
+            if let Some(pieces) = self.entries.get(self.index) {
+                output = if pieces.len() > 1 && pieces.len() == buffer.borrow_cursors().len() {
+                    buffer.insert_at_each_cursor(|i| pieces[i].to_owned(), listener)
+                } else {
+                    buffer.insert_string(join(pieces), listener)
+                };
             }
     
             output
@@ -64,31 +82,24 @@ mod clipboard_history {
     
         fn push_and_join_into_option(
             &mut self,
-            strings: Vec<String>
+            mut strings: Vec<String>
         ) -> Option<String> {
             if strings.is_empty() {
                 None
             } else {
-                let mut output = String::with_capacity(
-                    strings.len() * AVERAGE_SELECTION_SIZE_ESTIMATE
-                );
-    
-                let mut sep = "";
-                for s in strings {
-                    output.push_str(sep);
-    
-                    output.push_str(&s);
-    
-                    self.push_if_does_not_match_top(s);
-    
-                    sep = "\n";
-                }
-    
+                // The selections come in the order the cursors are stored in, which
+                // is from the end of the buffer to the start.
+                strings.reverse();
+
+                let output = join(&strings);
+
+                self.push_if_does_not_match_top(strings);
+
                 Some(output)
             }
         }
     
-        fn push_if_does_not_match_top(&mut self, to_push: String) {
+        fn push_if_does_not_match_top(&mut self, to_push: Vec<String>) {
             match self.entries.get(self.index).map(|s| s != &to_push) {
                 None => {
                     self.entries.push_back(to_push);
@@ -100,6 +111,23 @@ mod clipboard_history {
                 Some(false) => {}
             }
         }
+    }
+
+    fn join(strings: &[String]) -> String {
+        let mut output = String::with_capacity(
+            strings.len() * AVERAGE_SELECTION_SIZE_ESTIMATE
+        );
+
+        let mut sep = "";
+        for s in strings {
+            output.push_str(sep);
+
+            output.push_str(s);
+
+            sep = "\n";
+        }
+
+        output
     }
 }
 use clipboard_history::ClipboardHistory;
@@ -113,6 +141,12 @@ pub struct State {    buffers: EditorBuffers,
     file_switcher_results: FileSwitcherResults,
     find: TextBuffer,
     find_xywh: TextBoxXYWH,
+    search_options: SearchOptions,
+    find_results: FindResults,
+    // What the `find_results` for `FindReplaceMode::OpenBuffers` were found from.
+    find_in_paths_key: Option<FindInPathsKey>,
+    // Where to put the cursor once the buffer with the given path is selected.
+    pending_cursor_position: Option<(PathBuf, Position)>,
     replace: TextBuffer,
     replace_xywh: TextBoxXYWH,
     go_to_position: TextBuffer,
@@ -120,6 +154,8 @@ pub struct State {    buffers: EditorBuffers,
     font_info: FontInfo,
     clipboard_history: ClipboardHistory,
     parsers: parsers::Parsers,
+    // Whether to fill in `view.undo_tree_drawing`.
+    undo_tree_shown: bool,
     view: View,
 }
 
@@ -136,6 +172,13 @@ fmt_debug!(
         );
         blank_if_default!(find);
         blank_if_default!(find_xywh);
+        blank_if_default!(search_options);
+        blank_if_default!(
+            find_results,
+            me.find_results == <FindResults as Default>::default()
+        );
+        blank_if_default!(find_in_paths_key);
+        blank_if_default!(pending_cursor_position);
         blank_if_default!(replace);
         blank_if_default!(replace_xywh);
         blank_if_default!(go_to_position);
@@ -146,6 +189,7 @@ fmt_debug!(
             parsers,
             matches!(me.parsers, Parsers::NotInitializedYet)
         );
+        blank_if_default!(undo_tree_shown);
         blank_if_default!(view);
     }
 );
@@ -279,7 +323,7 @@ impl State {
         let mut output = 0;
         for b in self.buffers.iter() {
             match b.name {
-                BufferName::Path(_) => continue,
+                BufferName::Path(_) | BufferName::Hex(_) => continue,
                 BufferName::Scratch(n) => {
                     output = std::cmp::max(output, n);
                 }
@@ -356,6 +400,47 @@ impl State {
         }
     }
 
+    /// The directory searched by `FindReplaceMode::Directory`: the one containing
+    /// the current buffer, or the working directory if that is not known.
+    fn search_directory(&self) -> PathBuf {
+        match &self.buffers.get_current_buffer().name {
+            BufferName::Path(p) | BufferName::Hex(p) => p.parent()
+                .filter(|dir| dir != &std::path::Path::new(""))
+                .map(|dir| dir.to_path_buf()),
+            BufferName::Scratch(_) => None,
+        }.unwrap_or_else(|| PathBuf::from("."))
+    }
+
+    fn open_or_select_buffer(&mut self, path: PathBuf) -> Cmd {
+        if let Some(id) = self
+            .buffers
+            .iter_with_indexes()
+            .find(|(_, b)| match &b.name {
+                BufferName::Path(p) => *p == path,
+                BufferName::Hex(_) | BufferName::Scratch(_) => false,
+            })
+            .map(|(i, _)| b_id!(BufferIdKind::Text, i))
+        {
+            self.set_id(id);
+            self.set_menu_mode(MenuMode::Hidden);
+            self.move_to_pending_cursor_position();
+            Cmd::None
+        } else {
+            // No need to mark the edited transition here since we will do
+            // that in the `AddOrSelectBuffer` case when the file actualy arrives.
+            Cmd::LoadFile(path)
+        }
+    }
+
+    fn move_to_pending_cursor_position(&mut self) {
+        let (path, position) = some_or!(self.pending_cursor_position.take(), return);
+
+        let buffer = self.buffers.get_current_buffer_mut();
+        if buffer.name == BufferName::Path(path) {
+            buffer.text_buffer.set_cursor(position, ReplaceOrAdd::Replace);
+        }
+    }
+
     fn opened_paths(&self) -> Vec<&PathBuf> {
         let mut opened_paths: Vec<&PathBuf> = Vec::with_capacity(self.buffers.len().into());
 
@@ -364,7 +449,7 @@ impl State {
                 BufferName::Path(p) => {
                     opened_paths.push(p);
                 }
-                BufferName::Scratch(_) => {}
+                BufferName::Hex(_) | BufferName::Scratch(_) => {}
             };
         }
         opened_paths
@@ -445,13 +530,33 @@ pub fn update_and_render(state: &mut State, input: Input) -> UpdateAndRenderOutp
                 MenuMode::Hidden => {
                     state.buffers.get_current_buffer_mut().refresh_search_results(
                         (&state.find).into(),
+                        state.search_options,
                     );
                 }
-                MenuMode::FindReplace(_) => {
+                MenuMode::FindReplace(FindReplaceMode::CurrentFile) => {
                     state.buffers.get_current_buffer_mut().advance_or_refresh_search_results(
                         (&state.find).into(),
+                        state.search_options,
                     );
                 }
+                MenuMode::FindReplace(FindReplaceMode::OpenBuffers) => {
+                    let key = state.buffers.find_in_paths_key(
+                        (&state.find).into(),
+                        state.search_options,
+                    );
+                    // Searching every buffer on every sync would be wasteful.
+                    if state.find_in_paths_key.as_ref() != Some(&key) {
+                        state.find_results = state.buffers.find_in_paths(
+                            (&state.find).into(),
+                            state.search_options,
+                        );
+                        state.find_in_paths_key = Some(key);
+                    }
+                }
+                MenuMode::FindReplace(FindReplaceMode::Directory) => {
+                    // Searching a directory means reading every file in it, so we
+                    // only do that when the form is submitted.
+                }
                 MenuMode::FileSwitcher => {
                     let needle_string: String =
                         state.file_switcher.borrow_rope().into();
@@ -544,6 +649,30 @@ pub fn update_and_render(state: &mut State, input: Input) -> UpdateAndRenderOutp
                 $transition
             )
         };
+        // For when the current text buffer was edited from a menu buffer, like when
+        // replacing search results.
+        (current text, $transition: expr) => {{
+            let transition: Option<EditedTransition> = $transition.into();
+
+            if let Some(transition) = transition {
+                state.view.edited_transitions.push((
+                    state.buffers.current_index(),
+                    transition,
+                ));
+            }
+        }};
+        // For when a buffer changed without the user editing it, so which kind of
+        // buffer has focus does not matter.
+        (any $index: expr, $transition: expr) => {{
+            let transition: Option<EditedTransition> = $transition.into();
+
+            if let Some(transition) = transition {
+                state.view.edited_transitions.push((
+                    $index,
+                    transition,
+                ));
+            }
+        }};
         ($index: expr, $transition: expr) => {{
             // Since this may be an expression with side effects, 
             // we want this to be evaluated whether or not we want
@@ -569,6 +698,9 @@ pub fn update_and_render(state: &mut State, input: Input) -> UpdateAndRenderOutp
         CloseMenuIfAny => {
             close_menu_if_any!();
         }
+        Insert('\n') => text_buffer_call!(sync b, l {
+            mark_edited_transition!(current, b.insert_new_line(l));
+        }),
         Insert(c) => text_buffer_call!(sync b, l {
             mark_edited_transition!(current, b.insert(c, l));
         }),
@@ -584,6 +716,15 @@ pub fn update_and_render(state: &mut State, input: Input) -> UpdateAndRenderOutp
         Undo => text_buffer_call!(sync b, l {
             mark_edited_transition!(current, b.undo(l));
         }),
+        PreviousUndoBranch => text_buffer_call!(sync b, l {
+            mark_edited_transition!(current, b.previous_undo_branch(l));
+        }),
+        NextUndoBranch => text_buffer_call!(sync b, l {
+            mark_edited_transition!(current, b.next_undo_branch(l));
+        }),
+        SetUndoTreeShown(shown) => {
+            state.undo_tree_shown = shown;
+        }
         MoveAllCursors(r#move) => {
             text_buffer_call!(b{
                 b.move_all_cursors(r#move);
@@ -626,11 +767,11 @@ pub fn update_and_render(state: &mut State, input: Input) -> UpdateAndRenderOutp
         SetCursor(xy, replace_or_add) => {
             let char_dim = state.get_current_char_dim();
             text_buffer_call!(b{
-                let position = text_space_to_position(
+                let position = b.display_to_text_position(text_space_to_position(
                     text_box_to_text(xy, b.scroll),
                     char_dim,
                     PositionRound::Up,
-                );
+                ));
 
                 b.set_cursor(position, replace_or_add);
             });
@@ -638,11 +779,11 @@ pub fn update_and_render(state: &mut State, input: Input) -> UpdateAndRenderOutp
         DragCursors(xy) => {
             let char_dim = state.get_current_char_dim();
             text_buffer_call!(b{
-                let position = text_space_to_position(
+                let position = b.display_to_text_position(text_space_to_position(
                     text_box_to_text(xy, b.scroll),
                     char_dim,
                     PositionRound::Up,
-                );
+                ));
                 // In practice we currently expect this to be sent only immeadately after an
                 // `Input::SetCursors` input, so there will be only one cursor. But it seems like
                 // we might as well just do it to all the cursors
@@ -723,10 +864,38 @@ pub fn update_and_render(state: &mut State, input: Input) -> UpdateAndRenderOutp
                 mark_edited_transition!(current, b.tab_out(l));
             });
         }
+        ToggleComments => text_buffer_call!(sync b, l {
+            let comment_start = l.as_ref().and_then(|l| l.parser_kind.line_comment_start());
+            if let Some(comment_start) = comment_start {
+                mark_edited_transition!(current, b.toggle_comments(comment_start, l));
+            }
+        }),
+        SwapLinesUp => text_buffer_call!(sync b, l {
+            mark_edited_transition!(current, b.swap_lines(SwapDirection::Up, l));
+        }),
+        SwapLinesDown => text_buffer_call!(sync b, l {
+            mark_edited_transition!(current, b.swap_lines(SwapDirection::Down, l));
+        }),
+        SwapSyntaxNodeUp => text_buffer_call!(sync b, l {
+            mark_edited_transition!(current, b.swap_syntax_nodes(SwapDirection::Up, l));
+        }),
+        SwapSyntaxNodeDown => text_buffer_call!(sync b, l {
+            mark_edited_transition!(current, b.swap_syntax_nodes(SwapDirection::Down, l));
+        }),
+        ExtractFunction => text_buffer_call!(sync b, l {
+            mark_edited_transition!(current, b.extract_function(l));
+        }),
+        ExtractVariable => text_buffer_call!(sync b, l {
+            mark_edited_transition!(current, b.extract_variable(false, l));
+        }),
+        ExtractVariableEverywhere => text_buffer_call!(sync b, l {
+            mark_edited_transition!(current, b.extract_variable(true, l));
+        }),
         AddOrSelectBuffer(name, str) => {
             perf_viz::record_guard!("AddOrSelectBuffer");
             let edited_transition_opt = state.buffers.add_or_select_buffer(name, str);
             state.current_buffer_kind = BufferIdKind::Text;
+            state.move_to_pending_cursor_position();
 
             buffer_view_sync!();
             if let Some(edited_transition) = edited_transition_opt {
@@ -747,22 +916,13 @@ pub fn update_and_render(state: &mut State, input: Input) -> UpdateAndRenderOutp
             }
         }
         OpenOrSelectBuffer(path) => {
-            if let Some(id) = state
-                .buffers
-                .iter_with_indexes()
-                .find(|(_, b)| match &b.name {
-                    BufferName::Path(p) => *p == path,
-                    BufferName::Scratch(_) => false,
-                })
-                .map(|(i, _)| b_id!(BufferIdKind::Text, i))
-            {
-                state.set_id(id);
-                close_menu_if_any!();
-            } else {
-                cmd = Cmd::LoadFile(path);
-                // No need to mark the edited transition here since we will do
-                // that in the `AddOrSelectBuffer` case when the file actualy arrives.
-            }
+            state.pending_cursor_position = Option::None;
+            cmd = state.open_or_select_buffer(path);
+        }
+        OpenOrSelectBufferAt(path, position) => {
+            state.pending_cursor_position = Some((path.clone(), position));
+            cmd = state.open_or_select_buffer(path);
+            try_to_show_cursors!();
         }
         CloseBuffer(index) => {
             state.close_buffer(index);
@@ -783,10 +943,17 @@ pub fn update_and_render(state: &mut State, input: Input) -> UpdateAndRenderOutp
                     Option::None
                 };
 
+                let was_find_replace = state.find_replace_mode().is_some();
+                let mode_changed = state.menu_mode != mode;
+
                 state.set_menu_mode(mode);
 
                 selection = match (selection, mode) {
                     (Option::None, _) => {Option::None}
+                    // The selection is what we are going to search in, so it is
+                    // unlikely to also be what the user wants to search for.
+                    (Some(_), MenuMode::FindReplace(_))
+                    if state.search_options.in_selection => {Option::None}
                     (Some(selection), MenuMode::GoToPosition) => {
                         if parse_for_go_to_position(&selection).is_err() {
                             Option::None
@@ -825,6 +992,23 @@ pub fn update_and_render(state: &mut State, input: Input) -> UpdateAndRenderOutp
                     // We don't need to make sure a cursor is visible here since the user
                     // will understand where the cursor is.
                 });
+
+                if let (MenuMode::FindReplace(_), false) = (mode, was_find_replace) {
+                    state.buffers.get_current_buffer_mut().capture_search_selections(
+                        (&state.find).into(),
+                        state.search_options,
+                    );
+                }
+
+                if mode_changed {
+                    // Results from a previous search are not kept when switching modes,
+                    // since they might be from another directory, or be out of date.
+                    state.find_results = d!();
+                    state.find_in_paths_key = Option::None;
+                    if let MenuMode::FindReplace(FindReplaceMode::OpenBuffers) = mode {
+                        buffer_view_sync!();
+                    }
+                }
             }
         }
         NextLanguage => {
@@ -839,16 +1023,41 @@ pub fn update_and_render(state: &mut State, input: Input) -> UpdateAndRenderOutp
                 Some(FindReplaceMode::CurrentFile) => {
                     state.buffers.get_current_buffer_mut().advance_or_refresh_search_results(
                         (&state.find).into(),
+                        state.search_options,
                     );
                     
                     try_to_show_cursors!(BufferIdKind::Text);
                     try_to_show_cursors!();
                 }
+                Some(FindReplaceMode::OpenBuffers) => {
+                    buffer_view_sync!();
+                }
+                Some(FindReplaceMode::Directory) => {
+                    cmd = Cmd::LoadDirectoryFiles(state.search_directory());
+                }
+            },
+            BufferIdKind::Replace => match state.find_replace_mode() {
+                Option::None => {
+                    debug_assert!(false, "state.find_replace_mode() returned None");
+                }
+                Some(FindReplaceMode::CurrentFile) => {
+                    let replacement: String = state.replace.borrow_rope().into();
+                    mark_edited_transition!(
+                        current text,
+                        state.buffers.get_current_buffer_mut().replace_current_search_result(
+                            (&state.find).into(),
+                            state.search_options,
+                            replacement,
+                            &mut state.parsers,
+                        )
+                    );
+
+                    try_to_show_cursors!(BufferIdKind::Text);
+                    try_to_show_cursors!();
+                }
+                // Replacing in more than one file is not supported yet.
+                Some(FindReplaceMode::OpenBuffers) | Some(FindReplaceMode::Directory) => {}
             },
-            BufferIdKind::Replace => {
-                let i = state.buffers.current_index();
-                dbg!("TODO BufferIdKind::Replace {}", i);
-            }
             BufferIdKind::GoToPosition => {
                 text_buffer_call!(b{
                     let input: String = b.into();
@@ -864,6 +1073,100 @@ pub fn update_and_render(state: &mut State, input: Input) -> UpdateAndRenderOutp
                 post_edit_sync!();
             }
         },
+        ReplaceAll => match state.find_replace_mode() {
+            Option::None => {}
+            Some(FindReplaceMode::CurrentFile) => {
+                let replacement: String = state.replace.borrow_rope().into();
+                mark_edited_transition!(
+                    current text,
+                    state.buffers.get_current_buffer_mut().replace_all_search_results(
+                        (&state.find).into(),
+                        state.search_options,
+                        replacement,
+                        &mut state.parsers,
+                    )
+                );
+
+                try_to_show_cursors!(BufferIdKind::Text);
+            }
+            // Replacing in more than one file is not supported yet.
+            Some(FindReplaceMode::OpenBuffers) | Some(FindReplaceMode::Directory) => {}
+        },
+        ToggleRegexSearch => {
+            state.search_options.kind = state.search_options.kind.toggle_regex();
+            buffer_view_sync!();
+        }
+        CycleSearchCaseSensitivity => {
+            state.search_options.case = state.search_options.case.cycle();
+            buffer_view_sync!();
+        }
+        ToggleWholeWordSearch => {
+            state.search_options.whole_word = !state.search_options.whole_word;
+            buffer_view_sync!();
+        }
+        ToggleSearchInSelection => {
+            state.search_options.in_selection = !state.search_options.in_selection;
+            buffer_view_sync!();
+        }
+        SearchFiles(files) => {
+            // The files may arrive after the user has moved on to something else.
+            if let Some(FindReplaceMode::Directory) = state.find_replace_mode() {
+                let ropes: Vec<(PathBuf, Rope)> = files
+                    .into_iter()
+                    .map(|(path, s)| (path, Rope::from(s)))
+                    .collect();
+
+                state.find_results = find_in_texts(
+                    ropes.iter().map(|(path, rope)| (path, rope)),
+                    (&state.find).into(),
+                    state.search_options,
+                );
+            }
+        }
+        SerializeBufferHistories => {
+            cmd = Cmd::StoreBufferHistories(state.buffers.serialize_histories());
+        }
+        RestoreBufferHistory(name, serialized) => {
+            if let Some((index, transition)) = state.buffers.restore_history(&name, &serialized) {
+                mark_edited_transition!(any index, transition);
+            }
+        }
+        ReloadBuffer(index, s) => {
+            if let Some(transition) = state.buffers.reload(index, s, &mut state.parsers) {
+                mark_edited_transition!(any index, transition);
+            }
+            buffer_view_sync!();
+        }
+        SetOnDiskText(name, s) => {
+            if let Some((index, transition)) = state.buffers.set_on_disk_text(&name, &s) {
+                mark_edited_transition!(any index, transition);
+            }
+        }
+        Fold => {
+            text_buffer_call!(sync b, l {
+                let candidates = l
+                    .map(|l| l.parsers.get_fold_candidates(l.buffer_name, l.parser_kind))
+                    .unwrap_or_default();
+                b.fold(&candidates);
+            });
+            try_to_show_cursors!();
+        }
+        Unfold => {
+            text_buffer_call!(b.unfold());
+            try_to_show_cursors!();
+        }
+        JumpToMatchingBrackets => {
+            text_buffer_call!(sync b, l {
+                b.jump_to_matching_brackets(l);
+            });
+            try_to_show_cursors!();
+        }
+        SelectInsideBrackets => {
+            text_buffer_call!(sync b, l {
+                b.select_inside_brackets(l);
+            });
+            try_to_show_cursors!();
+        }
     }
 
     // updates the view
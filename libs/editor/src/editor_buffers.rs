@@ -3,10 +3,15 @@
 /// improperly set.
 use editor_types::{Cursor};
 use g_i::{SelectableVec1};
-use macros::{d, dbg, fmt_debug, u};
+use macros::{d, dbg, fmt_debug, some_or, u};
 use platform_types::*;
 use parsers::{ParserKind, Parsers};
-use text_buffer::{Editedness, TextBuffer};
+use text_buffer::{
+    Editedness,
+    ParserEditListener,
+    PossibleEditedTransition,
+    TextBuffer,
+};
 use search::{SearchResults};
 use panic_safe_rope::{RopeSlice, RopeSliceTrait};
 
@@ -87,12 +92,12 @@ impl EditorBuffer {
         );
     }
 
-    pub fn advance_or_refresh_search_results(&mut self, needle: RopeSlice) {
-        if needle == self.search_results.needle {
+    pub fn advance_or_refresh_search_results(&mut self, needle: RopeSlice, options: SearchOptions) {
+        if needle == self.search_results.needle && options == self.search_results.options {
             self.advance_to_next_search_result(needle);
         } else {
             dbg!("advance_or_refresh_search_results");
-            self.refresh_search_results(needle);
+            self.refresh_search_results(needle, options);
             self.advance_to_next_search_result(needle);
         }
     }
@@ -119,11 +124,116 @@ impl EditorBuffer {
         }
     }
 
-    pub fn refresh_search_results(&mut self, needle: RopeSlice) {
+    /// Remembers what the cursors currently have selected, so that searches with
+    /// `in_selection` set are restricted to that text.
+    pub fn capture_search_selections(&mut self, needle: RopeSlice, options: SearchOptions) {
+        self.text_buffer.capture_search_selections();
+
+        self.refresh_search_results(needle, options);
+    }
+
+    pub fn refresh_search_results(&mut self, needle: RopeSlice, options: SearchOptions) {
         self.search_results.refresh(
             needle,
+            options,
+            self.text_buffer.search_selections().to_vec(),
             self.text_buffer.borrow_rope()
         );
+    }
+
+    /// Replaces the current search result with `replacement`, then selects the next
+    /// search result after the replacement, if there is one.
+    pub fn replace_current_search_result(
+        &mut self,
+        needle: RopeSlice,
+        options: SearchOptions,
+        replacement: String,
+        parsers: &mut Parsers,
+    ) -> PossibleEditedTransition {
+        if needle != self.search_results.needle || options != self.search_results.options {
+            self.refresh_search_results(needle, options);
+        }
+
+        let range = *some_or!(
+            self.search_results.ranges.get(self.search_results.current_range),
+            return None
+        );
+
+        let transition = self.replace_ranges(vec1![range], &replacement, parsers);
+
+        self.refresh_search_results(needle, options);
+
+        // The replacement might contain the needle, so we look for the first result
+        // after the replacement, rather than assuming the current index is correct.
+        let after_replacement = self.text_buffer.borrow_cursors().last().get_position();
+        let search_results = &mut self.search_results;
+        search_results.current_range = search_results.ranges
+            .iter()
+            .position(|(start, _)| *start >= after_replacement)
+            .unwrap_or(0);
+
+        if let Some(pair) = search_results.ranges.get(search_results.current_range) {
+            let c: Cursor = pair.into();
+            self.text_buffer.set_cursor(c, ReplaceOrAdd::Replace);
+        }
+
+        transition
+    }
+
+    /// Replaces every search result with `replacement`, as a single undoable edit.
+    pub fn replace_all_search_results(
+        &mut self,
+        needle: RopeSlice,
+        options: SearchOptions,
+        replacement: String,
+        parsers: &mut Parsers,
+    ) -> PossibleEditedTransition {
+        self.refresh_search_results(needle, options);
+
+        // Search results can overlap, for example "aa" in "aaa", and we only want to
+        // replace text that was actually part of a single result.
+        let mut ranges: Vec<(Position, Position)> = Vec::with_capacity(
+            self.search_results.ranges.len()
+        );
+        for &(start, end) in self.search_results.ranges.iter() {
+            match ranges.last() {
+                Some(&(_, previous_end)) if start < previous_end => {}
+                _ => ranges.push((start, end)),
+            }
+        }
+
+        let ranges = some_or!(Vec1::try_from_vec(ranges).ok(), return None);
+
+        let transition = self.replace_ranges(ranges, &replacement, parsers);
+
+        self.refresh_search_results(needle, options);
+
+        transition
+    }
+
+    fn replace_ranges(
+        &mut self,
+        ranges: Vec1<(Position, Position)>,
+        replacement: &str,
+        parsers: &mut Parsers,
+    ) -> PossibleEditedTransition {
+        // This lets regex searches fill in capture groups from the text being replaced.
+        let replacements = self.search_results.get_replacements(
+            self.text_buffer.borrow_rope(),
+            &ranges,
+            replacement,
+        );
+
+        let parser_kind = self.get_parser_kind();
+        self.text_buffer.replace_ranges_with(
+            ranges,
+            |i| replacements.get(i).cloned().unwrap_or_default(),
+            Some(ParserEditListener {
+                buffer_name: &self.name,
+                parser_kind,
+                parsers,
+            })
+        )
     }
 }
 
@@ -280,10 +390,14 @@ impl EditorBuffers {
     }
 
     /// Sets the path and marks the buffer as unedited iff such a buffer exists.
-    /// Returns `Some` iff changes were made.
+    /// Returns `Some` iff changes were made. A hex view stays a hex view, now of the
+    /// file at `path`, since it was saved as the bytes it shows.
     pub fn saved_as(&mut self, index: g_i::Index, path: PathBuf) -> Option<()> {
         if let Some(b) = self.buffers.get_mut(index) {
-            (*b).name = BufferName::Path(path);
+            (*b).name = match &b.name {
+                BufferName::Hex(_) => BufferName::Hex(path),
+                _ => BufferName::Path(path),
+            };
             b.text_buffer.set_unedited();
             Some(())
         } else {
@@ -304,6 +418,122 @@ impl EditorBuffers {
     pub fn buffers(&self) -> &SelectableVec1<EditorBuffer> {
         &self.buffers
     }
+
+    pub fn serialize_histories(&self) -> Vec<(BufferName, String)> {
+        self.buffers
+            .iter()
+            .map(|b| (b.name.clone(), b.text_buffer.serialize_history()))
+            .collect()
+    }
+
+    /// Returns the index of the buffer with the given name, if there is one, along
+    /// with the edited transition, if restoring the history caused one. If the history
+    /// cannot be restored, then the buffer is left with just its text.
+    pub fn restore_history(
+        &mut self,
+        name: &BufferName,
+        serialized: &str,
+    ) -> Option<(g_i::Index, PossibleEditedTransition)> {
+        let index = self.index_with_name(name)?;
+        let buffer = self.buffers.get_mut(index)?;
+
+        let transition = match buffer.text_buffer.restore_history(serialized) {
+            Ok(transition) => transition,
+            Err(e) => {
+                dbg!(name, e);
+                None
+            }
+        };
+
+        Some((index, transition))
+    }
+
+    /// Replaces the text of the buffer at `index` with `s`, keeping its cursors near
+    /// where they were. Returns `None` iff there is no buffer at `index`.
+    pub fn reload(
+        &mut self,
+        index: g_i::Index,
+        s: String,
+        parsers: &mut Parsers,
+    ) -> Option<PossibleEditedTransition> {
+        let buffer = self.buffers.get_mut(index)?;
+        let parser_kind = buffer.get_parser_kind();
+
+        Some(buffer.text_buffer.reload(
+            s,
+            Some(ParserEditListener {
+                buffer_name: &buffer.name,
+                parser_kind,
+                parsers,
+            })
+        ))
+    }
+
+    /// Records that the file the buffer named `name` shows now contains `s`, as that
+    /// buffer would show it. Returns the index of that buffer, if there is one, along
+    /// with the edited transition, if that caused one.
+    pub fn set_on_disk_text(
+        &mut self,
+        name: &BufferName,
+        s: &str,
+    ) -> Option<(g_i::Index, PossibleEditedTransition)> {
+        let index = self.index_with_name(name)?;
+        let buffer = self.buffers.get_mut(index)?;
+
+        Some((index, buffer.text_buffer.set_unedited_text(s)))
+    }
+
+    /// Searches every buffer that has a path. Scratch buffers are skipped since
+    /// a result is identified by its path. Hex views are skipped since opening a
+    /// result opens the file as text.
+    pub fn find_in_paths(&self, needle: RopeSlice, options: SearchOptions) -> FindResults {
+        find_in_texts(
+            self.buffers.iter().filter_map(|b| match &b.name {
+                BufferName::Path(p) => Some((p, b.text_buffer.borrow_rope())),
+                BufferName::Hex(_) | BufferName::Scratch(_) => None,
+            }),
+            needle,
+            options,
+        )
+    }
+
+    /// Returns everything that the results of `find_in_paths` depend on, so a search
+    /// only needs to be done again if this changes.
+    pub fn find_in_paths_key(&self, needle: RopeSlice, options: SearchOptions) -> FindInPathsKey {
+        FindInPathsKey {
+            needle: needle.into(),
+            options,
+            buffers: self.buffers.iter().filter_map(|b| match &b.name {
+                BufferName::Path(p) => Some((p.clone(), b.text_buffer.version())),
+                BufferName::Hex(_) | BufferName::Scratch(_) => None,
+            }).collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FindInPathsKey {
+    needle: String,
+    options: SearchOptions,
+    buffers: Vec<(PathBuf, u64)>,
+}
+
+/// Returns the start of every match of `needle` in each of the texts, in order.
+pub fn find_in_texts<'text, I>(texts: I, needle: RopeSlice, options: SearchOptions) -> FindResults
+where
+    I: IntoIterator<Item = (&'text PathBuf, &'text Rope)>,
+{
+    let mut output = Vec::new();
+
+    for (path, haystack) in texts {
+        let search_results = SearchResults::new(needle, options, haystack);
+
+        for &(start, _) in search_results.ranges.iter() {
+            output.push(FindResult::new(path.clone(), start));
+        }
+    }
+
+    output
 }
 
 impl EditorBuffers {
@@ -336,7 +566,7 @@ fn create_text_buffer<I: Into<TextBuffer> + Into<String>>(name: &BufferName, s:
 
             buffer
         }
-        Path(_) => {
+        Path(_) | Hex(_) => {
             s.into()
         }
     }
@@ -369,6 +599,8 @@ pub mod tests {
             ) -> SearchResults {
                 SearchResults {
                     needle,
+                    options: d!(),
+                    selections: d!(),
                     ranges,
                     current_range,
                 }
@@ -13,6 +13,8 @@ pub fn render(
         ref mut buffers,
         font_info: FontInfo { text_char_dim, .. },
         menu_mode,
+        search_options,
+        ref find_results,
         ref file_switcher_results,
         ref file_switcher,
         ref find,
@@ -22,6 +24,7 @@ pub fn render(
             xy: text_box_pos, ..
         },
         ref mut parsers,
+        undo_tree_shown,
         ref mut view,
         ..
     } = state;
@@ -113,6 +116,15 @@ pub fn render(
     }
 
     perf_viz::end_record!("write view.status_line");
+    perf_viz::start_record!("write view.undo_tree_drawing");
+
+    if undo_tree_shown {
+        view.undo_tree_drawing = buffer.undo_tree_drawing();
+    } else {
+        view.undo_tree_drawing.clear();
+    }
+
+    perf_viz::end_record!("write view.undo_tree_drawing");
     perf_viz::start_record!("set view.menu");
 
     {
@@ -122,6 +134,7 @@ pub fn render(
             MenuMode::Hidden => MenuView::None,
             MenuMode::FindReplace(mode) => MenuView::FindReplace(FindReplaceView {
                 mode,
+                search_options,
                 find: text_buffer_to_buffer_view_data(
                     &find,
                     FIND_REPLACE_AVERAGE_SELECTION_LINES_ESTIMATE,
@@ -130,7 +143,12 @@ pub fn render(
                     &replace,
                     FIND_REPLACE_AVERAGE_SELECTION_LINES_ESTIMATE,
                 ),
-                result_count: search_results.ranges.len(),
+                result_count: match mode {
+                    FindReplaceMode::CurrentFile => search_results.ranges.len(),
+                    FindReplaceMode::OpenBuffers
+                    | FindReplaceMode::Directory => find_results.len(),
+                },
+                results: find_results.clone(),
             }),
             MenuMode::FileSwitcher => {
                 const FILE_SEARCH_SELECTION_LINES_ESTIMATE: usize = 1;
@@ -186,6 +204,7 @@ fn text_buffer_to_buffer_view_data(
         chars: buffer.clone_rope(),
         cursors,
         highlights,
+        folds: buffer.shown_folds(),
         ..d!()
     }
 }
@@ -202,7 +221,8 @@ fn editor_to_buffer_view_data(
 
     perf_viz::start_record!("parsers.get_spans");
     buffer_view_data.spans = parsers.get_spans(
-        buffer_view_data.chars.clone().into(),
+        editor_buffer.text_buffer.borrow_rope(),
+        editor_buffer.text_buffer.version(),
         &buffer_name,
         editor_buffer.get_parser_kind()
     );
@@ -223,6 +243,24 @@ fn editor_to_buffer_view_data(
         };
         push_highlights(highlights, p1, p2, kind);
     }
+
+    let bracket_positions = editor_buffer.text_buffer.bracket_match_positions(
+        parsers,
+        &buffer_name,
+        editor_buffer.get_parser_kind(),
+    );
+    for (bracket, matching) in bracket_positions {
+        let one_char = |p: Position| (p, Position { offset: p.offset + 1, ..p });
+        match matching {
+            Some(matching) => {
+                highlights.push(Highlight::new(one_char(bracket), HighlightKind::MatchingBracket));
+                highlights.push(Highlight::new(one_char(matching), HighlightKind::MatchingBracket));
+            }
+            None => {
+                highlights.push(Highlight::new(one_char(bracket), HighlightKind::UnmatchedBracket));
+            }
+        }
+    }
     perf_viz::end_record!("push all highlights");
 
     buffer_view_data
@@ -280,7 +280,7 @@ fn the_right_spans_are_set_after_typing_fn_below_this_fn_def() {
 
     let mut parsers = Parsers::default();
 
-    let parser_kind = Rust(Extra);
+    let parser_kind = Tree(parsers::RUST, Extra);
 
     let mut text_buffer = TextBuffer::from("fn foo() {}\n");
 
@@ -317,7 +317,12 @@ fn the_right_spans_are_set_after_typing_fn_below_this_fn_def() {
         );
     
         assert_eq!(
-            parsers.get_spans(text_buffer.borrow_rope().into(), &buffer_name, parser_kind),
+            parsers.get_spans(
+                text_buffer.borrow_rope(),
+                text_buffer.version(),
+                &buffer_name,
+                parser_kind,
+            ),
             expected_spans,
             "added \\n"
         );
@@ -358,7 +363,12 @@ fn the_right_spans_are_set_after_typing_fn_below_this_fn_def() {
         // simplest way to check both of those properties, but it does slightly 
         // over-assert.
         assert_eq!(
-            parsers.get_spans(text_buffer.borrow_rope().into(), &buffer_name, parser_kind),
+            parsers.get_spans(
+                text_buffer.borrow_rope(),
+                text_buffer.version(),
+                &buffer_name,
+                parser_kind,
+            ),
             expected_spans,
             "added f"
         );
@@ -395,7 +405,12 @@ fn the_right_spans_are_set_after_typing_fn_below_this_fn_def() {
         );
 
         assert_eq!(
-            parsers.get_spans(text_buffer.borrow_rope().into(), &buffer_name, parser_kind),
+            parsers.get_spans(
+                text_buffer.borrow_rope(),
+                text_buffer.version(),
+                &buffer_name,
+                parser_kind,
+            ),
             vec![
                 sv!(i 2 k PLAIN),
                 sv!(i 6 k 3),
@@ -413,7 +428,7 @@ fn the_right_spans_are_set_after_typing_fn_below_this_fn_def_reduction() {
 
     let mut parsers = Parsers::default();
 
-    let parser_kind = Rust(Extra);
+    let parser_kind = Tree(parsers::RUST, Extra);
 
     let mut text_buffer = TextBuffer::from("fn foo() {}\n");
 
@@ -450,7 +465,12 @@ fn the_right_spans_are_set_after_typing_fn_below_this_fn_def_reduction() {
         );
     
         assert_eq!(
-            parsers.get_spans(text_buffer.borrow_rope().into(), &buffer_name, parser_kind),
+            parsers.get_spans(
+                text_buffer.borrow_rope(),
+                text_buffer.version(),
+                &buffer_name,
+                parser_kind,
+            ),
             expected_spans,
             "added \\n"
         );
@@ -491,10 +511,42 @@ fn the_right_spans_are_set_after_typing_fn_below_this_fn_def_reduction() {
         // simplest way to check both of those properties, but it does slightly 
         // over-assert.
         assert_eq!(
-            parsers.get_spans(text_buffer.borrow_rope().into(), &buffer_name, parser_kind),
+            parsers.get_spans(
+                text_buffer.borrow_rope(),
+                text_buffer.version(),
+                &buffer_name,
+                parser_kind,
+            ),
             expected_spans,
             "added f"
         );
     }
 }
 
+
+#[test]
+fn pasting_with_as_many_cursors_as_were_copied_from_pastes_each_selection_at_its_cursor() {
+    let mut buffer = TextBuffer::from("1\n2\n3");
+    buffer.set_cursor(cur!{l 0 o 0 h l 0 o 1}, ReplaceOrAdd::Replace);
+    buffer.set_cursor(cur!{l 1 o 0 h l 1 o 1}, ReplaceOrAdd::Add);
+    buffer.set_cursor(cur!{l 2 o 0 h l 2 o 1}, ReplaceOrAdd::Add);
+
+    let mut clipboard_history = ClipboardHistory::default();
+
+    let copied = clipboard_history.copy(&buffer);
+    assert_eq!(copied.as_deref(), Some("1\n2\n3"));
+
+    buffer.move_all_cursors(Move::ToLineEnd);
+
+    // This is what we get back from the system clipboard.
+    clipboard_history.paste(&mut buffer, copied, None);
+
+    assert_eq!(buffer.borrow_rope(), "11\n22\n33");
+
+    // With a different amount of cursors we paste all of it at each one.
+    buffer.set_cursor(pos!{l 0 o 0}, ReplaceOrAdd::Replace);
+
+    clipboard_history.paste(&mut buffer, None, None);
+
+    assert_eq!(buffer.borrow_rope(), "1\n2\n311\n22\n33");
+}
@@ -306,6 +306,40 @@ fn retains_the_path_buffer_data_if_the_name_does_not_match_after_an_insert_then_
     assert_eq!(first_editor_buffer_char(&state).unwrap(), 'a');
 }
 
+#[test]
+fn a_hex_view_saved_as_a_new_file_stays_a_hex_view_of_that_file() {
+    u!{Input}
+    let mut state: State = d!();
+    update_and_render(
+        &mut state,
+        AddOrSelectBuffer(BufferName::Hex("a.bin".into()), "00000000  61\n".to_owned())
+    );
+    let index = state.buffers.current_index();
+
+    update_and_render(&mut state, SavedAs(index, "b.bin".into()));
+
+    assert_eq!(
+        state.buffers.get_current_buffer().name,
+        BufferName::Hex("b.bin".into())
+    );
+}
+
+#[test]
+fn only_draws_the_undo_tree_while_it_is_shown() {
+    u!{Input}
+    let mut state: State = d!();
+
+    let (view, _) = update_and_render(&mut state, Insert('a'));
+    assert_eq!(view.undo_tree_drawing, "");
+
+    let (view, _) = update_and_render(&mut state, SetUndoTreeShown(true));
+    assert_eq!(view.undo_tree_drawing, state.buffers.get_current_buffer().text_buffer.undo_tree_drawing());
+    assert_ne!(view.undo_tree_drawing, "");
+
+    let (view, _) = update_and_render(&mut state, SetUndoTreeShown(false));
+    assert_eq!(view.undo_tree_drawing, "");
+}
+
 fn keeps_the_state_buffers_index_state_the_same_as_the_view_buffers_index_state_on(
     mut state: State,
     inputs: Vec<Input>,
@@ -556,6 +590,449 @@ fn does_not_allow_clients_to_enter_text_into_the_find_field_outside_of_find_mode
     assert_eq!(first_char(&state.find), Option::None);
 }
 
+fn find_and_replace_in_current_file(state: &mut State, needle: &str, replacement: &str) {
+    u!{BufferIdKind, Input}
+    update_and_render(state, SetMenuMode(MenuMode::FindReplace(FindReplaceMode::CurrentFile)));
+
+    for c in needle.chars() {
+        update_and_render(state, Insert(c));
+    }
+
+    update_and_render(state, SelectBuffer(b_id!(Replace, state.buffers.current_index())));
+
+    for c in replacement.chars() {
+        update_and_render(state, Insert(c));
+    }
+}
+
+#[test]
+fn replace_all_replaces_every_result_in_this_case() {
+    let mut state: State = "abc abc\nabc".into();
+
+    find_and_replace_in_current_file(&mut state, "abc", "xy");
+
+    update_and_render(&mut state, Input::ReplaceAll);
+
+    assert_eq!(String::from(first_editor_buffer(&state)), "xy xy\nxy");
+}
+
+#[test]
+fn replace_all_can_be_undone_in_a_single_step_in_this_case() {
+    u!{BufferIdKind, Input}
+    const INITIAL: &str = "abc abc\nabc";
+    let mut state: State = INITIAL.into();
+
+    find_and_replace_in_current_file(&mut state, "abc", "xy");
+
+    update_and_render(&mut state, ReplaceAll);
+    let index = state.buffers.current_index();
+    update_and_render(&mut state, SelectBuffer(b_id!(Text, index)));
+    update_and_render(&mut state, Undo);
+
+    assert_eq!(String::from(first_editor_buffer(&state)), INITIAL);
+}
+
+#[test]
+fn replace_all_replaces_only_non_overlapping_results_in_this_case() {
+    let mut state: State = "aaa".into();
+
+    find_and_replace_in_current_file(&mut state, "aa", "b");
+
+    update_and_render(&mut state, Input::ReplaceAll);
+
+    assert_eq!(String::from(first_editor_buffer(&state)), "ba");
+}
+
+#[test]
+fn replace_all_replaces_touching_results_separately_in_this_case() {
+    let mut state: State = "abab".into();
+
+    find_and_replace_in_current_file(&mut state, "ab", "x");
+
+    update_and_render(&mut state, Input::ReplaceAll);
+
+    assert_eq!(String::from(first_editor_buffer(&state)), "xx");
+}
+
+#[test]
+fn submitting_the_replace_form_replaces_one_result_at_a_time_in_this_case() {
+    let mut state: State = "abc abc".into();
+
+    find_and_replace_in_current_file(&mut state, "abc", "x");
+
+    update_and_render(&mut state, Input::SubmitForm);
+
+    assert_eq!(String::from(first_editor_buffer(&state)), "x abc");
+
+    update_and_render(&mut state, Input::SubmitForm);
+
+    assert_eq!(String::from(first_editor_buffer(&state)), "x x");
+}
+
+#[test]
+fn replace_all_reports_an_edited_transition_for_the_text_buffer_in_this_case() {
+    u!{Input}
+    let mut state: State = d!();
+    update_and_render(
+        &mut state,
+        AddOrSelectBuffer(BufferName::Path(".fakefile".into()), "abc abc".to_owned())
+    );
+
+    find_and_replace_in_current_file(&mut state, "abc", "x");
+
+    let (view, _) = update_and_render(&mut state, ReplaceAll);
+
+    assert_eq!(
+        view.edited_transitions.into_iter().collect::<Vec<_>>(),
+        vec![(state.buffers.current_index(), EditedTransition::ToEdited)]
+    );
+}
+
+#[test]
+fn replace_all_expands_capture_groups_after_toggling_regex_search_in_this_case() {
+    let mut state: State = "a = 1;\nbc = 23;".into();
+
+    update_and_render(&mut state, Input::ToggleRegexSearch);
+
+    find_and_replace_in_current_file(&mut state, "([a-z]+) = ([0-9]+)", "$2 = $1");
+
+    update_and_render(&mut state, Input::ReplaceAll);
+
+    assert_eq!(String::from(first_editor_buffer(&state)), "1 = a;\n23 = bc;");
+}
+
+#[test]
+fn toggling_regex_search_twice_goes_back_to_plain_search_in_this_case() {
+    let mut state: State = "a+ aa".into();
+
+    update_and_render(&mut state, Input::ToggleRegexSearch);
+    update_and_render(&mut state, Input::ToggleRegexSearch);
+
+    find_and_replace_in_current_file(&mut state, "a+", "b");
+
+    update_and_render(&mut state, Input::ReplaceAll);
+
+    assert_eq!(String::from(first_editor_buffer(&state)), "b aa");
+}
+
+#[test]
+fn replace_all_ignores_case_after_cycling_case_sensitivity_in_this_case() {
+    let mut state: State = "Abc abc ABC".into();
+
+    update_and_render(&mut state, Input::CycleSearchCaseSensitivity);
+
+    find_and_replace_in_current_file(&mut state, "abc", "x");
+
+    update_and_render(&mut state, Input::ReplaceAll);
+
+    assert_eq!(String::from(first_editor_buffer(&state)), "x x x");
+}
+
+#[test]
+fn replace_all_only_replaces_whole_words_after_toggling_whole_word_search_in_this_case() {
+    let mut state: State = "cat concat cats cat".into();
+
+    update_and_render(&mut state, Input::ToggleWholeWordSearch);
+
+    find_and_replace_in_current_file(&mut state, "cat", "dog");
+
+    update_and_render(&mut state, Input::ReplaceAll);
+
+    assert_eq!(String::from(first_editor_buffer(&state)), "dog concat cats dog");
+}
+
+#[test]
+fn replace_all_only_replaces_inside_the_selections_when_searching_in_selection_in_this_case() {
+    u!{ReplaceOrAdd}
+    let mut state: State = "ab ab ab ab".into();
+    let text_buffer = &mut state.buffers.get_current_buffer_mut().text_buffer;
+    text_buffer.set_cursor(cur!{l 0 o 5 h l 0 o 3}, Replace);
+    text_buffer.set_cursor(cur!{l 0 o 9 h l 0 o 11}, Add);
+
+    update_and_render(&mut state, Input::ToggleSearchInSelection);
+
+    find_and_replace_in_current_file(&mut state, "ab", "x");
+
+    update_and_render(&mut state, Input::ReplaceAll);
+
+    assert_eq!(String::from(first_editor_buffer(&state)), "ab x ab x");
+}
+
+#[test]
+fn the_selection_being_searched_in_grows_with_the_replacements_in_this_case() {
+    u!{ReplaceOrAdd}
+    let mut state: State = "aa aa aa".into();
+    state.buffers.get_current_buffer_mut()
+        .text_buffer
+        .set_cursor(cur!{l 0 o 0 h l 0 o 5}, Replace);
+
+    update_and_render(&mut state, Input::ToggleSearchInSelection);
+
+    find_and_replace_in_current_file(&mut state, "a", "aa");
+
+    update_and_render(&mut state, Input::ReplaceAll);
+    update_and_render(&mut state, Input::ReplaceAll);
+
+    assert_eq!(String::from(first_editor_buffer(&state)), "aaaaaaaa aaaaaaaa aa");
+}
+
+#[test]
+fn the_selection_being_searched_in_follows_edits_made_before_it_in_this_case() {
+    u!{Input}
+    let mut state: State = "ab ab".into();
+    update_and_render(&mut state, Paste(Some("zz ".to_owned())));
+    state.buffers.get_current_buffer_mut()
+        .text_buffer
+        .set_cursor(cur!{l 0 o 6 h l 0 o 8}, ReplaceOrAdd::Replace);
+
+    update_and_render(&mut state, ToggleSearchInSelection);
+
+    find_and_replace_in_current_file(&mut state, "ab", "x");
+
+    // The find box has focus, so we undo the paste directly.
+    state.buffers.get_current_buffer_mut().text_buffer.undo(Option::None);
+    update_and_render(&mut state, ReplaceAll);
+
+    assert_eq!(String::from(first_editor_buffer(&state)), "ab x");
+}
+
+#[test]
+fn the_find_replace_view_shows_the_current_search_options_in_this_case() {
+    let mut state: State = "abc".into();
+
+    update_and_render(&mut state, Input::ToggleWholeWordSearch);
+    update_and_render(&mut state, Input::CycleSearchCaseSensitivity);
+    update_and_render(&mut state, Input::CycleSearchCaseSensitivity);
+    let (view, _) = update_and_render(
+        &mut state,
+        Input::SetMenuMode(MenuMode::FindReplace(d!()))
+    );
+
+    match view.menu {
+        MenuView::FindReplace(FindReplaceView { search_options, .. }) => {
+            assert_eq!(
+                search_options,
+                SearchOptions {
+                    kind: SearchKind::Plain,
+                    case: CaseSensitivity::Smart,
+                    whole_word: true,
+                    in_selection: false,
+                }
+            );
+        }
+        menu => panic!("expected the find/replace menu, got {:?}", menu),
+    }
+}
+
+fn find_result_labels(view: &View) -> Vec<String> {
+    match &view.menu {
+        MenuView::FindReplace(FindReplaceView { results, .. }) => {
+            results.iter().map(|r| r.label.clone()).collect()
+        }
+        menu => panic!("expected the find/replace menu, got {:?}", menu),
+    }
+}
+
+#[test]
+fn finding_in_open_buffers_lists_results_from_each_buffer_with_a_path_in_this_case() {
+    u!{BufferName, Input}
+    let mut state: State = "ab".into();
+    update_and_render(&mut state, AddOrSelectBuffer(Path("a.txt".into()), "ab\nb ab".to_owned()));
+    update_and_render(&mut state, AddOrSelectBuffer(Path("b.txt".into()), "xab".to_owned()));
+
+    update_and_render(&mut state, SetMenuMode(MenuMode::FindReplace(FindReplaceMode::OpenBuffers)));
+    update_and_render(&mut state, Insert('a'));
+    let (view, _) = update_and_render(&mut state, Insert('b'));
+
+    assert_eq!(
+        find_result_labels(&view),
+        vec!["a.txt:1:1", "a.txt:2:3", "b.txt:1:2"]
+    );
+}
+
+#[test]
+fn opening_a_find_result_in_an_open_buffer_selects_it_and_moves_the_cursor_there_in_this_case() {
+    u!{BufferName, Input}
+    let mut state: State = d!();
+    update_and_render(&mut state, AddOrSelectBuffer(Path("a.txt".into()), "ab\nb ab".to_owned()));
+    let a_index = state.buffers.current_index();
+    update_and_render(&mut state, AddOrSelectBuffer(Path("b.txt".into()), "xab".to_owned()));
+    update_and_render(&mut state, SetMenuMode(MenuMode::FindReplace(FindReplaceMode::OpenBuffers)));
+
+    let (_, cmd) = update_and_render(
+        &mut state,
+        OpenOrSelectBufferAt("a.txt".into(), pos!{l 1 o 2})
+    );
+
+    assert!(matches!(cmd, Cmd::None), "{:?}", cmd);
+    assert_eq!(state.menu_mode, MenuMode::Hidden);
+    assert_eq!(state.buffers.current_index(), a_index);
+    assert_eq!(
+        *state.buffers.get_current_buffer().text_buffer.borrow_cursors().first(),
+        cur!{pos!{l 1 o 2}}
+    );
+}
+
+#[test]
+fn opening_a_find_result_in_an_unopened_file_moves_the_cursor_there_once_it_is_loaded_in_this_case() {
+    u!{BufferName, Input}
+    let mut state: State = d!();
+
+    let (_, cmd) = update_and_render(
+        &mut state,
+        OpenOrSelectBufferAt("a.txt".into(), pos!{l 1 o 2})
+    );
+
+    assert!(matches!(cmd, Cmd::LoadFile(ref p) if p == &PathBuf::from("a.txt")), "{:?}", cmd);
+
+    update_and_render(&mut state, AddOrSelectBuffer(Path("a.txt".into()), "ab\nb ab".to_owned()));
+
+    assert_eq!(
+        *state.buffers.get_current_buffer().text_buffer.borrow_cursors().first(),
+        cur!{pos!{l 1 o 2}}
+    );
+}
+
+#[test]
+fn finding_in_a_directory_searches_the_files_sent_back_by_the_platform_layer_in_this_case() {
+    u!{BufferName, Input}
+    let mut state: State = d!();
+    update_and_render(&mut state, AddOrSelectBuffer(Path("dir/a.txt".into()), "".to_owned()));
+
+    update_and_render(&mut state, SetMenuMode(MenuMode::FindReplace(FindReplaceMode::Directory)));
+    update_and_render(&mut state, Insert('a'));
+    let (_, cmd) = update_and_render(&mut state, SubmitForm);
+
+    assert!(
+        matches!(cmd, Cmd::LoadDirectoryFiles(ref p) if p == &PathBuf::from("dir")),
+        "{:?}",
+        cmd
+    );
+
+    let (view, _) = update_and_render(&mut state, SearchFiles(vec![
+        ("dir/a.txt".into(), "ba".to_owned()),
+        ("dir/sub/b.txt".into(), "\naa".to_owned()),
+    ]));
+
+    assert_eq!(
+        find_result_labels(&view),
+        vec!["dir/a.txt:1:2", "dir/sub/b.txt:2:1", "dir/sub/b.txt:2:2"]
+    );
+}
+
+#[test]
+fn restoring_serialized_histories_allows_undoing_edits_made_before_in_this_case() {
+    u!{BufferName, EditedTransition, Input}
+    let name = Path("a.txt".into());
+
+    let mut state: State = d!();
+    update_and_render(&mut state, AddOrSelectBuffer(name.clone(), "b".to_owned()));
+    update_and_render(&mut state, Insert('a'));
+
+    let (_, cmd) = update_and_render(&mut state, SerializeBufferHistories);
+
+    let histories = match cmd {
+        Cmd::StoreBufferHistories(histories) => histories,
+        cmd => panic!("expected Cmd::StoreBufferHistories, got {:?}", cmd),
+    };
+    let history = histories
+        .into_iter()
+        .find(|(n, _)| n == &name)
+        .map(|(_, history)| history)
+        .expect("no history for the buffer");
+
+    let mut restored_state: State = d!();
+    update_and_render(&mut restored_state, AddOrSelectBuffer(name.clone(), "ab".to_owned()));
+    let (view, _) = update_and_render(&mut restored_state, RestoreBufferHistory(name, history));
+
+    assert_eq!(
+        view.edited_transitions.into_iter().map(|(_, t)| t).collect::<Vec<_>>(),
+        vec![ToEdited]
+    );
+
+    let (view, _) = update_and_render(&mut restored_state, Undo);
+
+    assert_eq!(
+        String::from(view.buffers.get_current_element().data.chars.clone()),
+        "b"
+    );
+    assert_eq!(
+        view.edited_transitions.into_iter().map(|(_, t)| t).collect::<Vec<_>>(),
+        vec![ToUnedited]
+    );
+}
+
+#[test]
+fn restoring_a_serialized_history_reports_the_buffer_as_edited_while_a_menu_is_open_in_this_case() {
+    u!{BufferName, EditedTransition, Input}
+    let name = Path("a.txt".into());
+
+    let mut text_buffer: TextBuffer = "b".into();
+    text_buffer.insert('a', Option::None);
+    let history = text_buffer.serialize_history();
+
+    let mut state: State = d!();
+    update_and_render(&mut state, AddOrSelectBuffer(name.clone(), "ab".to_owned()));
+    update_and_render(&mut state, SetMenuMode(MenuMode::FileSwitcher));
+    let (view, _) = update_and_render(&mut state, RestoreBufferHistory(name, history));
+
+    assert_eq!(
+        view.edited_transitions.into_iter().map(|(_, t)| t).collect::<Vec<_>>(),
+        vec![ToEdited]
+    );
+}
+
+#[test]
+fn reloading_a_buffer_reports_it_as_unedited_while_a_menu_is_open_in_this_case() {
+    u!{BufferName, EditedTransition, Input}
+    let mut state: State = d!();
+    update_and_render(&mut state, AddOrSelectBuffer(Path("a.txt".into()), "b".to_owned()));
+    update_and_render(&mut state, Insert('a'));
+    let index = state.buffers.current_index();
+
+    update_and_render(&mut state, SetMenuMode(MenuMode::FileSwitcher));
+    let (view, _) = update_and_render(&mut state, ReloadBuffer(index, "c".to_owned()));
+
+    assert_eq!(
+        view.edited_transitions.into_iter().map(|(_, t)| t).collect::<Vec<_>>(),
+        vec![ToUnedited]
+    );
+}
+
+#[test]
+fn a_file_changing_on_disk_reports_the_buffer_as_edited_while_a_menu_is_open_in_this_case() {
+    u!{BufferName, EditedTransition, Input}
+    let mut state: State = d!();
+    update_and_render(&mut state, AddOrSelectBuffer(Path("a.txt".into()), "b".to_owned()));
+
+    update_and_render(&mut state, SetMenuMode(MenuMode::FileSwitcher));
+    let (view, _) = update_and_render(&mut state, SetOnDiskText(Path("a.txt".into()), "c".to_owned()));
+
+    assert_eq!(
+        view.edited_transitions.into_iter().map(|(_, t)| t).collect::<Vec<_>>(),
+        vec![ToEdited]
+    );
+}
+
+#[test]
+fn a_file_changing_on_disk_only_affects_the_buffer_with_that_name_in_this_case() {
+    u!{BufferName, EditedTransition, Input}
+    let mut state: State = d!();
+    update_and_render(&mut state, AddOrSelectBuffer(Path("a.bin".into()), "a".to_owned()));
+    update_and_render(&mut state, AddOrSelectBuffer(Hex("a.bin".into()), "00000000  61\n".to_owned()));
+
+    let (view, _) = update_and_render(
+        &mut state,
+        SetOnDiskText(Hex("a.bin".into()), "00000000  62\n".to_owned())
+    );
+
+    let hex_index = state.buffers.index_with_name(&Hex("a.bin".into())).unwrap();
+    assert_eq!(
+        view.edited_transitions.into_iter().collect::<Vec<_>>(),
+        vec![(hex_index, ToEdited)]
+    );
+}
+
 fn returns_edited_if_a_file_is_loaded_then_changed_to_a_different_string_on(
     s1: String, s2: String
 ) {
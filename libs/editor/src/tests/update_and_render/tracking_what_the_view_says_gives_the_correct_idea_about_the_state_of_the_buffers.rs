@@ -565,6 +565,19 @@ fn in_this_insert_numbers_at_cursors_case() {
     )
 }
 
+#[test]
+fn if_the_file_changes_on_disk_but_the_buffer_does_not() {
+    u!{BufferName, Input}
+    on(
+        d!(),
+        vec![
+            AddOrSelectBuffer(Path(".fakefile".into()), String::new()),
+            SetOnDiskText(Path(".fakefile".into()), "a".to_owned()),
+            Insert('a'),
+        ]
+    )
+}
+
 #[test]
 fn if_we_do_this_cut() {
     u!{BufferName, Input}
@@ -646,7 +659,7 @@ fn on(
                                 EditorBuffer::new(name.clone(), ""),
                             );
                         }
-                        Path(_) => {
+                        Path(_) | Hex(_) => {
                             initial_buffer_states.insert(
                                 index_state,
                                 state.buffers.buffers().append_index(),
@@ -684,6 +697,16 @@ fn on(
                     saved_as_names.push(Path(p.clone()));
                 }
             }
+            SetOnDiskText(ref name, ref data) => {
+                // The buffer is now compared with what is on disk, so we need to as well.
+                if let Some(index) = state.buffers.index_with_name(name) {
+                    initial_buffer_states.insert(
+                        index_state,
+                        index,
+                        EditorBuffer::new(name.clone(), data.clone()),
+                    );
+                }
+            }
 
             _ => {}
         }
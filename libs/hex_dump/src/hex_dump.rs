@@ -0,0 +1,161 @@
+//! Converting bytes to and from the text shown in hex view buffers. Each line of that
+//! text has the offset of its first byte, then up to `BYTES_PER_LINE` bytes in hex, then
+//! those same bytes as ASCII, with `.` standing in for anything unprintable:
+//!
+//! ```text
+//! 00000000  68 65 6c 6c 6f 20 77 6f  72 6c 64 0a              |hello world.|
+//! ```
+//!
+//! Only the hex bytes are read back by `parse`, so editing those is how bytes are
+//! changed. Edits to the offsets or the ASCII are ignored.
+use std::fmt::Write;
+
+pub const BYTES_PER_LINE: usize = 16;
+
+/// Returns the hex dump of `bytes`. Every line, including the last one, ends in `\n`.
+pub fn format(bytes: &[u8]) -> String {
+    // Each line is about four times as long as the bytes on it.
+    let mut output = String::with_capacity(bytes.len() * 4 + 80);
+
+    for (line_index, line) in bytes.chunks(BYTES_PER_LINE).enumerate() {
+        // `write!` to a `String` cannot fail.
+        let _ = write!(output, "{:08x}  ", line_index * BYTES_PER_LINE);
+
+        for i in 0..BYTES_PER_LINE {
+            match line.get(i) {
+                Some(byte) => {
+                    let _ = write!(output, "{:02x} ", byte);
+                }
+                None => output.push_str("   "),
+            }
+            if i == BYTES_PER_LINE / 2 - 1 {
+                output.push(' ');
+            }
+        }
+
+        output.push_str(" |");
+        output.extend(line.iter().map(|&byte| if byte == b' ' || byte.is_ascii_graphic() {
+            byte as char
+        } else {
+            '.'
+        }));
+        output.push_str("|\n");
+    }
+
+    output
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// One-based, to match what the user sees.
+    pub line_number: usize,
+    /// The text on that line that was expected to be a byte.
+    pub token: String,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}: expected a byte as two hex digits, like \"0a\", but found \"{}\"",
+            self.line_number,
+            self.token,
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Returns the bytes in the hex columns of `dump`, which is expected to be in the
+/// format that `format` produces. Blank lines are skipped.
+pub fn parse(dump: &str) -> Result<Vec<u8>, ParseError> {
+    let mut bytes = Vec::with_capacity(dump.len() / 4);
+
+    for (line_index, line) in dump.lines().enumerate() {
+        let hex = match line.find('|') {
+            Some(gutter_start) => &line[..gutter_start],
+            None => line,
+        };
+
+        // The first token is the offset.
+        for token in hex.split_whitespace().skip(1) {
+            match u8::from_str_radix(token, 16) {
+                Ok(byte) if token.len() == 2 => bytes.push(byte),
+                _ => return Err(ParseError {
+                    line_number: line_index + 1,
+                    token: token.to_owned(),
+                }),
+            }
+        }
+    }
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::{collection, proptest, prelude::any};
+
+    #[test]
+    fn format_produces_the_expected_output_on_this_example() {
+        assert_eq!(
+            format(b"hello world\n"),
+            "00000000  68 65 6c 6c 6f 20 77 6f  72 6c 64 0a              |hello world.|\n"
+        );
+    }
+
+    #[test]
+    fn format_starts_a_new_line_every_bytes_per_line_bytes() {
+        let bytes: Vec<u8> = (0..=BYTES_PER_LINE as u8).collect();
+
+        assert_eq!(
+            format(&bytes),
+            "00000000  00 01 02 03 04 05 06 07  08 09 0a 0b 0c 0d 0e 0f  |................|\n\
+             00000010  10                                                |.|\n"
+        );
+    }
+
+    #[test]
+    fn format_produces_nothing_for_no_bytes() {
+        assert_eq!(format(&[]), "");
+    }
+
+    #[test]
+    fn parse_reads_edited_hex_bytes() {
+        let dump = "00000000  68 65 6c 6c 6f 20 77 6f  72 6c 64 0a              |hello world.|\n";
+        let edited = dump.replacen("68", "4a", 1);
+
+        assert_eq!(parse(&edited), Ok(b"Jello world\n".to_vec()));
+    }
+
+    #[test]
+    fn parse_ignores_edits_to_the_ascii_and_blank_lines() {
+        let dump = "00000000  68 69  |xyz|\n\n";
+
+        assert_eq!(parse(dump), Ok(b"hi".to_vec()));
+    }
+
+    #[test]
+    fn parse_reports_the_line_of_a_bad_byte() {
+        let dump = "00000000  68 69  |hi|\n00000002  6g  |.|\n";
+
+        assert_eq!(
+            parse(dump),
+            Err(ParseError { line_number: 2, token: "6g".to_owned() })
+        );
+    }
+
+    #[test]
+    fn parse_rejects_bytes_without_exactly_two_digits() {
+        assert!(parse("00000000  6 |.|").is_err());
+        assert!(parse("00000000  068 |.|").is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn parse_undoes_format(bytes in collection::vec(any::<u8>(), 0..256)) {
+            assert_eq!(parse(&format(&bytes)), Ok(bytes));
+        }
+    }
+}
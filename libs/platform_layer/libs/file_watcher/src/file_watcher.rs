@@ -0,0 +1,477 @@
+//! Watches files for changes made by other programs, like `git checkout` or a code
+//! formatter, so that the buffers showing those files can be brought up to date.
+//!
+//! On Linux this uses inotify. We watch the directories containing the files rather
+//! than the files themselves, because many programs replace a file by renaming a new
+//! one over it, which a watch on the old file would not see. When inotify is not
+//! available, or a directory cannot be watched, we fall back to polling the file's
+//! modification time and length.
+#![deny(unused)]
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::mpsc::{channel, Receiver, Sender, TryRecvError},
+    thread::JoinHandle,
+    time::{Duration, SystemTime},
+};
+
+const TICK: Duration = Duration::from_millis(100);
+/// Polling is more expensive than checking for notifications, so we do it less often.
+const TICKS_PER_POLL: u32 = 10;
+
+enum Message {
+    SetPaths(Vec<PathBuf>),
+    Quit,
+}
+
+/// Stops watching, and waits for the watching thread to finish, when dropped.
+pub struct Watcher {
+    sink: Sender<Message>,
+    join_handle: Option<JoinHandle<()>>,
+}
+
+impl Watcher {
+    /// Starts a thread that calls `callback` with the path of a watched file whenever
+    /// that file is written to, or replaced. Note that this includes writes made by
+    /// this process. Several changes made in quick succession may only be reported
+    /// once.
+    pub fn new<F>(callback: F) -> std::io::Result<Self>
+    where
+        F: Fn(PathBuf) + Send + 'static,
+    {
+        let (sink, source) = channel();
+
+        let join_handle = std::thread::Builder::new()
+            .name("file watcher".to_string())
+            .spawn(move || watch(source, callback))?;
+
+        Ok(Watcher {
+            sink,
+            join_handle: Some(join_handle),
+        })
+    }
+
+    /// Replaces the set of watched files with `paths`.
+    pub fn set_paths(&self, paths: Vec<PathBuf>) {
+        let _hope_it_gets_there = self.sink.send(Message::SetPaths(paths));
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        let _hope_it_gets_there = self.sink.send(Message::Quit);
+        if let Some(j_h) = self.join_handle.take() {
+            let _ = j_h.join();
+        }
+    }
+}
+
+/// What we compare to tell whether a polled file has changed. This is `None` if the
+/// file could not be read, for example because it was deleted.
+type Signature = Option<(SystemTime, u64)>;
+
+fn signature(path: &Path) -> Signature {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+fn watch<F: Fn(PathBuf)>(source: Receiver<Message>, callback: F) {
+    let mut notifier = Notifier::new();
+    let mut polled: HashMap<PathBuf, Signature> = HashMap::new();
+    let mut ticks: u32 = 0;
+
+    loop {
+        loop {
+            match source.try_recv() {
+                Ok(Message::SetPaths(paths)) => {
+                    let unnotified = notifier.set_paths(&paths);
+                    polled.retain(|path, _| unnotified.contains(path));
+                    for path in unnotified {
+                        if !polled.contains_key(&path) {
+                            let s = signature(&path);
+                            polled.insert(path, s);
+                        }
+                    }
+                }
+                Ok(Message::Quit) | Err(TryRecvError::Disconnected) => return,
+                Err(TryRecvError::Empty) => break,
+            }
+        }
+
+        let mut changed = notifier.changed_paths();
+
+        ticks = ticks.wrapping_add(1);
+        if ticks % TICKS_PER_POLL == 0 {
+            for (path, old_signature) in polled.iter_mut() {
+                let new_signature = signature(path);
+                if new_signature != *old_signature {
+                    *old_signature = new_signature;
+                    changed.insert(path.clone());
+                }
+            }
+        }
+
+        for path in changed {
+            callback(path);
+        }
+
+        std::thread::sleep(TICK);
+    }
+}
+
+#[cfg(target_os = "linux")]
+struct Notifier {
+    inotify: Option<inotify::Inotify>,
+    /// The watched directories, along with the file names we care about in each one,
+    /// and the paths those names were given to us as.
+    directories: HashMap<inotify::WatchDescriptor, (PathBuf, HashMap<std::ffi::OsString, PathBuf>)>,
+    buffer: Vec<u8>,
+}
+
+#[cfg(target_os = "linux")]
+impl Notifier {
+    fn new() -> Self {
+        Notifier {
+            inotify: inotify::Inotify::init().ok(),
+            directories: HashMap::new(),
+            buffer: vec![0; 4096],
+        }
+    }
+
+    /// Returns the paths that could not be watched, so they should be polled instead.
+    fn set_paths(&mut self, paths: &[PathBuf]) -> HashSet<PathBuf> {
+        let mut unwatched = HashSet::new();
+
+        let inotify = match self.inotify.as_mut() {
+            Some(inotify) => inotify,
+            None => {
+                unwatched.extend(paths.iter().cloned());
+                return unwatched;
+            }
+        };
+
+        let mut wanted: HashMap<PathBuf, HashMap<std::ffi::OsString, PathBuf>> = HashMap::new();
+        for path in paths {
+            match path.file_name() {
+                Some(name) => {
+                    let directory = match path.parent() {
+                        Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+                        _ => PathBuf::from("."),
+                    };
+                    wanted.entry(directory)
+                        .or_default()
+                        .insert(name.to_owned(), path.clone());
+                }
+                None => {
+                    unwatched.insert(path.clone());
+                }
+            }
+        }
+
+        for (wd, (directory, _)) in std::mem::take(&mut self.directories) {
+            if !wanted.contains_key(&directory) {
+                // If this fails, the directory was probably deleted, which removes the
+                // watch anyway.
+                let _ = inotify.rm_watch(wd);
+            }
+        }
+
+        use inotify::WatchMask;
+        for (directory, names) in wanted {
+            // Adding a watch for a directory that is already watched gives back the
+            // same descriptor, so this is fine for directories we kept.
+            match inotify.add_watch(&directory, WatchMask::CLOSE_WRITE | WatchMask::MOVED_TO) {
+                Ok(wd) => {
+                    self.directories.insert(wd, (directory, names));
+                }
+                Err(_) => {
+                    unwatched.extend(names.into_iter().map(|(_, path)| path));
+                }
+            }
+        }
+
+        unwatched
+    }
+
+    fn changed_paths(&mut self) -> HashSet<PathBuf> {
+        let mut changed = HashSet::new();
+        let inotify = match self.inotify.as_mut() {
+            Some(inotify) => inotify,
+            None => return changed,
+        };
+
+        loop {
+            let events = match inotify.read_events(&mut self.buffer) {
+                Ok(events) => events,
+                Err(_) => break,
+            };
+
+            let mut saw_any = false;
+            for event in events {
+                saw_any = true;
+
+                if event.mask.contains(inotify::EventMask::Q_OVERFLOW) {
+                    // We don't know what we missed, so we report everything.
+                    for (_, names) in self.directories.values() {
+                        changed.extend(names.values().cloned());
+                    }
+                    continue;
+                }
+
+                let path = self.directories.get(&event.wd)
+                    .and_then(|(_, names)| event.name.and_then(|name| names.get(name)));
+                if let Some(path) = path {
+                    changed.insert(path.clone());
+                }
+            }
+
+            if !saw_any {
+                break;
+            }
+        }
+
+        changed
+    }
+}
+
+/// Without a notification API, every file is polled.
+#[cfg(not(target_os = "linux"))]
+struct Notifier;
+
+#[cfg(not(target_os = "linux"))]
+impl Notifier {
+    fn new() -> Self {
+        Notifier
+    }
+
+    fn set_paths(&mut self, paths: &[PathBuf]) -> HashSet<PathBuf> {
+        paths.iter().cloned().collect()
+    }
+
+    fn changed_paths(&mut self) -> HashSet<PathBuf> {
+        HashSet::new()
+    }
+}
+
+/// How many unchanged lines to show around each change in a diff.
+const DIFF_CONTEXT: usize = 3;
+/// Finding the smallest diff takes time and space proportional to the product of the
+/// number of changed lines in each version, so past this we give a simpler diff.
+const MAX_DIFF_CELLS: usize = 1 << 22;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum LineChange {
+    Same,
+    Removed,
+    Added,
+}
+
+/// Returns a diff of the lines in `old` and `new`, in the unified format used by
+/// `diff -u` and `git diff`, or an empty string if there are no differences.
+pub fn unified_diff(old_name: &str, old: &str, new_name: &str, new: &str) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+
+    let prefix_len = old_lines.iter()
+        .zip(new_lines.iter())
+        .take_while(|(o, n)| o == n)
+        .count();
+    let suffix_len = old_lines[prefix_len..].iter().rev()
+        .zip(new_lines[prefix_len..].iter().rev())
+        .take_while(|(o, n)| o == n)
+        .count();
+
+    let old_middle = &old_lines[prefix_len..old_lines.len() - suffix_len];
+    let new_middle = &new_lines[prefix_len..new_lines.len() - suffix_len];
+
+    if old_middle.is_empty() && new_middle.is_empty() {
+        return String::new();
+    }
+
+    let mut changes = vec![LineChange::Same; prefix_len];
+    changes.extend(middle_changes(old_middle, new_middle));
+    changes.extend(std::iter::repeat(LineChange::Same).take(suffix_len));
+
+    let mut output = format!("--- {}\n+++ {}\n", old_name, new_name);
+
+    // The old and new line indexes before each change.
+    let mut line_indexes = Vec::with_capacity(changes.len() + 1);
+    let (mut old_index, mut new_index) = (0, 0);
+    for change in changes.iter() {
+        line_indexes.push((old_index, new_index));
+        match change {
+            LineChange::Same => { old_index += 1; new_index += 1; }
+            LineChange::Removed => { old_index += 1; }
+            LineChange::Added => { new_index += 1; }
+        }
+    }
+    line_indexes.push((old_index, new_index));
+
+    let mut i = 0;
+    while i < changes.len() {
+        if changes[i] == LineChange::Same {
+            i += 1;
+            continue;
+        }
+
+        let start = i.saturating_sub(DIFF_CONTEXT);
+        // Extend the hunk until there are enough unchanged lines in a row that the
+        // next change would not share any context with this one.
+        let mut end = i;
+        let mut same_in_a_row = 0;
+        while end < changes.len() && same_in_a_row <= 2 * DIFF_CONTEXT {
+            if changes[end] == LineChange::Same {
+                same_in_a_row += 1;
+            } else {
+                same_in_a_row = 0;
+            }
+            end += 1;
+        }
+        end -= same_in_a_row.saturating_sub(DIFF_CONTEXT);
+
+        let (old_start, new_start) = line_indexes[start];
+        let (old_end, new_end) = line_indexes[end];
+        let old_count = old_end - old_start;
+        let new_count = new_end - new_start;
+        output.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            // Like `diff`, we give the line before an empty range.
+            if old_count == 0 { old_start } else { old_start + 1 },
+            old_count,
+            if new_count == 0 { new_start } else { new_start + 1 },
+            new_count,
+        ));
+
+        for (change, &(old_index, new_index)) in changes[start..end].iter()
+            .zip(line_indexes[start..end].iter()) {
+            let (sigil, line) = match change {
+                LineChange::Same => (' ', old_lines[old_index]),
+                LineChange::Removed => ('-', old_lines[old_index]),
+                LineChange::Added => ('+', new_lines[new_index]),
+            };
+            output.push(sigil);
+            output.push_str(line);
+            output.push('\n');
+        }
+
+        i = end;
+    }
+
+    output
+}
+
+fn middle_changes(old: &[&str], new: &[&str]) -> Vec<LineChange> {
+    let mut output = Vec::with_capacity(old.len() + new.len());
+
+    let width = new.len() + 1;
+    if old.len().saturating_mul(new.len()) > MAX_DIFF_CELLS {
+        output.extend(std::iter::repeat(LineChange::Removed).take(old.len()));
+        output.extend(std::iter::repeat(LineChange::Added).take(new.len()));
+        return output;
+    }
+
+    // `lengths[o * width + n]` is the length of the longest common subsequence of
+    // `old[o..]` and `new[n..]`.
+    let mut lengths = vec![0u32; (old.len() + 1) * width];
+    for o in (0..old.len()).rev() {
+        for n in (0..new.len()).rev() {
+            lengths[o * width + n] = if old[o] == new[n] {
+                lengths[(o + 1) * width + n + 1] + 1
+            } else {
+                std::cmp::max(lengths[(o + 1) * width + n], lengths[o * width + n + 1])
+            };
+        }
+    }
+
+    let (mut o, mut n) = (0, 0);
+    while o < old.len() && n < new.len() {
+        if old[o] == new[n] {
+            output.push(LineChange::Same);
+            o += 1;
+            n += 1;
+        } else if lengths[(o + 1) * width + n] >= lengths[o * width + n + 1] {
+            output.push(LineChange::Removed);
+            o += 1;
+        } else {
+            output.push(LineChange::Added);
+            n += 1;
+        }
+    }
+    output.extend(std::iter::repeat(LineChange::Removed).take(old.len() - o));
+    output.extend(std::iter::repeat(LineChange::Added).take(new.len() - n));
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unified_diff_is_empty_for_the_same_text() {
+        assert_eq!(unified_diff("a", "1\n2\n", "b", "1\n2\n"), "");
+    }
+
+    #[test]
+    fn unified_diff_works_on_this_example() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\n14\n15\n";
+        let new = "1\n2\nthree\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\n15\n16\n";
+
+        assert_eq!(
+            unified_diff("ours", old, "theirs", new),
+            "--- ours\n\
+            +++ theirs\n\
+            @@ -1,6 +1,6 @@\n \
+            1\n \
+            2\n\
+            -3\n\
+            +three\n \
+            4\n \
+            5\n \
+            6\n\
+            @@ -11,5 +11,5 @@\n \
+            11\n \
+            12\n \
+            13\n\
+            -14\n \
+            15\n\
+            +16\n"
+        );
+    }
+
+    #[test]
+    fn unified_diff_joins_changes_that_share_context() {
+        assert_eq!(
+            unified_diff("ours", "a\nb\nc\nd\n", "theirs", "A\nb\nc\nD\n"),
+            "--- ours\n+++ theirs\n@@ -1,4 +1,4 @@\n-a\n+A\n b\n c\n-d\n+D\n"
+        );
+    }
+
+    #[test]
+    fn watcher_reports_a_file_that_is_replaced() {
+        let directory = std::env::temp_dir().join(format!(
+            "file_watcher_test_{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("watched.txt");
+        std::fs::write(&path, "before").unwrap();
+
+        let (sink, source) = channel();
+        let watcher = Watcher::new(move |p| { let _ = sink.send(p); }).unwrap();
+        watcher.set_paths(vec![path.clone()]);
+        // Give the watcher thread a chance to start watching.
+        std::thread::sleep(TICK * 3);
+
+        let temp_path = directory.join("watched.txt.tmp");
+        std::fs::write(&temp_path, "after, and longer").unwrap();
+        std::fs::rename(&temp_path, &path).unwrap();
+
+        let reported = source.recv_timeout(Duration::from_secs(5));
+
+        drop(watcher);
+        let _ = std::fs::remove_dir_all(&directory);
+
+        assert_eq!(reported, Ok(path));
+    }
+}
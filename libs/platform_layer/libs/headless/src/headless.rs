@@ -0,0 +1,690 @@
+//! A platform layer that applies a script of `Input`s to some files without opening a
+//! window, and then prints the result. This allows using rote's editing features in
+//! shell pipelines, and writing end-to-end tests that do not need a display.
+//!
+//! Each line of a script is either blank, a comment starting with `#`, or the name of
+//! an `Input` variant, optionally followed by a single space and an argument. For
+//! example:
+//!
+//! ```text
+//! # Select the first word, and the next place that word appears, then put a
+//! # semicolon at the end of both of their lines.
+//! ExtendSelectionForAllCursors ToNextLikelyEditLocation
+//! ExtendSelectionWithSearch
+//! MoveAllCursors ToLineEnd
+//! Insert ;
+//! ```
+//!
+//! Text arguments can contain `\n`, `\t` and `\\` escapes. `Insert` accepts any
+//! amount of text, and `Paste` without an argument pastes what was last copied or cut.
+//!
+//! Alternately, an input log recorded by another platform layer can be replayed, to
+//! check whether the editor ends up in the same state as when it was recorded. That
+//! check compares hashes of the final view, so it only works with the same build.
+#![deny(unused)]
+use macros::{d, some_or, u};
+use platform_types::{*};
+use shared::Res;
+use std::{
+    collections::VecDeque,
+    io::Read,
+    path::PathBuf,
+};
+
+/// The argument that selects this platform layer instead of the default one. It is
+/// expected to be the first argument.
+pub const HEADLESS: &str = "--headless";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Print {
+    /// Print only the text of the current text buffer, with nothing added.
+    Current,
+    /// Print the name and text of every text buffer.
+    Buffers,
+    /// Print the debug representation of the final `View`.
+    View,
+    Nothing,
+}
+d!(for Print: Print::Current);
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct Options {
+    script_path: Option<PathBuf>,
+    replay_path: Option<PathBuf>,
+    print: Print,
+    write: bool,
+    paths: Vec<PathBuf>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Res<Options> {
+    const HELP: &str = "--help";
+    const SCRIPT: &str = "--script";
+    const REPLAY: &str = "--replay";
+    const PRINT: &str = "--print";
+    const WRITE: &str = "--write";
+
+    let mut options: Options = d!();
+
+    while let Some(s) = args.next() {
+        let s: &str = &s;
+        match s {
+            HELP => {
+                println!("usage: {} [args] [file paths]", HEADLESS);
+                println!("accepted args: ");
+                println!("    {} <script path>", SCRIPT);
+                println!("        Read the script from this file instead of stdin. If no file paths");
+                println!("        are given, stdin is read into a scratch buffer instead.");
+                println!("    {} <input log path>", REPLAY);
+                println!("        Replay a recorded input log instead of running a script, and check");
+                println!("        that the final view matches the recorded one. Cannot be combined");
+                println!("        with a script, file paths or {}.", WRITE);
+                println!("    {} <current|buffers|view|nothing>", PRINT);
+                println!("        What to print after the script has run. Defaults to current.");
+                println!("    {}", WRITE);
+                println!("        Write every buffer with a path back to its file afterwards.");
+                println!("    {}", HELP);
+                std::process::exit(0)
+            }
+            SCRIPT => {
+                options.script_path = Some(args.next().ok_or_else(|| {
+                    format!(
+                        "{0} needs an argument. For example: {0} ./script.txt",
+                        SCRIPT
+                    )
+                })?)
+                .map(PathBuf::from);
+            }
+            REPLAY => {
+                options.replay_path = Some(args.next().ok_or_else(|| {
+                    format!(
+                        "{0} needs an argument. For example: {0} ./session.txt",
+                        REPLAY
+                    )
+                })?)
+                .map(PathBuf::from);
+            }
+            PRINT => {
+                let print = args.next().ok_or_else(|| {
+                    format!(
+                        "{0} needs an argument. For example: {0} buffers",
+                        PRINT
+                    )
+                })?;
+                options.print = match print.as_str() {
+                    "current" => Print::Current,
+                    "buffers" => Print::Buffers,
+                    "view" => Print::View,
+                    "nothing" => Print::Nothing,
+                    _ => return Err(format!("unknown {} value {:?}", PRINT, print).into()),
+                };
+            }
+            WRITE => {
+                options.write = true;
+            }
+            _ if s.starts_with("--") => {
+                return Err(format!("unknown arg {:?}", s).into());
+            }
+            _ => {
+                options.paths.push(PathBuf::from(s));
+            }
+        }
+    }
+
+    if options.replay_path.is_some()
+    && (options.script_path.is_some() || options.write || !options.paths.is_empty()) {
+        return Err(format!(
+            "{} cannot be combined with {}, {} or file paths",
+            REPLAY,
+            SCRIPT,
+            WRITE
+        ).into());
+    }
+
+    Ok(options)
+}
+
+/// `args` should not include the executable name or `HEADLESS`.
+pub fn run(
+    update_and_render: UpdateAndRender,
+    args: impl Iterator<Item = String>,
+) -> Res<()> {
+    let Options { script_path, replay_path, print, write, paths } = parse_args(args)?;
+
+    if let Some(replay_path) = replay_path {
+        let log = input_log::parse(&read_file(&replay_path)?)?;
+        let view = replay(update_and_render, &log)?;
+        print_view(print, &view);
+        return Ok(());
+    }
+
+    let mut stdin = String::new();
+    let script = match script_path {
+        Some(script_path) => {
+            if paths.is_empty() {
+                std::io::stdin().read_to_string(&mut stdin)?;
+            }
+            std::fs::read_to_string(script_path)?
+        }
+        None => {
+            std::io::stdin().read_to_string(&mut stdin)?;
+            std::mem::take(&mut stdin)
+        }
+    };
+
+    let inputs = parse_script(&script)?;
+
+    let mut headless = Headless::new(update_and_render);
+
+    if paths.is_empty() {
+        // The editor starts with a single scratch buffer, which this replaces the
+        // contents of.
+        headless.apply(Input::AddOrSelectBuffer(d!(), stdin))?;
+    } else {
+        let mut opened = Vec::with_capacity(paths.len());
+        for path in paths {
+            if headless.open(path.clone())? {
+                opened.push(path);
+            }
+        }
+        // Start with the first file selected, rather than the last one.
+        if let Some(first) = opened.into_iter().next() {
+            headless.open(first)?;
+        }
+    }
+
+    for input in inputs {
+        headless.apply(input)?;
+    }
+
+    if write {
+        headless.write()?;
+    }
+
+    print_view(print, &headless.view);
+
+    Ok(())
+}
+
+fn print_view(print: Print, view: &View) {
+    match print {
+        Print::Current => {
+            let (_, buffer) = view.current_text_index_and_buffer();
+            print!("{}", std::borrow::Cow::from(&buffer.data.chars));
+        }
+        Print::Buffers => {
+            for buffer in view.buffers.iter() {
+                println!("--- {} ---", buffer.name_string);
+                println!("{}", std::borrow::Cow::from(&buffer.data.chars));
+            }
+        }
+        Print::View => {
+            println!("{:#?}", view);
+        }
+        Print::Nothing => {}
+    }
+}
+
+/// Passes every input in `log` to `update_and_render` and returns the final `View`,
+/// or an error if that `View` does not have the hash that was recorded in `log`. The
+/// `Cmd`s are ignored, since any inputs that resulted from them were recorded too.
+pub fn replay(update_and_render: UpdateAndRender, log: &input_log::Log) -> Res<View> {
+    let mut view = d!();
+    for entry in log.entries.iter() {
+        let (v, _) = update_and_render(entry.input.clone());
+        view = v;
+    }
+
+    match log.view_hash {
+        Some(expected) => {
+            let actual = input_log::view_hash(&view);
+            if actual != expected {
+                return Err(format!(
+                    "The final view hash was {}, but {} was recorded. Note that view \
+                    hashes from a different build of the editor are not expected to match.",
+                    actual,
+                    expected
+                ).into());
+            }
+        }
+        None => {
+            eprintln!(
+                "The log has no final view hash, so the recording was probably not stopped \
+                by a Quit input. The view could not be checked."
+            );
+        }
+    }
+
+    Ok(view)
+}
+
+/// Drives an `UpdateAndRender` function, handling the `Cmd`s it returns the way a
+/// platform layer with a window would, as far as makes sense without one.
+pub struct Headless {
+    update_and_render: UpdateAndRender,
+    pub view: View,
+    pub clipboard: Option<String>,
+}
+
+impl Headless {
+    pub fn new(update_and_render: UpdateAndRender) -> Self {
+        Headless {
+            update_and_render,
+            view: d!(),
+            clipboard: None,
+        }
+    }
+
+    pub fn apply(&mut self, input: Input) -> Res<()> {
+        let mut inputs = VecDeque::with_capacity(2);
+        inputs.push_back(input);
+
+        while let Some(mut input) = inputs.pop_front() {
+            if let Input::Paste(Option::None) = input {
+                input = Input::Paste(self.clipboard.clone());
+            }
+
+            let (view, cmd) = (self.update_and_render)(input);
+            self.view = view;
+
+            u!{Cmd}
+            match cmd {
+                None => {}
+                SetClipboard(s) => {
+                    self.clipboard = Some(s);
+                }
+                LoadFile(path) => {
+                    if let Some(s) = read_text_file(&path)? {
+                        inputs.push_back(Input::AddOrSelectBuffer(BufferName::Path(path), s));
+                    }
+                }
+                LoadDirectoryFiles(dir) => {
+                    inputs.push_back(Input::SearchFiles(shared::read_directory_files(&dir)));
+                }
+                // Nothing is persisted between runs.
+                StoreBufferHistories(_) => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Opens the file at `path` as text, returning whether it was opened. See
+    /// `read_text_file` for which files are not.
+    pub fn open(&mut self, path: PathBuf) -> Res<bool> {
+        let s = some_or!(read_text_file(&path)?, return Ok(false));
+        self.apply(Input::AddOrSelectBuffer(BufferName::Path(path), s))?;
+        Ok(true)
+    }
+
+    /// Writes every buffer that has a path and has different contents than the file
+    /// at that path, to that path.
+    pub fn write(&self) -> Res<()> {
+        for buffer in self.view.buffers.iter() {
+            if let BufferName::Path(ref path) = buffer.name {
+                let chars = std::borrow::Cow::from(&buffer.data.chars);
+                let unchanged = std::fs::read_to_string(path)
+                    .map(|s| s == chars)
+                    .unwrap_or(false);
+                if !unchanged {
+                    std::fs::write(path, chars.as_ref())
+                        .map_err(|err| format!("{}: {}", path.display(), err))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn read_file(path: &std::path::Path) -> Res<String> {
+    std::fs::read_to_string(path)
+        .map_err(|err| format!("{}: {}", path.display(), err).into())
+}
+
+/// Like `read_file`, but a file that is not valid UTF-8 is reported and skipped, by
+/// returning `None`, rather than stopping the whole run.
+fn read_text_file(path: &std::path::Path) -> Res<Option<String>> {
+    match std::fs::read_to_string(path) {
+        Ok(s) => Ok(Some(s)),
+        Err(err) if err.kind() == std::io::ErrorKind::InvalidData => {
+            eprintln!("{}: not valid UTF-8, so it was skipped.", path.display());
+            Ok(Option::None)
+        }
+        Err(err) => Err(format!("{}: {}", path.display(), err).into()),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ScriptErrorKind {
+    UnknownInput(String),
+    MissingArgument,
+    UnexpectedArgument,
+    BadArgument(String),
+    UnknownEscape(char),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScriptError {
+    pub line_number: usize,
+    pub kind: ScriptErrorKind,
+}
+
+impl std::fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        u!{ScriptErrorKind}
+        write!(f, "script line {}: ", self.line_number)?;
+        match &self.kind {
+            UnknownInput(s) => write!(f, "Unknown or unsupported input \"{}\".", s),
+            MissingArgument => write!(f, "This input needs an argument."),
+            UnexpectedArgument => write!(f, "This input does not take an argument."),
+            BadArgument(s) => write!(f, "Unexpected argument \"{}\".", s),
+            UnknownEscape(c) => write!(f, "Unknown escape \"\\{}\".", c),
+        }
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+fn unescape(s: &str) -> Result<String, ScriptErrorKind> {
+    let mut output = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            output.push(match chars.next() {
+                Some('n') => '\n',
+                Some('t') => '\t',
+                Some('\\') => '\\',
+                Some(c) => return Err(ScriptErrorKind::UnknownEscape(c)),
+                None => return Err(ScriptErrorKind::UnknownEscape(' ')),
+            });
+        } else {
+            output.push(c);
+        }
+    }
+    Ok(output)
+}
+
+fn parse_move(s: &str) -> Result<Move, ScriptErrorKind> {
+    u!{Move}
+    Ok(match s {
+        "Up" => Up,
+        "Down" => Down,
+        "Left" => Left,
+        "Right" => Right,
+        "ToLineStart" => ToLineStart,
+        "ToLineEnd" => ToLineEnd,
+        "ToBufferStart" => ToBufferStart,
+        "ToBufferEnd" => ToBufferEnd,
+        "ToPreviousLikelyEditLocation" => ToPreviousLikelyEditLocation,
+        "ToNextLikelyEditLocation" => ToNextLikelyEditLocation,
+        _ => return Err(ScriptErrorKind::BadArgument(s.to_owned())),
+    })
+}
+
+fn parse_menu_mode(s: &str) -> Result<MenuMode, ScriptErrorKind> {
+    Ok(match s {
+        "Hidden" => MenuMode::Hidden,
+        "FileSwitcher" => MenuMode::FileSwitcher,
+        "GoToPosition" => MenuMode::GoToPosition,
+        "FindReplace" | "FindReplace CurrentFile" => MenuMode::FindReplace(FindReplaceMode::CurrentFile),
+        "FindReplace OpenBuffers" => MenuMode::FindReplace(FindReplaceMode::OpenBuffers),
+        "FindReplace Directory" => MenuMode::FindReplace(FindReplaceMode::Directory),
+        _ => return Err(ScriptErrorKind::BadArgument(s.to_owned())),
+    })
+}
+
+/// Only the inputs that make sense to use without a window are supported. For
+/// example, inputs that take screen positions are not.
+pub fn parse_script(script: &str) -> Result<Vec<Input>, ScriptError> {
+    let mut inputs = Vec::new();
+
+    for (i, line) in script.lines().enumerate() {
+        let line_number = i + 1;
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue
+        }
+
+        macro_rules! try_line {
+            ($result: expr) => {
+                $result.map_err(|kind| ScriptError { line_number, kind })?
+            }
+        }
+
+        let (name, argument) = match line.find(' ') {
+            Some(index) => (&line[..index], Some(&line[index + 1..])),
+            None => (line, None),
+        };
+
+        macro_rules! argument {
+            () => {
+                try_line!(argument.ok_or(ScriptErrorKind::MissingArgument))
+            };
+        }
+
+        macro_rules! no_argument {
+            ($input: expr) => {{
+                if argument.is_some() {
+                    try_line!(Err(ScriptErrorKind::UnexpectedArgument));
+                }
+                $input
+            }};
+        }
+
+        let input = match name {
+            "Insert" => {
+                let text = try_line!(unescape(argument!()));
+                inputs.extend(text.chars().map(Input::Insert));
+                continue
+            }
+            "Paste" => Input::Paste(match argument {
+                Some(text) => Some(try_line!(unescape(text))),
+                None => None,
+            }),
+            "NewScratchBuffer" => Input::NewScratchBuffer(match argument {
+                Some(text) => Some(try_line!(unescape(text))),
+                None => None,
+            }),
+            "MoveAllCursors" => Input::MoveAllCursors(
+                try_line!(parse_move(argument!()))
+            ),
+            "ExtendSelectionForAllCursors" => Input::ExtendSelectionForAllCursors(
+                try_line!(parse_move(argument!()))
+            ),
+            "AdjustBufferSelection" => Input::AdjustBufferSelection(
+                match argument!() {
+                    "Next" => SelectionAdjustment::Next,
+                    "Previous" => SelectionAdjustment::Previous,
+                    s => try_line!(Err(ScriptErrorKind::BadArgument(s.to_owned()))),
+                }
+            ),
+            "OpenOrSelectBuffer" => Input::OpenOrSelectBuffer(PathBuf::from(argument!())),
+            "SetMenuMode" => Input::SetMenuMode(try_line!(parse_menu_mode(argument!()))),
+            "CloseMenuIfAny" => no_argument!(Input::CloseMenuIfAny),
+            "Delete" => no_argument!(Input::Delete),
+            "DeleteLines" => no_argument!(Input::DeleteLines),
+            "SelectAll" => no_argument!(Input::SelectAll),
+            "ExtendSelectionWithSearch" => no_argument!(Input::ExtendSelectionWithSearch),
+            "Undo" => no_argument!(Input::Undo),
+            "Redo" => no_argument!(Input::Redo),
+            "PreviousUndoBranch" => no_argument!(Input::PreviousUndoBranch),
+            "NextUndoBranch" => no_argument!(Input::NextUndoBranch),
+            "Cut" => no_argument!(Input::Cut),
+            "Copy" => no_argument!(Input::Copy),
+            "InsertNumbersAtCursors" => no_argument!(Input::InsertNumbersAtCursors),
+            "TabIn" => no_argument!(Input::TabIn),
+            "TabOut" => no_argument!(Input::TabOut),
+            "ToggleComments" => no_argument!(Input::ToggleComments),
+            "JumpToMatchingBrackets" => no_argument!(Input::JumpToMatchingBrackets),
+            "SelectInsideBrackets" => no_argument!(Input::SelectInsideBrackets),
+            "SwapLinesUp" => no_argument!(Input::SwapLinesUp),
+            "SwapLinesDown" => no_argument!(Input::SwapLinesDown),
+            "SwapSyntaxNodeUp" => no_argument!(Input::SwapSyntaxNodeUp),
+            "SwapSyntaxNodeDown" => no_argument!(Input::SwapSyntaxNodeDown),
+            "ExtractFunction" => no_argument!(Input::ExtractFunction),
+            "ExtractVariable" => no_argument!(Input::ExtractVariable),
+            "ExtractVariableEverywhere" => no_argument!(Input::ExtractVariableEverywhere),
+            "NextLanguage" => no_argument!(Input::NextLanguage),
+            "Fold" => no_argument!(Input::Fold),
+            "Unfold" => no_argument!(Input::Unfold),
+            "SubmitForm" => no_argument!(Input::SubmitForm),
+            "ReplaceAll" => no_argument!(Input::ReplaceAll),
+            "ToggleRegexSearch" => no_argument!(Input::ToggleRegexSearch),
+            "CycleSearchCaseSensitivity" => no_argument!(Input::CycleSearchCaseSensitivity),
+            "ToggleWholeWordSearch" => no_argument!(Input::ToggleWholeWordSearch),
+            "ToggleSearchInSelection" => no_argument!(Input::ToggleSearchInSelection),
+            _ => try_line!(Err(ScriptErrorKind::UnknownInput(name.to_owned()))),
+        };
+
+        inputs.push(input);
+    }
+
+    Ok(inputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update_and_render(input: Input) -> UpdateAndRenderOutput {
+        thread_local! {
+            static STATE: std::cell::RefCell<editor::State> =
+                std::cell::RefCell::new(editor::new());
+        }
+        STATE.with(|state| editor::update_and_render(&mut state.borrow_mut(), input))
+    }
+
+    fn run_on_scratch_text(text: &str, script: &str) -> String {
+        let mut headless = Headless::new(update_and_render);
+        headless.apply(Input::AddOrSelectBuffer(d!(), text.to_owned())).unwrap();
+        for input in parse_script(script).unwrap() {
+            headless.apply(input).unwrap();
+        }
+
+        let (_, buffer) = headless.view.current_text_index_and_buffer();
+        std::borrow::Cow::from(&buffer.data.chars).into_owned()
+    }
+
+    #[test]
+    fn parse_script_parses_this_example() {
+        assert_eq!(
+            parse_script("# A comment\n\nSelectAll\nInsert a\\nb\nMoveAllCursors ToLineEnd\nPaste\n"),
+            Ok(vec![
+                Input::SelectAll,
+                Input::Insert('a'),
+                Input::Insert('\n'),
+                Input::Insert('b'),
+                Input::MoveAllCursors(Move::ToLineEnd),
+                Input::Paste(None),
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_script_reports_the_line_of_the_first_problem() {
+        u!{ScriptErrorKind}
+        assert_eq!(
+            parse_script("Undo\nUndo now\n"),
+            Err(ScriptError { line_number: 2, kind: UnexpectedArgument })
+        );
+        assert_eq!(
+            parse_script("Redo\n\nMoveAllCursors\n"),
+            Err(ScriptError { line_number: 3, kind: MissingArgument })
+        );
+        assert_eq!(
+            parse_script("MoveAllCursors Sideways\n"),
+            Err(ScriptError { line_number: 1, kind: BadArgument("Sideways".to_owned()) })
+        );
+        assert_eq!(
+            parse_script("SetCursor 1 2\n"),
+            Err(ScriptError { line_number: 1, kind: UnknownInput("SetCursor".to_owned()) })
+        );
+        assert_eq!(
+            parse_script("Insert \\q\n"),
+            Err(ScriptError { line_number: 1, kind: UnknownEscape('q') })
+        );
+    }
+
+    #[test]
+    fn running_a_script_edits_at_every_cursor() {
+        assert_eq!(
+            run_on_scratch_text(
+                "let a = 1\nlet b = 2\n",
+                "ExtendSelectionForAllCursors ToNextLikelyEditLocation\nExtendSelectionWithSearch\nMoveAllCursors ToLineEnd\nInsert ;\n"
+            ),
+            "let a = 1;\nlet b = 2;\n"
+        );
+    }
+
+    #[test]
+    fn pasting_without_an_argument_pastes_what_was_copied() {
+        assert_eq!(
+            run_on_scratch_text(
+                "abc",
+                "SelectAll\nCopy\nMoveAllCursors ToBufferEnd\nPaste\n"
+            ),
+            "abcabc"
+        );
+    }
+
+    #[test]
+    fn opening_a_file_that_is_not_utf8_skips_it() {
+        let path = std::env::temp_dir()
+            .join("headless_opening_a_file_that_is_not_utf8_skips_it.bin");
+        std::fs::write(&path, [0xff, 0xfe, 0xfd]).unwrap();
+
+        let mut headless = Headless::new(update_and_render);
+        let opened = headless.open(path.clone());
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(opened.unwrap(), false);
+        assert!(
+            headless.view.buffers.iter().all(|b| b.name != BufferName::Path(path.clone()))
+        );
+    }
+
+    fn recorded_log(inputs: Vec<Input>) -> input_log::Log {
+        // Each thread has its own editor state, so this starts from a fresh one.
+        std::thread::spawn(move || {
+            let mut log = input_log::header_line();
+            let mut view = d!();
+            for (i, input) in inputs.iter().enumerate() {
+                let since_start = std::time::Duration::from_micros(i as u64);
+                log.push_str(&input_log::entry_line(since_start, input));
+                view = update_and_render(input.clone()).0;
+            }
+            log.push_str(&input_log::view_hash_line(input_log::view_hash(&view)));
+
+            input_log::parse(&log).unwrap()
+        }).join().unwrap()
+    }
+
+    #[test]
+    fn replaying_a_recorded_log_reaches_the_same_view() {
+        let log = recorded_log(vec![
+            Input::Insert('a'),
+            Input::SelectAll,
+            Input::Copy,
+            Input::Paste(Some("bc".to_owned())),
+            Input::Quit,
+        ]);
+
+        let view = std::thread::spawn(move || replay(update_and_render, &log).unwrap())
+            .join()
+            .unwrap();
+
+        let (_, buffer) = view.current_text_index_and_buffer();
+        assert_eq!(std::borrow::Cow::from(&buffer.data.chars), "bc");
+    }
+
+    #[test]
+    fn replaying_a_log_with_a_different_view_hash_fails() {
+        let mut log = recorded_log(vec![Input::Insert('a'), Input::Quit]);
+        log.view_hash = log.view_hash.map(|h| h.wrapping_add(1));
+
+        let result = std::thread::spawn(move || {
+            replay(update_and_render, &log).map_err(|e| e.to_string())
+        }).join().unwrap();
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,60 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use editor::{update_and_render, State};
+use platform_types::{BufferName, Input};
+
+const LINE_COUNT: usize = 10_000;
+
+fn rust_code_with_line_count(line_count: usize) -> String {
+    let mut code = String::with_capacity(line_count * 32);
+
+    let mut i = 0;
+    let mut current_line_count = 0;
+    while current_line_count < line_count {
+        let function = format!(
+            "/// Returns a number.\nfn f{0}(x: u32) -> u32 {{\n    let s = \"{0}\";\n    x + s.len() as u32 // {0}\n}}\n\n",
+            i
+        );
+        current_line_count += function.lines().count();
+        code.push_str(&function);
+        i += 1;
+    }
+
+    code
+}
+
+/// A `State` with a Rust buffer containing `code`, with the cursor on the line at the
+/// middle of it.
+fn rust_buffer_with_cursor_in_the_middle(code: &str) -> State {
+    let middle = code[..code.len() / 2].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let (first_half, second_half) = code.split_at(middle);
+
+    let mut state = State::default();
+    update_and_render(
+        &mut state,
+        Input::AddOrSelectBuffer(
+            BufferName::Path("typing.rs".into()),
+            second_half.to_owned(),
+        ),
+    );
+    // Pasting at the start of the buffer leaves the cursor in the middle.
+    update_and_render(&mut state, Input::Paste(Some(first_half.to_owned())));
+
+    state
+}
+
+fn typing_benchmark(c: &mut Criterion) {
+    let mut state = rust_buffer_with_cursor_in_the_middle(
+        &rust_code_with_line_count(LINE_COUNT)
+    );
+
+    c.bench_function("typing a char then deleting it in 10k lines of rust", move |b| {
+        b.iter(|| {
+            black_box(update_and_render(&mut state, Input::Insert('a')));
+            black_box(update_and_render(&mut state, Input::Delete));
+        })
+    });
+}
+
+criterion_group!(typing_group, typing_benchmark);
+criterion_main!(typing_group);
@@ -5,7 +5,8 @@ use wimp_types::{BufferStatus, BufferStatusTransition};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
-pub type BufferInfo = (BufferView, BufferStatus);
+/// The last element is the buffer's serialized history, if we have it.
+pub type BufferInfo = (BufferView, BufferStatus, Option<String>);
 
 fn get_names_to_uuid(edited_files_index_path: &Path) -> HashMap<BufferName, u128> {
     let index_string = std::fs::read_to_string(edited_files_index_path).unwrap_or_default();
@@ -37,7 +38,7 @@ pub fn store_buffers(
 
     let mut result = Vec::with_capacity(all_buffers.len());
 
-    for (i, (buffer, status)) in all_buffers.into_iter().enumerate() {
+    for (i, (buffer, status, history)) in all_buffers.into_iter().enumerate() {
         let filename = if let Some(uuid) = names_to_uuid.get(&buffer.name) {
             get_path(buffer.name_string, uuid)
         } else {
@@ -48,24 +49,41 @@ pub fn store_buffers(
             // we don't expect to read this again in the same
             // loop, but it should be saved back to disk for
             // next time.
-            names_to_uuid.insert(buffer.name, uuid);
+            names_to_uuid.insert(buffer.name.clone(), uuid);
 
             path
         };
 
         let path = edited_files_dir.join(filename);
+        let history_path = get_history_path(&path);
 
-        // TODO replace all files in directory with these files atomically if possible
-        match status {
-            BufferStatus::Unedited => {
-                match remove_file(path).map_err(|e| e.kind()) {
+        macro_rules! remove_if_present {
+            ($path: expr) => {
+                match remove_file($path).map_err(|e| e.kind()) {
                     Err(std::io::ErrorKind::NotFound) => {}
                     otherwise => otherwise?,
                 };
             }
+        }
+
+        // TODO replace all files in directory with these files atomically if possible
+        match status {
+            BufferStatus::Unedited => {
+                remove_if_present!(path);
+                remove_if_present!(history_path);
+                // There is nothing stored for it any more, so there is no need to
+                // remember where we would store it.
+                names_to_uuid.remove(&buffer.name);
+            }
             _ => {
                 let chars: String = buffer.data.chars.into();
                 write(path, chars)?;
+
+                match history {
+                    Some(history) => write(history_path, history)?,
+                    // An old history would not match the new text.
+                    None => remove_if_present!(history_path),
+                }
             }
         }
 
@@ -87,10 +105,12 @@ pub fn store_buffers(
     Ok(result)
 }
 
+/// Returns the name and text of each previously stored buffer, along with its
+/// serialized history if that was stored too.
 pub fn load_previous_tabs(
     edited_files_dir: &Path,
     edited_files_index_path: &Path,
-) -> Vec<(BufferName, String)> {
+) -> Vec<(BufferName, String, Option<String>)> {
     let names_to_uuid: HashMap<BufferName, u128> = get_names_to_uuid(edited_files_index_path);
 
     let mut result = Vec::with_capacity(names_to_uuid.len());
@@ -102,8 +122,10 @@ pub fn load_previous_tabs(
     for (name, uuid) in pairs {
         let path = edited_files_dir.join(get_path(name.to_string(), &uuid));
 
-        if let Ok(data) = std::fs::read_to_string(path) {
-            result.push((name, data));
+        if let Ok(data) = std::fs::read_to_string(&path) {
+            let history = std::fs::read_to_string(get_history_path(&path)).ok();
+
+            result.push((name, data, history));
         }
     }
     result
@@ -115,7 +137,15 @@ fn get_path(buffer_name: String, uuid: &u128) -> PathBuf {
     PathBuf::from(format!("{}_{:032x}", slug, uuid))
 }
 
+/// The history is kept next to the buffer's text, in a file with the same name plus
+/// an extension. `get_path` never produces a path with an extension, so these cannot
+/// collide with each other.
+fn get_history_path(path: &Path) -> PathBuf {
+    path.with_extension("history")
+}
+
 const PATH_PREFIX: &str = "Path: ";
+const HEX_PREFIX: &str = "Hex: ";
 const SCRATCH_PREFIX: &str = "Scratch: ";
 const SCRATCH_PREFIX_LENGTH: usize = 9;
 
@@ -149,6 +179,15 @@ fn serialize(name: &BufferName, uuid: u128, append_target: &mut String) {
                 PATH_PREFIX, uuid, path_string
             )
         }
+        Hex(p) => {
+            // See the `Path` case about the lossy conversion.
+            let path_string = p.to_string_lossy();
+            write!(
+                append_target,
+                "{}{:032x},{}",
+                HEX_PREFIX, uuid, path_string
+            )
+        }
         Scratch(n) => write!(append_target, "{}{:032x},{}", SCRATCH_PREFIX, uuid, n),
     };
 }
@@ -161,6 +200,13 @@ fn deserialize(s: &str) -> Option<(BufferName, u128)> {
         if let Some((uuid, path)) = split_off_uuid_and_comma(s) {
             return Some((BufferName::Path(PathBuf::from(path)), uuid));
         }
+    } else if s.starts_with(HEX_PREFIX) {
+        // works becasue the prefix is ASCII
+        let s = &s[HEX_PREFIX.len()..];
+
+        if let Some((uuid, path)) = split_off_uuid_and_comma(s) {
+            return Some((BufferName::Hex(PathBuf::from(path)), uuid));
+        }
     } else if s.starts_with(SCRATCH_PREFIX) {
         // works becasue the prefix is ASCII
         let s = &s[SCRATCH_PREFIX.len()..];
@@ -195,16 +241,21 @@ mod tests {
     use proptest::{arbitrary::any, prop_oneof, proptest, strategy::Strategy};
 
     pub fn buffer_name() -> impl Strategy<Value = BufferName> {
-        prop_oneof![
-            any::<u32>().prop_map(BufferName::Scratch),
+        fn path() -> impl Strategy<Value = PathBuf> {
             any::<std::ffi::OsString>().prop_map(|s| {
                 let s = if s.len() == 0 {
                     "empty-paths-are-invalid".into()
                 } else {
                     s
                 };
-                BufferName::Path(PathBuf::from(s))
+                PathBuf::from(s)
             })
+        }
+
+        prop_oneof![
+            any::<u32>().prop_map(BufferName::Scratch),
+            path().prop_map(BufferName::Path),
+            path().prop_map(BufferName::Hex),
         ]
     }
 
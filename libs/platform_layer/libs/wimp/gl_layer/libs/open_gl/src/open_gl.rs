@@ -156,6 +156,17 @@ impl State {
         }
     }
 
+    pub fn set_clear_colour(&mut self, clear_colour: [f32; 4]) {
+        unsafe {
+            gl::ClearColor(
+                clear_colour[0],
+                clear_colour[1],
+                clear_colour[2],
+                clear_colour[3],
+            );
+        }
+    }
+
     pub fn draw_vertices(&mut self, mut vertices: Vec<Vertex>) {
         let vertex_count = &mut self.vertex_count;
         let vertex_max = &mut self.vertex_max;
@@ -39,6 +39,10 @@ pub fn set_dimensions(state: &mut State, hidpi_factor: f32, wh: (i32, i32)) {
     state.text_rendering.set_dimensions(hidpi_factor);
 }
 
+pub fn set_clear_colour(state: &mut State, clear_colour: [f32; 4]) {
+    state.open_gl.set_clear_colour(clear_colour);
+}
+
 #[perf_viz::record]
 pub fn render(
     state: &mut State,
@@ -1,2 +1,190 @@
+use platform_types::BufferName;
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+};
+
 pub type Res<T> = Result<T, Box<dyn std::error::Error>>;
 
+/// Files larger than this many bytes are skipped by `read_directory_files`. These
+/// are unlikely to be something the user wants to search through, for example build
+/// artifacts, and reading them would take a while.
+pub const MAX_SEARCHED_FILE_LEN: u64 = 1 << 20;
+
+/// `read_directory_files` stops after reading this many files, so searching a large
+/// directory by accident does not read all of it into memory.
+pub const MAX_SEARCHED_FILE_COUNT: usize = 10_000;
+
+/// Reads every file under `dir` that contains valid UTF-8, skipping hidden files
+/// and directories, such as `.git`. Symlinks are not followed, so a link to a parent
+/// directory cannot cause the same files to be read over and over. Files that look
+/// like binary files are skipped, as are files larger than `MAX_SEARCHED_FILE_LEN`.
+/// At most `MAX_SEARCHED_FILE_COUNT` files are returned.
+pub fn read_directory_files(dir: &Path) -> Vec<(PathBuf, String)> {
+    let mut output = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        if output.len() >= MAX_SEARCHED_FILE_COUNT {
+            break;
+        }
+
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+
+        let mut entries: Vec<std::fs::DirEntry> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                entry.file_name()
+                    .to_str()
+                    .map(|name| !name.starts_with('.'))
+                    .unwrap_or(false)
+            })
+            .collect();
+        // Sorted so which files get read when there are too many is predictable.
+        entries.sort_by_key(|entry| entry.path());
+
+        for entry in entries {
+            // Unlike `Path::is_dir`, this does not follow symlinks.
+            let file_type = match entry.file_type() {
+                Ok(file_type) => file_type,
+                Err(_) => continue,
+            };
+
+            if file_type.is_dir() {
+                dirs.push(entry.path());
+            } else if file_type.is_file() {
+                if output.len() >= MAX_SEARCHED_FILE_COUNT {
+                    break;
+                }
+
+                let is_small_enough = entry.metadata()
+                    .map(|metadata| metadata.len() <= MAX_SEARCHED_FILE_LEN)
+                    .unwrap_or(false);
+                if !is_small_enough {
+                    continue;
+                }
+
+                let path = entry.path();
+                if let Ok(s) = std::fs::read_to_string(&path) {
+                    // Valid UTF-8 can still contain NUL bytes, but text rarely does.
+                    if !s.contains('\0') {
+                        output.push((path, s));
+                    }
+                }
+            }
+        }
+    }
+
+    // Sorted so results come out in a predictable order.
+    output.sort_by(|(p1, _), (p2, _)| p1.cmp(p2));
+
+    output
+}
+
+/// Writes the buffer named `name`, which contains `text`, to `path`. `path` need not
+/// be the path in `name`, so this can be used for saving as a new file too. A hex view
+/// is written as the bytes its hex dump describes, rather than as the dump itself.
+/// Returns the bytes that were written.
+pub fn save_buffer<'text>(
+    name: &BufferName,
+    text: &'text str,
+    path: &Path,
+) -> Res<Cow<'text, [u8]>> {
+    let bytes = match name {
+        BufferName::Hex(_) => Cow::Owned(hex_dump::parse(text)?),
+        BufferName::Path(_) | BufferName::Scratch(_) => Cow::Borrowed(text.as_bytes()),
+    };
+
+    std::fs::write(path, &bytes)?;
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "rote_shared_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn read_directory_files_skips_hidden_binary_and_large_files() {
+        let dir = temp_dir("skips");
+        std::fs::create_dir(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("a.txt"), "a").unwrap();
+        std::fs::write(dir.join("sub").join("b.txt"), "b").unwrap();
+        std::fs::write(dir.join(".hidden"), "hidden").unwrap();
+        std::fs::write(dir.join("binary"), b"\0\x01\x02").unwrap();
+        std::fs::write(
+            dir.join("large.txt"),
+            "a".repeat(MAX_SEARCHED_FILE_LEN as usize + 1),
+        ).unwrap();
+
+        let files = read_directory_files(&dir);
+
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(
+            files,
+            vec![
+                (dir.join("a.txt"), "a".to_owned()),
+                (dir.join("sub").join("b.txt"), "b".to_owned()),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn read_directory_files_does_not_follow_a_symlink_to_a_parent_directory() {
+        let dir = temp_dir("symlink");
+        std::fs::create_dir(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("sub").join("a.txt"), "a").unwrap();
+        std::os::unix::fs::symlink(&dir, dir.join("sub").join("loop")).unwrap();
+
+        let files = read_directory_files(&dir);
+
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(files, vec![(dir.join("sub").join("a.txt"), "a".to_owned())]);
+    }
+
+    #[test]
+    fn save_buffer_writes_the_bytes_of_a_hex_view_to_a_new_path() {
+        let dir = temp_dir("save_hex");
+        let original = dir.join("original.bin");
+        let new = dir.join("new.bin");
+        let bytes = vec![0x00, 0xff, b'\n', 0x80, b'a'];
+        std::fs::write(&original, &bytes).unwrap();
+        let dump = hex_dump::format(&bytes);
+
+        let written = save_buffer(&BufferName::Hex(original.clone()), &dump, &new).unwrap();
+        let on_disk = std::fs::read(&new).unwrap();
+
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(written, bytes);
+        assert_eq!(on_disk, bytes);
+    }
+
+    #[test]
+    fn save_buffer_does_not_write_a_hex_view_that_does_not_parse() {
+        let dir = temp_dir("save_bad_hex");
+        let path = dir.join("a.bin");
+        std::fs::write(&path, b"a").unwrap();
+
+        let result = save_buffer(&BufferName::Hex(path.clone()), "00000000  zz\n", &path);
+        let on_disk = std::fs::read(&path).unwrap();
+
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(result.is_err());
+        assert_eq!(on_disk, b"a");
+    }
+}
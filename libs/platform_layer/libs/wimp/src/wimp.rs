@@ -10,7 +10,7 @@ use std::{
     time::Duration,
 };
 use wimp_render::{get_find_replace_info, FindReplaceInfo, get_go_to_position_info, GoToPositionInfo, ViewOutput, ViewAction};
-use wimp_types::{ui, ui::{PhysicalButtonState, Navigation}, transform_at, BufferStatus, BufferStatusTransition, CustomEvent, get_clipboard, ClipboardProvider, Dimensions, LabelledCommand, RunConsts, RunState, MenuMode};
+use wimp_types::{ui, ui::{PhysicalButtonState, Navigation}, transform_at, BufferStatus, BufferStatusTransition, ChangedFileChoice, NotUtf8Choice, CustomEvent, get_clipboard, ClipboardProvider, Dimensions, CommandKey, LabelledCommand, RunState, MenuMode, WimpMenuMode, keybindings, theme};
 use macros::{d, dbg};
 use platform_types::{screen_positioning::screen_to_text_box, *};
 use shared::{Res};
@@ -39,18 +39,20 @@ pub fn run(update_and_render: UpdateAndRender) -> Res<()> {
 
     let mut data_dir = None;
     let mut hidpi_factor_override = None;
+    let mut record_path = None;
 
     const VERSION: &str = "--version";
     const HELP: &str = "--help";
     const DATA_DIR_OVERRIDE: &str = "--data-dir-override";
     const HIDPI_OVERRIDE: &str = "--hidpi-override";
     const LICENSE: &str = "--license";
+    const RECORD: &str = "--record";
 
     while let Some(s) = args.next() {
         let s: &str = &s;
         match s {
             HELP => {
-                let accepted_args = [VERSION, HELP, DATA_DIR_OVERRIDE, HIDPI_OVERRIDE, LICENSE];
+                let accepted_args = [VERSION, HELP, DATA_DIR_OVERRIDE, HIDPI_OVERRIDE, LICENSE, RECORD];
                 println!("accepted args: ");
                 for arg in accepted_args.iter() {
                     print!("    {}", arg);
@@ -60,6 +62,9 @@ pub fn run(update_and_render: UpdateAndRender) -> Res<()> {
                     if *arg == HIDPI_OVERRIDE {
                         print!(" <hidpi factor (positive floating point number)>");
                     }
+                    if *arg == RECORD {
+                        print!(" <input log path>");
+                    }
                     println!()
                 }
                 std::process::exit(0)
@@ -91,6 +96,15 @@ pub fn run(update_and_render: UpdateAndRender) -> Res<()> {
                     f64::from_str(&s).ok()
                 });
             }
+            RECORD => {
+                record_path = Some(args.next().ok_or_else(|| {
+                    format!(
+                        "{0} needs an argument. For example: {0} ./session.txt",
+                        RECORD
+                    )
+                })?)
+                .map(PathBuf::from);
+            }
             LICENSE => {
                 println!("{} program by Ryan Wiedemann.", title);
                 println!("Source and license available at:");
@@ -145,6 +159,8 @@ pub fn run(update_and_render: UpdateAndRender) -> Res<()> {
 
     let edited_files_dir_buf = data_dir.join("edited_files_v1/");
     let edited_files_index_path_buf = data_dir.join("edited_files_v1_index.txt");
+    let keybindings_path = data_dir.join(keybindings::FILE_NAME);
+    let themes_dir = data_dir.join(theme::DIRECTORY_NAME);
 
     use glutin::event_loop::EventLoop;
     let events: EventLoop<CustomEvent> = glutin::event_loop::EventLoop::with_user_event();
@@ -173,10 +189,13 @@ pub fn run(update_and_render: UpdateAndRender) -> Res<()> {
         }
     }
 
+    // The theme is loaded later, once we can report errors, so this gets updated then.
+    let mut clear_colour = theme::Theme::default().ui.text_background;
+
     let (mut gl_state, char_dims) = gl_layer::init(
         get_hidpi_factor!() as f32,
         &wimp_render::TEXT_SIZES,
-        wimp_render::TEXT_BACKGROUND_COLOUR,
+        clear_colour,
         |symbol| glutin_context.get_proc_address(symbol) as _,
     )?;
 
@@ -263,21 +282,78 @@ pub fn run(update_and_render: UpdateAndRender) -> Res<()> {
     // out of the editor thread
     let (editor_out_sink, editor_out_source) = channel();
 
-    let mut editor_join_handle = Some(
+    // Every input the editor receives is logged to this file, if there is one, so that
+    // the session can be replayed later.
+    let input_log_file = match record_path {
+        Some(path) => {
+            use std::io::Write;
+            let mut file = std::fs::File::create(path)?;
+            file.write_all(input_log::header_line().as_bytes())?;
+            Some(file)
+        }
+        None => None,
+    };
+
+    let mut editor_join_handle = Some({
+        let proxy = event_proxy.clone();
+
         std::thread::Builder::new()
             .name("editor".to_string())
             .spawn(move || {
+                let mut input_log_file = input_log_file;
+                let start = std::time::Instant::now();
+
+                macro_rules! log_line {
+                    ($line: expr) => {
+                        if let Some(file) = input_log_file.as_mut() {
+                            use std::io::Write;
+                            if let Err(e) = file.write_all($line.as_bytes()) {
+                                let _hope_it_gets_there =
+                                    proxy.send_event(CustomEvent::InputLogError(e.to_string()));
+                                // Only report the error once, instead of for every input.
+                                input_log_file = None;
+                            }
+                        }
+                    };
+                }
+
                 while let Ok(input) = editor_in_source.recv() {
+                    log_line!(input_log::entry_line(start.elapsed(), &input));
                     let was_quit = Input::Quit == input;
                     let pair = update_and_render(input);
+                    if was_quit {
+                        log_line!(input_log::view_hash_line(input_log::view_hash(&pair.0)));
+                    }
                     let _hope_it_gets_there = editor_out_sink.send(pair);
                     if was_quit {
                         return;
                     }
                 }
             })
-            .expect("Could not start editor thread!"),
-    );
+            .expect("Could not start editor thread!")
+    });
+
+    let file_watcher = {
+        let proxy = event_proxy.clone();
+
+        file_watcher::Watcher::new(move |path| {
+            // We read the file here, on the watcher's thread, so that the event loop
+            // does not have to wait for the disk.
+            let read_result = match std::fs::read(&path) {
+                Ok(bytes) => Ok(bytes),
+                // The file was deleted or moved away, so there is no new text to show.
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => return,
+                Err(err) => Err(err.to_string()),
+            };
+            let _hope_it_gets_there = proxy.send_event(
+                CustomEvent::FileChanged(path, read_result)
+            );
+        })?
+    };
+    // The paths the `file_watcher` was last told to watch.
+    let mut watched_paths: Vec<PathBuf> = Vec::new();
+    // What the editor was last told about whether the undo tree is shown.
+    let mut undo_tree_shown = false;
 
     let previous_tabs =
             edited_storage::load_previous_tabs(&edited_files_dir_buf, &edited_files_index_path_buf);
@@ -328,9 +404,12 @@ pub fn run(update_and_render: UpdateAndRender) -> Res<()> {
             ),
         };
 
-        let (v, c) = update_and_render(
+        // This goes through the editor thread, like every other input, so that it gets
+        // recorded.
+        editor_in_sink.send(
             get_non_font_size_dependents_input!(d!(), dimensions)
-        );
+        )?;
+        let (v, c) = editor_out_source.recv()?;
 
         let mut cmds = VecDeque::with_capacity(EVENTS_PER_FRAME);
         cmds.push_back(c);
@@ -355,6 +434,8 @@ pub fn run(update_and_render: UpdateAndRender) -> Res<()> {
             dimensions,
             clipboard,
             event_proxy,
+            written_hashes: d!(),
+            themes_dir,
         }
     };
 
@@ -400,36 +481,57 @@ pub fn run(update_and_render: UpdateAndRender) -> Res<()> {
             }};
         }
 
-        for (i, (name, data)) in previous_tabs.into_iter().enumerate() {
-            call_u_and_r!(Input::AddOrSelectBuffer(name, data));
+        for (i, (name, data, history)) in previous_tabs.into_iter().enumerate() {
+            // What is on disk may have changed since we stored the buffer, so we
+            // compare against that rather than what it was back then.
+            let on_disk = match &name {
+                BufferName::Path(p) => std::fs::read_to_string(p).ok(),
+                BufferName::Hex(p) => std::fs::read(p).ok().map(|bytes| hex_dump::format(&bytes)),
+                BufferName::Scratch(_) => None,
+            };
+            let matches_disk = on_disk.as_ref() == Some(&data);
 
-            let index_state = r_s.view.index_state();
+            call_u_and_r!(Input::AddOrSelectBuffer(name.clone(), data));
 
-            // if we bothered saving them before, they were clearly edited.
-            r_s.buffer_status_map.insert(
-                index_state,
-                index_state.new_index(g_i::IndexPart::or_max(i)),
-                BufferStatus::EditedAndSaved,
-            );
+            if let Some(history) = history {
+                call_u_and_r!(Input::RestoreBufferHistory(name.clone(), history));
+            }
+
+            if let Some(on_disk) = on_disk {
+                call_u_and_r!(Input::SetOnDiskText(name, on_disk));
+            }
+
+            // If we bothered saving them before, they were edited then. If they now
+            // match the disk anyway, we leave them as unedited so the stored copy
+            // gets cleaned up.
+            if !matches_disk {
+                let index_state = r_s.view.index_state();
+
+                r_s.buffer_status_map.insert(
+                    index_state,
+                    index_state.new_index(g_i::IndexPart::or_max(i)),
+                    BufferStatus::EditedAndSaved,
+                );
+            }
         }
 
         type CommandVars = RunState;
 
-        let mut r_c: RunConsts = RunConsts {
-            commands: std::collections::BTreeMap::new(),
-        };
+        let mut default_commands: Vec<(CommandKey, LabelledCommand)> = Vec::with_capacity(64);
 
         macro_rules! register_command {
-            ($modifiers: expr, $main_key: ident, $label: literal, $(_)? $code: block) => {
-                register_command!($modifiers, $main_key, $label, _unused_identifier $code)
+            ($modifiers: expr, $main_key: ident, $name: ident, $label: literal, $(_)? $code: block) => {
+                register_command!($modifiers, $main_key, $name, $label, _unused_identifier $code)
             };
-            ($modifiers: expr, $main_key: ident, $label: literal, $vars: ident $code: block) => {{
+            ($modifiers: expr, $main_key: ident, $name: ident, $label: literal, $vars: ident $code: block) => {{
                 fn command($vars: &mut CommandVars) {
                     $code
                 }
-                let key = ($modifiers, VirtualKeyCode::$main_key);  
-                debug_assert!(r_c.commands.get(&key).is_none());
-                r_c.commands.insert(key, LabelledCommand{ label: $label, command});
+                let key = ($modifiers, VirtualKeyCode::$main_key);
+                default_commands.push((
+                    key,
+                    LabelledCommand{ name: stringify!($name), label: $label, command }
+                ));
             }}
         }
 
@@ -472,24 +574,29 @@ pub fn run(update_and_render: UpdateAndRender) -> Res<()> {
         }
 
         macro_rules! save_to_disk {
-            ($path: expr, $str: expr, $buffer_index: expr) => {
-                save_to_disk!(r_s, $path, $str, $buffer_index)
+            ($path: expr, $name: expr, $str: expr, $buffer_index: expr) => {
+                save_to_disk!(r_s, $path, $name, $str, $buffer_index)
             };
-            ($r_s: ident, $path: expr, $str: expr, $buffer_index: expr) => {
+            ($r_s: ident, $path: expr, $name: expr, $str: expr, $buffer_index: expr) => {
                 save_to_disk!(
                     &mut $r_s.ui,
                     &$r_s.editor_in_sink,
                     &mut $r_s.buffer_status_map,
+                    &mut $r_s.written_hashes,
                     &$r_s.view,
                     $path,
+                    $name,
                     $str,
                     $buffer_index
                 )
             };
-            ($ui: expr, $editor_in_sink: expr, $buffer_status_map: expr, $view: expr, $path: expr, $str: expr, $buffer_index: expr) => {
+            ($ui: expr, $editor_in_sink: expr, $buffer_status_map: expr, $written_hashes: expr, $view: expr, $path: expr, $name: expr, $str: expr, $buffer_index: expr) => {
                 let index = $buffer_index;
-                match std::fs::write($path, $str) {
-                    Ok(_) => {
+                let name: &BufferName = $name;
+                let s: &str = &$str;
+                match shared::save_buffer(name, s, $path) {
+                    Ok(bytes) => {
+                        $written_hashes.insert($path.to_path_buf(), content_hash(&bytes));
                         transform_at(
                             $buffer_status_map,
                             $view.index_state(), 
@@ -497,6 +604,16 @@ pub fn run(update_and_render: UpdateAndRender) -> Res<()> {
                             BufferStatusTransition::Save
                         );
                         call_u_and_r!($ui, $editor_in_sink, Input::SavedAs(index, $path.to_path_buf()));
+
+                        if let BufferName::Hex(_) = name {
+                            // Edits to the ASCII column are not saved, and the bytes
+                            // may have been moved between lines, so we show the dump
+                            // of what was saved, which is also what reopening it shows.
+                            let formatted = hex_dump::format(&bytes);
+                            if formatted != s {
+                                call_u_and_r!($ui, $editor_in_sink, Input::ReloadBuffer(index, formatted));
+                            }
+                        }
                     }
                     Err(err) => {
                         handle_platform_error!($ui, $editor_in_sink, $view, err);
@@ -512,6 +629,9 @@ pub fn run(update_and_render: UpdateAndRender) -> Res<()> {
                     Ok(s) => {
                         call_u_and_r!(Input::AddOrSelectBuffer(BufferName::Path(p), s));
                     }
+                    Err(err) if err.kind() == std::io::ErrorKind::InvalidData => {
+                        r_s.view.show_not_utf8_menu(p);
+                    }
                     Err(err) => {
                         handle_platform_error!(r_s, err);
                     }
@@ -519,6 +639,63 @@ pub fn run(update_and_render: UpdateAndRender) -> Res<()> {
             }};
         }
 
+        macro_rules! load_theme {
+            ($r_s: ident, $name: expr) => {{
+                let name: String = $name;
+                let path = theme::path(&$r_s.themes_dir, &name);
+                match std::fs::read_to_string(&path) {
+                    Ok(file) => {
+                        load_theme!($r_s, name, &file);
+                    }
+                    Err(err) => {
+                        handle_platform_error!($r_s, format!("{}: {}", path.display(), err));
+
+                        // We make the dark theme the current one, so that the error
+                        // does not come back on every start, and so that we watch a
+                        // theme file that exists.
+                        match std::fs::read_to_string(theme::path(&$r_s.themes_dir, theme::DARK)) {
+                            Ok(file) => {
+                                load_theme!($r_s, theme::DARK.to_owned(), &file);
+                            }
+                            Err(_) => {
+                                $r_s.view.set_theme(theme::DARK.to_owned(), theme::Theme::dark());
+                            }
+                        }
+                        let current_path = $r_s.themes_dir.join(theme::CURRENT_FILE_NAME);
+                        if let Err(err) = std::fs::write(current_path, theme::DARK) {
+                            handle_platform_error!($r_s, err);
+                        }
+                    }
+                }
+            }};
+            ($r_s: ident, $name: expr, $file: expr) => {{
+                let name: String = $name;
+                let path = theme::path(&$r_s.themes_dir, &name);
+                let (loaded, errors) = theme::parse($file);
+                for err in errors {
+                    handle_platform_error!($r_s, format!("{}: {}", path.display(), err));
+                }
+                $r_s.view.set_theme(name, loaded);
+            }};
+        }
+
+        macro_rules! load_hex_view {
+            ($r_s: ident, $path: expr) => {{
+                let p = $path;
+                match std::fs::read(&p) {
+                    Ok(bytes) => {
+                        call_u_and_r!(
+                            $r_s,
+                            Input::AddOrSelectBuffer(BufferName::Hex(p), hex_dump::format(&bytes))
+                        );
+                    }
+                    Err(err) => {
+                        handle_platform_error!($r_s, err);
+                    }
+                }
+            }};
+        }
+
         macro_rules! file_chooser_call {
             ($event_proxy: expr, $func: ident, $default_path: expr, $path: ident in $event: expr) => {
                 let proxy =
@@ -571,10 +748,10 @@ pub fn run(update_and_render: UpdateAndRender) -> Res<()> {
         const SHIFT: ModifiersState = ModifiersState::SHIFT;
 
         register_commands!{
-            [empty, Apps, "Open/Close this menu.", r_s {
+            [empty, Apps, toggle_command_menu, "Open/Close this menu.", r_s {
                 r_s.view.toggle_command_menu();
             }]
-            [empty, Escape, "Close menus.", r_s {
+            [empty, Escape, close_menus, "Close menus.", r_s {
                 r_s.view.close_menus();
 
                 call_u_and_r!(r_s, Input::SetSizeDependents(
@@ -592,48 +769,48 @@ pub fn run(update_and_render: UpdateAndRender) -> Res<()> {
                 ));
                 call_u_and_r!(r_s, Input::CloseMenuIfAny);
             }]
-            [empty, F1, "Delete lines.", r_s {
+            [empty, F1, delete_lines, "Delete lines.", r_s {
                 call_u_and_r!(r_s, Input::DeleteLines);
             }]
-            [empty, Back, "Backspace.", r_s {
+            [empty, Back, backspace, "Backspace.", r_s {
                 call_u_and_r!(r_s, Input::Delete);
             }]
-            [empty, Up, "Move all cursors up.", r_s {
+            [empty, Up, move_cursors_up, "Move all cursors up.", r_s {
                 call_u_and_r!(r_s, Input::MoveAllCursors(Move::Up));
                 r_s.ui.fresh_navigation = Navigation::Up;
             }]
-            [empty, Down, "Move all cursors down.", r_s {
+            [empty, Down, move_cursors_down, "Move all cursors down.", r_s {
                 call_u_and_r!(r_s, Input::MoveAllCursors(Move::Down));
                 r_s.ui.fresh_navigation = Navigation::Down;
             }]
-            [empty, Left, "Move all cursors left.", r_s {
+            [empty, Left, move_cursors_left, "Move all cursors left.", r_s {
                 call_u_and_r!(r_s, Input::MoveAllCursors(Move::Left));
             }]
-            [empty, Right, "Move all cursors right.", r_s {
+            [empty, Right, move_cursors_right, "Move all cursors right.", r_s {
                 call_u_and_r!(r_s, Input::MoveAllCursors(Move::Right));
             }]
-            [empty, Home, "Move all cursors to line start.", r_s {
+            [empty, Home, move_cursors_to_line_start, "Move all cursors to line start.", r_s {
                 call_u_and_r!(r_s, Input::MoveAllCursors(Move::ToLineStart));
             }]
-            [empty, End, "Move all cursors to line end.", r_s {
+            [empty, End, move_cursors_to_line_end, "Move all cursors to line end.", r_s {
                 call_u_and_r!(r_s, Input::MoveAllCursors(Move::ToLineEnd));
             }]
-            [empty, Tab, "Indent in selection/line.", r_s {
+            [empty, Tab, tab_in, "Indent in selection/line.", r_s {
                 call_u_and_r!(r_s, Input::TabIn);
             }]
-            [CTRL, Home, "Move cursors to start.", state {
+            [CTRL, Home, move_cursors_to_buffer_start, "Move cursors to start.", state {
                 call_u_and_r!(state, Input::MoveAllCursors(Move::ToBufferStart))
             }]
-            [CTRL, End, "Move cursors to end.", state {
+            [CTRL, End, move_cursors_to_buffer_end, "Move cursors to end.", state {
                 call_u_and_r!(state, Input::MoveAllCursors(Move::ToBufferEnd))
             }]
-            [CTRL, Left, "Move cursors to previous likely edit location.", state {
+            [CTRL, Left, move_cursors_to_previous_likely_edit_location, "Move cursors to previous likely edit location.", state {
                 call_u_and_r!(state, Input::MoveAllCursors(Move::ToPreviousLikelyEditLocation))
             }]
-            [CTRL, Right, "Move cursors to next likely edit location.", state {
+            [CTRL, Right, move_cursors_to_next_likely_edit_location, "Move cursors to next likely edit location.", state {
                 call_u_and_r!(state, Input::MoveAllCursors(Move::ToNextLikelyEditLocation))
             }]
-            [CTRL, Key0, "Reset scroll (context sensitive).", r_s {
+            [CTRL, Key0, reset_scroll, "Reset scroll (context sensitive).", r_s {
                 let ui = &mut r_s.ui;
                 let font_info = r_s.dimensions.font;
                 if wimp_render::inside_tab_area(ui.mouse_pos, font_info) {
@@ -646,22 +823,31 @@ pub fn run(update_and_render: UpdateAndRender) -> Res<()> {
                     call_u_and_r!(r_s, Input::ResetScroll);
                 }
             }]
-            [CTRL, A, "Select all.", state {
+            [CTRL, A, select_all, "Select all.", state {
                 call_u_and_r!(state, Input::SelectAll)
             }]
-            [CTRL, C, "Copy.", state {
+            [CTRL, C, copy, "Copy.", state {
                 call_u_and_r!(state, Input::Copy)
             }]
-            [CTRL, D, "Extend selection with search.", state {
+            [CTRL, D, extend_selection_with_search, "Extend selection with search.", state {
                 call_u_and_r!(state, Input::ExtendSelectionWithSearch)
             }]
-            [CTRL, F, "Find/Replace in current file.", r_s {
+            [CTRL, E, toggle_comments, "Toggle line comments.", state {
+                call_u_and_r!(state, Input::ToggleComments);
+            }]
+            [CTRL, F, find_in_current_file, "Find/Replace in current file.", r_s {
                 switch_menu_mode!(r_s, MenuMode::FindReplace(FindReplaceMode::CurrentFile));
             }]
-            [CTRL, G, "Go to position.", r_s {
+            [CTRL, G, go_to_position, "Go to position.", r_s {
                 switch_menu_mode!(r_s, MenuMode::GoToPosition);
             }]
-            [CTRL, O, "Open file.", r_s {
+            [CTRL, H, replace_all, "Replace all in current file.", state {
+                call_u_and_r!(state, Input::ReplaceAll);
+            }]
+            [CTRL, M, jump_to_matching_brackets, "Jump to matching bracket.", state {
+                call_u_and_r!(state, Input::JumpToMatchingBrackets);
+            }]
+            [CTRL, O, open_file, "Open file.", r_s {
                 file_chooser_call!(
                     r_s.event_proxy,
                     single,
@@ -669,10 +855,10 @@ pub fn run(update_and_render: UpdateAndRender) -> Res<()> {
                     p in CustomEvent::OpenFile(p)
                 );
             }]
-            [CTRL, P, "Switch files.", r_s {
+            [CTRL, P, switch_files, "Switch files.", r_s {
                 switch_menu_mode!(r_s, MenuMode::FileSwitcher);
             }]
-            [CTRL, S, "Save.", r_s {
+            [CTRL, S, save, "Save.", r_s {
                 let (i, buffer) = r_s.view.current_text_index_and_buffer();
                 match buffer.name {
                     BufferName::Scratch(_) => {
@@ -683,18 +869,24 @@ pub fn run(update_and_render: UpdateAndRender) -> Res<()> {
                             p in CustomEvent::SaveNewFile(p, i)
                         );
                     }
-                    BufferName::Path(ref p) => {
-                        save_to_disk!(r_s, p, std::borrow::Cow::from(buffer.data.chars.clone()).as_ref(), i);
+                    BufferName::Path(ref p) | BufferName::Hex(ref p) => {
+                        save_to_disk!(
+                            r_s,
+                            p,
+                            &buffer.name,
+                            std::borrow::Cow::from(buffer.data.chars.clone()),
+                            i
+                        );
                     }
                 }
             }]
-            [CTRL, T, "New scratch buffer.", state {
+            [CTRL, T, new_scratch_buffer, "New scratch buffer.", state {
                 call_u_and_r!(state, Input::NewScratchBuffer(None));
             }]
-            [CTRL, V, "Paste.", state {
+            [CTRL, V, paste, "Paste.", state {
                 call_u_and_r!(state, Input::Paste(state.clipboard.get_contents().ok()));
             }]
-            [CTRL, W, "Close tab.", r_s {
+            [CTRL, W, close_tab, "Close tab.", r_s {
                 match r_s.view.current_buffer_id() {
                     BufferId {
                         kind: BufferIdKind::Text,
@@ -708,16 +900,25 @@ pub fn run(update_and_render: UpdateAndRender) -> Res<()> {
                     }
                 }
             }]
-            [CTRL, X, "Cut.", state {
+            [CTRL, X, cut, "Cut.", state {
                 call_u_and_r!(state, Input::Cut);
             }]
-            [CTRL, Y, "Redo.", state {
+            [CTRL, Y, redo, "Redo.", state {
                 call_u_and_r!(state, Input::Redo);
             }]
-            [CTRL, Z, "Undo.", state {
+            [CTRL, Z, undo, "Undo.", state {
                 call_u_and_r!(state, Input::Undo);
             }]
-            [CTRL | SHIFT, Tab, "Previous Tab.", state {
+            [CTRL, Up, swap_lines_up, "Swap selected lines with the line above.", state {
+                call_u_and_r!(state, Input::SwapLinesUp);
+            }]
+            [CTRL, Down, swap_lines_down, "Swap selected lines with the line below.", state {
+                call_u_and_r!(state, Input::SwapLinesDown);
+            }]
+            [CTRL, Period, fold, "Fold selected lines or the syntax node at the cursors.", state {
+                call_u_and_r!(state, Input::Fold);
+            }]
+            [CTRL | SHIFT, Tab, previous_tab, "Previous Tab.", state {
                 call_u_and_r!(
                     state,
                     Input::AdjustBufferSelection(
@@ -725,7 +926,7 @@ pub fn run(update_and_render: UpdateAndRender) -> Res<()> {
                     )
                 );
             }]
-            [CTRL, Tab, "Next tab.", state {
+            [CTRL, Tab, next_tab, "Next tab.", state {
                 call_u_and_r!(
                     state,
                     Input::AdjustBufferSelection(
@@ -733,37 +934,111 @@ pub fn run(update_and_render: UpdateAndRender) -> Res<()> {
                     )
                 );
             }]
-            [CTRL | SHIFT, F1, "Add RunState snapshot", r_s {
+            [CTRL | SHIFT, F, find_in_open_buffers, "Find in open buffers.", r_s {
+                switch_menu_mode!(r_s, MenuMode::FindReplace(FindReplaceMode::OpenBuffers));
+            }]
+            [CTRL | SHIFT, F1, add_run_state_snapshot, "Add RunState snapshot", r_s {
                 let snapshot = format!("{:#?}", r_s);
                 call_u_and_r!(r_s, Input::NewScratchBuffer(snapshot.into()));
             }]
-            [CTRL | ALT, Key0, "Insert sequential numbers at cursors.", state {
+            [CTRL | ALT, Up, swap_syntax_node_up, "Move the syntax node at the cursors before the previous one.", state {
+                call_u_and_r!(state, Input::SwapSyntaxNodeUp);
+            }]
+            [CTRL | ALT, Down, swap_syntax_node_down, "Move the syntax node at the cursors after the next one.", state {
+                call_u_and_r!(state, Input::SwapSyntaxNodeDown);
+            }]
+            [CTRL | ALT, M, extract_function, "Extract the selected code into a new function.", state {
+                call_u_and_r!(state, Input::ExtractFunction);
+            }]
+            [CTRL | ALT, V, extract_variable, "Extract the selected expression into a new variable.", state {
+                call_u_and_r!(state, Input::ExtractVariable);
+            }]
+            [CTRL | ALT | SHIFT, V, extract_variable_everywhere, "Extract the selected expression, and the identical ones in the same block, into a new variable.", state {
+                call_u_and_r!(state, Input::ExtractVariableEverywhere);
+            }]
+            [CTRL | ALT, Key0, insert_numbers_at_cursors, "Insert sequential numbers at cursors.", state {
                 call_u_and_r!(state, Input::InsertNumbersAtCursors);
             }]
-            [CTRL | ALT, L, "Switch document parsing to next language.", state {
+            [CTRL | ALT, T, switch_theme, "Switch to the next theme.", r_s {
+                match std::fs::read_dir(&r_s.themes_dir) {
+                    Ok(entries) => {
+                        let mut names: Vec<String> = entries
+                            .filter_map(|entry| theme::name_from_path(&entry.ok()?.path()))
+                            .collect();
+                        names.sort();
+
+                        let next = names.iter()
+                            .position(|name| name == r_s.view.theme_name())
+                            .and_then(|i| names.get(i + 1))
+                            .or_else(|| names.first())
+                            .cloned();
+
+                        if let Some(name) = next {
+                            let current_path = r_s.themes_dir.join(theme::CURRENT_FILE_NAME);
+                            if let Err(err) = std::fs::write(current_path, &name) {
+                                handle_platform_error!(r_s, err);
+                            }
+                            load_theme!(r_s, name);
+                        }
+                    }
+                    Err(err) => {
+                        handle_platform_error!(r_s, err);
+                    }
+                }
+            }]
+            [CTRL | ALT, H, open_hex_view, "Open a hex view of the current file.", r_s {
+                if let Some(p) = r_s.view.current_path() {
+                    load_hex_view!(r_s, p);
+                }
+            }]
+            [CTRL | ALT, F, find_in_directory, "Find in the current file's directory.", r_s {
+                switch_menu_mode!(r_s, MenuMode::FindReplace(FindReplaceMode::Directory));
+            }]
+            [CTRL | ALT, L, next_language, "Switch document parsing to next language.", state {
                 call_u_and_r!(state, Input::NextLanguage);
             }]
-            [CTRL | SHIFT, Home, "Move all cursors to buffer start.", state {
+            [CTRL | ALT, R, toggle_regex_search, "Toggle regex search.", state {
+                call_u_and_r!(state, Input::ToggleRegexSearch);
+            }]
+            [CTRL | ALT, C, cycle_search_case_sensitivity, "Cycle search case sensitivity.", state {
+                call_u_and_r!(state, Input::CycleSearchCaseSensitivity);
+            }]
+            [CTRL | ALT, W, toggle_whole_word_search, "Toggle whole word search.", state {
+                call_u_and_r!(state, Input::ToggleWholeWordSearch);
+            }]
+            [CTRL | ALT, S, toggle_search_in_selection, "Toggle searching only in the selection.", state {
+                call_u_and_r!(state, Input::ToggleSearchInSelection);
+            }]
+            [CTRL | ALT, Y, next_undo_branch, "Switch to the next undo branch.", state {
+                call_u_and_r!(state, Input::NextUndoBranch);
+            }]
+            [CTRL | ALT, Z, previous_undo_branch, "Switch to the previous undo branch.", state {
+                call_u_and_r!(state, Input::PreviousUndoBranch);
+            }]
+            [CTRL | SHIFT, Home, extend_selections_to_buffer_start, "Move all cursors to buffer start.", state {
                 call_u_and_r!(state, Input::ExtendSelectionForAllCursors(
                     Move::ToBufferStart
                 ));
             }]
-            [CTRL | SHIFT, End, "Move all cursors to buffer end.", state {
+            [CTRL | SHIFT, End, extend_selections_to_buffer_end, "Move all cursors to buffer end.", state {
                 call_u_and_r!(state, Input::ExtendSelectionForAllCursors(
                     Move::ToBufferEnd
                 ));
             }]
-            [CTRL | SHIFT, Left, "Move all cursors to previous likely edit location.", state {
+            [CTRL | SHIFT, Left, extend_selections_to_previous_likely_edit_location, "Move all cursors to previous likely edit location.", state {
                 call_u_and_r!(state, Input::ExtendSelectionForAllCursors(
                     Move::ToPreviousLikelyEditLocation
                 ));
             }]
-            [CTRL | SHIFT, Right, "Move all cursors to next likely edit location.", state {
+            [CTRL | SHIFT, Right, extend_selections_to_next_likely_edit_location, "Move all cursors to next likely edit location.", state {
                 call_u_and_r!(state, Input::ExtendSelectionForAllCursors(
                     Move::ToNextLikelyEditLocation
                 ));
             }]
-            [CTRL | SHIFT, S, "Save new file.", r_s {
+            [CTRL | SHIFT, M, select_inside_brackets, "Select inside brackets.", state {
+                call_u_and_r!(state, Input::SelectInsideBrackets);
+            }]
+            [CTRL | SHIFT, S, save_new_file, "Save new file.", r_s {
                 let i = r_s.view.current_text_index();
                 file_chooser_call!(
                     r_s.event_proxy,
@@ -772,68 +1047,118 @@ pub fn run(update_and_render: UpdateAndRender) -> Res<()> {
                     p in CustomEvent::SaveNewFile(p, i)
                 );
             }]
-            [CTRL | SHIFT, Z, "Redo.", state {
+            [CTRL | SHIFT, Z, redo, "Redo.", state {
                 call_u_and_r!(state, Input::Redo);
             }]
-            [CTRL | SHIFT, Slash, "Toggle debug menu.", r_s {
+            [CTRL | SHIFT, Key8, toggle_whitespace_visibility, "Show/Hide whitespace and line endings.", r_s {
+                r_s.view.toggle_whitespace_visibility();
+            }]
+            [CTRL | SHIFT, Period, unfold, "Unfold the fold at the cursors.", state {
+                call_u_and_r!(state, Input::Unfold);
+            }]
+            [CTRL | SHIFT, Slash, toggle_debug_menu, "Toggle debug menu.", r_s {
                 r_s.view.toggle_debug_menu();
             }]
-            [SHIFT, Tab, "Indent out selection/line.", r_s {
+            [SHIFT, Tab, tab_out, "Indent out selection/line.", r_s {
                 call_u_and_r!(r_s, Input::TabOut);
             }]
-            [SHIFT, Up, "Extend selection(s) upward.", r_s {
+            [SHIFT, Up, extend_selections_up, "Extend selection(s) upward.", r_s {
                 call_u_and_r!(r_s, Input::ExtendSelectionForAllCursors(Move::Up));
                 r_s.ui.fresh_navigation = Navigation::Up;
             }]
-            [SHIFT, Down, "Extend selection(s) downward.", r_s {
+            [SHIFT, Down, extend_selections_down, "Extend selection(s) downward.", r_s {
                 call_u_and_r!(r_s, Input::ExtendSelectionForAllCursors(Move::Down));
                 r_s.ui.fresh_navigation = Navigation::Down;
             }]
-            [SHIFT, Left, "Extend selection(s) leftward.", r_s {
+            [SHIFT, Left, extend_selections_left, "Extend selection(s) leftward.", r_s {
                 call_u_and_r!(r_s, Input::ExtendSelectionForAllCursors(Move::Left));
             }]
-            [SHIFT, Right, "Extend selection(s) rightward.", r_s {
+            [SHIFT, Right, extend_selections_right, "Extend selection(s) rightward.", r_s {
                 call_u_and_r!(r_s, Input::ExtendSelectionForAllCursors(Move::Right));
             }]
-            [SHIFT, Home, "Extend selection(s) to line start.", r_s {
+            [SHIFT, Home, extend_selections_to_line_start, "Extend selection(s) to line start.", r_s {
                 call_u_and_r!(r_s, Input::ExtendSelectionForAllCursors(Move::ToLineStart));
             }]
-            [SHIFT, End, "Extend selection(s) to line end.", r_s {
+            [SHIFT, End, extend_selections_to_line_end, "Extend selection(s) to line end.", r_s {
                 call_u_and_r!(r_s, Input::ExtendSelectionForAllCursors(Move::ToLineEnd));
             }]
-            [SHIFT, Return, "Insert new line.", r_s {
+            [SHIFT, Return, insert_new_line, "Insert new line.", r_s {
                 // TODO: Do we actually need this command?
                 call_u_and_r!(r_s, Input::Insert('\n'));
             }]
-            [LOGO | CTRL, Tab, "Move current tab right.", r_s {
+            [LOGO | CTRL, Tab, move_tab_right, "Move current tab right.", r_s {
                 call_u_and_r!(r_s, Input::AdjustBufferSelection(
                     SelectionAdjustment::Move(SelectionMove::Right)
                 ));
             }]
-            [LOGO | CTRL | SHIFT, Tab, "Move current tab left.", r_s {
+            [LOGO | CTRL | SHIFT, Tab, move_tab_left, "Move current tab left.", r_s {
                 call_u_and_r!(r_s, Input::AdjustBufferSelection(
                     SelectionAdjustment::Move(SelectionMove::Left)
                 ));
             }]
-            [LOGO | CTRL, Home, "Move current tab to start of row.", r_s {
+            [LOGO | CTRL, Home, move_tab_to_row_start, "Move current tab to start of row.", r_s {
                 call_u_and_r!(r_s, Input::AdjustBufferSelection(
                     SelectionAdjustment::Move(SelectionMove::ToStart)
                 ));
             }]
-            [LOGO | CTRL, End, "Move current tab to end of row.", r_s {
+            [LOGO | CTRL, End, move_tab_to_row_end, "Move current tab to end of row.", r_s {
                 call_u_and_r!(r_s, Input::AdjustBufferSelection(
                     SelectionAdjustment::Move(SelectionMove::ToEnd)
                 ));
             }]
         }
 
+        let keybindings = match std::fs::read_to_string(&keybindings_path) {
+            Ok(s) => s,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                let default_file = keybindings::default_file(&default_commands);
+                if let Err(err) = std::fs::write(&keybindings_path, &default_file) {
+                    handle_platform_error!(r_s, err);
+                }
+                default_file
+            }
+            Err(err) => {
+                handle_platform_error!(r_s, err);
+                String::new()
+            }
+        };
+
+        let (r_c, keybindings_errors) = keybindings::apply(&default_commands, &keybindings);
+        for err in keybindings_errors {
+            handle_platform_error!(r_s, err);
+        }
+
+        if let Err(err) = std::fs::create_dir_all(&r_s.themes_dir) {
+            handle_platform_error!(r_s, err);
+        }
+        for (name, shipped) in theme::Theme::shipped() {
+            let path = theme::path(&r_s.themes_dir, name);
+            if !path.exists() {
+                if let Err(err) = std::fs::write(&path, shipped.to_file()) {
+                    handle_platform_error!(r_s, err);
+                }
+            }
+        }
+        let current_theme_name = std::fs::read_to_string(
+            r_s.themes_dir.join(theme::CURRENT_FILE_NAME)
+        )
+            .map(|s| s.trim().to_owned())
+            .unwrap_or_else(|_| theme::DARK.to_owned());
+        load_theme!(r_s, current_theme_name);
+
         macro_rules! perform_command {
-            ($key: expr) => {
-                if let Some(LabelledCommand{ label, command }) = r_c.commands.get($key) {
+            (named $name: expr) => {
+                perform_command!(labelled r_c.named_commands.get($name))
+            };
+            (labelled $labelled_option: expr) => {
+                if let Some(LabelledCommand{ label, command, .. }) = $labelled_option {
                     dbg!(label);
                     command(&mut r_s);
                 }
-            }
+            };
+            ($key: expr) => {
+                perform_command!(labelled r_c.commands.get($key).map(|bound| &bound.command))
+            };
         }
 
         events.run(move |event, _, control_flow| {
@@ -938,6 +1263,7 @@ pub fn run(update_and_render: UpdateAndRender) -> Res<()> {
                              && c != '\u{c}'    // new page/form feed (sent with Ctrl-l)
                              && c != '\u{f}'    // "shift in" AKA use black ink apparently, (sent with Ctrl-o)
                              && c != '\u{10}'   // "data link escape" AKA interprt the following as raw data, (sent with Ctrl-p)
+                             && c != '\u{12}'   // "device control 2" (sent with Ctrl-r)
                              && c != '\u{13}'   // "device control 3" (sent with Ctrl-s)
                              && c != '\u{14}'   // "device control 4" (sent with Ctrl-t)
                              && c != '\u{16}'   // "synchronous idle" (sent with Ctrl-v)
@@ -1118,6 +1444,29 @@ pub fn run(update_and_render: UpdateAndRender) -> Res<()> {
 
                     buffer_status_map.migrate_all(index_state);
 
+                    let mut paths: Vec<PathBuf> = r_s.view.buffer_iter()
+                        .filter_map(|(_, b)| match &b.name {
+                            BufferName::Path(p) | BufferName::Hex(p) => Some(p.clone()),
+                            BufferName::Scratch(_) => None,
+                        })
+                        .collect();
+                    // A file can be open as text and as a hex view at once.
+                    paths.sort();
+                    paths.dedup();
+                    // So that edits to the theme show up right away.
+                    paths.push(theme::path(&r_s.themes_dir, r_s.view.theme_name()));
+                    if paths != watched_paths {
+                        file_watcher.set_paths(paths.clone());
+                        watched_paths = paths;
+                    }
+
+                    // The debug menu is the only thing that shows the undo tree.
+                    let debug_menu_shown = r_s.view.menu_mode() == WimpMenuMode::Debug;
+                    if debug_menu_shown != undo_tree_shown {
+                        call_u_and_r!(Input::SetUndoTreeShown(debug_menu_shown));
+                        undo_tree_shown = debug_menu_shown;
+                    }
+
                     // Queue a RedrawRequested event so we draw the updated view quickly.
                     glutin_context.window().request_redraw();
                     perf_viz::end_record!("MainEventsCleared");
@@ -1128,6 +1477,12 @@ pub fn run(update_and_render: UpdateAndRender) -> Res<()> {
 
                     let sswh!(width, height) = r_s.dimensions.window;
 
+                    let background = r_s.view.theme().ui.text_background;
+                    if background != clear_colour {
+                        gl_layer::set_clear_colour(&mut gl_state, background);
+                        clear_colour = background;
+                    }
+
                     let ViewOutput { text_or_rects, action } =
                         wimp_render::view(
                             &mut r_s,
@@ -1154,6 +1509,42 @@ pub fn run(update_and_render: UpdateAndRender) -> Res<()> {
                                     }
                                 }
                                 Cmd::LoadFile(path) => load_file!(path),
+                                Cmd::LoadDirectoryFiles(dir) => {
+                                    let proxy = r_s.event_proxy.clone();
+                                    let spawn_result = std::thread::Builder::new()
+                                        .name("directory_files".to_string())
+                                        .spawn(move || {
+                                            let _hope_it_gets_there = proxy.send_event(
+                                                CustomEvent::SearchFiles(shared::read_directory_files(&dir))
+                                            );
+                                        });
+                                    if let Err(err) = spawn_result {
+                                        handle_platform_error!(r_s, err);
+                                    }
+                                }
+                                Cmd::StoreBufferHistories(histories) => {
+                                    let mut histories: std::collections::HashMap<_, _> =
+                                        histories.into_iter().collect();
+
+                                    let view = &r_s.view;
+                                    let index_state = view.index_state();
+                                    let buffer_status_map = &mut r_s.buffer_status_map;
+                                    let _hope_it_gets_there = edited_files_in_sink.send(
+                                        EditedFilesThread::Buffers(
+                                            index_state,
+                                            view.buffer_iter().map(|(i, b)|
+                                                (
+                                                    b.to_owned(),
+                                                    buffer_status_map
+                                                        .get(index_state, i)
+                                                        .cloned()
+                                                        .unwrap_or_default(),
+                                                    histories.remove(&b.name),
+                                                )
+                                            ).collect()
+                                        )
+                                    );
+                                }
                                 Cmd::None => {}
                             }
                         } else {
@@ -1168,11 +1559,62 @@ pub fn run(update_and_render: UpdateAndRender) -> Res<()> {
                             call_u_and_r!(input);
                             perf_viz::end_record!("ViewAction::Input");
                         }
-                        ViewAction::Command(key) => {
+                        ViewAction::Command(name) => {
                             perf_viz::start_record!("ViewAction::Command");
-                            perform_command!(&key);
+                            perform_command!(named name);
                             perf_viz::end_record!("ViewAction::Command");
                         }
+                        ViewAction::ChangedFile(choice) => {
+                            perf_viz::start_record!("ViewAction::ChangedFile");
+                            if let Some(name) = r_s.view.take_changed_file_name() {
+                                let buffer = r_s.view.buffer_iter()
+                                    .find(|(_, b)| b.name == name)
+                                    .map(|(i, b)| (i, b.data.chars.clone()));
+                                let path = match &name {
+                                    BufferName::Path(p) | BufferName::Hex(p) => Some(p.clone()),
+                                    BufferName::Scratch(_) => None,
+                                };
+
+                                // The buffer may have been closed since the menu came up.
+                                if let (Some((index, ours)), Some(path)) = (buffer, path) {
+                                    match choice {
+                                        ChangedFileChoice::KeepOurs => {}
+                                        ChangedFileChoice::Reload | ChangedFileChoice::Diff => {
+                                            let on_disk = if let BufferName::Hex(_) = name {
+                                                std::fs::read(&path).map(|bytes| hex_dump::format(&bytes))
+                                            } else {
+                                                std::fs::read_to_string(&path)
+                                            };
+                                            match on_disk {
+                                                Ok(on_disk) => if choice == ChangedFileChoice::Reload {
+                                                    call_u_and_r!(Input::ReloadBuffer(index, on_disk));
+                                                } else {
+                                                    let diff = file_watcher::unified_diff(
+                                                        &format!("{} (on disk)", path.display()),
+                                                        &on_disk,
+                                                        &format!("{} (edited)", path.display()),
+                                                        &std::borrow::Cow::from(&ours),
+                                                    );
+                                                    call_u_and_r!(Input::NewScratchBuffer(Some(diff)));
+                                                },
+                                                Err(err) => {
+                                                    handle_platform_error!(r_s, err);
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                            perf_viz::end_record!("ViewAction::ChangedFile");
+                        }
+                        ViewAction::NotUtf8(choice) => {
+                            if let Some(path) = r_s.view.take_not_utf8_path() {
+                                match choice {
+                                    NotUtf8Choice::OpenHexView => load_hex_view!(r_s, path),
+                                    NotUtf8Choice::Cancel => {}
+                                }
+                            }
+                        }
                         ViewAction::None => {}
                     }
 
@@ -1224,27 +1666,13 @@ pub fn run(update_and_render: UpdateAndRender) -> Res<()> {
                         // generational indices.
                         if let Some(b) = r_s.view.get_buffer(index)
                         {
-                            save_to_disk!(r_s, p, std::borrow::Cow::from(b.data.chars.clone()).as_ref(), index);
+                            save_to_disk!(r_s, p, &b.name, std::borrow::Cow::from(b.data.chars.clone()), index);
                         }
                     }
                     CustomEvent::SendBuffersToBeSaved => {
-                        let view = &r_s.view;
-                        let index_state = view.index_state();
-                        let buffer_status_map = &mut r_s.buffer_status_map;
-                        let _hope_it_gets_there = edited_files_in_sink.send(
-                            EditedFilesThread::Buffers(
-                                index_state,
-                                view.buffer_iter().map(|(i, b)|
-                                    (
-                                        b.to_owned(), 
-                                        buffer_status_map
-                                            .get(index_state, i)
-                                            .cloned()
-                                            .unwrap_or_default()
-                                    )
-                                ).collect()
-                            )
-                        );
+                        // The buffers are sent to the edited files thread once the
+                        // editor responds with a `Cmd::StoreBufferHistories`.
+                        call_u_and_r!(Input::SerializeBufferHistories);
                     }
                     CustomEvent::EditedBufferError(e) => {
                         // TODO show warning dialog to user and ask if they want to continue
@@ -1253,6 +1681,81 @@ pub fn run(update_and_render: UpdateAndRender) -> Res<()> {
                         // now.
                         handle_platform_error!(r_s, e);
                     }
+                    CustomEvent::InputLogError(e) => {
+                        handle_platform_error!(r_s, e);
+                    }
+                    CustomEvent::SearchFiles(files) => {
+                        call_u_and_r!(Input::SearchFiles(files));
+                    }
+                    CustomEvent::FileChanged(path, Err(err)) => {
+                        handle_platform_error!(r_s, format!("{}: {}", path.display(), err));
+                    }
+                    CustomEvent::FileChanged(path, Ok(bytes)) => {
+                        if path == theme::path(&r_s.themes_dir, r_s.view.theme_name()) {
+                            let name = r_s.view.theme_name().to_owned();
+                            match std::str::from_utf8(&bytes) {
+                                Ok(file) => load_theme!(r_s, name, file),
+                                Err(err) => {
+                                    handle_platform_error!(r_s, format!("{}: {}", path.display(), err));
+                                }
+                            }
+                        }
+
+                        let was_our_own_save = r_s.written_hashes.get(&path)
+                            == Some(&content_hash(&bytes));
+
+                        // A text buffer and a hex view show the same file differently.
+                        // Our own saves already match what the buffers show.
+                        let changed: Vec<_> = r_s.view.buffer_iter()
+                            .filter(|_| !was_our_own_save)
+                            .filter_map(|(index, b)| {
+                                let on_disk = match &b.name {
+                                    BufferName::Path(p) if *p == path => std::str::from_utf8(&bytes)
+                                        .map(str::to_owned)
+                                        .map_err(|err| err.to_string()),
+                                    BufferName::Hex(p) if *p == path => Ok(hex_dump::format(&bytes)),
+                                    _ => return None,
+                                };
+                                Some((
+                                    index,
+                                    b.name.clone(),
+                                    on_disk.map(|s| {
+                                        let matches = b.data.chars == s;
+                                        (s, matches)
+                                    }),
+                                ))
+                            })
+                            .collect();
+
+                        for (index, name, on_disk) in changed {
+                            match on_disk {
+                                Err(err) => {
+                                    handle_platform_error!(r_s, format!("{}: {}", path.display(), err));
+                                }
+                                Ok((on_disk, true)) => {
+                                    // The buffer may have been edited to match the
+                                    // new contents, in which case it is now unedited.
+                                    call_u_and_r!(Input::SetOnDiskText(name, on_disk));
+                                }
+                                Ok((on_disk, false)) => {
+                                    let status = r_s.buffer_status_map
+                                        .get(r_s.view.index_state(), index)
+                                        .cloned()
+                                        .unwrap_or_default();
+                                    match status {
+                                        BufferStatus::Unedited => {
+                                            call_u_and_r!(Input::ReloadBuffer(index, on_disk));
+                                        }
+                                        BufferStatus::EditedAndSaved
+                                        | BufferStatus::EditedAndUnSaved => {
+                                            call_u_and_r!(Input::SetOnDiskText(name.clone(), on_disk));
+                                            r_s.view.show_file_changed_menu(name);
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
                 },
                 Event::NewEvents(StartCause::Init) => {
                     // At least try to measure the first frame accurately
@@ -1,7 +1,7 @@
 #![deny(bindings_with_variant_name, unused)]
 use gl_layer::{ColouredText, MulticolourTextSpec, TextLayout, TextOrRect, TextSpec, VisualSpec};
-use wimp_types::{CommandsMap, LocalMenuView, View, WimpMenuMode, MenuView, WimpMenuView, FindReplaceMode, ui_id, ui, ui::{ButtonState}, BufferStatus, CommandKey, Dimensions, RunConsts, RunState, command_keys};
-use macros::{c, d, dbg, invariant_assert, u};
+use wimp_types::{BoundCommand, ChangedFileChoice, NotUtf8Choice, LocalMenuView, View, WimpMenuMode, MenuView, WimpMenuView, FindReplaceMode, ui_id, ui, ui::{ButtonState}, theme::Theme, BufferStatus, CommandKey, CommandName, Dimensions, RunConsts, RunState, command_names};
+use macros::{d, dbg, invariant_assert, some_or, u};
 use platform_types::{
     *,
     screen_positioning::*,
@@ -24,6 +24,7 @@ use platform_types::{
     ssr,
     Spans,
 };
+use std::borrow::Cow;
 
 
 type Colour = [f32; 4];
@@ -94,55 +95,44 @@ macro_rules! grey_scale_bright {
     }};
 }
 
-macro_rules! palette {
-  (black $($tokens:tt)*) => {   c![0x22 as f32 / 255.0, 0x22 as f32 / 255.0, 0x22 as f32 / 255.0 $($tokens)*] };
-  (red $($tokens:tt)*) => {     c![0xde as f32 / 255.0, 0x49 as f32 / 255.0, 0x49 as f32 / 255.0 $($tokens)*] };
-  (green $($tokens:tt)*) => {   c![0x30 as f32 / 255.0, 0xb0 as f32 / 255.0, 0x6e as f32 / 255.0 $($tokens)*] };
-  (yellow $($tokens:tt)*) => {  c![0xff as f32 / 255.0, 0xb9 as f32 / 255.0, 0x37 as f32 / 255.0 $($tokens)*] };
-  (blue $($tokens:tt)*) => {    c![0x33 as f32 / 255.0, 0x52 as f32 / 255.0, 0xe1 as f32 / 255.0 $($tokens)*] };
-  (magenta $($tokens:tt)*) => { c![0x53 as f32 / 255.0, 0x33 as f32 / 255.0, 0x54 as f32 / 255.0 $($tokens)*] };
-  (cyan $($tokens:tt)*) => {    c![0x5a as f32 / 255.0, 0x7d as f32 / 255.0, 0x8b as f32 / 255.0 $($tokens)*] };
-  (white $($tokens:tt)*) => {   c![0xee as f32 / 255.0, 0xee as f32 / 255.0, 0xee as f32 / 255.0 $($tokens)*] };
-
-  (alt black $($tokens:tt)*) => {   c![0x66 as f32 / 255.0, 0x66 as f32 / 255.0, 0x66 as f32 / 255.0 $($tokens)*] };
-  (alt red $($tokens:tt)*) => {     c![0x49 as f32 / 255.0, 0x0b as f32 / 255.0, 0x0b as f32 / 255.0 $($tokens)*] };
-  (alt green $($tokens:tt)*) => {   c![0x16 as f32 / 255.0, 0x4f as f32 / 255.0, 0x31 as f32 / 255.0 $($tokens)*] };
-  (alt yellow $($tokens:tt)*) => {  c![0xff as f32 / 255.0, 0xff as f32 / 255.0, 0x00 as f32 / 255.0 $($tokens)*] };
-  (alt blue $($tokens:tt)*) => {    c![0x00 as f32 / 255.0, 0x37 as f32 / 255.0, 0xff as f32 / 255.0 $($tokens)*] };
-  (alt magenta $($tokens:tt)*) => { c![0xa9 as f32 / 255.0, 0x68 as f32 / 255.0, 0xab as f32 / 255.0 $($tokens)*] };
-  (alt cyan $($tokens:tt)*) => {    c![0x48 as f32 / 255.0, 0x91 as f32 / 255.0, 0xae as f32 / 255.0 $($tokens)*] };
-  (alt white $($tokens:tt)*) => {   c![0xff as f32 / 255.0, 0xff as f32 / 255.0, 0xff as f32 / 255.0 $($tokens)*] };
+/// Used for colours from the theme whose alpha is replaced by a fading one.
+fn with_alpha([r, g, b, _]: Colour, alpha: f32) -> Colour {
+    [r, g, b, alpha]
 }
 
-pub const TEXT_BACKGROUND_COLOUR: Colour = palette![black];
-pub const TEXT_HOVER_BACKGROUND_COLOUR: Colour = lighten!(palette![black]);
-pub const TEXT_PRESSED_BACKGROUND_COLOUR: Colour = darken!(palette![black]);
-
-const HIGHLIGHT_ALPHA: f32 = 0.6;
-const USER_HIGHLIGHT_COLOUR: Colour = palette![alt black, HIGHLIGHT_ALPHA];
-const RESULT_HIGHLIGHT_COLOUR: Colour = palette![yellow, HIGHLIGHT_ALPHA];
-const CURRENT_RESULT_HIGHLIGHT_COLOUR: Colour = palette![green, HIGHLIGHT_ALPHA];
-
-fn highlight_kind_colour(kind: HighlightKind) -> Colour {
+/// Returns the rects to draw for a highlight of the given kind that covers `rect`.
+/// Brackets get underlined rather than covered, and an unmatched bracket gets a dotted
+/// underline, so the two can be told apart without relying on the colour.
+fn highlight_rects(
+    rect: ScreenSpaceRect,
+    kind: HighlightKind,
+    char_w: abs::Length,
+) -> Vec<ScreenSpaceRect> {
     u!{HighlightKind}
+    let underline = rect.with_min_y(rect.max.y - SEPARATOR_LINE_THICKNESS);
     match kind {
-        User => USER_HIGHLIGHT_COLOUR,
-        Result => RESULT_HIGHLIGHT_COLOUR,
-        CurrentResult => CURRENT_RESULT_HIGHLIGHT_COLOUR,
+        User | Result | CurrentResult => vec![rect],
+        MatchingBracket => vec![underline],
+        UnmatchedBracket => {
+            let dot_width = char_w.halve().halve();
+            let first_dot_x = underline.min.x;
+            let second_dot_x = first_dot_x + dot_width.double();
+            vec![
+                underline.with_max_x(first_dot_x + dot_width),
+                underline.with_min_x(second_dot_x).with_max_x(second_dot_x + dot_width),
+            ]
+        }
     }
 }
 
-const CHROME_BACKGROUND_COLOUR: Colour = palette![alt green];
-const CHROME_TEXT_COLOUR: Colour = palette![alt cyan];
-const TAB_BAR_BACKGROUND_COLOUR: Colour = palette![alt cyan];
-const TAB_BACKGROUND_COLOUR: Colour = palette![cyan];
-const TAB_TEXT_COLOUR: Colour = palette![white];
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum ViewAction {
     None,
     Input(Input),
-    Command(CommandKey),
+    Command(CommandName),
+    ChangedFile(ChangedFileChoice),
+    NotUtf8(NotUtf8Choice),
 }
 d!{for ViewAction: ViewAction::None}
 
@@ -173,11 +163,11 @@ impl From<Option<Input>> for ViewAction {
     }
 }
 
-impl From<Option<CommandKey>> for ViewAction {
-    fn from(op: Option<CommandKey>) -> Self {
+impl From<Option<CommandName>> for ViewAction {
+    fn from(op: Option<CommandName>) -> Self {
         u!{ViewAction}
         match op {
-            Some(key) => Command(key),
+            Some(name) => Command(name),
             Option::None => None
         }
     }
@@ -199,8 +189,9 @@ pub fn view<'view>(
         ..
     }: &'view mut RunState,
     RunConsts {
-        commands
-    }: &RunConsts,
+        commands,
+        named_commands,
+    }: &'view RunConsts,
     dt: std::time::Duration,
 ) -> ViewOutput<'view> {
     if cfg!(feature = "extra-prints") {
@@ -209,6 +200,8 @@ pub fn view<'view>(
     
     ui::begin_view(ui, view);
 
+    let theme = view.theme();
+
     let dimensions = *dimensions;
     let sswh!(width, height) = dimensions.window;
     let FontInfo {
@@ -239,7 +232,7 @@ pub fn view<'view>(
 
     text_or_rects.push(TextOrRect::Rect(VisualSpec {
         rect: ssr!(_, ssxy!(width, edit_y)),
-        colour: TAB_BAR_BACKGROUND_COLOUR,
+        colour: theme.ui.tab_bar_background,
         z: TAB_BACKGROUND_Z,
     }));
 
@@ -265,6 +258,7 @@ pub fn view<'view>(
             ui,
             ui_id!(i),
             &mut text_or_rects,
+            theme,
             OutlineButtonSpec {
                 text: &name_string,
                 size: TAB_SIZE,
@@ -274,7 +268,7 @@ pub fn view<'view>(
                 rect,
                 underline: if index == selected_index {
                     Some(LineSpec {
-                        colour: palette![blue],
+                        colour: theme.ui.selected_tab_underline,
                         thickness: padding.into_ltrb().b,
                     })
                 } else {
@@ -290,11 +284,11 @@ pub fn view<'view>(
                 {
                     BufferStatus::Unedited => None,
                     BufferStatus::EditedAndUnSaved => Some(LineSpec {
-                        colour: palette![red],
+                        colour: theme.ui.unsaved_tab_overline,
                         thickness: padding.into_ltrb().t,
                     }),
                     BufferStatus::EditedAndSaved => Some(LineSpec {
-                        colour: palette![alt black],
+                        colour: theme.ui.saved_tab_overline,
                         thickness: padding.into_ltrb().t,
                     }),
                 },
@@ -311,22 +305,6 @@ pub fn view<'view>(
 
     perf_viz::start_record!("render BufferIdKind::Text");
     let (index, BufferView { data, .. }) = view.current_text_index_and_buffer();
-    // let text = {
-    //     chars
-    //     // perf_viz::record_guard!("map unprinatbles to symbols for themselves");
-    //     // let s = chars
-    //     //     .chars()
-    //     //     .map(|c| {
-    //     //         // map unprinatbles to symbols for themselves
-    //     //         if c < 0x20 as char {
-    //     //             std::char::from_u32(c as u32 | 0x2400u32).unwrap_or(c)
-    //     //         } else {
-    //     //             c
-    //     //         }
-    //     //     })
-    //     //     .collect::<String>();
-    //     // s
-    // };
     let edit_buffer_text_rect = get_edit_buffer_xywh(view.menu_mode(), dimensions);
 
     let edit_buffer_text_rect: ScreenSpaceRect = edit_buffer_text_rect.into();
@@ -335,6 +313,7 @@ pub fn view<'view>(
     action = into_action(text_box(
         ui,
         &mut text_or_rects,
+        theme,
         edit_buffer_text_rect,
         d!(),
         *text_char_dim,
@@ -346,6 +325,19 @@ pub fn view<'view>(
         view.current_buffer_id(),
     ))
     .or(action);
+
+    if view.is_showing_whitespace() {
+        whitespace_marks_view(
+            &mut text_or_rects,
+            theme,
+            edit_buffer_text_rect,
+            d!(),
+            *text_char_dim,
+            TEXT_SIZE,
+            &data,
+            EDIT_Z,
+        );
+    }
     perf_viz::end_record!("render BufferIdKind::Text");
 
     perf_viz::start_record!("render view.menu()");
@@ -355,9 +347,11 @@ pub fn view<'view>(
                 MenuView::None => {}
                 MenuView::FindReplace(FindReplaceView {
                     mode,
+                    search_options,
                     find,
                     replace,
                     result_count,
+                    results,
                 }) => {
                     //
                     //    Find/Replace
@@ -371,65 +365,95 @@ pub fn view<'view>(
                         replace_outer_rect,
                         ..
                     } = get_find_replace_info(dimensions);
-                    match mode.into() {
+                    let mode: FindReplaceMode = mode.into();
+
+                    let outer_rect = get_full_width_ssr(top_y, width, bottom_y);
+                    text_or_rects.push(TextOrRect::Rect(VisualSpec {
+                        rect: outer_rect,
+                        colour: theme.ui.chrome_background,
+                        z: FIND_REPLACE_BACKGROUND_Z,
+                    }));
+                    text_or_rects.push(TextOrRect::Text(TextSpec {
+                        text: find_replace_label(
+                            mode,
+                            find.chars.len_bytes() == 0,
+                            *result_count,
+                        ),
+                        size: FIND_REPLACE_SIZE,
+                        layout: TextLayout::Unbounded,
+                        spec: VisualSpec {
+                            rect: label_rect,
+                            colour: theme.ui.chrome_text,
+                            z: FIND_REPLACE_Z,
+                        },
+                    }));
+
+                    // The flags are right-aligned, so we lay them out from
+                    // right to left.
+                    let mut flags_max_x = label_rect.max.x;
+                    for flag_label in search_flag_labels(*search_options).iter().rev() {
+                        if flag_label.is_empty() {
+                            continue;
+                        }
+                        let flag_w: abs::Length = (
+                            abs::Ratio::from(flag_label.chars().count())
+                            * find_replace_char_dim.w
+                        ).get().into();
+                        flags_max_x = flags_max_x - flag_w;
+                        text_or_rects.push(TextOrRect::Text(TextSpec {
+                            text: flag_label,
+                            size: FIND_REPLACE_SIZE,
+                            layout: TextLayout::Unbounded,
+                            spec: VisualSpec {
+                                rect: label_rect.with_min_x(flags_max_x),
+                                colour: theme.ui.chrome_text,
+                                z: FIND_REPLACE_Z,
+                            },
+                        }));
+                    }
+                    macro_rules! spaced_input_box {
+                        ($data: expr, $input: expr, $outer_rect: expr) => {{
+                            action = into_action(text_box(
+                                ui,
+                                &mut text_or_rects,
+                                theme,
+                                $outer_rect,
+                                padding,
+                                *find_replace_char_dim,
+                                FIND_REPLACE_SIZE,
+                                TextBoxColour::Single(theme.ui.chrome_text),
+                                &$data,
+                                $input,
+                                FIND_REPLACE_Z,
+                                view.current_buffer_id(),
+                            ))
+                            .or(action);
+                        }};
+                    }
+    
+                    spaced_input_box!(find, b_id!(BufferIdKind::Find, index), find_outer_rect);
+                    match mode {
                         FindReplaceMode::CurrentFile => {
-                            let outer_rect = get_full_width_ssr(top_y, width, bottom_y);
-                            text_or_rects.push(TextOrRect::Rect(VisualSpec {
-                                rect: outer_rect,
-                                colour: CHROME_BACKGROUND_COLOUR,
-                                z: FIND_REPLACE_BACKGROUND_Z,
-                            }));
-                            text_or_rects.push(TextOrRect::Text(TextSpec {
-                                text: if find.chars.len_bytes() == 0 {
-                                    "In current file"
-                                } else {
-                                    // cheap hack to avoid lifetime issues
-                                    match result_count {                                        0 => "In current file (0 results)",
-                                        1 => "In current file (1 result)",
-                                        2 => "In current file (2 results)",
-                                        3 => "In current file (3 results)",
-                                        4 => "In current file (4 results)",
-                                        5 => "In current file (5 results)",
-                                        6 => "In current file (6 results)",
-                                        7 => "In current file (7 results)",
-                                        8 => "In current file (8 results)",
-                                        _ => "In current file (9+ results)",
-                                    }
-                                },
-                                size: FIND_REPLACE_SIZE,
-                                layout: TextLayout::Unbounded,
-                                spec: VisualSpec {
-                                    rect: label_rect,
-                                    colour: CHROME_TEXT_COLOUR,
-                                    z: FIND_REPLACE_Z,
-                                },
-                            }));
-                            macro_rules! spaced_input_box {
-                                ($data: expr, $input: expr, $outer_rect: expr) => {{
-                                    action = into_action(text_box(
-                                        ui,
-                                        &mut text_or_rects,
-                                        $outer_rect,
-                                        padding,
-                                        *find_replace_char_dim,
-                                        FIND_REPLACE_SIZE,
-                                        TextBoxColour::Single(CHROME_TEXT_COLOUR),
-                                        &$data,
-                                        $input,
-                                        FIND_REPLACE_Z,
-                                        view.current_buffer_id(),
-                                    ))
-                                    .or(action);
-                                }};
-                            }
-            
-                            spaced_input_box!(find, b_id!(BufferIdKind::Find, index), find_outer_rect);
                             spaced_input_box!(
                                 replace,
                                 b_id!(BufferIdKind::Replace, index),
                                 replace_outer_rect
                             );
                         }
+                        // Replacing in more than one file is not supported yet, so
+                        // the space the replace box would use is left blank.
+                        FindReplaceMode::OpenBuffers | FindReplaceMode::Directory => {
+                            render_find_results(
+                                index,
+                                results,
+                                ui,
+                                view.current_buffer_id(),
+                                dimensions,
+                                &mut text_or_rects,
+                                theme,
+                                &mut action,
+                            );
+                        }
                     }
                 }
                 MenuView::FileSwitcher(ref fs_view) => {
@@ -440,6 +464,7 @@ pub fn view<'view>(
                         view.current_buffer_id(),
                         dimensions,
                         &mut text_or_rects,
+                        theme,
                         &mut action,
                     );
             
@@ -465,7 +490,7 @@ pub fn view<'view>(
                     let outer_rect = get_full_width_ssr(top_y, width, bottom_y);
                     text_or_rects.push(TextOrRect::Rect(VisualSpec {
                         rect: outer_rect,
-                        colour: CHROME_BACKGROUND_COLOUR,
+                        colour: theme.ui.chrome_background,
                         z: FIND_REPLACE_BACKGROUND_Z,
                     }));
                     
@@ -475,7 +500,7 @@ pub fn view<'view>(
                         layout: TextLayout::Unbounded,
                         spec: VisualSpec {
                             rect: label_rect,
-                            colour: CHROME_TEXT_COLOUR,
+                            colour: theme.ui.chrome_text,
                             z: FIND_REPLACE_Z,
                         },
                     }));
@@ -483,11 +508,12 @@ pub fn view<'view>(
                     action = into_action(text_box(
                         ui,
                         &mut text_or_rects,
+                        theme,
                         input_outer_rect,
                         padding,
                         *find_replace_char_dim,
                         FIND_REPLACE_SIZE,
-                        TextBoxColour::Single(CHROME_TEXT_COLOUR),
+                        TextBoxColour::Single(theme.ui.chrome_text),
                         go_to_position,
                         b_id!(BufferIdKind::GoToPosition, index),
                         FIND_REPLACE_Z,
@@ -499,15 +525,15 @@ pub fn view<'view>(
         WimpMenuView { local_menu: Some(local_menu), .. } => {
             match local_menu {
                 LocalMenuView::Command => {
-                    let CommandMenuInfo {
+                    let LocalMenuInfo {
                         list_margin,
                         first_button_rect,
                         outer_rect,
                         ..
-                    } = get_command_menu_info(dimensions);
+                    } = get_local_menu_info(dimensions);
                     text_or_rects.push(TextOrRect::Rect(VisualSpec {
                         rect: outer_rect,
-                        colour: CHROME_BACKGROUND_COLOUR,
+                        colour: theme.ui.chrome_background,
                         z: FIND_REPLACE_BACKGROUND_Z,
                     }));
             
@@ -538,7 +564,8 @@ pub fn view<'view>(
                             Interact => {
                                 action = results
                                     .get(ui.file_switcher_pos.index)
-                                    .cloned()
+                                    .and_then(|key| commands.get(key))
+                                    .map(|bound| bound.command.name)
                                     .into();
                             }
                         }
@@ -555,44 +582,142 @@ pub fn view<'view>(
                             }
                             _ => {}
                         };
-                    
-                        command_button(
-                            ui,
-                            ui_id!(),
-                            text_or_rects,
-                            current_rect,
-                            dimensions,
-                            &commands,
-                            result,
-                            &mut action,
-                        );
+
+                        let bound_option = commands.get(result);
+                        invariant_assert!(bound_option.is_some(), "{:?} has no command associated with it!", result);
+
+                        if let Some(BoundCommand { command, menu_text }) = bound_option {
+                            menu_button(
+                                ui,
+                                ui_id!(),
+                                text_or_rects,
+                                theme,
+                                current_rect,
+                                dimensions,
+                                menu_text,
+                                ViewAction::Command(command.name),
+                                &mut action,
+                            );
+                        }
                             
                         current_rect.min.y += vertical_shift;
                         current_rect.max.y += vertical_shift;
                     }
                 }
                 LocalMenuView::Debug => {
-                    let DebugMenuInfo {
+                    let LocalMenuInfo {
                         outer_rect,
                         first_button_rect,
                         ..
-                    } = get_debug_menu_info(dimensions);
+                    } = get_local_menu_info(dimensions);
                     text_or_rects.push(TextOrRect::Rect(VisualSpec {
                         rect: outer_rect,
-                        colour: CHROME_BACKGROUND_COLOUR,
+                        colour: theme.ui.chrome_background,
                         z: FIND_REPLACE_BACKGROUND_Z,
                     }));
 
-                    command_button(
-                        ui,
-                        ui_id!(),
-                        &mut text_or_rects,
-                        first_button_rect,
-                        dimensions,
-                        &commands,
-                        &command_keys::add_run_state_snapshot(),
-                        &mut action,
-                    );
+                    let snapshot_option = named_commands.get(command_names::ADD_RUN_STATE_SNAPSHOT);
+                    invariant_assert!(snapshot_option.is_some(), "There is no command named {}!", command_names::ADD_RUN_STATE_SNAPSHOT);
+
+                    if let Some(command) = snapshot_option {
+                        menu_button(
+                            ui,
+                            ui_id!(),
+                            &mut text_or_rects,
+                            theme,
+                            first_button_rect,
+                            dimensions,
+                            command.label,
+                            ViewAction::Command(command.name),
+                            &mut action,
+                        );
+                    }
+
+                    text_or_rects.push(TextOrRect::Text(TextSpec {
+                        text: view.undo_tree_drawing(),
+                        size: FIND_REPLACE_SIZE,
+                        layout: TextLayout::Unbounded,
+                        spec: VisualSpec {
+                            rect: outer_rect
+                                .with_min_x(first_button_rect.min.x)
+                                .with_min_y(first_button_rect.max.y),
+                            colour: theme.ui.chrome_text,
+                            z: FIND_REPLACE_Z,
+                        },
+                    }));
+                }
+                LocalMenuView::FileChanged { message, .. }
+                | LocalMenuView::NotUtf8 { message, .. } => {
+                    let LocalMenuInfo {
+                        list_margin,
+                        first_button_rect,
+                        outer_rect,
+                        ..
+                    } = get_local_menu_info(dimensions);
+                    text_or_rects.push(TextOrRect::Rect(VisualSpec {
+                        rect: outer_rect,
+                        colour: theme.ui.chrome_background,
+                        z: FIND_REPLACE_BACKGROUND_Z,
+                    }));
+
+                    text_or_rects.push(TextOrRect::Text(TextSpec {
+                        text: message,
+                        size: FIND_REPLACE_SIZE,
+                        layout: TextLayout::Unbounded,
+                        spec: VisualSpec {
+                            rect: first_button_rect,
+                            colour: theme.ui.chrome_text,
+                            z: FIND_REPLACE_Z,
+                        },
+                    }));
+
+                    let mut current_rect = first_button_rect;
+                    let vertical_shift = first_button_rect.height()
+                        + list_margin.into_ltrb().b;
+
+                    let choices: Vec<(&str, ViewAction)> = match local_menu {
+                        LocalMenuView::NotUtf8 { .. } => vec![
+                            (
+                                "Open a hex view of the file.",
+                                ViewAction::NotUtf8(NotUtf8Choice::OpenHexView),
+                            ),
+                            (
+                                "Cancel.",
+                                ViewAction::NotUtf8(NotUtf8Choice::Cancel),
+                            ),
+                        ],
+                        _ => vec![
+                            (
+                                "Reload the file from disk, discarding the edits here. This can be undone.",
+                                ViewAction::ChangedFile(ChangedFileChoice::Reload),
+                            ),
+                            (
+                                "Keep the edits here. Saving will overwrite the file on disk.",
+                                ViewAction::ChangedFile(ChangedFileChoice::KeepOurs),
+                            ),
+                            (
+                                "Open a diff of the file on disk against the edits here.",
+                                ViewAction::ChangedFile(ChangedFileChoice::Diff),
+                            ),
+                        ],
+                    };
+
+                    for (choice_index, (text, choice)) in choices.into_iter().enumerate() {
+                        current_rect.min.y += vertical_shift;
+                        current_rect.max.y += vertical_shift;
+
+                        menu_button(
+                            ui,
+                            ui_id!(choice_index),
+                            &mut text_or_rects,
+                            theme,
+                            current_rect,
+                            dimensions,
+                            text,
+                            choice,
+                            &mut action,
+                        );
+                    }
                 }
             }
         }
@@ -612,7 +737,7 @@ pub fn view<'view>(
             width,
             status_line_y + SEPARATOR_LINE_THICKNESS
         ),
-        colour: TAB_BAR_BACKGROUND_COLOUR,
+        colour: theme.ui.tab_bar_background,
         z: STATUS_BACKGROUND_Z,
     }));
 
@@ -624,7 +749,7 @@ pub fn view<'view>(
 
     text_or_rects.push(TextOrRect::Rect(VisualSpec {
         rect,
-        colour: CHROME_BACKGROUND_COLOUR,
+        colour: theme.ui.chrome_background,
         z: STATUS_BACKGROUND_Z,
     }));
 
@@ -634,7 +759,7 @@ pub fn view<'view>(
         layout: TextLayout::Unbounded,
         spec: VisualSpec {
             rect: rect.with_min_y(status_line_y + abs::Ratio::TWO * SEPARATOR_LINE_THICKNESS),
-            colour: CHROME_TEXT_COLOUR,
+            colour: theme.ui.chrome_text,
             z: STATUS_Z,
         },
     }));
@@ -664,6 +789,7 @@ pub fn view<'view>(
         ui,
         ui_id!(),
         &mut text_or_rects,
+        theme,
         OutlineButtonSpec {
             text: "?", // 0x3F
             size: STATUS_SIZE,
@@ -678,7 +804,7 @@ pub fn view<'view>(
         // TODO render a bar chart of the last N view renders, where the x axis is 
         // the Input variant, and the y axis is duration statisics like maximum, 
         // mean, median and mode.
-        action = Some(command_keys::debug_menu()).into()
+        action = Some(command_names::DEBUG_MENU).into()
     }
 
 
@@ -686,6 +812,7 @@ pub fn view<'view>(
         ui,
         ui_id!(),
         &mut text_or_rects,
+        theme,
         OutlineButtonSpec {
             text: "≡", // U+2261
             size: STATUS_SIZE,
@@ -697,7 +824,7 @@ pub fn view<'view>(
             ..d!()
         },
     ) {
-        action = Some(command_keys::command_menu()).into()
+        action = Some(command_names::COMMAND_MENU).into()
     }
     perf_viz::end_record!("Status line");
 
@@ -735,43 +862,40 @@ pub fn view<'view>(
 
 // This is a private function so having a slightly awkward API is tolerable
 #[allow(clippy::too_many_arguments)]
-fn command_button<'view> (
+fn menu_button<'view> (
     ui: &mut ui::State,
     id: ui::Id,
     text_or_rects: &mut Vec<TextOrRect<'view>>,
+    theme: &Theme,
     rect: ScreenSpaceRect,
     dimensions: Dimensions,
-    commands: &CommandsMap,
-    command_key: &CommandKey,
+    text: &'view str,
+    on_click: ViewAction,
     action: &mut ViewAction,
 ) {
-    let cmd_option = commands.get(command_key);
-    invariant_assert!(cmd_option.is_some(), "{:?} has no command associated with it!", command_key);
-
-    if let Some(cmd) = cmd_option {
-        let Dimensions {
-            window: sswh!(_w, height),
-            ..
-        } = dimensions;
-        let SpacingAllSpec { margin, .. } = get_menu_spacing(height);
+    let Dimensions {
+        window: sswh!(_w, height),
+        ..
+    } = dimensions;
+    let SpacingAllSpec { margin, .. } = get_menu_spacing(height);
 
-        if do_outline_button(
-            ui,
-            id,
-            text_or_rects,
-            OutlineButtonSpec {
-                text: cmd.label,
-                size: TAB_SIZE,
-                char_dim: dimensions.font.tab_char_dim,
-                layout: TextLayout::Unbounded,
-                margin: Spacing::All(margin * LIST_MARGIN_TO_PADDING_RATIO),
-                rect,
-                z: TAB_Z,
-                ..d!()
-            },
-        ) {
-            *action = ViewAction::Command(*command_key);
-        }
+    if do_outline_button(
+        ui,
+        id,
+        text_or_rects,
+        theme,
+        OutlineButtonSpec {
+            text,
+            size: TAB_SIZE,
+            char_dim: dimensions.font.tab_char_dim,
+            layout: TextLayout::Unbounded,
+            margin: Spacing::All(margin * LIST_MARGIN_TO_PADDING_RATIO),
+            rect,
+            z: TAB_Z,
+            ..d!()
+        },
+    ) {
+        *action = on_click;
     }
 }
 
@@ -782,6 +906,7 @@ fn render_file_switcher_menu<'view>(
     current_buffer_id: BufferId,
     dimensions: Dimensions,
     text_or_rects: &mut Vec<TextOrRect<'view>>,
+    theme: &Theme,
     action: &mut ViewAction,
 ) {
     let FontInfo {
@@ -804,7 +929,7 @@ fn render_file_switcher_menu<'view>(
     let outer_rect = get_full_width_ssr(top_y, dimensions.window.w, bottom_y);
     text_or_rects.push(TextOrRect::Rect(VisualSpec {
         rect: outer_rect,
-        colour: CHROME_BACKGROUND_COLOUR,
+        colour: theme.ui.chrome_background,
         z: FIND_REPLACE_BACKGROUND_Z,
     }));
 
@@ -830,7 +955,7 @@ fn render_file_switcher_menu<'view>(
         layout: TextLayout::Unbounded,
         spec: VisualSpec {
             rect: label_rect,
-            colour: CHROME_TEXT_COLOUR,
+            colour: theme.ui.chrome_text,
             z: FIND_REPLACE_Z,
         },
     }));
@@ -908,11 +1033,12 @@ fn render_file_switcher_menu<'view>(
             *action = into_action(text_box(
                 ui,
                 text_or_rects,
+                theme,
                 $outer_rect,
                 padding,
                 *find_replace_char_dim,
                 FIND_REPLACE_SIZE,
-                TextBoxColour::Single(CHROME_TEXT_COLOUR),
+                TextBoxColour::Single(theme.ui.chrome_text),
                 &$data,
                 $input,
                 FIND_REPLACE_Z,
@@ -949,6 +1075,7 @@ fn render_file_switcher_menu<'view>(
             ui,
             result_id,
             text_or_rects,
+            theme,
             OutlineButtonSpec {
                 text: &path_text,
                 size: TAB_SIZE,
@@ -964,6 +1091,149 @@ fn render_file_switcher_menu<'view>(
         }
         current_rect.min.y += vertical_shift;
         current_rect.max.y += vertical_shift;
+    }
+}
+
+fn render_find_results<'view>(
+    buffer_index: g_i::Index,
+    results: &'view FindResults,
+    ui: &mut ui::State,
+    current_buffer_id: BufferId,
+    dimensions: Dimensions,
+    text_or_rects: &mut Vec<TextOrRect<'view>>,
+    theme: &Theme,
+    action: &mut ViewAction,
+) {
+    let FontInfo {
+        ref tab_char_dim,
+        ..
+    } = dimensions.font;
+
+    let FindReplaceInfo {
+        margin,
+        top_y,
+        find_outer_rect,
+        ..
+    } = get_find_replace_info(dimensions);
+    let list_margin = Spacing::All(
+        margin.into_ltrb().b * LIST_MARGIN_TO_PADDING_RATIO
+    );
+    let UpperPositionInfo { edit_y, .. } = upper_position_info(tab_char_dim);
+
+    // The results are listed upwards from the find/replace menu, so the first
+    // result is the closest one to the find box.
+    let outer_rect = get_full_width_ssr(edit_y, dimensions.window.w, top_y);
+    text_or_rects.push(TextOrRect::Rect(VisualSpec {
+        rect: outer_rect,
+        colour: theme.ui.chrome_background,
+        z: FIND_REPLACE_BACKGROUND_Z,
+    }));
+
+    let find_buffer_id = b_id!(BufferIdKind::Find, buffer_index);
+
+    fn get_result_id(index: usize) -> ui::Id {
+        ui::Id::TaggedUsize(ui::Tag::FindResults, index)
+    }
+
+    let mut navigated_result = None;
+
+    if action.is_none() {
+        u!{ui::Navigation}
+        match ui.navigation {
+            None => {
+                if let ui::Id::TaggedUsize(
+                    ui::Tag::FindResults,
+                    result_index,
+                ) = ui.keyboard.hot
+                {
+                    navigated_result = Some(result_index);
+                }
+            }
+            Up => {
+                if let ui::Id::TaggedUsize(
+                    ui::Tag::FindResults,
+                    result_index,
+                ) = ui.keyboard.hot
+                {
+                    navigated_result = Some((result_index + 1) % results.len());
+                } else if !results.is_empty() && current_buffer_id == find_buffer_id {
+                    navigated_result = Some(0);
+                    *action = ViewAction::Input(
+                        Input::SelectBuffer(b_id!(BufferIdKind::None, buffer_index))
+                    );
+                }
+            }
+            Down => {
+                if let ui::Id::TaggedUsize(
+                    ui::Tag::FindResults,
+                    result_index,
+                ) = ui.keyboard.hot
+                {
+                    if result_index == 0 {
+                        *action = ViewAction::Input(Input::SelectBuffer(find_buffer_id));
+                    } else {
+                        navigated_result = Some(result_index - 1);
+                    }
+                }
+            }
+            Interact => {
+                if let ui::Id::TaggedUsize(
+                    ui::Tag::FindResults,
+                    result_index,
+                ) = ui.keyboard.hot
+                {
+                    *action = results
+                        .get(result_index)
+                        .cloned()
+                        .map(|r| Input::OpenOrSelectBufferAt(r.path, r.position))
+                        .into();
+                }
+            }
+        }
+    }
+
+    let vertical_shift = find_outer_rect.max.y - find_outer_rect.min.y;
+    let mut current_rect = find_outer_rect;
+    current_rect.min.y = top_y - vertical_shift;
+    current_rect.max.y = top_y;
+
+    for (result_index, result) in results.iter().enumerate() {
+        if current_rect.min.y < edit_y {
+            break;
+        }
+        let rect = shrink_by(current_rect, list_margin);
+
+        let result_id = get_result_id(result_index);
+
+        match navigated_result {
+            Some(i) if i == result_index => {
+                ui.keyboard.set_next_hot(result_id);
+            }
+            _ => {}
+        };
+
+        if do_outline_button(
+            ui,
+            result_id,
+            text_or_rects,
+            theme,
+            OutlineButtonSpec {
+                text: &result.label,
+                size: TAB_SIZE,
+                char_dim: *tab_char_dim,
+                layout: TextLayout::Unbounded,
+                margin: list_margin,
+                rect,
+                z: TAB_Z,
+                ..d!()
+            },
+        ) {
+            *action = ViewAction::Input(
+                Input::OpenOrSelectBufferAt(result.path.clone(), result.position)
+            );
+        }
+        current_rect.min.y = current_rect.min.y - vertical_shift;
+        current_rect.max.y = current_rect.max.y - vertical_shift;
     }
 }
 
@@ -974,26 +1244,257 @@ enum TextBoxColour {
 d!(for TextBoxColour: TextBoxColour::FromSpans);
 
 #[perf_viz::record]
-fn colourize<'text>(to_colourize: RopeSlice<'text>, spans: &Spans) -> Vec<ColouredText<'text>> {
+fn colourize<'text>(
+    to_colourize: RopeSlice<'text>,
+    spans: &Spans,
+    theme: &Theme,
+) -> Vec<ColouredText<'text>> {
     spans
         .labelled_slices(to_colourize)
         .map(move |l_s| ColouredText {
             text: l_s.slice.as_cow_str(),
-            colour: match l_s.kind.get_byte() & 0b111 {
-                1 => palette![cyan],
-                2 => palette![green],
-                3 => palette![yellow],
-                4 => palette![magenta],
-                _ => palette![blue]
-            },
+            colour: theme.span(l_s.kind),
         }).collect()
 }
 
+/// Replaces the lines hidden by each of the `shown` folds with a single placeholder line.
+/// See `platform_types::folds`.
+#[perf_viz::record]
+fn fold_lines<'text>(
+    texts: Vec<ColouredText<'text>>,
+    shown: &[Fold],
+    placeholder_colour: Colour,
+) -> Vec<ColouredText<'text>> {
+    if shown.is_empty() {
+        return texts;
+    }
+
+    let mut output = Vec::with_capacity(texts.len() + shown.len());
+    let mut line = 0;
+    let mut last_placeholder_fold = None;
+
+    for ColouredText { text, colour } in texts {
+        let slice = |start: usize, end: usize| -> Cow<'text, str> {
+            match &text {
+                Cow::Borrowed(s) => {
+                    let s: &'text str = s;
+                    Cow::Borrowed(&s[start..end])
+                }
+                Cow::Owned(s) => Cow::Owned(s[start..end].to_owned()),
+            }
+        };
+
+        let mut visible_start = 0;
+        let mut segment_start = 0;
+        while segment_start < text.len() {
+            let segment_end = text[segment_start..]
+                .find('\n')
+                .map(|i| segment_start + i + 1)
+                .unwrap_or_else(|| text.len());
+
+            if let Some(fold) = folds::hiding(shown, line) {
+                if visible_start < segment_start {
+                    output.push(ColouredText { text: slice(visible_start, segment_start), colour });
+                }
+                visible_start = segment_end;
+
+                if last_placeholder_fold != Some(fold) {
+                    last_placeholder_fold = Some(fold);
+                    output.push(ColouredText {
+                        text: Cow::Owned(format!("⋯ {} lines folded\n", fold.line_count())),
+                        colour: placeholder_colour,
+                    });
+                }
+            }
+
+            if text[segment_start..segment_end].ends_with('\n') {
+                line += 1;
+            }
+            segment_start = segment_end;
+        }
+
+        if visible_start < text.len() {
+            output.push(ColouredText { text: slice(visible_start, text.len()), colour });
+        }
+    }
+
+    output
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum WhitespaceKind {
+    Usual,
+    /// Whitespace after the last visible character on a line, or on a blank line.
+    Trailing,
+    /// Indentation that has both tabs and spaces in it.
+    MixedIndentation,
+}
+
+/// A character that we draw a visible glyph in place of, when showing whitespace.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct WhitespaceMark {
+    /// The char offset of the character in its line.
+    offset: usize,
+    glyph: &'static str,
+    kind: WhitespaceKind,
+}
+
+const CONTROL_PICTURES: [&str; 32] = [
+    "␀", "␁", "␂", "␃", "␄", "␅", "␆", "␇", "␈", "␉", "␊", "␋", "␌", "␍", "␎", "␏",
+    "␐", "␑", "␒", "␓", "␔", "␕", "␖", "␗", "␘", "␙", "␚", "␛", "␜", "␝", "␞", "␟",
+];
+
+/// The glyph from the Unicode Control Pictures block that stands for `c`, if `c` is
+/// whitespace or a control character.
+fn control_picture(c: char) -> Option<&'static str> {
+    match c {
+        ' ' => Some("␣"),
+        '\u{7f}' => Some("␡"),
+        '\u{85}' | '\u{2028}' | '\u{2029}' => Some("␤"),
+        _ => CONTROL_PICTURES.get(c as usize).copied(),
+    }
+}
+
+/// The marks to draw for the whitespace and control characters in `line`, which may
+/// include its line ending.
+fn whitespace_marks(line: &str) -> Vec<WhitespaceMark> {
+    let content = line.trim_end_matches(is_linebreak_char::is_linebreak_char);
+    let content_char_count = content.chars().count();
+    let trailing_start = content_char_count - content.chars()
+        .rev()
+        .take_while(|c| c.is_whitespace())
+        .count();
+
+    let indent = content.chars().take_while(|&c| c == ' ' || c == '\t');
+    let (mut has_spaces, mut has_tabs) = (false, false);
+    let mut indent_len = 0;
+    for c in indent {
+        has_spaces |= c == ' ';
+        has_tabs |= c == '\t';
+        indent_len += 1;
+    }
+    let is_indent_mixed = has_spaces && has_tabs;
+
+    line.chars()
+        .enumerate()
+        .filter_map(|(offset, c)| {
+            let glyph = control_picture(c)?;
+            let kind = if offset >= content_char_count {
+                WhitespaceKind::Usual
+            } else if offset >= trailing_start {
+                WhitespaceKind::Trailing
+            } else if is_indent_mixed && offset < indent_len {
+                WhitespaceKind::MixedIndentation
+            } else {
+                WhitespaceKind::Usual
+            };
+
+            Some(WhitespaceMark { offset, glyph, kind })
+        })
+        .collect()
+}
+
+/// Draws visible marks over the whitespace and control characters on the lines of the
+/// buffer that are on screen, without moving anything else.
+#[allow(clippy::too_many_arguments)]
+fn whitespace_marks_view<'view>(
+    text_or_rects: &mut Vec<TextOrRect<'view>>,
+    theme: &Theme,
+    outer_rect: ScreenSpaceRect,
+    padding: Spacing,
+    char_dim: CharDim,
+    size: f32,
+    BufferViewData {
+        scroll,
+        chars,
+        folds,
+        ..
+    }: &'view BufferViewData,
+    z: u16,
+) {
+    let scroll = *scroll;
+    let text_box_pos = tbxy!{
+        outer_rect.min.x,
+        outer_rect.min.y,
+    };
+    let first_line = screen_space_to_position(
+        outer_rect.min,
+        text_box_pos,
+        scroll,
+        char_dim,
+        PositionRound::TowardsZero,
+    ).line;
+    let last_line = screen_space_to_position(
+        outer_rect.max,
+        text_box_pos,
+        scroll,
+        char_dim,
+        PositionRound::Up,
+    ).line;
+
+    let CharDim { w, h } = char_dim;
+    for display_line in first_line..=last_line {
+        let text_line = folds::text_line(folds, display_line);
+        if folds::hiding(folds, text_line).is_some() {
+            // This is a placeholder line, so there is nothing of the buffer to mark.
+            continue
+        }
+        let line = some_or!(chars.line(LineIndex(text_line)), break);
+
+        for WhitespaceMark { offset, glyph, kind } in whitespace_marks(&line.as_cow_str()) {
+            let screen_xy = position_to_screen_space(
+                Position {
+                    line: display_line,
+                    offset: CharOffset(offset),
+                },
+                char_dim,
+                scroll,
+                text_box_pos
+            );
+            let mark_rect = shrink_by(ssr!(screen_xy, outer_rect.max), padding);
+
+            let background_colour = match kind {
+                WhitespaceKind::Usual => None,
+                WhitespaceKind::Trailing => Some(theme.ui.trailing_whitespace),
+                WhitespaceKind::MixedIndentation => Some(theme.ui.mixed_indentation),
+            };
+            if let Some(colour) = background_colour {
+                let ssxy!(x y) = mark_rect.min;
+                let mut rect = ssr!(x, y, x + w, y + h);
+                clamp_within(&mut rect, outer_rect);
+
+                if rect.has_any_area() {
+                    text_or_rects.push(TextOrRect::Rect(VisualSpec {
+                        rect,
+                        colour,
+                        // Below the text, but above the background.
+                        z: z.saturating_sub(1),
+                    }));
+                }
+            }
+
+            text_or_rects.push(TextOrRect::Text(TextSpec {
+                text: glyph,
+                size,
+                // Clipped like the text itself, so marks scrolled out of the text box
+                // are not drawn over the rest of the UI.
+                layout: TextLayout::UnboundedLayoutClipped(outer_rect, scroll),
+                spec: VisualSpec {
+                    rect: mark_rect,
+                    colour: theme.ui.whitespace_mark,
+                    z: z.saturating_add(1),
+                },
+            }));
+        }
+    }
+}
+
 // These are private functions so having a slightly awkward API is tolerable
 #[allow(clippy::too_many_arguments)]
 fn text_box<'view>(
     ui: &mut ui::State,
     text_or_rects: &mut Vec<TextOrRect<'view>>,
+    theme: &Theme,
     outer_rect: ScreenSpaceRect,
     padding: Spacing,
     char_dim: CharDim,
@@ -1012,13 +1513,13 @@ fn text_box<'view>(
     }
 
     let (background_colour, cursor_alpha) = if current_buffer_id == buffer_id {
-        (TEXT_BACKGROUND_COLOUR, ui.get_fade_alpha())
+        (theme.ui.text_background, ui.get_fade_alpha())
     } else {
         (
             match button_state {
-                ButtonState::Usual => TEXT_BACKGROUND_COLOUR,
-                ButtonState::Hover(_) => TEXT_HOVER_BACKGROUND_COLOUR,
-                ButtonState::Pressed(_) => TEXT_PRESSED_BACKGROUND_COLOUR,
+                ButtonState::Usual => theme.ui.text_background,
+                ButtonState::Hover(_) => lighten!(theme.ui.text_background),
+                ButtonState::Pressed(_) => darken!(theme.ui.text_background),
             },
             1.0,
         )
@@ -1026,6 +1527,7 @@ fn text_box<'view>(
 
     text_box_view(
         text_or_rects,
+        theme,
         outer_rect,
         padding,
         char_dim,
@@ -1043,6 +1545,7 @@ fn text_box<'view>(
 #[allow(clippy::too_many_arguments)]
 fn text_box_view<'view>(
     text_or_rects: &mut Vec<TextOrRect<'view>>,
+    theme: &Theme,
     outer_rect: ScreenSpaceRect,
     padding: Spacing,
     char_dim: CharDim,
@@ -1054,6 +1557,7 @@ fn text_box_view<'view>(
         scroll,
         chars,
         spans,
+        folds,
         ..
     }: &'view BufferViewData,
     background_colour: Colour,
@@ -1082,16 +1586,19 @@ fn text_box_view<'view>(
     text_or_rects.push(TextOrRect::MulticolourText(MulticolourTextSpec {
         text: {
             perf_viz::record_guard!("de-roping for colourization");
-            match text_colour {
+            let texts = match text_colour {
                 TextBoxColour::FromSpans => colourize(
                     chars.full_slice(),
-                    spans
+                    spans,
+                    theme,
                 ),
                 TextBoxColour::Single(colour) => {
                     perf_viz::record_guard!("Single colour chars.into()");
                     vec![ColouredText{ colour, text: chars.into() }]
                 },
-            }
+            };
+
+            fold_lines(texts, folds, theme.ui.fold_placeholder)
         },
         size,
         layout: TextLayout::UnboundedLayoutClipped(
@@ -1108,7 +1615,15 @@ fn text_box_view<'view>(
     }));
 
     for c in cursors.iter() {
-        let screen_xy = position_to_screen_space(c.position, char_dim, scroll, text_box_pos);
+        let screen_xy = position_to_screen_space(
+            Position {
+                line: folds::display_line(folds, c.position.line),
+                ..c.position
+            },
+            char_dim,
+            scroll,
+            text_box_pos
+        );
         let cursor_rect = shrink_by(ssr!(screen_xy, outer_rect.max), padding);
         text_or_rects.push(TextOrRect::Text(TextSpec {
             text: "▏",
@@ -1117,8 +1632,10 @@ fn text_box_view<'view>(
             spec: VisualSpec {
                 rect: cursor_rect,
                 colour: match c.state {
-                    CursorState::None => palette![red, cursor_alpha],
-                    CursorState::PressedAgainstWall(_) => palette![yellow, cursor_alpha],
+                    CursorState::None => with_alpha(theme.ui.cursor, cursor_alpha),
+                    CursorState::PressedAgainstWall(_) => {
+                        with_alpha(theme.ui.cursor_against_wall, cursor_alpha)
+                    }
                 },
                 z: z.saturating_add(3),
             },
@@ -1130,25 +1647,31 @@ fn text_box_view<'view>(
     text_or_rects.extend(
         highlights
             .iter()
-            .filter_map(|Highlight { min, max, kind, .. }| {
-                let mut rect = ssr!(
+            .flat_map(|Highlight { min, max, kind, .. }| {
+                let min_line = folds::display_line(folds, min.line);
+                let max_line = folds::display_line(folds, max.line);
+                let rect = ssr!(
                     abs::Ratio::from(min.offset.0) * w + x,
-                    abs::Ratio::from(min.line) * h + y,
+                    abs::Ratio::from(min_line) * h + y,
                     abs::Ratio::from(max.offset.0) * w + x,
-                    abs::Ratio::from(max.line + 1) * h + y
+                    abs::Ratio::from(max_line + 1) * h + y
                 );
 
-                clamp_within(&mut rect, outer_rect);
-
-                if rect.has_any_area() {
-                    Some(TextOrRect::Rect(VisualSpec {
-                        rect,
-                        colour: highlight_kind_colour(*kind),
-                        z: z.saturating_add(4),
-                    }))
-                } else {
-                    None
-                }
+                highlight_rects(rect, *kind, w)
+                    .into_iter()
+                    .filter_map(move |mut rect| {
+                        clamp_within(&mut rect, outer_rect);
+
+                        if rect.has_any_area() {
+                            Some(TextOrRect::Rect(VisualSpec {
+                                rect,
+                                colour: theme.highlight(*kind),
+                                z: z.saturating_add(4),
+                            }))
+                        } else {
+                            None
+                        }
+                    })
             }),
     );
 }
@@ -1239,17 +1762,19 @@ fn do_outline_button<'view>(
     ui_state: &mut ui::State,
     id: ui::Id,
     text_or_rects: &mut Vec<TextOrRect<'view>>,
+    theme: &Theme,
     spec: OutlineButtonSpec<'view>,
 ) -> bool {
     let (clicked, state) = ui::do_button_logic(ui_state, id, spec.rect);
 
-    render_outline_button(text_or_rects, spec, state, ui_state.get_fade_alpha());
+    render_outline_button(text_or_rects, theme, spec, state, ui_state.get_fade_alpha());
 
     clicked
 }
 
 fn render_outline_button<'view>(
     text_or_rects: &mut Vec<TextOrRect<'view>>,
+    theme: &Theme,
     OutlineButtonSpec {
         text,
         size,
@@ -1266,8 +1791,6 @@ fn render_outline_button<'view>(
 ) {
     u!{ButtonState}
 
-    const BACKGROUND_COLOUR: Colour = TAB_BACKGROUND_COLOUR;
-    const TEXT_COLOUR: Colour = TAB_TEXT_COLOUR;
 
     let text_z = z.saturating_add(3);
     let overline_z = z.saturating_add(2);
@@ -1279,8 +1802,9 @@ fn render_outline_button<'view>(
         ($input_type: expr) => {{
             u!{ui::InputType}
             match $input_type {
-                Mouse => palette![yellow, fade_alpha],
-                Keyboard => palette![blue, fade_alpha],                Both => palette![green, fade_alpha],
+                Mouse => with_alpha(theme.ui.mouse_selection, fade_alpha),
+                Keyboard => with_alpha(theme.ui.keyboard_selection, fade_alpha),
+                Both => with_alpha(theme.ui.mouse_and_keyboard_selection, fade_alpha),
             }
         }};
     }
@@ -1293,7 +1817,7 @@ fn render_outline_button<'view>(
                 layout,
                 spec: VisualSpec {
                     rect: get_inner_text_rect(text, char_dim, rect),
-                    colour: TEXT_COLOUR,
+                    colour: theme.ui.tab_text,
                     z: text_z,
                 },
             }));
@@ -1319,7 +1843,7 @@ fn render_outline_button<'view>(
 
             text_or_rects.push(TextOrRect::Rect(VisualSpec {
                 rect,
-                colour: BACKGROUND_COLOUR,
+                colour: theme.ui.tab_background,
                 z,
             }));
             push_text!();
@@ -1327,7 +1851,7 @@ fn render_outline_button<'view>(
         Usual => {
             text_or_rects.push(TextOrRect::Rect(VisualSpec {
                 rect,
-                colour: BACKGROUND_COLOUR,
+                colour: theme.ui.tab_background,
                 z,
             }));
             push_text!();
@@ -1596,6 +2120,62 @@ pub struct FindReplaceInfo {
     pub replace_text_xywh: TextBoxXYWH,
 }
 
+/// The heading of the find/replace menu, which says which text is being searched,
+/// and how many results there are, if anything is being searched for.
+fn find_replace_label(
+    mode: FindReplaceMode,
+    is_find_empty: bool,
+    result_count: usize,
+) -> &'static str {
+    // cheap hack to avoid lifetime issues
+    macro_rules! with_count {
+        ($prefix: literal) => {
+            if is_find_empty {
+                $prefix
+            } else {
+                match result_count {
+                    0 => concat!($prefix, " (0 results)"),
+                    1 => concat!($prefix, " (1 result)"),
+                    2 => concat!($prefix, " (2 results)"),
+                    3 => concat!($prefix, " (3 results)"),
+                    4 => concat!($prefix, " (4 results)"),
+                    5 => concat!($prefix, " (5 results)"),
+                    6 => concat!($prefix, " (6 results)"),
+                    7 => concat!($prefix, " (7 results)"),
+                    8 => concat!($prefix, " (8 results)"),
+                    _ => concat!($prefix, " (9+ results)"),
+                }
+            }
+        };
+    }
+
+    match mode {
+        FindReplaceMode::CurrentFile => with_count!("In current file"),
+        FindReplaceMode::OpenBuffers => with_count!("In open buffers"),
+        FindReplaceMode::Directory => with_count!("In current file's directory"),
+    }
+}
+
+/// Short descriptions of the non-default search options, for the find/replace menu.
+/// Options that are set to the default are represented by empty strings.
+fn search_flag_labels(
+    SearchOptions { kind, case, whole_word, in_selection }: SearchOptions
+) -> [&'static str; 4] {
+    [
+        match kind {
+            SearchKind::Plain => "",
+            SearchKind::Regex => "[regex]",
+        },
+        match case {
+            CaseSensitivity::Sensitive => "",
+            CaseSensitivity::Insensitive => "[ignore case]",
+            CaseSensitivity::Smart => "[smart case]",
+        },
+        if whole_word { "[word]" } else { "" },
+        if in_selection { "[selection]" } else { "" },
+    ]
+}
+
 pub fn get_find_replace_info(
     Dimensions {
         font: FontInfo {
@@ -1803,7 +2383,9 @@ pub fn get_go_to_position_info(
     }
 }
 
-pub struct CommandMenuInfo {
+/// Info for the menus that are drawn by the platform layer instead of the editor,
+/// which cover the text area.
+pub struct LocalMenuInfo {
     pub margin: Spacing,
     pub padding: Spacing,
     pub top_y: abs::Pos,
@@ -1813,61 +2395,9 @@ pub struct CommandMenuInfo {
     pub list_margin: Spacing,
 }
 
-pub fn get_command_menu_info(
-    dimensions: Dimensions,
-) -> CommandMenuInfo {
-    let Dimensions {
-        font: FontInfo {
-            tab_char_dim,
-            ..
-        },
-        window: sswh!(width, _),
-    } = dimensions;
-
-    let CoverTextAreaInfo {
-        margin,
-        padding,
-        top_y,
-        bottom_y,
-        outer_rect,
-    } = cover_text_area_info(dimensions);
-
-    let list_margin = margin * LIST_MARGIN_TO_PADDING_RATIO;
-
-    let first_button_rect = shrink_by(
-        ssr!(
-            _,
-            _,
-            width, 
-            top_y + padding.double() + tab_char_dim.h
-        ),
-        Spacing::Horizontal(margin)
-    ).with_min_y(top_y + list_margin);
-    CommandMenuInfo {
-        margin: Spacing::All(margin),
-        padding: Spacing::All(padding),
-        top_y,
-        bottom_y,
-        outer_rect,
-        list_margin: Spacing::All(list_margin),
-        first_button_rect,
-    }
-}
-
-// If we end up with a third menu like this and CommandMenu, merge the infos.
-pub struct DebugMenuInfo {
-    pub margin: Spacing,
-    pub padding: Spacing,
-    pub top_y: abs::Pos,
-    pub bottom_y: abs::Pos,
-    pub outer_rect: ScreenSpaceRect,
-    pub first_button_rect: ScreenSpaceRect,
-    pub list_margin: Spacing,
-}
-
-pub fn get_debug_menu_info(
+pub fn get_local_menu_info(
     dimensions: Dimensions,
-) -> DebugMenuInfo {
+) -> LocalMenuInfo {
     let Dimensions {
         font: FontInfo {
             tab_char_dim,
@@ -1896,7 +2426,7 @@ pub fn get_debug_menu_info(
         Spacing::Horizontal(margin)
     ).with_min_y(top_y + list_margin);
 
-    DebugMenuInfo {
+    LocalMenuInfo {
         margin: Spacing::All(margin),
         padding: Spacing::All(padding),
         top_y,
@@ -1974,7 +2504,7 @@ pub fn get_edit_buffer_xywh(
     let max_y = match mode {
         Hidden | GoToPosition => get_status_line_y(status_char_dim, height),
         FindReplace(_) => get_find_replace_info(dimensions).top_y,
-        FileSwitcher | Command | Debug => height.into(),
+        FileSwitcher | Command | Debug | FileChanged | NotUtf8 => height.into(),
     };
     let y = upper_position_info(tab_char_dim).edit_y;
     TextBoxXYWH {
@@ -2013,7 +2543,7 @@ pub fn should_show_text_cursor(
     let inside_edit_buffer = inside_rect(xy, get_edit_buffer_xywh(mode, dimensions).into());
 
     inside_edit_buffer || match mode {
-        Hidden | Command | Debug => false,
+        Hidden | Command | Debug | FileChanged | NotUtf8 => false,
         FindReplace(_) => {
             let FindReplaceInfo {
                 find_outer_rect,
@@ -92,7 +92,7 @@ fn render_outline_button_centers_this_example_properly() {
         z: TAB_Z,
         ..d!()
     };
-    render_outline_button(&mut text_or_rects, spec, ButtonState::Usual, 1.0);
+    render_outline_button(&mut text_or_rects, &d!(), spec, ButtonState::Usual, 1.0);
 
     let background_rect = text_or_rects
         .iter()
@@ -495,8 +495,79 @@ fn render_file_switcher_menu_selects_the_fileswitcher_buffer_when_the_navigation
         b_id!(BufferIdKind::FileSwitcher, index), 
         dimensions, 
         &mut view_output.text_or_rects,
+        &d!(),
         &mut view_output.action,
     );
 
     assert_eq!(view_output.action, ViewAction::Input(Input::SelectBuffer(b_id!(BufferIdKind::FileSwitcher, index))));
 }
+
+fn mark_kinds(line: &str) -> Vec<(usize, &'static str, WhitespaceKind)> {
+    whitespace_marks(line)
+        .into_iter()
+        .map(|WhitespaceMark { offset, glyph, kind }| (offset, glyph, kind))
+        .collect()
+}
+
+#[test]
+fn whitespace_marks_marks_spaces_tabs_and_line_endings() {
+    use WhitespaceKind::*;
+    assert_eq!(
+        mark_kinds("a b\tc\r\n"),
+        vec![(1, "␣", Usual), (3, "␉", Usual), (5, "␍", Usual), (6, "␊", Usual)]
+    );
+}
+
+#[test]
+fn whitespace_marks_marks_other_control_characters() {
+    use WhitespaceKind::*;
+    assert_eq!(
+        mark_kinds("a\u{0}b\u{1b}c\u{7f}\u{2028}"),
+        vec![(1, "␀", Usual), (3, "␛", Usual), (5, "␡", Usual), (6, "␤", Usual)]
+    );
+}
+
+#[test]
+fn whitespace_marks_does_not_mark_visible_characters() {
+    assert_eq!(mark_kinds("fn main() {}"), vec![
+        (2, "␣", WhitespaceKind::Usual),
+        (9, "␣", WhitespaceKind::Usual),
+    ]);
+    assert_eq!(mark_kinds("é→"), vec![]);
+}
+
+#[test]
+fn whitespace_marks_marks_trailing_whitespace_but_not_the_line_ending() {
+    use WhitespaceKind::*;
+    assert_eq!(
+        mark_kinds("a \t\n"),
+        vec![(1, "␣", Trailing), (2, "␉", Trailing), (3, "␊", Usual)]
+    );
+}
+
+#[test]
+fn whitespace_marks_marks_blank_lines_as_trailing_whitespace() {
+    use WhitespaceKind::*;
+    assert_eq!(
+        mark_kinds("  \n"),
+        vec![(0, "␣", Trailing), (1, "␣", Trailing), (2, "␊", Usual)]
+    );
+}
+
+#[test]
+fn whitespace_marks_marks_mixed_indentation_but_not_the_whitespace_after_it() {
+    use WhitespaceKind::*;
+    assert_eq!(
+        mark_kinds("\t  a b"),
+        vec![
+            (0, "␉", MixedIndentation),
+            (1, "␣", MixedIndentation),
+            (2, "␣", MixedIndentation),
+            (4, "␣", Usual),
+        ]
+    );
+    assert_eq!(
+        mark_kinds("\t\ta"),
+        vec![(0, "␉", Usual), (1, "␉", Usual)]
+    );
+}
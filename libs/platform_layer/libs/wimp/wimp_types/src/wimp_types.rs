@@ -2,7 +2,7 @@ use glutin::event::{ModifiersState, VirtualKeyCode};
 use macros::{d, dbg, ord, u};
 use platform_types::{screen_positioning::*, abs, g_i, Input, Cmd, EditedTransition};
 
-use std::collections::{VecDeque, BTreeMap};
+use std::collections::{VecDeque, BTreeMap, HashMap};
 use std::path::PathBuf;
 
 pub use glutin::event_loop::EventLoopProxy;
@@ -90,6 +90,12 @@ pub enum CustomEvent {
     SaveNewFile(PathBuf, g_i::Index),
     SendBuffersToBeSaved,
     EditedBufferError(String),
+    InputLogError(String),
+    /// A file was changed on disk, possibly by another program. This holds what is
+    /// now in the file, or the error we got trying to read it. Files that were deleted
+    /// or moved away are not reported.
+    FileChanged(PathBuf, Result<Vec<u8>, String>),
+    SearchFiles(Vec<(PathBuf, String)>),
 }
 
 /// This module exists because when adding WIMP only UI elements we found that 
@@ -102,11 +108,14 @@ mod view {
     use macros::{d};
     use super::ui; // Your app's written in Electron? Shoulda used Super UI.
     use super::g_i;
+    use super::theme::Theme;
     pub use platform_types::{CursorView, BufferName, BufferViewData, FileSwitcherView, FindReplaceView, GoToPositionView, MenuMode, MenuView, IndexedEditedTransition};
 
     #[derive(Clone, Copy, Debug, PartialEq)]
     pub enum FindReplaceMode {
         CurrentFile,
+        OpenBuffers,
+        Directory,
     }
     d!(for FindReplaceMode: FindReplaceMode::CurrentFile);
 
@@ -118,6 +127,8 @@ mod view {
         GoToPosition,
         Command,
         Debug,
+        FileChanged,
+        NotUtf8,
     }
     d!(for WimpMenuMode: WimpMenuMode::Hidden);
 
@@ -125,6 +136,17 @@ mod view {
     pub enum LocalMenuView {
         Command,
         Debug,
+        /// Asks what to do about a file that changed on disk while a buffer showing it
+        /// had unsaved edits.
+        FileChanged {
+            name: BufferName,
+            message: String,
+        },
+        /// Offers to open a hex view of a file that could not be opened as text.
+        NotUtf8 {
+            path: std::path::PathBuf,
+            message: String,
+        },
     }
 
     #[derive(Clone, Debug)]
@@ -137,6 +159,8 @@ mod view {
             match self.local_menu {
                 Some(LocalMenuView::Command) => WimpMenuMode::Command,
                 Some(LocalMenuView::Debug) => WimpMenuMode::Debug,
+                Some(LocalMenuView::FileChanged { .. }) => WimpMenuMode::FileChanged,
+                Some(LocalMenuView::NotUtf8 { .. }) => WimpMenuMode::NotUtf8,
                 None => self.platform_menu.get_mode().clone().into()
             }
         }
@@ -158,6 +182,8 @@ mod view {
             use FindReplaceMode::*;
             match p_m {
                 platform_types::FindReplaceMode::CurrentFile => CurrentFile,
+                platform_types::FindReplaceMode::OpenBuffers => OpenBuffers,
+                platform_types::FindReplaceMode::Directory => Directory,
             }
         }
     }
@@ -167,6 +193,8 @@ mod view {
             use FindReplaceMode::*;
             match p_m {
                 platform_types::FindReplaceMode::CurrentFile => CurrentFile,
+                platform_types::FindReplaceMode::OpenBuffers => OpenBuffers,
+                platform_types::FindReplaceMode::Directory => Directory,
             }
         }
     }
@@ -177,7 +205,11 @@ mod view {
         // because we had a bug when LocalMenuView was introduced that hiding
         // the `platform_types::View` prevents.
         platform_view: platform_types::View,
-        local_menu: Option<LocalMenuView>
+        local_menu: Option<LocalMenuView>,
+        show_whitespace: bool,
+        theme: Theme,
+        /// The name of the file in the themes directory that `theme` was read from.
+        theme_name: String,
     }
 
     macro_rules! toggle_impl {
@@ -219,11 +251,81 @@ mod view {
             toggle_impl!{
                 self,
                 LocalMenuView::Debug,
+            }
+        }
+
+        /// Unlike the other local menus, this is shown even if an editor menu is up,
+        /// since the user needs to decide what to do.
+        pub fn show_file_changed_menu(&mut self, name: BufferName) {
+            let (file, edited) = match &name {
+                BufferName::Path(path) => (path.display().to_string(), "it"),
+                BufferName::Hex(path) => (path.display().to_string(), "its hex view"),
+                BufferName::Scratch(_) => (name.to_string(), "it"),
+            };
+            let message = format!(
+                "{} was changed on disk, but there are unsaved edits to {} here.",
+                file,
+                edited
+            );
+            self.local_menu = Some(LocalMenuView::FileChanged { name, message });
+        }
+
+        /// Closes the file changed menu, if it is up, and returns the name of the buffer
+        /// it was for.
+        pub fn take_changed_file_name(&mut self) -> Option<BufferName> {
+            match self.local_menu.take() {
+                Some(LocalMenuView::FileChanged { name, .. }) => Some(name),
+                other => {
+                    self.local_menu = other;
+                    None
+                }
+            }
+        }
+
+        pub fn show_not_utf8_menu(&mut self, path: std::path::PathBuf) {
+            let message = format!(
+                "{} is not valid UTF-8, so it cannot be opened as text.",
+                path.display()
+            );
+            self.local_menu = Some(LocalMenuView::NotUtf8 { path, message });
+        }
+
+        /// Closes the not UTF-8 menu, if it is up, and returns the path it was for.
+        pub fn take_not_utf8_path(&mut self) -> Option<std::path::PathBuf> {
+            match self.local_menu.take() {
+                Some(LocalMenuView::NotUtf8 { path, .. }) => Some(path),
+                other => {
+                    self.local_menu = other;
+                    None
+                }
             }
         }
 
         pub fn update(&mut self, p_view: platform_types::View) {
             self.platform_view = p_view;
+        }
+
+        /// Toggles drawing visible marks for the whitespace, line endings and other
+        /// control characters in the edit buffer.
+        pub fn toggle_whitespace_visibility(&mut self) {
+            self.show_whitespace = !self.show_whitespace;
+        }
+
+        pub fn theme(&self) -> &Theme {
+            &self.theme
+        }
+
+        pub fn theme_name(&self) -> &str {
+            &self.theme_name
+        }
+
+        pub fn set_theme(&mut self, name: String, theme: Theme) {
+            self.theme_name = name;
+            self.theme = theme;
+        }
+
+        pub fn is_showing_whitespace(&self) -> bool {
+            self.show_whitespace
         }
     }
 
@@ -281,6 +383,10 @@ mod view {
 
         pub fn stats(&self) -> &platform_types::ViewStats {
             &self.platform_view.stats
+        }
+
+        pub fn undo_tree_drawing(&self) -> &str {
+            &self.platform_view.undo_tree_drawing
         }
 
         pub fn menu(&self) -> WimpMenuView {
@@ -340,33 +446,53 @@ pub struct RunState {
     pub dimensions: Dimensions,
     pub event_proxy: EventLoopProxy<CustomEvent>, 
     pub clipboard: Clipboard,
+    /// The `content_hash` of what we last wrote to each path, so that we can tell our
+    /// own saves apart from changes made by other programs.
+    pub written_hashes: HashMap<PathBuf, u64>,
+    /// Where the theme files are. See the `theme` module.
+    pub themes_dir: PathBuf,
+}
+
+/// What the user can choose to do about a file that changed on disk while its buffer
+/// had unsaved edits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChangedFileChoice {
+    /// Replace the buffer's text with what is on disk. This can be undone.
+    Reload,
+    /// Keep the buffer as it is. Saving it will overwrite what is on disk.
+    KeepOurs,
+    /// Open a scratch buffer showing how the buffer differs from what is on disk.
+    Diff,
+}
+
+/// What the user can choose to do about a file that is not valid UTF-8.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NotUtf8Choice {
+    /// Open a buffer showing the bytes of the file as a hex dump, which can be
+    /// edited and saved back as bytes.
+    OpenHexView,
+    /// Leave the file unopened.
+    Cancel,
 }
 
 pub type CommandKey = (ModifiersState, VirtualKeyCode);
 
-pub mod command_keys {
-    use super::{CommandKey, ModifiersState, VirtualKeyCode};
-    pub const CTRL: ModifiersState = ModifiersState::CTRL;
-    pub const SHIFT: ModifiersState = ModifiersState::SHIFT;
-    pub const ALT: ModifiersState = ModifiersState::ALT;
-    pub const LOGO: ModifiersState = ModifiersState::LOGO;
+/// A stable name for a command, which is used to refer to it in the keybindings file.
+pub type CommandName = &'static str;
 
-    pub fn command_menu() -> CommandKey {
-        (ModifiersState::empty(), VirtualKeyCode::Apps)
-    }
-    
-    pub fn debug_menu() -> CommandKey {
-        (CTRL | SHIFT, VirtualKeyCode::Slash)
-    }
+/// The names of commands that are run by parts of the UI other than the command menu,
+/// so they need to work no matter what keys the user has bound them to, if any.
+pub mod command_names {
+    use super::CommandName;
 
-    pub fn add_run_state_snapshot() -> CommandKey {
-        (CTRL | SHIFT, VirtualKeyCode::F1)
-    }
+    pub const COMMAND_MENU: CommandName = "toggle_command_menu";
+    pub const DEBUG_MENU: CommandName = "toggle_debug_menu";
+    pub const ADD_RUN_STATE_SNAPSHOT: CommandName = "add_run_state_snapshot";
 }
 
-
-
+#[derive(Clone, Copy)]
 pub struct LabelledCommand {
+    pub name: CommandName,
     pub label: &'static str, 
     pub command: fn(&mut RunState),
 }
@@ -374,12 +500,22 @@ pub struct LabelledCommand {
 impl std::fmt::Debug for LabelledCommand {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         f.debug_struct("LabelledCommand")
+           .field("name", &self.name)
            .field("label", &self.label)
            .field("command", &"fn(&mut RunState)")           .finish()
     }
 }
 
-pub type CommandsMap = BTreeMap<CommandKey, LabelledCommand>;
+#[derive(Clone, Debug)]
+pub struct BoundCommand {
+    pub command: LabelledCommand,
+    /// The label along with the key combination and the command name, as shown in the
+    /// command menu.
+    pub menu_text: String,
+}
+
+pub type CommandsMap = BTreeMap<CommandKey, BoundCommand>;
+pub type NamedCommandsMap = BTreeMap<CommandName, LabelledCommand>;
 
 #[derive(Debug)]
 /// Values that should not be changed (i.e. should be left constant,) which were 
@@ -387,7 +523,667 @@ pub type CommandsMap = BTreeMap<CommandKey, LabelledCommand>;
 /// called inside `run`. Keeping this separate from `RunState` also simplifies some 
 /// borrow checking when using the `commands` map.
 pub struct RunConsts {
-    pub commands: CommandsMap
+    /// The effective bindings, that is, the default ones with the ones from the
+    /// keybindings file applied on top.
+    pub commands: CommandsMap,
+    /// Every command, whether or not it is currently bound to a key.
+    pub named_commands: NamedCommandsMap,
+}
+
+pub mod keybindings {
+    //! The keybindings file lets users change which key combinations run which 
+    //! commands. Each line that is not blank and does not start with `#` should look
+    //! like `Ctrl+Alt+Z = previous_undo_branch`. Binding a key combination in the file
+    //! replaces the default binding for that key combination, if there is one, and
+    //! binding one to `none` means it will run no command at all. All other default
+    //! bindings are left as they are.
+    use super::*;
+    use std::fmt::Write;
+
+    pub const FILE_NAME: &str = "keybindings.txt";
+
+    pub const UNBOUND: &str = "none";
+
+    const MODIFIERS: [(&str, ModifiersState); 4] = [
+        ("Logo", ModifiersState::LOGO),
+        ("Ctrl", ModifiersState::CTRL),
+        ("Alt", ModifiersState::ALT),
+        ("Shift", ModifiersState::SHIFT),
+    ];
+
+    macro_rules! keys {
+        ($($key: ident)+) => {
+            /// The keys that can be named in the keybindings file. They are named the
+            /// same way as the `VirtualKeyCode` variants.
+            const KEYS: &[VirtualKeyCode] = &[$(VirtualKeyCode::$key,)+];
+        }
+    }
+
+    keys!{
+        Key0 Key1 Key2 Key3 Key4 Key5 Key6 Key7 Key8 Key9
+        A B C D E F G H I J K L M N O P Q R S T U V W X Y Z
+        F1 F2 F3 F4 F5 F6 F7 F8 F9 F10 F11 F12
+        F13 F14 F15 F16 F17 F18 F19 F20 F21 F22 F23 F24
+        Escape Back Return Space Tab Apps Snapshot Scroll Pause
+        Insert Delete Home End PageUp PageDown Left Up Right Down
+        Numpad0 Numpad1 Numpad2 Numpad3 Numpad4 Numpad5 Numpad6 Numpad7 Numpad8 Numpad9
+        Apostrophe Backslash Comma Equals Grave LBracket Minus Period RBracket Semicolon Slash
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub enum LineErrorKind {
+        MissingEquals,
+        UnknownModifier(String),
+        UnknownKey(String),
+        UnknownCommand(String),
+        /// The key combination was already bound on the line with this number.
+        Conflict(usize),
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub enum Error {
+        /// Two different commands were given the same default key combination.
+        DefaultConflict(CommandKey),
+        /// Two different commands were given the same name.
+        DuplicateName(CommandName),
+        Line {
+            line_number: usize,
+            kind: LineErrorKind,
+        },
+    }
+
+    impl std::fmt::Display for Error {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            u!{Error, LineErrorKind}
+            match self {
+                DefaultConflict(key) => write!(
+                    f,
+                    "More than one command has {} as its default key combination.",
+                    command_key_to_string(*key)
+                ),
+                DuplicateName(name) => write!(
+                    f,
+                    "More than one command is named \"{}\".",
+                    name
+                ),
+                Line { line_number, kind } => {
+                    write!(f, "{} line {}: ", FILE_NAME, line_number)?;
+                    match kind {
+                        MissingEquals => write!(f, "Expected a line like \"Ctrl+Z = undo\"."),
+                        UnknownModifier(s) => write!(
+                            f,
+                            "Unknown modifier \"{}\". Expected one of Logo, Ctrl, Alt or Shift.",
+                            s
+                        ),
+                        UnknownKey(s) => write!(f, "Unknown key \"{}\".", s),
+                        UnknownCommand(s) => write!(f, "Unknown command \"{}\".", s),
+                        Conflict(previous_line_number) => write!(
+                            f,
+                            "This key combination was already bound on line {}.",
+                            previous_line_number
+                        ),
+                    }
+                }
+            }
+        }
+    }
+
+    impl std::error::Error for Error {}
+
+    pub fn command_key_to_string((modifiers, key): CommandKey) -> String {
+        let mut output = String::new();
+        for &(name, modifier) in MODIFIERS.iter() {
+            if modifiers.contains(modifier) {
+                output.push_str(name);
+                output.push('+');
+            }
+        }
+        let _cannot_actually_fail = write!(output, "{:?}", key);
+        output
+    }
+
+    /// Names are matched case-insensitively, and spaces around the `+`s are ignored.
+    pub fn parse_command_key(s: &str) -> Result<CommandKey, LineErrorKind> {
+        u!{LineErrorKind}
+        let mut parts: Vec<&str> = s.split('+').map(str::trim).collect();
+        let key_name = parts.pop().unwrap_or_default();
+
+        let mut modifiers = ModifiersState::empty();
+        for part in parts {
+            let (_, modifier) = MODIFIERS.iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(part))
+                .ok_or_else(|| UnknownModifier(part.to_owned()))?;
+            modifiers |= *modifier;
+        }
+
+        let key = KEYS.iter()
+            .find(|key| format!("{:?}", key).eq_ignore_ascii_case(key_name))
+            .ok_or_else(|| UnknownKey(key_name.to_owned()))?;
+
+        Ok((modifiers, *key))
+    }
+
+    fn bound_command(key: CommandKey, command: LabelledCommand) -> BoundCommand {
+        BoundCommand {
+            menu_text: format!(
+                "{} ({} = {})",
+                command.label,
+                command_key_to_string(key),
+                command.name
+            ),
+            command,
+        }
+    }
+
+    /// Returns the `RunConsts` resulting from applying the bindings in `file`, which
+    /// should have the contents of the keybindings file, to the `defaults`. Every
+    /// problem found is returned, and the entries with problems are skipped.
+    pub fn apply(
+        defaults: &[(CommandKey, LabelledCommand)],
+        file: &str,
+    ) -> (RunConsts, Vec<Error>) {
+        let mut errors = Vec::new();
+
+        let mut named_commands = NamedCommandsMap::new();
+        let mut commands = CommandsMap::new();
+        for &(key, command) in defaults {
+            match named_commands.get(command.name) {
+                // Some commands have more than one default key combination.
+                Some(existing) if existing.label == command.label => {}
+                Some(_) => {
+                    errors.push(Error::DuplicateName(command.name));
+                    continue
+                }
+                None => {
+                    named_commands.insert(command.name, command);
+                }
+            }
+
+            if commands.contains_key(&key) {
+                errors.push(Error::DefaultConflict(key));
+                continue
+            }
+            commands.insert(key, bound_command(key, command));
+        }
+
+        let mut bound_on_lines = BTreeMap::new();
+        for (i, line) in file.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue
+            }
+
+            macro_rules! line_error {
+                ($kind: expr) => {{
+                    errors.push(Error::Line { line_number, kind: $kind });
+                    continue
+                }}
+            }
+
+            let (key_str, name) = match line.find('=') {
+                Some(index) => (&line[..index], line[index + 1..].trim()),
+                None => line_error!(LineErrorKind::MissingEquals),
+            };
+
+            let key = match parse_command_key(key_str) {
+                Ok(key) => key,
+                Err(kind) => line_error!(kind),
+            };
+
+            let command = if name.eq_ignore_ascii_case(UNBOUND) {
+                None
+            } else {
+                match named_commands.get(name) {
+                    Some(command) => Some(*command),
+                    None => line_error!(LineErrorKind::UnknownCommand(name.to_owned())),
+                }
+            };
+
+            if let Some(previous_line_number) = bound_on_lines.get(&key) {
+                line_error!(LineErrorKind::Conflict(*previous_line_number));
+            }
+            bound_on_lines.insert(key, line_number);
+
+            match command {
+                Some(command) => {
+                    commands.insert(key, bound_command(key, command));
+                }
+                None => {
+                    commands.remove(&key);
+                }
+            }
+        }
+
+        (RunConsts { commands, named_commands }, errors)
+    }
+
+    /// The contents to use for a keybindings file that does not exist yet: an
+    /// explanation of the format, along with the default bindings commented out.
+    pub fn default_file(defaults: &[(CommandKey, LabelledCommand)]) -> String {
+        let mut output = format!(
+            "# Each line looks like \"Ctrl+Alt+Z = previous_undo_branch\".\n\
+             # The modifiers are Logo, Ctrl, Alt and Shift.\n\
+             # Bind a key combination to \"{}\" to have it run no command.\n\
+             # The default bindings are:\n",
+            UNBOUND
+        );
+        for &(key, command) in defaults {
+            let _cannot_actually_fail = writeln!(
+                output,
+                "# {} = {}",
+                command_key_to_string(key),
+                command.name
+            );
+        }
+        output
+    }
+}
+
+pub mod theme {
+    //! A theme sets the colours that everything is drawn in. Each line of a theme file
+    //! that is not blank and does not start with `#` should look like
+    //! `chrome_background = #164f31`, with the colour written as `#rrggbb`, or as
+    //! `#rrggbbaa` to make it partly transparent. The names are either the name of a
+    //! part of the UI, `highlight.` followed by the name of a kind of highlight, or
+    //! `span.` followed by a kind of span. Span kinds can be named `plain`, `comment`
+    //! or `string`, and any kind can be given by its number, from 0 to 255. Text in
+    //! spans of a kind that the theme has no colour for is drawn in the `span.plain`
+    //! colour. Any other colours that a file leaves out are the same as in the dark
+    //! theme.
+    //!
+    //! The themes are the files ending in `.txt` in the themes directory. The files
+    //! for the shipped themes are written there when they are missing, so they can be
+    //! edited, or copied to make new themes.
+    use super::*;
+    use platform_types::{HighlightKind, SpanKind, spans::SpanKindRaw};
+    use std::fmt::Write;
+    use std::path::Path;
+
+    pub type Colour = [f32; 4];
+
+    pub const DIRECTORY_NAME: &str = "themes";
+
+    pub const EXTENSION: &str = "txt";
+
+    /// The name of the file in the themes directory that holds the name of the theme
+    /// in use, so it can be used again next time. It has no extension, so it is not
+    /// mistaken for a theme.
+    pub const CURRENT_FILE_NAME: &str = "current";
+
+    pub const DARK: &str = "dark";
+    pub const LIGHT: &str = "light";
+
+    const HIGHLIGHT_PREFIX: &str = "highlight.";
+    const SPAN_PREFIX: &str = "span.";
+
+    const SPAN_NAMES: [(&str, SpanKind); 3] = [
+        ("plain", SpanKind::PLAIN),
+        ("comment", SpanKind::COMMENT),
+        ("string", SpanKind::STRING),
+    ];
+
+    macro_rules! named_colours {
+        (
+            $(#[$attr: meta])*
+            $name: ident {
+                $($(#[$field_attr: meta])* $field: ident)+
+            }
+        ) => {
+            $(#[$attr])*
+            #[derive(Clone, Copy, Debug, PartialEq)]
+            pub struct $name {
+                $($(#[$field_attr])* pub $field: Colour,)+
+            }
+
+            impl $name {
+                fn get_mut(&mut self, name: &str) -> Option<&mut Colour> {
+                    match name {
+                        $(stringify!($field) => Some(&mut self.$field),)+
+                        _ => None,
+                    }
+                }
+
+                fn named(&self) -> Vec<(&'static str, Colour)> {
+                    vec![$((stringify!($field), self.$field),)+]
+                }
+            }
+        }
+    }
+
+    named_colours!{
+        /// The colours of everything other than the text in the buffers and the
+        /// highlights on it.
+        UiColours {
+            text_background
+            chrome_background
+            chrome_text
+            tab_bar_background
+            tab_background
+            tab_text
+            /// Drawn under the tab of the buffer being shown.
+            selected_tab_underline
+            /// Drawn over the tabs of buffers with edits that have not been saved.
+            unsaved_tab_overline
+            /// Drawn over the tabs of buffers with edits that have been saved.
+            saved_tab_overline
+            /// Drawn around buttons selected with the mouse. The alpha value is not
+            /// used, since the outline fades out.
+            mouse_selection
+            /// Like `mouse_selection`, for buttons selected with the keyboard.
+            keyboard_selection
+            /// Like `mouse_selection`, for buttons selected with both.
+            mouse_and_keyboard_selection
+            /// The alpha value is not used, since cursors blink.
+            cursor
+            /// The cursor colour when it was just moved against the edge of the text.
+            cursor_against_wall
+            fold_placeholder
+            whitespace_mark
+            trailing_whitespace
+            mixed_indentation
+        }
+    }
+
+    named_colours!{
+        /// The colours of each `HighlightKind`, named like the variants.
+        HighlightColours {
+            user
+            result
+            current_result
+            matching_bracket
+            unmatched_bracket
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Theme {
+        pub ui: UiColours,
+        pub highlights: HighlightColours,
+        /// This always has a colour for `SpanKind::PLAIN`, which every kind without
+        /// a colour of its own uses.
+        spans: BTreeMap<SpanKindRaw, Colour>,
+    }
+    d!(for Theme: Theme::dark());
+
+    fn rgb(hex: u32) -> Colour {
+        rgba(hex << 8 | 0xff)
+    }
+
+    fn rgba(hex: u32) -> Colour {
+        let [r, g, b, a] = hex.to_be_bytes();
+        [
+            r as f32 / 255.0,
+            g as f32 / 255.0,
+            b as f32 / 255.0,
+            a as f32 / 255.0,
+        ]
+    }
+
+    impl Theme {
+        pub fn dark() -> Self {
+            Theme {
+                ui: UiColours {
+                    text_background: rgb(0x222222),
+                    chrome_background: rgb(0x164f31),
+                    chrome_text: rgb(0x4891ae),
+                    tab_bar_background: rgb(0x4891ae),
+                    tab_background: rgb(0x5a7d8b),
+                    tab_text: rgb(0xeeeeee),
+                    selected_tab_underline: rgb(0x3352e1),
+                    unsaved_tab_overline: rgb(0xde4949),
+                    saved_tab_overline: rgb(0x666666),
+                    mouse_selection: rgb(0xffb937),
+                    keyboard_selection: rgb(0x3352e1),
+                    mouse_and_keyboard_selection: rgb(0x30b06e),
+                    cursor: rgb(0xde4949),
+                    cursor_against_wall: rgb(0xffb937),
+                    fold_placeholder: rgb(0x666666),
+                    whitespace_mark: rgb(0x666666),
+                    trailing_whitespace: rgb(0x490b0b),
+                    mixed_indentation: rgb(0x533354),
+                },
+                highlights: HighlightColours {
+                    user: rgba(0x66666699),
+                    result: rgba(0xffb93799),
+                    current_result: rgba(0x30b06e99),
+                    matching_bracket: rgb(0x4891ae),
+                    unmatched_bracket: rgb(0xde4949),
+                },
+                spans: vec![
+                    (SpanKind::PLAIN.get_byte(), rgb(0x3352e1)),
+                    (SpanKind::COMMENT.get_byte(), rgb(0x5a7d8b)),
+                    (SpanKind::STRING.get_byte(), rgb(0x30b06e)),
+                    (SpanKind::FIRST_UNASSIGNED_RAW, rgb(0xffb937)),
+                    (SpanKind::FIRST_UNASSIGNED_RAW + 1, rgb(0x533354)),
+                ].into_iter().collect(),
+            }
+        }
+
+        pub fn light() -> Self {
+            Theme {
+                ui: UiColours {
+                    text_background: rgb(0xfafafa),
+                    chrome_background: rgb(0xdde8e2),
+                    chrome_text: rgb(0x1d5a73),
+                    tab_bar_background: rgb(0xc4d8e0),
+                    tab_background: rgb(0xe8eef0),
+                    tab_text: rgb(0x222222),
+                    selected_tab_underline: rgb(0x3352e1),
+                    unsaved_tab_overline: rgb(0xc62828),
+                    saved_tab_overline: rgb(0x999999),
+                    mouse_selection: rgb(0xd48a00),
+                    keyboard_selection: rgb(0x3352e1),
+                    mouse_and_keyboard_selection: rgb(0x208050),
+                    cursor: rgb(0xc62828),
+                    cursor_against_wall: rgb(0xd48a00),
+                    fold_placeholder: rgb(0x999999),
+                    whitespace_mark: rgb(0xbbbbbb),
+                    trailing_whitespace: rgb(0xf4c7c7),
+                    mixed_indentation: rgb(0xd9b8da),
+                },
+                highlights: HighlightColours {
+                    user: rgba(0xb0c4de99),
+                    result: rgba(0xffd75e99),
+                    current_result: rgba(0x8fd9a899),
+                    matching_bracket: rgb(0x1d5a73),
+                    unmatched_bracket: rgb(0xc62828),
+                },
+                spans: vec![
+                    (SpanKind::PLAIN.get_byte(), rgb(0x1a237e)),
+                    (SpanKind::COMMENT.get_byte(), rgb(0x6a7f89)),
+                    (SpanKind::STRING.get_byte(), rgb(0x1b7a45)),
+                    (SpanKind::FIRST_UNASSIGNED_RAW, rgb(0x9a6700)),
+                    (SpanKind::FIRST_UNASSIGNED_RAW + 1, rgb(0x7b3f7d)),
+                ].into_iter().collect(),
+            }
+        }
+
+        /// The themes that a new themes directory starts out with, by name.
+        pub fn shipped() -> Vec<(&'static str, Self)> {
+            vec![(DARK, Theme::dark()), (LIGHT, Theme::light())]
+        }
+
+        pub fn highlight(&self, kind: HighlightKind) -> Colour {
+            u!{HighlightKind}
+            match kind {
+                User => self.highlights.user,
+                Result => self.highlights.result,
+                CurrentResult => self.highlights.current_result,
+                MatchingBracket => self.highlights.matching_bracket,
+                UnmatchedBracket => self.highlights.unmatched_bracket,
+            }
+        }
+
+        pub fn span(&self, kind: SpanKind) -> Colour {
+            *self.spans.get(&kind.get_byte())
+                .unwrap_or_else(|| &self.spans[&SpanKind::PLAIN.get_byte()])
+        }
+
+        /// The contents of a theme file that `parse` would read back as this theme.
+        pub fn to_file(&self) -> String {
+            let mut output = String::from(
+                "# Each line looks like \"chrome_background = #164f31\".\n\
+                 # Colours can also have an alpha value, like #66666699.\n\
+                 # Spans of kinds without a colour use the span.plain colour.\n"
+            );
+
+            macro_rules! line {
+                ($($arg: tt)*) => {
+                    let _cannot_actually_fail = writeln!(output, $($arg)*);
+                }
+            }
+
+            for (name, colour) in self.ui.named() {
+                line!("{} = {}", name, colour_to_string(colour));
+            }
+            for (name, colour) in self.highlights.named() {
+                line!("{}{} = {}", HIGHLIGHT_PREFIX, name, colour_to_string(colour));
+            }
+            for (&raw, &colour) in self.spans.iter() {
+                match SPAN_NAMES.iter().find(|(_, kind)| kind.get_byte() == raw) {
+                    Some((name, _)) => {
+                        line!("{}{} = {}", SPAN_PREFIX, name, colour_to_string(colour));
+                    }
+                    None => {
+                        line!("{}{} = {}", SPAN_PREFIX, raw, colour_to_string(colour));
+                    }
+                }
+            }
+
+            output
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub enum LineErrorKind {
+        MissingEquals,
+        UnknownName(String),
+        BadColour(String),
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct Error {
+        pub line_number: usize,
+        pub kind: LineErrorKind,
+    }
+
+    impl std::fmt::Display for Error {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            u!{LineErrorKind}
+            write!(f, "line {}: ", self.line_number)?;
+            match &self.kind {
+                MissingEquals => write!(f, "Expected a line like \"chrome_text = #4891ae\"."),
+                UnknownName(s) => write!(f, "Unknown name \"{}\".", s),
+                BadColour(s) => write!(
+                    f,
+                    "Expected a colour like #4891ae or #66666699, but found \"{}\".",
+                    s
+                ),
+            }
+        }
+    }
+
+    impl std::error::Error for Error {}
+
+    /// Accepts `#rrggbb` and `#rrggbbaa`, with the digits in either case.
+    pub fn parse_colour(s: &str) -> Option<Colour> {
+        let digits = s.strip_prefix('#')?;
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None
+        }
+        let hex = u32::from_str_radix(digits, 16).ok()?;
+        match digits.len() {
+            6 => Some(rgb(hex)),
+            8 => Some(rgba(hex)),
+            _ => None,
+        }
+    }
+
+    /// Leaves off the alpha value when the colour is opaque.
+    pub fn colour_to_string(colour: Colour) -> String {
+        let [r, g, b, a] = {
+            let mut bytes = [0; 4];
+            for (byte, component) in bytes.iter_mut().zip(colour.iter()) {
+                *byte = (component.max(0.0).min(1.0) * 255.0).round() as u8;
+            }
+            bytes
+        };
+        if a == 0xff {
+            format!("#{:02x}{:02x}{:02x}", r, g, b)
+        } else {
+            format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a)
+        }
+    }
+
+    fn parse_span_kind(s: &str) -> Option<SpanKind> {
+        SPAN_NAMES.iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(s))
+            .map(|(_, kind)| *kind)
+            .or_else(|| s.parse().ok().map(SpanKind::new))
+    }
+
+    /// Returns the theme described by `file`, which should have the contents of a
+    /// theme file, starting from the dark theme. Every problem found is returned, and
+    /// the lines with problems are skipped.
+    pub fn parse(file: &str) -> (Theme, Vec<Error>) {
+        let mut theme = Theme::dark();
+        let mut errors = Vec::new();
+
+        for (i, line) in file.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue
+            }
+
+            macro_rules! line_error {
+                ($kind: expr) => {{
+                    errors.push(Error { line_number, kind: $kind });
+                    continue
+                }}
+            }
+
+            let (name, colour_str) = match line.find('=') {
+                Some(index) => (line[..index].trim(), line[index + 1..].trim()),
+                None => line_error!(LineErrorKind::MissingEquals),
+            };
+
+            let colour = match parse_colour(colour_str) {
+                Some(colour) => colour,
+                None => line_error!(LineErrorKind::BadColour(colour_str.to_owned())),
+            };
+
+            let target = if let Some(kind) = name.strip_prefix(SPAN_PREFIX) {
+                parse_span_kind(kind)
+                    .map(|kind| theme.spans.entry(kind.get_byte()).or_default())
+            } else if let Some(kind) = name.strip_prefix(HIGHLIGHT_PREFIX) {
+                theme.highlights.get_mut(kind)
+            } else {
+                theme.ui.get_mut(name)
+            };
+
+            match target {
+                Some(target) => *target = colour,
+                None => line_error!(LineErrorKind::UnknownName(name.to_owned())),
+            }
+        }
+
+        (theme, errors)
+    }
+
+    /// Returns the name of the theme in the file at `path`, if it is a theme file.
+    pub fn name_from_path(path: &Path) -> Option<String> {
+        if path.extension()? == EXTENSION {
+            path.file_stem()?.to_str().map(str::to_owned)
+        } else {
+            None
+        }
+    }
+
+    pub fn path(themes_dir: &Path, name: &str) -> PathBuf {
+        themes_dir.join(format!("{}.{}", name, EXTENSION))
+    }
 }
 
 pub mod ui {
@@ -397,9 +1193,10 @@ pub mod ui {
     /// The varaints here represent sections of code that want to be able to store information in the
     /// ids. For example, so that the ui state can change differently based on which part of some
     /// dynamically generated UI is selected.
-    #[derive(Clone, Copy, Debug)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
     pub enum Tag {
         FileSwitcherResults,
+        FindResults,
     }
 
     /// 31 to leave space for the enum variant tag.
@@ -457,8 +1254,8 @@ pub mod ui {
             (Data(d1), Data(d2)) => {
                 d1.cmp(&d2)
             }
-            (TaggedUsize(Tag::FileSwitcherResults, payload1), TaggedUsize(Tag::FileSwitcherResults, payload2)) => {
-                payload1.cmp(&payload2)
+            (TaggedUsize(tag1, payload1), TaggedUsize(tag2, payload2)) => {
+                tag1.cmp(&tag2).then_with(|| payload1.cmp(&payload2))
             }
         }
     });
@@ -883,6 +1680,222 @@ pub fn transform_at(
 mod tests {
     use super::*;
 
+    fn no_op(_: &mut RunState) {}
+
+    fn test_defaults() -> Vec<(CommandKey, LabelledCommand)> {
+        vec![
+            (
+                (ModifiersState::CTRL, VirtualKeyCode::Z),
+                LabelledCommand { name: "undo", label: "Undo.", command: no_op },
+            ),
+            (
+                (ModifiersState::CTRL, VirtualKeyCode::Y),
+                LabelledCommand { name: "redo", label: "Redo.", command: no_op },
+            ),
+            (
+                (ModifiersState::CTRL | ModifiersState::SHIFT, VirtualKeyCode::Z),
+                LabelledCommand { name: "redo", label: "Redo.", command: no_op },
+            ),
+        ]
+    }
+
+    fn bound_names(r_c: &RunConsts) -> Vec<(String, CommandName)> {
+        r_c.commands.iter()
+            .map(|(key, bound)| (keybindings::command_key_to_string(*key), bound.command.name))
+            .collect()
+    }
+
+    #[test]
+    fn parse_command_key_round_trips_through_command_key_to_string_on_these_examples() {
+        for key in [
+            (ModifiersState::empty(), VirtualKeyCode::Apps),
+            (ModifiersState::CTRL | ModifiersState::ALT, VirtualKeyCode::Key0),
+            (ModifiersState::LOGO | ModifiersState::CTRL | ModifiersState::SHIFT, VirtualKeyCode::Tab),
+            (ModifiersState::SHIFT, VirtualKeyCode::F12),
+        ].iter() {
+            let s = keybindings::command_key_to_string(*key);
+            assert_eq!(keybindings::parse_command_key(&s), Ok(*key), "{}", s);
+        }
+    }
+
+    #[test]
+    fn parse_command_key_ignores_case_and_spaces() {
+        assert_eq!(
+            keybindings::parse_command_key(" shift + CTRL+slash "),
+            Ok((ModifiersState::CTRL | ModifiersState::SHIFT, VirtualKeyCode::Slash))
+        );
+    }
+
+    #[test]
+    fn keybindings_apply_returns_the_defaults_given_a_file_of_only_comments() {
+        let defaults = test_defaults();
+        let (r_c, errors) = keybindings::apply(
+            &defaults,
+            &keybindings::default_file(&defaults)
+        );
+
+        assert_eq!(errors, vec![]);
+        assert_eq!(
+            bound_names(&r_c),
+            vec![
+                ("Ctrl+Y".to_owned(), "redo"),
+                ("Ctrl+Z".to_owned(), "undo"),
+                ("Ctrl+Shift+Z".to_owned(), "redo"),
+            ]
+        );
+        assert_eq!(r_c.named_commands.len(), 2);
+    }
+
+    #[test]
+    fn keybindings_apply_rebinds_and_unbinds_keys() {
+        let (r_c, errors) = keybindings::apply(
+            &test_defaults(),
+            "Ctrl+Y = undo\nCtrl+Shift+Z = none\nAlt+Back = redo\n"
+        );
+
+        assert_eq!(errors, vec![]);
+        assert_eq!(
+            bound_names(&r_c),
+            vec![
+                ("Ctrl+Y".to_owned(), "undo"),
+                ("Ctrl+Z".to_owned(), "undo"),
+                ("Alt+Back".to_owned(), "redo"),
+            ]
+        );
+    }
+
+    #[test]
+    fn keybindings_apply_reports_unknown_and_conflicting_entries_and_skips_them() {
+        use keybindings::{Error, LineErrorKind::*};
+        let (r_c, errors) = keybindings::apply(
+            &test_defaults(),
+            "# A comment\n\
+             Ctrl+Y = undo\n\
+             Ctrl+Y = redo\n\
+             Hyper+Y = redo\n\
+             Ctrl+Yes = redo\n\
+             Ctrl+Y = do_a_barrel_roll\n\
+             Ctrl+Y\n"
+        );
+
+        assert_eq!(
+            errors,
+            vec![
+                Error::Line { line_number: 3, kind: Conflict(2) },
+                Error::Line { line_number: 4, kind: UnknownModifier("Hyper".to_owned()) },
+                Error::Line { line_number: 5, kind: UnknownKey("Yes".to_owned()) },
+                Error::Line { line_number: 6, kind: UnknownCommand("do_a_barrel_roll".to_owned()) },
+                Error::Line { line_number: 7, kind: MissingEquals },
+            ]
+        );
+        assert_eq!(r_c.commands[&(ModifiersState::CTRL, VirtualKeyCode::Y)].command.name, "undo");
+    }
+
+    #[test]
+    fn keybindings_apply_reports_conflicting_defaults_instead_of_panicking() {
+        use keybindings::Error;
+        let mut defaults = test_defaults();
+        defaults.push((
+            (ModifiersState::CTRL, VirtualKeyCode::Z),
+            LabelledCommand { name: "zap", label: "Zap.", command: no_op },
+        ));
+        defaults.push((
+            (ModifiersState::ALT, VirtualKeyCode::Z),
+            LabelledCommand { name: "undo", label: "Not undo.", command: no_op },
+        ));
+
+        let (r_c, errors) = keybindings::apply(&defaults, "");
+
+        assert_eq!(
+            errors,
+            vec![
+                Error::DefaultConflict((ModifiersState::CTRL, VirtualKeyCode::Z)),
+                Error::DuplicateName("undo"),
+            ]
+        );
+        assert_eq!(r_c.commands[&(ModifiersState::CTRL, VirtualKeyCode::Z)].command.name, "undo");
+        assert_eq!(r_c.named_commands["undo"].label, "Undo.");
+    }
+
+    #[test]
+    fn theme_parse_reads_back_the_file_of_each_shipped_theme() {
+        for (name, theme) in theme::Theme::shipped() {
+            let (parsed, errors) = theme::parse(&theme.to_file());
+
+            assert_eq!(errors, vec![], "{}", name);
+            assert_eq!(parsed, theme, "{}", name);
+        }
+    }
+
+    #[test]
+    fn theme_parse_colours_span_kinds_by_name_or_number() {
+        use platform_types::SpanKind;
+        let (theme, errors) = theme::parse(
+            "span.Comment = #010203\n\
+             span.200 = #040506\n\
+             span.plain = #070809"
+        );
+
+        assert_eq!(errors, vec![]);
+        assert_eq!(theme.span(SpanKind::COMMENT), theme::parse_colour("#010203").unwrap());
+        assert_eq!(theme.span(SpanKind::new(200)), theme::parse_colour("#040506").unwrap());
+        // Kinds without a colour of their own use the plain colour.
+        assert_eq!(theme.span(SpanKind::new(201)), theme::parse_colour("#070809").unwrap());
+        assert_eq!(theme.span(SpanKind::STRING), theme::Theme::dark().span(SpanKind::STRING));
+    }
+
+    #[test]
+    fn theme_parse_reports_bad_lines_and_skips_them() {
+        use theme::{Error, LineErrorKind::*};
+        let (theme, errors) = theme::parse(
+            "# A comment.\n\
+             chrome_text = #ABCDEF80\n\
+             chrome_text #123456\n\
+             highlight.user = #12345\n\
+             highlight.nobody = #123456\n\
+             span.-1 = #123456\n\
+             tab_text = red"
+        );
+
+        assert_eq!(
+            errors,
+            vec![
+                Error { line_number: 3, kind: MissingEquals },
+                Error { line_number: 4, kind: BadColour("#12345".to_owned()) },
+                Error { line_number: 5, kind: UnknownName("highlight.nobody".to_owned()) },
+                Error { line_number: 6, kind: UnknownName("span.-1".to_owned()) },
+                Error { line_number: 7, kind: BadColour("red".to_owned()) },
+            ]
+        );
+
+        let mut expected = theme::Theme::dark();
+        expected.ui.chrome_text = [
+            0xab as f32 / 255.0,
+            0xcd as f32 / 255.0,
+            0xef as f32 / 255.0,
+            0x80 as f32 / 255.0,
+        ];
+        assert_eq!(theme, expected);
+    }
+
+    #[test]
+    fn theme_colour_to_string_leaves_off_opaque_alpha() {
+        assert_eq!(theme::colour_to_string([1.0, 0.0, 0.2, 1.0]), "#ff0033");
+        assert_eq!(theme::colour_to_string([1.0, 0.0, 0.2, 0.6]), "#ff003399");
+    }
+
+    #[test]
+    fn theme_name_from_path_only_accepts_theme_files() {
+        use std::path::Path;
+        assert_eq!(theme::name_from_path(Path::new("themes/solar.ized.txt")), Some("solar.ized".to_owned()));
+        assert_eq!(theme::name_from_path(Path::new("themes/current")), None);
+        assert_eq!(theme::name_from_path(Path::new("themes/notes.md")), None);
+        assert_eq!(
+            theme::path(Path::new("themes"), "solar.ized"),
+            Path::new("themes/solar.ized.txt")
+        );
+    }
+
     fn state_at_generation(generation: g_i::Generation) -> g_i::State {
         let mut s: g_i::State = d!();
         for _ in 0..generation {
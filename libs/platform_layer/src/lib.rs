@@ -1,9 +1,25 @@
 // We might have different platform layer options later, so let's keep this separate.
 use platform_types::UpdateAndRender;
+
+/// Returns whether the headless platform layer will be used instead of the default one.
+pub fn is_headless() -> bool {
+    std::env::args().nth(1).as_deref() == Some(headless::HEADLESS)
+}
+
 pub fn run(update_and_render: UpdateAndRender) {
+    if is_headless() {
+        let result = headless::run(update_and_render, std::env::args().skip(2));
+
+        if let Err(e) = result {
+            eprintln!("headless::run(update_and_render, args) error:\n{}", e);
+            std::process::exit(1)
+        }
+        return
+    }
+
     let result = wimp::run(update_and_render);
 
     if let Err(e) = result {
         println!("wimp::run(update_and_render) error:\n{}", e);
     }
-}
+}
\ No newline at end of file
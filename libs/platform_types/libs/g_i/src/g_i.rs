@@ -237,6 +237,21 @@ impl std::cmp::PartialEq<IndexPart> for Index {
 }
 
 impl Index {
+    /// Along with `to_parts`, this allows storing an `Index` exactly, which is
+    /// otherwise only possible when the `State` it was created from is available.
+    #[must_use]
+    pub fn from_parts(generation: Generation, index: IndexPart) -> Self {
+        Index {
+            generation,
+            index,
+        }
+    }
+
+    #[must_use]
+    pub fn to_parts(self) -> (Generation, IndexPart) {
+        (self.generation, self.index)
+    }
+
     #[must_use]
     pub fn get(self, state: State) -> Option<usize> {
         self.get_index_part(state).map(|IndexPart(i)| i as usize)
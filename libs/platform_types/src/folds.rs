@@ -0,0 +1,147 @@
+//! Folds hide ranges of lines, and a single placeholder line is shown in their place.
+//! "Lines" here are lines of the text, as in `Position::line`, and "display lines" are
+//! lines as they are shown, where each fold takes up a single line.
+use std::cmp::max;
+
+/// A range of lines, inclusive at both ends, that are hidden while folded.
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Fold {
+    pub first_line: usize,
+    pub last_line: usize,
+}
+
+impl Fold {
+    pub fn contains_line(&self, line: usize) -> bool {
+        self.first_line <= line && line <= self.last_line
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.last_line.saturating_sub(self.first_line).saturating_add(1)
+    }
+
+    /// The lines around the hidden ones, that a cursor could be on and reasonably
+    /// be said to be "at" this fold.
+    pub fn region_contains_line(&self, line: usize) -> bool {
+        self.first_line.saturating_sub(1) <= line && line <= self.last_line.saturating_add(1)
+    }
+}
+
+/// Returns the folds as they are actually shown, given that some of `folds` may be
+/// inside of others. That is, the outermost folds, sorted, with overlapping or adjacent
+/// ones merged together, so that the lines right before and after each of the returned
+/// folds are not hidden. The other functions in this module expect folds like this.
+pub fn shown(folds: &[Fold]) -> Vec<Fold> {
+    let mut sorted = folds.to_vec();
+    sorted.sort();
+
+    let mut output: Vec<Fold> = Vec::with_capacity(sorted.len());
+    for fold in sorted {
+        match output.last_mut() {
+            Some(last) if fold.first_line <= last.last_line.saturating_add(1) => {
+                last.last_line = max(last.last_line, fold.last_line);
+            }
+            _ => output.push(fold),
+        }
+    }
+
+    output
+}
+
+/// Returns the fold that hides `line`, if there is one.
+pub fn hiding(shown: &[Fold], line: usize) -> Option<Fold> {
+    shown.iter().find(|f| f.contains_line(line)).copied()
+}
+
+/// Hidden lines are given the display line of the placeholder that hides them.
+pub fn display_line(shown: &[Fold], line: usize) -> usize {
+    let mut hidden_before = 0;
+    for fold in shown {
+        if fold.last_line < line {
+            hidden_before += fold.line_count() - 1;
+        } else {
+            if fold.first_line <= line {
+                return fold.first_line - hidden_before;
+            }
+            break;
+        }
+    }
+
+    line - hidden_before
+}
+
+/// The inverse of `display_line`, where a placeholder is given the first line that
+/// its fold hides.
+pub fn text_line(shown: &[Fold], display_line: usize) -> usize {
+    let mut hidden_before = 0;
+    for fold in shown {
+        let placeholder_line = fold.first_line - hidden_before;
+        if display_line < placeholder_line {
+            break;
+        }
+        if display_line == placeholder_line {
+            return fold.first_line;
+        }
+        hidden_before += fold.line_count() - 1;
+    }
+
+    display_line.saturating_add(hidden_before)
+}
+
+/// Updates `folds` for an edit that started on `line`, and removed `removed` line
+/// breaks and then added `added` line breaks there. Folds that the edit touched are
+/// dropped, since what they should cover afterwards is not clear, and folds after
+/// the edit are moved along with their lines.
+pub fn adjust_for_edit(folds: &mut Vec<Fold>, line: usize, removed: usize, added: usize) {
+    let last_edited_line = line.saturating_add(removed);
+
+    folds.retain(|f| f.last_line < line || f.first_line > last_edited_line);
+
+    for fold in folds.iter_mut() {
+        if fold.first_line > last_edited_line {
+            fold.first_line = fold.first_line - removed + added;
+            fold.last_line = fold.last_line - removed + added;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fold(first_line: usize, last_line: usize) -> Fold {
+        Fold { first_line, last_line }
+    }
+
+    #[test]
+    fn shown_merges_nested_overlapping_and_adjacent_folds() {
+        let folds = vec![fold(10, 12), fold(2, 8), fold(3, 4), fold(8, 9), fold(14, 15)];
+
+        assert_eq!(shown(&folds), vec![fold(2, 12), fold(14, 15)]);
+    }
+
+    #[test]
+    fn display_line_and_text_line_agree_around_folds() {
+        let shown = shown(&[fold(2, 4), fold(7, 7)]);
+
+        let display_lines: Vec<_> = (0..10).map(|l| display_line(&shown, l)).collect();
+        assert_eq!(display_lines, vec![0, 1, 2, 2, 2, 3, 4, 5, 6, 7]);
+
+        let text_lines: Vec<_> = (0..8).map(|l| text_line(&shown, l)).collect();
+        assert_eq!(text_lines, vec![0, 1, 2, 5, 6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn adjust_for_edit_moves_later_folds_and_drops_touched_ones() {
+        let mut folds = vec![fold(1, 2), fold(5, 6), fold(9, 10)];
+
+        // Joining line 4 and 5 touches the second fold.
+        adjust_for_edit(&mut folds, 4, 1, 0);
+
+        assert_eq!(folds, vec![fold(1, 2), fold(8, 9)]);
+
+        // Adding lines at the end of the line before a fold keeps it.
+        adjust_for_edit(&mut folds, 7, 0, 3);
+
+        assert_eq!(folds, vec![fold(1, 2), fold(11, 12)]);
+    }
+}
@@ -0,0 +1,855 @@
+//! A stable, line based, text format for logs of `Input`s, so that a session can be
+//! recorded and then replayed later. Any build that understands the `HEADER` can read
+//! a log, but checking that a replay ends with the same view is only meaningful with
+//! the same build that recorded it, since that is all the view hash is valid for.
+//!
+//! The first line is the `HEADER`. Each line after that is either an entry, which is
+//! the number of microseconds since the recording started followed by an `Input`, or
+//! the hash of the final `View`, which is written once the `Quit` input has been
+//! handled. Every part of an `Input` is separated by a single space. Strings and
+//! paths are quoted, with `\\`, `\"`, `\n`, `\r` and `\t` escapes, so that they can
+//! contain spaces and line breaks, and enum variants are written as their names.
+//! Paths that are not valid unicode are not stored exactly.
+use super::*;
+use crate::screen_positioning::{FontInfo, TextBoxXYWH};
+
+/// This should be changed whenever the format changes, so that logs in an older
+/// format are not misread.
+pub const HEADER: &str = "rote input log v1";
+
+const VIEW_HASH: &str = "view_hash";
+
+const NONE_TOKEN: &str = "-";
+const SOME_TOKEN: &str = "+";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    pub since_start: Duration,
+    pub input: Input,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Log {
+    pub entries: Vec<Entry>,
+    /// This will be `None` if the recording was not stopped by a `Quit` input, for
+    /// example, because the editor crashed.
+    pub view_hash: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    UnknownVersion,
+    /// The line with this number could not be parsed.
+    Malformed(usize),
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ParseError::UnknownVersion => write!(f, "Expected \"{}\" as the first line.", HEADER),
+            ParseError::Malformed(line_number) => write!(f, "Could not parse line {}.", line_number),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+pub fn header_line() -> String {
+    format!("{}\n", HEADER)
+}
+
+pub fn entry_line(since_start: Duration, input: &Input) -> String {
+    let mut output = String::new();
+    write_u128(&mut output, since_start.as_micros());
+    write_input(&mut output, input);
+    output.push('\n');
+    output
+}
+
+pub fn view_hash_line(view_hash: u64) -> String {
+    format!("{} {}\n", VIEW_HASH, view_hash)
+}
+
+pub fn parse(log: &str) -> Result<Log, ParseError> {
+    let mut lines = log.lines();
+    if lines.next() != Some(HEADER) {
+        return Err(ParseError::UnknownVersion);
+    }
+
+    let mut output: Log = d!();
+    // The header was line 1.
+    for (line_number, line) in (2..).zip(lines) {
+        let malformed = ParseError::Malformed(line_number);
+        if output.view_hash.is_some() {
+            // Nothing should come after the hash.
+            return Err(malformed);
+        }
+
+        let mut reader = Reader(line);
+        if line.starts_with(VIEW_HASH) {
+            reader.token();
+            output.view_hash = Some(reader.u64().ok_or(malformed)?);
+        } else {
+            let since_start = Duration::from_micros(reader.u64().ok_or(malformed)?);
+            let input = read_input(&mut reader).ok_or(malformed)?;
+            output.entries.push(Entry { since_start, input });
+        }
+
+        if !reader.0.is_empty() {
+            return Err(malformed);
+        }
+    }
+
+    Ok(output)
+}
+
+/// A hash of everything in the `View` that should be the same when the same `Input`s
+/// are given to the same build of the editor, in the same environment. This hashes the
+/// `Debug` output of the view, which can change along with the code, or with the
+/// compiler used to build it, so hashes are only comparable between runs of one build.
+pub fn view_hash(view: &View) -> u64 {
+    let mut view = view.clone();
+    // This depends on how fast the editor ran.
+    view.stats = d!();
+
+    // Unlike the standard library's hashers, `content_hash` does not depend on the
+    // compiler version, so the hash itself is not what changes between builds.
+    content_hash(&format!("{:?}", view))
+}
+
+//
+// Writing. Every token is preceded by a space.
+//
+
+fn write_token(output: &mut String, token: &str) {
+    output.push(' ');
+    output.push_str(token);
+}
+
+fn write_u128(output: &mut String, n: u128) {
+    // The entries start with a number, so we leave off the space in that case.
+    if !output.is_empty() {
+        output.push(' ');
+    }
+    output.push_str(&n.to_string());
+}
+
+fn write_usize(output: &mut String, n: usize) {
+    write_token(output, &n.to_string());
+}
+
+fn write_f32(output: &mut String, f: f32) {
+    // The `Display` implementation for floats produces output that parses back to the
+    // same value.
+    write_token(output, &f.to_string());
+}
+
+fn write_pos(output: &mut String, pos: abs::Pos) {
+    write_token(output, &pos.to_bits().to_string());
+}
+
+fn write_length(output: &mut String, length: abs::Length) {
+    write_pos(output, length.into());
+}
+
+fn write_str(output: &mut String, s: &str) {
+    output.push_str(" \"");
+    for c in s.chars() {
+        match c {
+            '\\' => output.push_str("\\\\"),
+            '"' => output.push_str("\\\""),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            _ => output.push(c),
+        }
+    }
+    output.push('"');
+}
+
+fn write_path(output: &mut String, path: &std::path::Path) {
+    write_str(output, &path.to_string_lossy());
+}
+
+fn write_option_str(output: &mut String, op: &Option<String>) {
+    match op {
+        Some(s) => {
+            write_token(output, SOME_TOKEN);
+            write_str(output, s);
+        }
+        None => write_token(output, NONE_TOKEN),
+    }
+}
+
+fn write_index(output: &mut String, index: g_i::Index) {
+    let (generation, index_part) = index.to_parts();
+    write_token(output, &generation.to_string());
+    write_usize(output, index_part.into());
+}
+
+fn write_buffer_name(output: &mut String, name: &BufferName) {
+    match name {
+        BufferName::Path(p) => {
+            write_token(output, "Path");
+            write_path(output, p);
+        }
+        BufferName::Hex(p) => {
+            write_token(output, "Hex");
+            write_path(output, p);
+        }
+        BufferName::Scratch(n) => {
+            write_token(output, "Scratch");
+            write_token(output, &n.to_string());
+        }
+    }
+}
+
+fn write_text_box_space_xy(output: &mut String, xy: TextBoxSpaceXY) {
+    write_pos(output, xy.x);
+    write_pos(output, xy.y);
+}
+
+fn write_char_dim(output: &mut String, char_dim: CharDim) {
+    write_length(output, char_dim.w);
+    write_length(output, char_dim.h);
+}
+
+fn write_option_text_box_xywh(output: &mut String, op: Option<TextBoxXYWH>) {
+    match op {
+        Some(TextBoxXYWH { xy, wh }) => {
+            write_token(output, SOME_TOKEN);
+            write_pos(output, xy.x);
+            write_pos(output, xy.y);
+            write_length(output, wh.w);
+            write_length(output, wh.h);
+        }
+        None => write_token(output, NONE_TOKEN),
+    }
+}
+
+fn write_size_dependents(output: &mut String, size_dependents: &SizeDependents) {
+    match size_dependents.font_info {
+        Some(FontInfo { text_char_dim, status_char_dim, tab_char_dim, find_replace_char_dim }) => {
+            write_token(output, SOME_TOKEN);
+            write_char_dim(output, text_char_dim);
+            write_char_dim(output, status_char_dim);
+            write_char_dim(output, tab_char_dim);
+            write_char_dim(output, find_replace_char_dim);
+        }
+        None => write_token(output, NONE_TOKEN),
+    }
+    write_option_text_box_xywh(output, size_dependents.buffer_xywh);
+    write_option_text_box_xywh(output, size_dependents.find_xywh);
+    write_option_text_box_xywh(output, size_dependents.replace_xywh);
+    write_option_text_box_xywh(output, size_dependents.go_to_position_xywh);
+}
+
+fn replace_or_add_name(replace_or_add: ReplaceOrAdd) -> &'static str {
+    match replace_or_add {
+        ReplaceOrAdd::Replace => "Replace",
+        ReplaceOrAdd::Add => "Add",
+    }
+}
+
+fn move_name(r#move: Move) -> &'static str {
+    u!{Move}
+    match r#move {
+        Up => "Up",
+        Down => "Down",
+        Left => "Left",
+        Right => "Right",
+        ToLineStart => "ToLineStart",
+        ToLineEnd => "ToLineEnd",
+        ToBufferStart => "ToBufferStart",
+        ToBufferEnd => "ToBufferEnd",
+        ToPreviousLikelyEditLocation => "ToPreviousLikelyEditLocation",
+        ToNextLikelyEditLocation => "ToNextLikelyEditLocation",
+    }
+}
+
+const ALL_MOVES: [Move; 10] = {
+    u!{Move}
+    [
+        Up,
+        Down,
+        Left,
+        Right,
+        ToLineStart,
+        ToLineEnd,
+        ToBufferStart,
+        ToBufferEnd,
+        ToPreviousLikelyEditLocation,
+        ToNextLikelyEditLocation,
+    ]
+};
+
+fn selection_adjustment_name(adjustment: SelectionAdjustment) -> &'static str {
+    match adjustment {
+        SelectionAdjustment::Next => "Next",
+        SelectionAdjustment::Previous => "Previous",
+        SelectionAdjustment::Move(SelectionMove::Left) => "MoveLeft",
+        SelectionAdjustment::Move(SelectionMove::Right) => "MoveRight",
+        SelectionAdjustment::Move(SelectionMove::ToStart) => "MoveToStart",
+        SelectionAdjustment::Move(SelectionMove::ToEnd) => "MoveToEnd",
+    }
+}
+
+const ALL_SELECTION_ADJUSTMENTS: [SelectionAdjustment; 6] = [
+    SelectionAdjustment::Next,
+    SelectionAdjustment::Previous,
+    SelectionAdjustment::Move(SelectionMove::Left),
+    SelectionAdjustment::Move(SelectionMove::Right),
+    SelectionAdjustment::Move(SelectionMove::ToStart),
+    SelectionAdjustment::Move(SelectionMove::ToEnd),
+];
+
+fn buffer_id_kind_name(kind: BufferIdKind) -> &'static str {
+    u!{BufferIdKind}
+    match kind {
+        None => "None",
+        Text => "Text",
+        Find => "Find",
+        Replace => "Replace",
+        FileSwitcher => "FileSwitcher",
+        GoToPosition => "GoToPosition",
+    }
+}
+
+const ALL_BUFFER_ID_KINDS: [BufferIdKind; 6] = {
+    u!{BufferIdKind}
+    [None, Text, Find, Replace, FileSwitcher, GoToPosition]
+};
+
+fn menu_mode_name(mode: MenuMode) -> &'static str {
+    match mode {
+        MenuMode::Hidden => "Hidden",
+        MenuMode::FileSwitcher => "FileSwitcher",
+        MenuMode::FindReplace(FindReplaceMode::CurrentFile) => "FindReplaceCurrentFile",
+        MenuMode::FindReplace(FindReplaceMode::OpenBuffers) => "FindReplaceOpenBuffers",
+        MenuMode::FindReplace(FindReplaceMode::Directory) => "FindReplaceDirectory",
+        MenuMode::GoToPosition => "GoToPosition",
+    }
+}
+
+const ALL_MENU_MODES: [MenuMode; 6] = [
+    MenuMode::Hidden,
+    MenuMode::FileSwitcher,
+    MenuMode::FindReplace(FindReplaceMode::CurrentFile),
+    MenuMode::FindReplace(FindReplaceMode::OpenBuffers),
+    MenuMode::FindReplace(FindReplaceMode::Directory),
+    MenuMode::GoToPosition,
+];
+
+fn write_input(output: &mut String, input: &Input) {
+    u!{Input}
+    macro_rules! name {
+        ($name: ident) => {
+            write_token(output, stringify!($name))
+        };
+    }
+
+    match input {
+        None => name!(None),
+        Quit => name!(Quit),
+        CloseMenuIfAny => name!(CloseMenuIfAny),
+        Insert(c) => {
+            name!(Insert);
+            write_str(output, c.encode_utf8(&mut [0; 4]));
+        }
+        Delete => name!(Delete),
+        DeleteLines => name!(DeleteLines),
+        ResetScroll => name!(ResetScroll),
+        ScrollVertically(f) => {
+            name!(ScrollVertically);
+            write_f32(output, *f);
+        }
+        ScrollHorizontally(f) => {
+            name!(ScrollHorizontally);
+            write_f32(output, *f);
+        }
+        SetSizeDependents(size_dependents) => {
+            name!(SetSizeDependents);
+            write_size_dependents(output, size_dependents);
+        }
+        MoveAllCursors(r#move) => {
+            name!(MoveAllCursors);
+            write_token(output, move_name(*r#move));
+        }
+        ExtendSelectionForAllCursors(r#move) => {
+            name!(ExtendSelectionForAllCursors);
+            write_token(output, move_name(*r#move));
+        }
+        SelectAll => name!(SelectAll),
+        SetCursor(xy, replace_or_add) => {
+            name!(SetCursor);
+            write_text_box_space_xy(output, *xy);
+            write_token(output, replace_or_add_name(*replace_or_add));
+        }
+        DragCursors(xy) => {
+            name!(DragCursors);
+            write_text_box_space_xy(output, *xy);
+        }
+        SelectCharTypeGrouping(xy, replace_or_add) => {
+            name!(SelectCharTypeGrouping);
+            write_text_box_space_xy(output, *xy);
+            write_token(output, replace_or_add_name(*replace_or_add));
+        }
+        ExtendSelectionWithSearch => name!(ExtendSelectionWithSearch),
+        SavedAs(index, path) => {
+            name!(SavedAs);
+            write_index(output, *index);
+            write_path(output, path);
+        }
+        Undo => name!(Undo),
+        Redo => name!(Redo),
+        PreviousUndoBranch => name!(PreviousUndoBranch),
+        NextUndoBranch => name!(NextUndoBranch),
+        SetUndoTreeShown(shown) => {
+            name!(SetUndoTreeShown);
+            write_token(output, &shown.to_string());
+        }
+        Cut => name!(Cut),
+        Copy => name!(Copy),
+        Paste(op) => {
+            name!(Paste);
+            write_option_str(output, op);
+        }
+        InsertNumbersAtCursors => name!(InsertNumbersAtCursors),
+        AddOrSelectBuffer(name, s) => {
+            name!(AddOrSelectBuffer);
+            write_buffer_name(output, name);
+            write_str(output, s);
+        }
+        NewScratchBuffer(op) => {
+            name!(NewScratchBuffer);
+            write_option_str(output, op);
+        }
+        TabIn => name!(TabIn),
+        TabOut => name!(TabOut),
+        ToggleComments => name!(ToggleComments),
+        JumpToMatchingBrackets => name!(JumpToMatchingBrackets),
+        SelectInsideBrackets => name!(SelectInsideBrackets),
+        SwapLinesUp => name!(SwapLinesUp),
+        SwapLinesDown => name!(SwapLinesDown),
+        SwapSyntaxNodeUp => name!(SwapSyntaxNodeUp),
+        SwapSyntaxNodeDown => name!(SwapSyntaxNodeDown),
+        ExtractFunction => name!(ExtractFunction),
+        ExtractVariable => name!(ExtractVariable),
+        ExtractVariableEverywhere => name!(ExtractVariableEverywhere),
+        AdjustBufferSelection(adjustment) => {
+            name!(AdjustBufferSelection);
+            write_token(output, selection_adjustment_name(*adjustment));
+        }
+        NextLanguage => name!(NextLanguage),
+        SelectBuffer(BufferId { kind, index }) => {
+            name!(SelectBuffer);
+            write_token(output, buffer_id_kind_name(*kind));
+            write_index(output, *index);
+        }
+        OpenOrSelectBuffer(path) => {
+            name!(OpenOrSelectBuffer);
+            write_path(output, path);
+        }
+        OpenOrSelectBufferAt(path, position) => {
+            name!(OpenOrSelectBufferAt);
+            write_path(output, path);
+            write_usize(output, position.line);
+            write_usize(output, position.offset.0);
+        }
+        CloseBuffer(index) => {
+            name!(CloseBuffer);
+            write_index(output, *index);
+        }
+        SetMenuMode(mode) => {
+            name!(SetMenuMode);
+            write_token(output, menu_mode_name(*mode));
+        }
+        SubmitForm => name!(SubmitForm),
+        ReplaceAll => name!(ReplaceAll),
+        ToggleRegexSearch => name!(ToggleRegexSearch),
+        CycleSearchCaseSensitivity => name!(CycleSearchCaseSensitivity),
+        ToggleWholeWordSearch => name!(ToggleWholeWordSearch),
+        ToggleSearchInSelection => name!(ToggleSearchInSelection),
+        SearchFiles(files) => {
+            name!(SearchFiles);
+            write_usize(output, files.len());
+            for (path, s) in files {
+                write_path(output, path);
+                write_str(output, s);
+            }
+        }
+        SerializeBufferHistories => name!(SerializeBufferHistories),
+        RestoreBufferHistory(name, s) => {
+            name!(RestoreBufferHistory);
+            write_buffer_name(output, name);
+            write_str(output, s);
+        }
+        ReloadBuffer(index, s) => {
+            name!(ReloadBuffer);
+            write_index(output, *index);
+            write_str(output, s);
+        }
+        SetOnDiskText(name, s) => {
+            name!(SetOnDiskText);
+            write_buffer_name(output, name);
+            write_str(output, s);
+        }
+        Fold => name!(Fold),
+        Unfold => name!(Unfold),
+    }
+}
+
+//
+// Reading
+//
+
+struct Reader<'s>(&'s str);
+
+impl <'s> Reader<'s> {
+    /// Returns the next unquoted token.
+    fn token(&mut self) -> Option<&'s str> {
+        let s = self.0.strip_prefix(' ').unwrap_or(self.0);
+        if s.is_empty() || s.starts_with('"') {
+            return Option::None;
+        }
+        let end = s.find(' ').unwrap_or_else(|| s.len());
+        self.0 = &s[end..];
+        Some(&s[..end])
+    }
+
+    fn parse<T: std::str::FromStr>(&mut self) -> Option<T> {
+        self.token()?.parse().ok()
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        self.parse()
+    }
+
+    fn usize(&mut self) -> Option<usize> {
+        self.parse()
+    }
+
+    fn bool(&mut self) -> Option<bool> {
+        self.parse()
+    }
+
+    fn f32(&mut self) -> Option<f32> {
+        self.parse()
+    }
+
+    fn pos(&mut self) -> Option<abs::Pos> {
+        self.parse().map(abs::Pos::from_bits)
+    }
+
+    fn length(&mut self) -> Option<abs::Length> {
+        self.pos().map(abs::Length::from)
+    }
+
+    fn str(&mut self) -> Option<String> {
+        let s = self.0.strip_prefix(" \"")?;
+        let mut output = String::new();
+        let mut chars = s.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.0 = &s[i + 1..];
+                    return Some(output);
+                }
+                '\\' => output.push(match chars.next()?.1 {
+                    '\\' => '\\',
+                    '"' => '"',
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    _ => return Option::None,
+                }),
+                _ => output.push(c),
+            }
+        }
+        // The closing quote was missing.
+        Option::None
+    }
+
+    fn path(&mut self) -> Option<PathBuf> {
+        self.str().map(PathBuf::from)
+    }
+
+    fn char(&mut self) -> Option<char> {
+        let s = self.str()?;
+        let mut chars = s.chars();
+        let c = chars.next()?;
+        if chars.next().is_some() {
+            return Option::None;
+        }
+        Some(c)
+    }
+
+    /// Returns `Some(true)` for `SOME_TOKEN` and `Some(false)` for `NONE_TOKEN`.
+    fn is_some(&mut self) -> Option<bool> {
+        match self.token()? {
+            SOME_TOKEN => Some(true),
+            NONE_TOKEN => Some(false),
+            _ => Option::None,
+        }
+    }
+
+    fn option_str(&mut self) -> Option<Option<String>> {
+        Some(if self.is_some()? { Some(self.str()?) } else { Option::None })
+    }
+
+    fn named<T: Copy>(&mut self, all: &[T], name_of: fn(T) -> &'static str) -> Option<T> {
+        let token = self.token()?;
+        all.iter().find(|t| name_of(**t) == token).cloned()
+    }
+
+    fn index(&mut self) -> Option<g_i::Index> {
+        let generation = self.parse()?;
+        let index_part = g_i::IndexPart::or_max(self.usize()?);
+        Some(g_i::Index::from_parts(generation, index_part))
+    }
+
+    fn buffer_name(&mut self) -> Option<BufferName> {
+        match self.token()? {
+            "Path" => Some(BufferName::Path(self.path()?)),
+            "Hex" => Some(BufferName::Hex(self.path()?)),
+            "Scratch" => Some(BufferName::Scratch(self.parse()?)),
+            _ => Option::None,
+        }
+    }
+
+    fn text_box_space_xy(&mut self) -> Option<TextBoxSpaceXY> {
+        Some(TextBoxSpaceXY {
+            x: self.pos()?,
+            y: self.pos()?,
+        })
+    }
+
+    fn replace_or_add(&mut self) -> Option<ReplaceOrAdd> {
+        self.named(&[ReplaceOrAdd::Replace, ReplaceOrAdd::Add], replace_or_add_name)
+    }
+
+    fn char_dim(&mut self) -> Option<CharDim> {
+        Some(CharDim {
+            w: self.length()?,
+            h: self.length()?,
+        })
+    }
+
+    fn option_text_box_xywh(&mut self) -> Option<Option<TextBoxXYWH>> {
+        Some(if self.is_some()? {
+            Some(TextBoxXYWH {
+                xy: TextBoxXY {
+                    x: self.pos()?,
+                    y: self.pos()?,
+                },
+                wh: ScreenSpaceWH {
+                    w: self.length()?,
+                    h: self.length()?,
+                },
+            })
+        } else {
+            Option::None
+        })
+    }
+
+    fn size_dependents(&mut self) -> Option<SizeDependents> {
+        let font_info = if self.is_some()? {
+            Some(FontInfo {
+                text_char_dim: self.char_dim()?,
+                status_char_dim: self.char_dim()?,
+                tab_char_dim: self.char_dim()?,
+                find_replace_char_dim: self.char_dim()?,
+            })
+        } else {
+            Option::None
+        };
+
+        Some(SizeDependents {
+            font_info,
+            buffer_xywh: self.option_text_box_xywh()?,
+            find_xywh: self.option_text_box_xywh()?,
+            replace_xywh: self.option_text_box_xywh()?,
+            go_to_position_xywh: self.option_text_box_xywh()?,
+        })
+    }
+}
+
+fn read_input(reader: &mut Reader) -> Option<Input> {
+    u!{Input}
+    Some(match reader.token()? {
+        "None" => None,
+        "Quit" => Quit,
+        "CloseMenuIfAny" => CloseMenuIfAny,
+        "Insert" => Insert(reader.char()?),
+        "Delete" => Delete,
+        "DeleteLines" => DeleteLines,
+        "ResetScroll" => ResetScroll,
+        "ScrollVertically" => ScrollVertically(reader.f32()?),
+        "ScrollHorizontally" => ScrollHorizontally(reader.f32()?),
+        "SetSizeDependents" => SetSizeDependents(Box::new(reader.size_dependents()?)),
+        "MoveAllCursors" => MoveAllCursors(reader.named(&ALL_MOVES, move_name)?),
+        "ExtendSelectionForAllCursors" => ExtendSelectionForAllCursors(
+            reader.named(&ALL_MOVES, move_name)?
+        ),
+        "SelectAll" => SelectAll,
+        "SetCursor" => SetCursor(reader.text_box_space_xy()?, reader.replace_or_add()?),
+        "DragCursors" => DragCursors(reader.text_box_space_xy()?),
+        "SelectCharTypeGrouping" => SelectCharTypeGrouping(
+            reader.text_box_space_xy()?,
+            reader.replace_or_add()?
+        ),
+        "ExtendSelectionWithSearch" => ExtendSelectionWithSearch,
+        "SavedAs" => SavedAs(reader.index()?, reader.path()?),
+        "Undo" => Undo,
+        "Redo" => Redo,
+        "PreviousUndoBranch" => PreviousUndoBranch,
+        "NextUndoBranch" => NextUndoBranch,
+        "SetUndoTreeShown" => SetUndoTreeShown(reader.bool()?),
+        "Cut" => Cut,
+        "Copy" => Copy,
+        "Paste" => Paste(reader.option_str()?),
+        "InsertNumbersAtCursors" => InsertNumbersAtCursors,
+        "AddOrSelectBuffer" => AddOrSelectBuffer(reader.buffer_name()?, reader.str()?),
+        "NewScratchBuffer" => NewScratchBuffer(reader.option_str()?),
+        "TabIn" => TabIn,
+        "TabOut" => TabOut,
+        "ToggleComments" => ToggleComments,
+        "JumpToMatchingBrackets" => JumpToMatchingBrackets,
+        "SelectInsideBrackets" => SelectInsideBrackets,
+        "SwapLinesUp" => SwapLinesUp,
+        "SwapLinesDown" => SwapLinesDown,
+        "SwapSyntaxNodeUp" => SwapSyntaxNodeUp,
+        "SwapSyntaxNodeDown" => SwapSyntaxNodeDown,
+        "ExtractFunction" => ExtractFunction,
+        "ExtractVariable" => ExtractVariable,
+        "ExtractVariableEverywhere" => ExtractVariableEverywhere,
+        "AdjustBufferSelection" => AdjustBufferSelection(
+            reader.named(&ALL_SELECTION_ADJUSTMENTS, selection_adjustment_name)?
+        ),
+        "NextLanguage" => NextLanguage,
+        "SelectBuffer" => SelectBuffer(BufferId {
+            kind: reader.named(&ALL_BUFFER_ID_KINDS, buffer_id_kind_name)?,
+            index: reader.index()?,
+        }),
+        "OpenOrSelectBuffer" => OpenOrSelectBuffer(reader.path()?),
+        "OpenOrSelectBufferAt" => OpenOrSelectBufferAt(
+            reader.path()?,
+            Position {
+                line: reader.usize()?,
+                offset: CharOffset(reader.usize()?),
+            }
+        ),
+        "CloseBuffer" => CloseBuffer(reader.index()?),
+        "SetMenuMode" => SetMenuMode(reader.named(&ALL_MENU_MODES, menu_mode_name)?),
+        "SubmitForm" => SubmitForm,
+        "ReplaceAll" => ReplaceAll,
+        "ToggleRegexSearch" => ToggleRegexSearch,
+        "CycleSearchCaseSensitivity" => CycleSearchCaseSensitivity,
+        "ToggleWholeWordSearch" => ToggleWholeWordSearch,
+        "ToggleSearchInSelection" => ToggleSearchInSelection,
+        "SearchFiles" => {
+            let len = reader.usize()?;
+            // The length came from a file, so we don't trust it enough to preallocate
+            // with it.
+            let mut files = Vec::new();
+            for _ in 0..len {
+                files.push((reader.path()?, reader.str()?));
+            }
+            SearchFiles(files)
+        }
+        "SerializeBufferHistories" => SerializeBufferHistories,
+        "RestoreBufferHistory" => RestoreBufferHistory(reader.buffer_name()?, reader.str()?),
+        "ReloadBuffer" => ReloadBuffer(reader.index()?, reader.str()?),
+        "SetOnDiskText" => SetOnDiskText(reader.buffer_name()?, reader.str()?),
+        "Fold" => Fold,
+        "Unfold" => Unfold,
+        _ => return Option::None,
+    })
+}
+
+#[cfg(test)]
+mod input_log_tests {
+    use super::*;
+    use crate::tests::arb;
+    use proptest::proptest;
+
+    proptest! {
+        #[test]
+        fn entry_line_round_trips_through_parse(
+            micros in 0..u64::max_value(),
+            input in arb::input(),
+        ) {
+            let since_start = Duration::from_micros(micros);
+            let log = format!("{}{}", header_line(), entry_line(since_start, &input));
+
+            assert_eq!(
+                parse(&log),
+                Ok(Log {
+                    entries: vec![Entry { since_start, input }],
+                    view_hash: Option::None,
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn parse_reads_the_view_hash_after_the_entries() {
+        let log = format!(
+            "{}{}{}{}",
+            header_line(),
+            entry_line(Duration::from_micros(1), &Input::Insert(' ')),
+            entry_line(Duration::from_micros(20), &Input::Paste(Some("a \"b\"\n".to_owned()))),
+            view_hash_line(u64::max_value()),
+        );
+
+        assert_eq!(
+            parse(&log),
+            Ok(Log {
+                entries: vec![
+                    Entry { since_start: Duration::from_micros(1), input: Input::Insert(' ') },
+                    Entry {
+                        since_start: Duration::from_micros(20),
+                        input: Input::Paste(Some("a \"b\"\n".to_owned())),
+                    },
+                ],
+                view_hash: Some(u64::max_value()),
+            })
+        );
+    }
+
+    #[test]
+    fn parse_rejects_logs_with_a_different_header() {
+        assert_eq!(parse("rote input log v0\n"), Err(ParseError::UnknownVersion));
+        assert_eq!(parse(""), Err(ParseError::UnknownVersion));
+    }
+
+    #[test]
+    fn parse_reports_the_line_number_of_malformed_lines() {
+        let log = format!(
+            "{}{}12 NotAnInput\n",
+            header_line(),
+            entry_line(Duration::from_micros(1), &Input::Undo),
+        );
+
+        assert_eq!(parse(&log), Err(ParseError::Malformed(3)));
+    }
+
+    #[test]
+    fn parse_rejects_entries_after_the_view_hash() {
+        let log = format!(
+            "{}{}{}",
+            header_line(),
+            view_hash_line(0),
+            entry_line(Duration::from_micros(1), &Input::Undo),
+        );
+
+        assert_eq!(parse(&log), Err(ParseError::Malformed(3)));
+    }
+
+    #[test]
+    fn parse_rejects_unterminated_strings() {
+        let log = format!("{}1 Paste + \"abc\n", header_line());
+
+        assert_eq!(parse(&log), Err(ParseError::Malformed(2)));
+    }
+}
@@ -8,7 +8,7 @@ use std::{
 };
 
 pub use vec1::{vec1, Vec1};
-pub use panic_safe_rope::{Rope, RopeSlice, RopeSliceTrait, ByteIndex};
+pub use panic_safe_rope::{Rope, RopeSlice, RopeSliceTrait, ByteIndex, LineIndex};
 pub use text_pos::*;
 
 pub mod floating_point;
@@ -36,6 +36,11 @@ pub use spans::{
     SpanView,
     SpanKind,
 };
+
+pub mod input_log;
+
+pub mod folds;
+pub use folds::Fold;
 
 pub use abs;
 pub use f32_0_1::{F32_0_1, f32_0_1};
@@ -76,6 +81,11 @@ pub enum Input {
     SavedAs(g_i::Index, PathBuf),
     Undo,
     Redo,
+    PreviousUndoBranch,
+    NextUndoBranch,
+    /// Whether `View::undo_tree_drawing` should be filled in. Drawing the tree takes
+    /// a while, so it is left empty unless something is going to show it.
+    SetUndoTreeShown(bool),
     Cut,
     Copy,
     Paste(Option<String>),
@@ -84,13 +94,63 @@ pub enum Input {
     NewScratchBuffer(Option<String>),
     TabIn,
     TabOut,
+    /// Comment out the line(s) each cursor is on, or uncomment them if they are all
+    /// commented out already, using the comment syntax of the current language.
+    ToggleComments,
+    /// Move each cursor that is next to a bracket to the bracket that matches it.
+    JumpToMatchingBrackets,
+    /// Select the contents of the closest pair of brackets around each selection.
+    SelectInsideBrackets,
+    /// Swap the line(s) each cursor is on with the line above them.
+    SwapLinesUp,
+    /// Swap the line(s) each cursor is on with the line below them.
+    SwapLinesDown,
+    /// Move the smallest syntax node containing each selection before its previous sibling.
+    SwapSyntaxNodeUp,
+    /// Move the smallest syntax node containing each selection after its next sibling.
+    SwapSyntaxNodeDown,
+    /// Move the code selected by the first cursor into a new function, and call that
+    /// function in its place.
+    ExtractFunction,
+    /// Move the expression selected by the first cursor into a new variable, and use that
+    /// variable in its place.
+    ExtractVariable,
+    /// Like `ExtractVariable`, but also use the variable in place of the identical
+    /// expressions in the same block.
+    ExtractVariableEverywhere,
     AdjustBufferSelection(SelectionAdjustment),
     NextLanguage,
     SelectBuffer(BufferId),
     OpenOrSelectBuffer(PathBuf),
+    OpenOrSelectBufferAt(PathBuf, Position),
     CloseBuffer(g_i::Index),
     SetMenuMode(MenuMode),
     SubmitForm,
+    ReplaceAll,
+    ToggleRegexSearch,
+    CycleSearchCaseSensitivity,
+    ToggleWholeWordSearch,
+    ToggleSearchInSelection,
+    SearchFiles(Vec<(PathBuf, String)>),
+    /// Ask for a `Cmd::StoreBufferHistories` for all the current buffers.
+    SerializeBufferHistories,
+    /// Restore a buffer's history from a string previously produced by
+    /// `Input::SerializeBufferHistories`. If the history does not match the buffer's
+    /// text, or is in an older format, this does nothing.
+    RestoreBufferHistory(BufferName, String),
+    /// Replace the text of a buffer with what is now in the file at its path, because
+    /// another program changed that file. This is undoable, and the cursors are kept
+    /// where they were, as far as the new text allows.
+    ReloadBuffer(g_i::Index, String),
+    /// The file the buffer with the given name shows now has the given contents, as
+    /// that buffer would show them. That buffer, if there is one, is unedited exactly
+    /// when its text matches them.
+    SetOnDiskText(BufferName, String),
+    /// Fold the lines selected by the cursors, or if none of them have multiple lines
+    /// selected, the smallest unfolded syntax node that each cursor is at.
+    Fold,
+    /// Unfold the smallest shown fold that each cursor is at.
+    Unfold,
 }
 d!(for Input : Input::None);
 
@@ -164,6 +224,10 @@ pub enum HighlightKind {
     User,
     Result,
     CurrentResult,
+    /// A bracket next to a cursor, or the bracket matching that one.
+    MatchingBracket,
+    /// A bracket next to a cursor that has no matching bracket.
+    UnmatchedBracket,
 }
 d!(for HighlightKind: HighlightKind::User);
 
@@ -324,6 +388,9 @@ pub fn push_highlights<O: Into<Option<Position>>>(
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum BufferName {
     Path(PathBuf),
+    /// A hex dump of the file at the path, which is saved back to it as bytes.
+    /// See the `hex_dump` crate for the format.
+    Hex(PathBuf),
     Scratch(u32),
 }
 d!(for BufferName: BufferName::Scratch(d!()));
@@ -333,27 +400,36 @@ fmt_display!(for BufferName: name in "{}",
             .file_name()
             .map(|s| s.to_string_lossy().into_owned())
             .unwrap_or_else(|| "?Unknown Path?".to_string()),
+        BufferName::Hex(p) => format!(
+            "{} (hex)",
+            p.file_name()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "?Unknown Path?".to_string())
+        ),
         BufferName::Scratch(n) => format!("*scratch {}*", n),
     }
  );
 ord!(for BufferName: name, other in {
      use BufferName::*;
      use std::cmp::Ordering::*;
-     match (name, other) {
-         (Path(p1), Path(p2)) => {
-             match (p1.canonicalize(), p2.canonicalize() ) {
-                 (Ok(ref cp1), Ok(ref cp2)) if cp1 == cp2 => {
-                     Equal
-                 }
-                 _ => {
-                     p1.cmp(&p2)
-                 }
+     let cmp_paths = |p1: &PathBuf, p2: &PathBuf| {
+         match (p1.canonicalize(), p2.canonicalize() ) {
+             (Ok(ref cp1), Ok(ref cp2)) if cp1 == cp2 => {
+                 Equal
              }
+             _ => {
+                 p1.cmp(&p2)
+             }
+         }
+     };
+     match (name, other) {
+         (Path(p1), Path(p2)) | (Hex(p1), Hex(p2)) => {
+             cmp_paths(p1, p2)
          }
-         (Path(_), Scratch(_)) => {
+         (Path(_), Hex(_)) | (Path(_), Scratch(_)) | (Hex(_), Scratch(_)) => {
              Less
          }
-         (Scratch(_), Path(_)) => {
+         (Hex(_), Path(_)) | (Scratch(_), Path(_)) | (Scratch(_), Hex(_)) => {
              Greater
          }
          (Scratch(n1), Scratch(n2)) => {
@@ -376,6 +452,25 @@ impl BufferName {
     }
 }
 
+/// A hash of some text or bytes, given in pieces, that is used to tell whether a buffer
+/// matches what is on disk. It does not depend on how the text is split into pieces.
+pub fn content_hash_chunks<B: AsRef<[u8]>>(chunks: impl IntoIterator<Item = B>) -> u64 {
+    // An FNV-1a hash, which unlike the standard library's hashers, we know will not
+    // change between versions of the compiler.
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for chunk in chunks {
+        for &byte in chunk.as_ref() {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+pub fn content_hash(bytes: impl AsRef<[u8]>) -> u64 {
+    content_hash_chunks(std::iter::once(bytes))
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub enum CursorState {
     None,
@@ -480,15 +575,118 @@ pub struct FileSwitcherView {
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum FindReplaceMode {
     CurrentFile,
+    /// Search every open buffer that has a path.
+    OpenBuffers,
+    /// Search every file under the directory that contains the current buffer.
+    Directory,
 }
 d!(for FindReplaceMode: FindReplaceMode::CurrentFile);
 
+/// A match found while searching more than one file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FindResult {
+    pub path: PathBuf,
+    pub position: Position,
+    /// The path, line and column in `path:line:col` form. The line and column start
+    /// at one, like they do in most error messages.
+    pub label: String,
+}
+
+impl FindResult {
+    pub fn new(path: PathBuf, position: Position) -> Self {
+        let label = format!(
+            "{}:{}:{}",
+            path.display(),
+            position.line.saturating_add(1),
+            position.offset.0.saturating_add(1)
+        );
+
+        FindResult {
+            path,
+            position,
+            label,
+        }
+    }
+}
+
+pub type FindResults = Vec<FindResult>;
+
+/// How the text in the find box is interpreted when searching.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum SearchKind {
+    /// The text is searched for exactly as written.
+    Plain,
+    /// The text is a regular expression, with the same syntax as the `regex` crate,
+    /// except that anchors like `^` and `$`, and word boundaries like `\b` are not
+    /// supported.
+    Regex,
+}
+d!(for SearchKind: SearchKind::Plain);
+
+impl SearchKind {
+    pub fn toggle_regex(self) -> Self {
+        u!{SearchKind}
+        match self {
+            Plain => Regex,
+            Regex => Plain,
+        }
+    }
+}
+
+/// How the case of letters is treated when searching.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum CaseSensitivity {
+    /// Letters only match letters of the same case.
+    Sensitive,
+    /// Letters match letters of either case.
+    Insensitive,
+    /// Like `Insensitive`, unless the text in the find box contains an uppercase
+    /// letter, in which case like `Sensitive`.
+    Smart,
+}
+d!(for CaseSensitivity: CaseSensitivity::Sensitive);
+
+impl CaseSensitivity {
+    pub fn cycle(self) -> Self {
+        u!{CaseSensitivity}
+        match self {
+            Sensitive => Insensitive,
+            Insensitive => Smart,
+            Smart => Sensitive,
+        }
+    }
+
+    /// Returns whether a search for `needle` should be case sensitive.
+    pub fn is_sensitive_for(self, needle: &str) -> bool {
+        u!{CaseSensitivity}
+        match self {
+            Sensitive => true,
+            Insensitive => false,
+            Smart => needle.chars().any(char::is_uppercase),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, Debug, Hash, PartialEq, Eq)]
+pub struct SearchOptions {
+    pub kind: SearchKind,
+    pub case: CaseSensitivity,
+    /// Only match text that starts and ends on a Unicode word boundary.
+    pub whole_word: bool,
+    /// Only match text inside the ranges that were selected when the find menu was
+    /// opened. If nothing was selected then the whole buffer is searched.
+    pub in_selection: bool,
+}
+
 #[derive(Clone, Default, Debug, PartialEq)]
 pub struct FindReplaceView {
     pub mode: FindReplaceMode,
+    pub search_options: SearchOptions,
     pub find: BufferViewData,
     pub replace: BufferViewData,
     pub result_count: usize,
+    /// The matches in other files, for the modes that search more than one file.
+    pub results: FindResults,
 }
 
 #[derive(Clone, Default, Debug, PartialEq)]
@@ -545,6 +743,9 @@ pub struct View {    pub buffers: SelectableVec1<BufferView>,
     pub current_buffer_kind: BufferIdKind,
     pub edited_transitions: EditedTransitions,
     pub stats: ViewStats,
+    /// A plain text drawing of the current text buffer's undo tree, for debugging.
+    /// This is empty unless an `Input::SetUndoTreeShown(true)` was the last of those.
+    pub undo_tree_drawing: String,
 }
 
 impl View {
@@ -599,11 +800,12 @@ impl View {
         }
     }
 
-    /// returns the currently visible editor buffer path if it has one.
+    /// returns the currently visible editor buffer path if it has one. For hex views,
+    /// this is the path of the file they show.
     pub fn current_path(&self) -> Option<PathBuf> {
         u!{BufferName}
         match self.buffers.get_current_element().name {
-            Path(ref p) => Some(p.to_owned()),
+            Path(ref p) | Hex(ref p) => Some(p.to_owned()),
             Scratch(_) => None,
         }
     }
@@ -630,6 +832,8 @@ pub struct BufferViewData {
     pub cursors: Vec<CursorView>,
     pub highlights: Vec<Highlight>,
     pub spans: Spans,
+    /// The folds as returned by `folds::shown`.
+    pub folds: Vec<Fold>,
 }
 
 fmt_debug!(collapse default for BufferViewData: me {
@@ -638,6 +842,7 @@ fmt_debug!(collapse default for BufferViewData: me {
     blank_if_default!(cursors, me.cursors.is_empty());
     blank_if_default!(highlights, me.highlights.is_empty());
     blank_if_default!(spans, me.spans.is_empty());
+    blank_if_default!(folds, me.folds.is_empty());
 });
 
 #[macro_export]
@@ -657,6 +862,11 @@ pub enum Cmd {
     None,
     SetClipboard(String),
     LoadFile(PathBuf),
+    /// Read every file under the given directory, and send their contents back in an
+    /// `Input::SearchFiles`.
+    LoadDirectoryFiles(PathBuf),
+    /// Store the serialized histories alongside the edited buffers with the same names.
+    StoreBufferHistories(Vec<(BufferName, String)>),
 }
 
 d!(for Cmd : Cmd::None);
@@ -107,6 +107,8 @@ arb_enum!{
         User => Just(User),
         Result => Just(Result),
         CurrentResult => Just(CurrentResult),
+        MatchingBracket => Just(MatchingBracket),
+        UnmatchedBracket => Just(UnmatchedBracket),
     }
 }
 
@@ -190,22 +192,76 @@ prop_compose!{
 prop_compose!{
     pub fn find_replace_view()(
         mode in find_replace_mode(),
+        search_options in search_options(),
         find in buffer_view_data(),
         replace in buffer_view_data(),
         result_count in any::<usize>(),
+        results in find_results(16),
     ) -> FindReplaceView {
         FindReplaceView {
             mode,
+            search_options,
             find,
             replace,
             result_count,
+            results,
         }
     }
 }
 
+prop_compose!{
+    pub fn find_result()(
+        path in path_buf(),
+        position in position(),
+    ) -> FindResult {
+        FindResult::new(path, position)
+    }
+}
+
+prop_compose!{
+    pub fn find_results(max_len: usize)(
+        results in vec(find_result(), 0..=max_len),
+    ) -> FindResults {
+        results
+    }
+}
+
 arb_enum!{
     pub fn find_replace_mode() -> FindReplaceMode {
         CurrentFile => Just(CurrentFile),
+        OpenBuffers => Just(OpenBuffers),
+        Directory => Just(Directory),
+    }
+}
+
+arb_enum!{
+    pub fn search_kind() -> SearchKind {
+        Plain => Just(Plain),
+        Regex => Just(Regex),
+    }
+}
+
+arb_enum!{
+    pub fn case_sensitivity() -> CaseSensitivity {
+        Sensitive => Just(Sensitive),
+        Insensitive => Just(Insensitive),
+        Smart => Just(Smart),
+    }
+}
+
+prop_compose!{
+    pub fn search_options()(
+        kind in search_kind(),
+        case in case_sensitivity(),
+        whole_word in any::<bool>(),
+        in_selection in any::<bool>(),
+    ) -> SearchOptions {
+        SearchOptions {
+            kind,
+            case,
+            whole_word,
+            in_selection,
+        }
     }
 }
 
@@ -223,6 +279,7 @@ arb_enum!{
     pub fn buffer_name() -> BufferName {
         Scratch(_) => scratch_buffer_name(),
         Path(_) => path_buffer_name(),
+        Hex(_) => path_buf().prop_map(BufferName::Hex),
     }
 }
 
@@ -264,6 +321,7 @@ prop_compose!{
         cursors in vec(cursor_view(), 0..=16),
         highlights in vec(highlight(), 0..=16),
         spans in spans(16),
+        folds in vec(fold(), 0..=4),
     ) -> BufferViewData {
         BufferViewData {
             chars: Rope::from(chars),
@@ -271,6 +329,19 @@ prop_compose!{
             cursors,
             highlights,
             spans,
+            folds: crate::folds::shown(&folds),
+        }
+    }
+}
+
+prop_compose!{
+    pub fn fold()(
+        first_line in 0..64usize,
+        line_count in 1..16usize,
+    ) -> Fold {
+        Fold {
+            first_line,
+            last_line: first_line + line_count - 1,
         }
     }
 }
@@ -448,6 +519,7 @@ prop_compose!{
         status_line in status_line_view(),
         e_t in edited_transitions(),
         stats in stats(),
+        undo_tree_drawing in ".*",
     ) -> View {
         View {
             current_buffer_kind,
@@ -456,6 +528,7 @@ prop_compose!{
             status_line,
             edited_transitions: e_t,
             stats,
+            undo_tree_drawing,
         }
     }
 }
@@ -509,6 +582,9 @@ arb_enum!{
         SavedAs(_, _) => saved_as(),
         Undo => Just(Undo),
         Redo => Just(Redo),
+        PreviousUndoBranch => Just(PreviousUndoBranch),
+        NextUndoBranch => Just(NextUndoBranch),
+        SetUndoTreeShown(_) => any::<bool>().prop_map(SetUndoTreeShown),
         Cut => Just(Cut),
         Copy => Just(Copy),
         Paste(_) => proptest::option::of(".*").prop_map(Paste),
@@ -517,14 +593,38 @@ arb_enum!{
         NewScratchBuffer(_) => proptest::option::of(".*").prop_map(NewScratchBuffer),
         TabIn => Just(TabIn),
         TabOut => Just(TabOut),
+        ToggleComments => Just(ToggleComments),
+        JumpToMatchingBrackets => Just(JumpToMatchingBrackets),
+        SelectInsideBrackets => Just(SelectInsideBrackets),
+        SwapLinesUp => Just(SwapLinesUp),
+        SwapLinesDown => Just(SwapLinesDown),
+        SwapSyntaxNodeUp => Just(SwapSyntaxNodeUp),
+        SwapSyntaxNodeDown => Just(SwapSyntaxNodeDown),
+        ExtractFunction => Just(ExtractFunction),
+        ExtractVariable => Just(ExtractVariable),
+        ExtractVariableEverywhere => Just(ExtractVariableEverywhere),
         AdjustBufferSelection(_) => selection_adjustment()
             .prop_map(AdjustBufferSelection),
         NextLanguage => Just(NextLanguage),
         SelectBuffer(_) => buffer_id().prop_map(SelectBuffer),
         OpenOrSelectBuffer(_) => path_buf().prop_map(OpenOrSelectBuffer),
+        OpenOrSelectBufferAt(_, _) => (path_buf(), position())
+            .prop_map(|(p, pos)| OpenOrSelectBufferAt(p, pos)),
         CloseBuffer(_) => close_buffer(),
         SetMenuMode(_) => menu_mode().prop_map(SetMenuMode),
         SubmitForm => Just(SubmitForm),
+        ReplaceAll => Just(ReplaceAll),
+        ToggleRegexSearch => Just(ToggleRegexSearch),
+        CycleSearchCaseSensitivity => Just(CycleSearchCaseSensitivity),
+        ToggleWholeWordSearch => Just(ToggleWholeWordSearch),
+        ToggleSearchInSelection => Just(ToggleSearchInSelection),
+        SearchFiles(_) => vec((path_buf(), ".*"), 0..=4).prop_map(SearchFiles),
+        SerializeBufferHistories => Just(SerializeBufferHistories),
+        RestoreBufferHistory(_, _) => (buffer_name(), ".*").prop_map(|(bn, s)| RestoreBufferHistory(bn, s)),
+        ReloadBuffer(_, _) => (pub_arb_g_i::index(16), ".*").prop_map(|(i, s)| ReloadBuffer(i, s)),
+        SetOnDiskText(_, _) => (buffer_name(), ".*").prop_map(|(bn, s)| SetOnDiskText(bn, s)),
+        Fold => Just(Fold),
+        Unfold => Just(Unfold),
     }
 }
 
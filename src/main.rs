@@ -19,6 +19,9 @@ fn update_and_render(input: Input) -> UpdateAndRenderOutput {
 }
 
 fn main() {
-    println!("{} v{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+    // In headless mode, stdout is reserved for the results.
+    if !platform_layer::is_headless() {
+        println!("{} v{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+    }
     platform_layer::run(update_and_render);
 }